@@ -0,0 +1,80 @@
+//!
+//! Tokenizer stage with TokenizerError vs ParserError.
+//!
+//! Runs a failing tokenizer in a loop and counts time and allocations.
+//! TokenizerError must not allocate at all.
+//!
+//! cargo bench --bench tokenizer
+//!
+
+use kparse::examples::{ExAorB, ExCode, ExNumber, ExTagA, ExTagB};
+use kparse::{KParseError, KParser, ParserError, TokenizerError};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::{IResult, Parser};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountAlloc = CountAlloc;
+
+const ROUNDS: usize = 1_000_000;
+
+fn nom_a_or_b<E>(i: &'static str) -> IResult<&'static str, &'static str, E>
+where
+    E: KParseError<ExCode, &'static str> + nom::error::ParseError<&'static str>,
+{
+    alt((
+        tag("a").with_code(ExTagA),
+        tag("b").with_code(ExTagB),
+        digit1.with_code(ExNumber),
+    ))
+    .with_code(ExAorB)
+    .parse(i)
+}
+
+fn bench<E>(name: &str) -> usize
+where
+    E: KParseError<ExCode, &'static str> + nom::error::ParseError<&'static str>,
+{
+    let allocs = ALLOCS.load(Ordering::Relaxed);
+    let now = Instant::now();
+    for _ in 0..ROUNDS {
+        let r = nom_a_or_b::<E>(black_box("x"));
+        black_box(r.is_err());
+    }
+    let elapsed = now.elapsed();
+    let allocs = ALLOCS.load(Ordering::Relaxed) - allocs;
+
+    println!(
+        "{:16} {:>8.1} ns/iter {:>10} allocations",
+        name,
+        elapsed.as_nanos() as f64 / ROUNDS as f64,
+        allocs
+    );
+    allocs
+}
+
+fn main() {
+    let tokenizer = bench::<TokenizerError<ExCode, &str>>("TokenizerError");
+    bench::<ParserError<ExCode, &str>>("ParserError");
+    assert_eq!(tokenizer, 0, "TokenizerError allocated");
+}
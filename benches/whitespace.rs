@@ -0,0 +1,42 @@
+//!
+//! Whitespace and line skipping, nom vs the byte based combinators.
+//!
+//! cargo bench --bench whitespace
+//!
+
+use kparse::combinators::{take_till_newline, take_while_ascii_ws};
+use nom::bytes::complete::is_not;
+use nom::character::complete::multispace0;
+use nom::IResult;
+use std::hint::black_box;
+use std::time::Instant;
+
+const ROUNDS: usize = 100_000;
+
+fn bench(
+    name: &str,
+    text: &'static str,
+    f: impl Fn(&'static str) -> IResult<&'static str, &'static str>,
+) {
+    let now = Instant::now();
+    for _ in 0..ROUNDS {
+        let r = f(black_box(text));
+        black_box(r.is_ok());
+    }
+    let elapsed = now.elapsed();
+    println!(
+        "{:24} {:>8.1} ns/iter",
+        name,
+        elapsed.as_nanos() as f64 / ROUNDS as f64
+    );
+}
+
+fn main() {
+    let ws: &'static str = " \t\r\n".repeat(64).leak();
+    let line: &'static str = format!("{}\nnext", "x".repeat(256)).leak();
+
+    bench("multispace0", ws, multispace0);
+    bench("take_while_ascii_ws", ws, take_while_ascii_ws);
+    bench("is_not(\"\\r\\n\")", line, is_not("\r\n"));
+    bench("take_till_newline", line, take_till_newline);
+}
@@ -1,5 +1,4 @@
 #![allow(dead_code)]
-
 use crate::ExCode::*;
 use kparse::combinators::track;
 use kparse::prelude::*;
@@ -145,13 +144,13 @@ fn token_number(i: ExSpan<'_>) -> ExParserResult<'_, AstNumber<'_>> {
         .parse(i)
 }
 
-fn parse_a(input: ExSpan<'_>) -> ExParserResult<'_, AstA> {
+fn parse_a(input: ExSpan<'_>) -> ExParserResult<'_, AstA<'_>> {
     Track.enter(ExTagA, input);
     let (rest, tok) = nom_parse_a.parse(input).err_into().track()?;
     Track.ok(rest, tok, AstA { span: tok })
 }
 
-fn parse_b(input: ExSpan<'_>) -> ExParserResult<'_, AstB> {
+fn parse_b(input: ExSpan<'_>) -> ExParserResult<'_, AstB<'_>> {
     track(
         ExTagB, //
         nom_parse_b.map(|span| AstB { span }),
@@ -161,7 +160,7 @@ fn parse_b(input: ExSpan<'_>) -> ExParserResult<'_, AstB> {
 }
 
 // := a b
-fn parse_ab(input: ExSpan<'_>) -> ExParserResult<'_, AstAthenB> {
+fn parse_ab(input: ExSpan<'_>) -> ExParserResult<'_, AstAthenB<'_>> {
     Track.enter(ExAthenB, input);
 
     let rest = input;
@@ -175,14 +174,14 @@ fn parse_ab(input: ExSpan<'_>) -> ExParserResult<'_, AstAthenB> {
 }
 
 // := a b
-fn parse_ab_v2(input: ExSpan<'_>) -> ExParserResult<'_, AstAthenB> {
+fn parse_ab_v2(input: ExSpan<'_>) -> ExParserResult<'_, AstAthenB<'_>> {
     Track.enter(ExAthenB, input);
     let (rest, (span, (a, b))) = consumed(tuple((parse_a, parse_b)))(input).track()?;
     Track.ok(rest, span, AstAthenB { a, b })
 }
 
 // := a? b
-fn parse_a_opt_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAoptB> {
+fn parse_a_opt_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAoptB<'_>> {
     track(
         ExAoptB,
         tuple((parse_a.opt(), parse_b)) //
@@ -193,7 +192,7 @@ fn parse_a_opt_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAoptB> {
 }
 
 // := a* b
-fn parse_a_star_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAstarB> {
+fn parse_a_star_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAstarB<'_>> {
     track(
         ExAstarB,
         tuple((many0(parse_a), parse_b)) //
@@ -204,7 +203,7 @@ fn parse_a_star_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAstarB> {
 }
 
 // := ( a | b )*
-fn parse_a_b_star(input: ExSpan<'_>) -> ExParserResult<'_, AstABstar> {
+fn parse_a_b_star(input: ExSpan<'_>) -> ExParserResult<'_, AstABstar<'_>> {
     Track.enter(ExABstar, input);
 
     let mut loop_rest = input;
@@ -248,7 +247,7 @@ fn parse_a_b_star(input: ExSpan<'_>) -> ExParserResult<'_, AstABstar> {
     Track.ok(loop_rest, input, res)
 }
 
-fn parse_a_or_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAorB> {
+fn parse_a_or_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAorB<'_>> {
     track(
         ExAorB,
         parse_a
@@ -259,7 +258,7 @@ fn parse_a_or_b(input: ExSpan<'_>) -> ExParserResult<'_, AstAorB> {
     .parse(input)
 }
 
-fn parse_a_b_num(input: ExSpan<'_>) -> ExParserResult<'_, AstABNum> {
+fn parse_a_b_num(input: ExSpan<'_>) -> ExParserResult<'_, AstABNum<'_>> {
     track(
         ExABNum,
         tuple((
@@ -15,8 +15,6 @@ pub mod parser4 {
         dump_diagnostics as dump_diagnostics_v4, dump_diagnostics_info as dump_diagnostics_info_v4,
         dump_trace as dump_trace_v4,
     };
-    #[cfg(debug_assertions)]
-    use kparse::prelude::*;
     use kparse::token_error::TokenizerError;
     use kparse::{define_span, Code, ParserError, ParserResult, TokenizerResult};
     use std::fmt::{Display, Formatter};
@@ -995,7 +993,7 @@ pub mod parser4 {
                     Err(e) => return Track.err(e),
                 }
 
-                if loop_rest.len() > 0 {
+                if !loop_rest.fragment().is_empty() {
                     match parse_kultur(loop_rest) {
                         Ok((rest, val)) => {
                             loop_rest = rest;
@@ -1283,19 +1281,7 @@ pub mod parser4 {
             match nom_name(rest) {
                 Ok((rest, tok)) => {
                     // trim trailing whitespace after the fact.
-                    let trim = tok.trim_end();
-
-                    // the trimmed span is part of original.
-                    // so reusing the rest ought to be fine.
-                    #[cfg(debug_assertions)]
-                    let trim = unsafe {
-                        APSpan::new_from_raw_offset(
-                            tok.location_offset(),
-                            tok.location_line(),
-                            trim,
-                            tok.extra,
-                        )
-                    };
+                    let trim = tok.trim_end_ws();
 
                     // could rewind the rest too, but since it'_ whitespace
                     // which would be thrown away anyway ...
@@ -7,8 +7,6 @@
 
 use chrono::NaiveDate;
 use kparse::test::{str_parse, CheckDump};
-#[cfg(debug_assertions)]
-use kparse::ParseSpan;
 use kparse::{define_span, Code, ParserError, ParserResult, TokenizerResult};
 pub use parser::*;
 use rust_decimal::Decimal;
@@ -197,14 +195,12 @@ mod debug {
                 println!(" {:04}  {}", t_line, t);
             }
 
-            if expect.is_empty() {
-                if t_line == s_line {
-                    println!("      {}^", " ".repeat(s_column - 1));
-                    if !msg.is_empty() {
-                        println!("expected: {}", msg);
-                    } else {
-                        println!("expected: {}", err.code);
-                    }
+            if expect.is_empty() && t_line == s_line {
+                println!("      {}^", " ".repeat(s_column - 1));
+                if !msg.is_empty() {
+                    println!("expected: {}", msg);
+                } else {
+                    println!("expected: {}", err.code);
                 }
             }
 
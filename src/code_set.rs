@@ -0,0 +1,111 @@
+//!
+//! Sets of error codes.
+//!
+//! Codes with an [id](crate::Code::id) below 128 are kept in a bitset,
+//! so contains() is a single bit test. All other codes go into a list.
+//!
+//! ```rust
+//! use kparse::code_set::CodeSet;
+//! use kparse::examples::{ExAorB, ExTagA, ExTagB};
+//!
+//! let starts = CodeSet::new().with(ExTagA).with(ExTagB);
+//! assert!(starts.contains(ExTagA));
+//! assert!(!starts.contains(ExAorB));
+//! ```
+//!
+
+use crate::Code;
+use alloc::vec::Vec;
+
+/// A set of codes.
+#[derive(Debug, Clone)]
+pub struct CodeSet<C> {
+    bits: u128,
+    other: Vec<C>,
+}
+
+impl<C> Default for CodeSet<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> CodeSet<C> {
+    /// Empty set.
+    pub const fn new() -> Self {
+        Self {
+            bits: 0,
+            other: Vec::new(),
+        }
+    }
+}
+
+impl<C> CodeSet<C>
+where
+    C: Code,
+{
+    /// Adds a code.
+    pub fn with(mut self, code: C) -> Self {
+        self.insert(code);
+        self
+    }
+
+    /// Adds a code.
+    pub fn insert(&mut self, code: C) {
+        match bit(code) {
+            Some(bit) => self.bits |= bit,
+            None => {
+                if !self.other.contains(&code) {
+                    self.other.push(code);
+                }
+            }
+        }
+    }
+
+    /// Is the code in the set.
+    #[inline]
+    pub fn contains(&self, code: C) -> bool {
+        match bit(code) {
+            Some(bit) => self.bits & bit != 0,
+            None => self.other.contains(&code),
+        }
+    }
+
+    /// All codes of both sets.
+    pub fn union(&self, other: &CodeSet<C>) -> CodeSet<C> {
+        let mut union = self.clone();
+        union.bits |= other.bits;
+        for code in &other.other {
+            if !union.other.contains(code) {
+                union.other.push(*code);
+            }
+        }
+        union
+    }
+
+    /// No codes.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0 && self.other.is_empty()
+    }
+}
+
+impl<C> FromIterator<C> for CodeSet<C>
+where
+    C: Code,
+{
+    fn from_iter<T: IntoIterator<Item = C>>(iter: T) -> Self {
+        let mut set = CodeSet::new();
+        for code in iter {
+            set.insert(code);
+        }
+        set
+    }
+}
+
+#[inline]
+fn bit<C: Code>(code: C) -> Option<u128> {
+    match code.id() {
+        Some(id) if id < 128 => Some(1 << id),
+        _ => None,
+    }
+}
@@ -2,11 +2,22 @@
 //! Provides some extra parser combinators.
 //!
 
-use crate::{Code, KParseError, TrackedSpan};
+use crate::code_set::CodeSet;
+use crate::report::Severity;
+use crate::{Code, KParseError, ParserError, ParserResult, TrackedSpan};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt::Debug;
+use core::iter::Peekable;
+use core::ops::{Range, RangeFrom, RangeTo};
 use nom::error::{ErrorKind, ParseError};
-use nom::{AsBytes, AsChar, IResult, InputIter, InputLength, InputTake, Parser, Slice};
-use std::fmt::Debug;
-use std::ops::{Range, RangeFrom, RangeTo};
+use nom::{
+    AsBytes, AsChar, FindToken, IResult, InputIter, InputLength, InputTake, Offset, Parser, Slice,
+};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
 /// Tracked execution of a parser.
 ///
@@ -80,9 +91,13 @@ where
                 Ok((rest, token))
             }
             Err(err) => match err.parts() {
-                None => Err(err),
+                None => {
+                    input.track_info("incomplete");
+                    input.track_exit();
+                    Err(err)
+                }
                 Some((code, span, e)) => {
-                    span.track_err(code, e);
+                    span.track_err_expected(code, e, || err.expected());
                     span.track_exit();
                     Err(err)
                 }
@@ -193,6 +208,634 @@ where
     }
 }
 
+/// Runs the parser only if the flag is set, otherwise fails with the code.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use nom::Parser;
+/// use kparse::combinators::cond;
+/// use kparse::examples::{ExSpan, ExTagA, ExTokenizerResult};
+/// use kparse::KParser;
+///
+/// fn nom_a(i: ExSpan<'_>, dialect_2023: bool) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     cond(dialect_2023, tag("a"), ExTagA).parse(i)
+/// }
+/// ```
+#[inline]
+pub fn cond<PA, C, I, O, E>(
+    flag: bool,
+    mut parser: PA,
+    code: C,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>>
+where
+    PA: Parser<I, O, E>,
+    C: Code,
+    E: KParseError<C, I>,
+{
+    move |i| -> Result<(I, O), nom::Err<E>> {
+        if flag {
+            parser.parse(i)
+        } else {
+            Err(nom::Err::Error(E::from(code, i)))
+        }
+    }
+}
+
+/// Runs the parser only if the flag is set, otherwise returns None.
+///
+/// Unlike [nom::combinator::cond] the parser must match if the flag is set.
+#[inline]
+pub fn cond_opt<PA, I, O, E>(
+    flag: bool,
+    mut parser: PA,
+) -> impl FnMut(I) -> Result<(I, Option<O>), nom::Err<E>>
+where
+    PA: Parser<I, O, E>,
+{
+    move |i| -> Result<(I, Option<O>), nom::Err<E>> {
+        if flag {
+            let (rest, v) = parser.parse(i)?;
+            Ok((rest, Some(v)))
+        } else {
+            Ok((i, None))
+        }
+    }
+}
+
+/// Tries each parser in turn, like [nom::branch::alt]. Each parser comes with
+/// its own error code.
+///
+/// If all parsers fail the error has the given code and the span of the
+/// furthest failure. The codes of all the branches are added as expected hints.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use kparse::combinators::alt_with_codes;
+/// use kparse::examples::{ExAorB, ExParserResult, ExSpan, ExTagA, ExTagB};
+/// use kparse::KParser;
+///
+/// fn parse_a_or_b(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+///     alt_with_codes(ExAorB, ((ExTagA, tag("a")), (ExTagB, tag("b"))))(i)
+/// }
+/// ```
+#[inline]
+pub fn alt_with_codes<L, C, I, O>(code: C, mut list: L) -> impl FnMut(I) -> ParserResult<C, I, O>
+where
+    L: AltWithCodes<C, I, O>,
+    C: Code,
+{
+    move |i| -> ParserResult<C, I, O> { list.choice(code, i) }
+}
+
+/// Helper trait for [alt_with_codes]. Implemented for tuples of
+/// `(code, parser)` pairs.
+pub trait AltWithCodes<C, I, O> {
+    /// Tries each parser in turn.
+    fn choice(&mut self, code: C, input: I) -> ParserResult<C, I, O>;
+}
+
+fn alt_with_codes_error<C, I>(
+    code: C,
+    input: I,
+    errs: Vec<(C, ParserError<C, I>)>,
+) -> ParserError<C, I>
+where
+    C: Code,
+    I: Clone + Offset,
+{
+    let mut furthest = input.clone();
+    for (_, e) in &errs {
+        if input.offset(&e.span) > input.offset(&furthest) {
+            furthest = e.span.clone();
+        }
+    }
+
+    let mut err = ParserError::new(code, furthest);
+    for (c, e) in errs {
+        err.expect(c, e.span);
+    }
+    err
+}
+
+macro_rules! alt_with_codes_tuple {
+    ($($p:ident $idx:tt),+) => {
+        impl<C, I, O, $($p),+> AltWithCodes<C, I, O> for ($((C, $p),)+)
+        where
+            C: Code,
+            I: Clone + Offset,
+            $($p: Parser<I, O, ParserError<C, I>>),+
+        {
+            fn choice(&mut self, code: C, input: I) -> ParserResult<C, I, O> {
+                let mut errs = Vec::new();
+                $(
+                match self.$idx.1.parse(input.clone()) {
+                    Ok(v) => return Ok(v),
+                    Err(nom::Err::Error(e)) => errs.push((self.$idx.0, e)),
+                    Err(e) => return Err(e),
+                }
+                )+
+                Err(nom::Err::Error(alt_with_codes_error(code, input, errs)))
+            }
+        }
+    };
+}
+
+alt_with_codes_tuple!(P0 0);
+alt_with_codes_tuple!(P0 0, P1 1);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9, P10 10);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9, P10 10, P11 11);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9, P10 10, P11 11, P12 12);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9, P10 10, P11 11, P12 12, P13 13);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9, P10 10, P11 11, P12 12, P13 13, P14 14);
+alt_with_codes_tuple!(P0 0, P1 1, P2 2, P3 3, P4 4, P5 5, P6 6, P7 7, P8 8, P9 9, P10 10, P11 11, P12 12, P13 13, P14 14, P15 15);
+
+/// Same as nom::bytes::complete::take_while_m_n but with an error code.
+///
+/// If fewer than min characters match, the error span is the short field.
+///
+/// ```rust
+/// use kparse::combinators::take_while_m_n;
+/// use kparse::examples::{ExNumber, ExSpan, ExTokenizerResult};
+///
+/// fn nom_day(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     take_while_m_n(2, 2, |c| c.is_ascii_digit(), ExNumber)(i)
+/// }
+/// ```
+#[inline]
+pub fn take_while_m_n<FN, C, I, E>(
+    min: usize,
+    max: usize,
+    pred: FN,
+    code: C,
+) -> impl Fn(I) -> Result<(I, I), nom::Err<E>>
+where
+    FN: Fn(char) -> bool,
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        let mut n = 0;
+        let mut len = 0;
+        for (off, c) in i.iter_indices() {
+            if n == max || !pred(c.as_char()) {
+                break;
+            }
+            n += 1;
+            len = off + c.len();
+        }
+
+        if n < min {
+            Err(nom::Err::Error(E::from(code, i.slice(..len))))
+        } else {
+            Ok((i.slice(len..), i.slice(..len)))
+        }
+    }
+}
+
+/// Takes exactly n characters or fails with the code.
+#[inline]
+pub fn chars<C, I, E>(n: usize, code: C) -> impl Fn(I) -> Result<(I, I), nom::Err<E>>
+where
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    take_while_m_n(n, n, |_| true, code)
+}
+
+/// Recognizes a float and returns the span and the value.
+///
+/// Accepts an optional sign, the mantissa with an optional decimal point and
+/// an optional exponent. A malformed exponent fails with a span that covers
+/// the exponent only.
+///
+/// ```rust
+/// use kparse::combinators::float_token;
+/// use kparse::examples::{ExNumber, ExSpan, ExTokenizerResult};
+///
+/// fn nom_float(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExSpan<'_>, f64)> {
+///     float_token(ExNumber)(i)
+/// }
+/// ```
+#[inline]
+pub fn float_token<C, I, E>(code: C) -> impl Fn(I) -> Result<(I, (I, f64)), nom::Err<E>>
+where
+    C: Code,
+    I: Slice<Range<usize>> + Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        let mut it = i.iter_indices().peekable();
+        let mut end = 0;
+
+        next_char_if(&mut it, &mut end, |c| c == '+' || c == '-');
+        let mut digits = 0;
+        while next_char_if(&mut it, &mut end, |c| c.is_ascii_digit()) {
+            digits += 1;
+        }
+        if next_char_if(&mut it, &mut end, |c| c == '.') {
+            while next_char_if(&mut it, &mut end, |c| c.is_ascii_digit()) {
+                digits += 1;
+            }
+        }
+        if digits == 0 {
+            return Err(nom::Err::Error(E::from(code, i)));
+        }
+
+        let exp_start = end;
+        if next_char_if(&mut it, &mut end, |c| c == 'e' || c == 'E') {
+            next_char_if(&mut it, &mut end, |c| c == '+' || c == '-');
+            let mut exp_digits = 0;
+            while next_char_if(&mut it, &mut end, |c| c.is_ascii_digit()) {
+                exp_digits += 1;
+            }
+            if exp_digits == 0 {
+                return Err(nom::Err::Error(E::from(code, i.slice(exp_start..end))));
+            }
+        }
+
+        let token = i.slice(..end);
+        let txt: String = token.iter_elements().map(|c| c.as_char()).collect();
+        match txt.parse::<f64>() {
+            Ok(value) => Ok((i.slice(end..), (token, value))),
+            Err(_) => Err(nom::Err::Error(E::from(code, token))),
+        }
+    }
+}
+
+fn next_char_if<It, T>(it: &mut Peekable<It>, end: &mut usize, pred: impl Fn(char) -> bool) -> bool
+where
+    It: Iterator<Item = (usize, T)>,
+    T: AsChar + Copy,
+{
+    match it.peek() {
+        Some((off, c)) if pred(c.as_char()) => {
+            *end = off + c.len();
+            it.next();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Succeeds only at the end of the input, otherwise fails with the code.
+/// The error span is the remaining input.
+///
+/// Unlike `parser.all_consuming(code)` this doesn't wrap another parser.
+#[inline]
+pub fn eof<C, I, E>(code: C) -> impl Fn(I) -> Result<(I, I), nom::Err<E>>
+where
+    C: Code,
+    I: Clone + InputLength,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        if i.input_len() == 0 {
+            Ok((i.clone(), i))
+        } else {
+            Err(nom::Err::Error(E::from(code, i)))
+        }
+    }
+}
+
+/// Skips ASCII whitespace: space, tab, '\r' and '\n'. Never fails.
+///
+/// Same as nom::character::complete::multispace0 but works on the bytes
+/// directly, 8 at a time, instead of decoding each char.
+///
+/// ```rust
+/// use kparse::combinators::take_while_ascii_ws;
+/// use kparse::examples::{ExSpan, ExTokenizerResult};
+///
+/// fn nom_ws(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     take_while_ascii_ws(i)
+/// }
+/// ```
+#[inline]
+pub fn take_while_ascii_ws<I, E>(i: I) -> IResult<I, I, E>
+where
+    I: AsBytes + InputLength + Slice<RangeTo<usize>> + Slice<RangeFrom<usize>>,
+    E: ParseError<I>,
+{
+    let len = ascii_ws_len(i.as_bytes());
+    Ok((i.slice(len..), i.slice(..len)))
+}
+
+// Checks 8 bytes at a time.
+pub(crate) fn ascii_ws_len(bytes: &[u8]) -> usize {
+    const LO: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+    const HI: u64 = 0x8080_8080_8080_8080;
+    const fn splat(b: u8) -> u64 {
+        (b as u64) * 0x0101_0101_0101_0101
+    }
+    // high bit set for each zero byte.
+    #[inline(always)]
+    fn zero_bytes(x: u64) -> u64 {
+        !(((x & LO) + LO) | x | LO)
+    }
+
+    let mut len = 0;
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        let x = u64::from_le_bytes(word);
+        let ws = zero_bytes(x ^ splat(b' '))
+            | zero_bytes(x ^ splat(b'\t'))
+            | zero_bytes(x ^ splat(b'\r'))
+            | zero_bytes(x ^ splat(b'\n'));
+        if ws != HI {
+            return len + ((!ws & HI).trailing_zeros() / 8) as usize;
+        }
+        len += 8;
+    }
+    len + chunks
+        .remainder()
+        .iter()
+        .position(|b| !matches!(b, b' ' | b'\t' | b'\r' | b'\n'))
+        .unwrap_or(chunks.remainder().len())
+}
+
+/// Takes everything up to the next '\n' or the end of the input.
+/// The line break, '\n' or '\r\n', stays in the rest. Never fails.
+///
+/// Uses memchr to find the newline, which uses SIMD where available.
+///
+/// ```rust
+/// use kparse::combinators::take_till_newline;
+/// use kparse::examples::{ExSpan, ExTokenizerResult};
+///
+/// fn nom_comment(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     take_till_newline(i)
+/// }
+/// ```
+#[inline]
+pub fn take_till_newline<I, E>(i: I) -> IResult<I, I, E>
+where
+    I: AsBytes + InputLength + Slice<RangeTo<usize>> + Slice<RangeFrom<usize>>,
+    E: ParseError<I>,
+{
+    let bytes = i.as_bytes();
+    let len = match memchr::memchr(b'\n', bytes) {
+        Some(nl) if nl > 0 && bytes[nl - 1] == b'\r' => nl - 1,
+        Some(nl) => nl,
+        None => i.input_len(),
+    };
+    Ok((i.slice(len..), i.slice(..len)))
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static RECURSION_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(feature = "std")]
+struct RecursionDepth;
+
+#[cfg(feature = "std")]
+impl RecursionDepth {
+    fn enter() -> (Self, usize) {
+        let depth = RECURSION_DEPTH.with(|v| {
+            v.set(v.get() + 1);
+            v.get()
+        });
+        (RecursionDepth, depth)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for RecursionDepth {
+    fn drop(&mut self) {
+        RECURSION_DEPTH.with(|v| v.set(v.get() - 1));
+    }
+}
+
+/// Limits the nesting depth of recursive parsers.
+///
+/// The depth is counted per thread for all parsers wrapped with
+/// recursion_guard(). If the depth exceeds max_depth the parser fails with
+/// a nom::Err::Failure with the given code.
+///
+/// Needs the feature std for the thread-local counter.
+///
+/// ```rust
+/// use nom::branch::alt;
+/// use nom::bytes::complete::tag;
+/// use nom::character::complete::digit1;
+/// use nom::sequence::delimited;
+/// use kparse::combinators::recursion_guard;
+/// use kparse::examples::{ExAthenB, ExSpan, ExTokenizerResult};
+///
+/// fn nom_nested(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     recursion_guard(
+///         100,
+///         ExAthenB,
+///         alt((digit1, delimited(tag("("), nom_nested, tag(")")))),
+///     )(i)
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[inline]
+pub fn recursion_guard<PA, C, I, O, E>(
+    max_depth: usize,
+    code: C,
+    mut parser: PA,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>>
+where
+    PA: Parser<I, O, E>,
+    C: Code,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        let (_guard, depth) = RecursionDepth::enter();
+        if depth > max_depth {
+            return Err(nom::Err::Failure(E::from(code, i)));
+        }
+        parser.parse(i)
+    }
+}
+
+/// Budget for [step_limit].
+///
+/// Counts the invocations of all parsers wrapped with step_limit() and
+/// optionally checks a deadline.
+#[derive(Debug)]
+pub struct StepLimit {
+    max_steps: usize,
+    steps: Cell<usize>,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+}
+
+impl StepLimit {
+    /// New budget with the maximum number of steps.
+    pub fn new(max_steps: usize) -> Self {
+        Self {
+            max_steps,
+            steps: Cell::new(0),
+            #[cfg(feature = "std")]
+            deadline: None,
+        }
+    }
+
+    /// Adds a timeout starting now.
+    #[cfg(feature = "std")]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    /// Steps used so far.
+    pub fn steps(&self) -> usize {
+        self.steps.get()
+    }
+
+    /// Counts one step and checks the budget.
+    pub fn step(&self) -> bool {
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get() > self.max_steps {
+            return false;
+        }
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.deadline {
+            return Instant::now() < deadline;
+        }
+        true
+    }
+}
+
+/// Counts each invocation of the parser against the budget. If the budget
+/// is exceeded the parser fails with a nom::Err::Failure with the given code.
+///
+/// Wrap the rules that are invoked most often, to protect against inputs
+/// that lead to exponential backtracking.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use kparse::combinators::{step_limit, StepLimit};
+/// use kparse::examples::{ExSpan, ExTagA, ExTokenizerResult};
+/// use kparse::KParser;
+///
+/// fn nom_a<'s>(limit: &StepLimit, i: ExSpan<'s>) -> ExTokenizerResult<'s, ExSpan<'s>> {
+///     step_limit(limit, ExTagA, tag("a"))(i)
+/// }
+/// ```
+#[inline]
+pub fn step_limit<'a, PA, C, I, O, E>(
+    limit: &'a StepLimit,
+    code: C,
+    mut parser: PA,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>> + 'a
+where
+    PA: Parser<I, O, E> + 'a,
+    C: Code + 'a,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        if !limit.step() {
+            return Err(nom::Err::Failure(E::from(code, i)));
+        }
+        parser.parse(i)
+    }
+}
+
+/// Runs the parser on the next len bytes of the input only.
+///
+/// Fails with the code if there are not enough bytes left, or if the parser
+/// tried to read past the boundary. That is, it fails at the end of the
+/// limited input, returns Incomplete, or gets further without the limit.
+/// For the last check the parser runs a second time on the whole input,
+/// but only if it failed.
+///
+/// ```rust
+/// use nom::number::complete::be_u16;
+/// use kparse::combinators::limited;
+/// use kparse::examples::ExTagA;
+/// use kparse::TokenizerError;
+///
+/// fn record(i: &[u8]) -> nom::IResult<&[u8], u16, TokenizerError<kparse::examples::ExCode, &[u8]>> {
+///     limited(2, be_u16, ExTagA)(i)
+/// }
+/// ```
+#[inline]
+pub fn limited<PA, C, I, O, E>(
+    len: usize,
+    mut parser: PA,
+    code: C,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>>
+where
+    PA: Parser<I, O, E>,
+    C: Code,
+    I: Clone + InputLength + InputTake + Offset + Slice<RangeFrom<usize>>,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        if i.input_len() < len {
+            return Err(nom::Err::Error(E::from(code, i)));
+        }
+
+        let sub = i.take(len);
+        match parser.parse(sub.clone()) {
+            Ok((sub_rest, value)) => {
+                let consumed = len - sub_rest.input_len();
+                Ok((i.slice(consumed..), value))
+            }
+            Err(nom::Err::Error(e)) => {
+                if past_boundary(&mut parser, i, len, &e) {
+                    Err(nom::Err::Error(e.with_code(code)))
+                } else {
+                    Err(nom::Err::Error(e))
+                }
+            }
+            Err(nom::Err::Failure(e)) => {
+                if past_boundary(&mut parser, i, len, &e) {
+                    Err(nom::Err::Failure(e.with_code(code)))
+                } else {
+                    Err(nom::Err::Failure(e))
+                }
+            }
+            Err(nom::Err::Incomplete(_)) => Err(nom::Err::Error(E::from(code, sub.slice(len..)))),
+        }
+    }
+}
+
+/// The error is at the end of the limited input, or the parser gets
+/// further without the limit.
+fn past_boundary<PA, C, I, O, E>(parser: &mut PA, i: I, len: usize, e: &E) -> bool
+where
+    PA: Parser<I, O, E>,
+    C: Code,
+    I: Clone + InputLength + Offset,
+    E: KParseError<C, I>,
+{
+    let Some(span) = e.span() else {
+        return false;
+    };
+    if span.input_len() == 0 {
+        return true;
+    }
+    let err_offset = i.offset(&span);
+    match parser.parse(i.clone()) {
+        Ok((rest, _)) => i.offset(&rest) > len,
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            matches!(e.span(), Some(v) if i.offset(&v) > err_offset)
+        }
+        Err(nom::Err::Incomplete(_)) => true,
+    }
+}
+
 /// Same as nom::char but return the input type instead of the char.
 #[inline]
 pub fn pchar<I, Error: ParseError<I>>(c: char) -> impl Fn(I) -> IResult<I, I, Error>
@@ -257,6 +900,369 @@ where
     }
 }
 
+/// Parses one or more operands separated by operators and folds them
+/// left-associative.
+///
+/// The fold gets the span of both operands and the operator, the left
+/// operand, the operator and the right operand.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use nom::character::complete::digit1;
+/// use nom::Parser;
+/// use kparse::combinators::chainl1;
+/// use kparse::examples::{ExNumber, ExSpan, ExTokenizerResult};
+/// use kparse::KParser;
+///
+/// fn nom_sub(i: ExSpan<'_>) -> ExTokenizerResult<'_, i64> {
+///     chainl1(
+///         digit1.parse_from_str(ExNumber),
+///         tag("-"),
+///         |_span, a: i64, _op, b: i64| a - b,
+///     )(i)
+/// }
+/// ```
+pub fn chainl1<PA, POp, FN, I, O, OOp, E>(
+    mut operand: PA,
+    mut operator: POp,
+    fold: FN,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>>
+where
+    I: Clone + InputLength + Offset + Slice<RangeTo<usize>>,
+    PA: Parser<I, O, E>,
+    POp: Parser<I, OOp, E>,
+    FN: Fn(I, O, OOp, O) -> O,
+    E: ParseError<I>,
+{
+    move |i: I| {
+        let (mut rest, mut lhs) = operand.parse(i.clone())?;
+
+        loop {
+            let len = rest.input_len();
+
+            let (rest2, op) = match operator.parse(rest.clone()) {
+                Ok(v) => v,
+                Err(nom::Err::Error(_)) => return Ok((rest, lhs)),
+                Err(e) => return Err(e),
+            };
+            let (rest2, rhs) = operand.parse(rest2)?;
+
+            if rest2.input_len() == len {
+                return Err(nom::Err::Error(E::from_error_kind(rest2, ErrorKind::Many1)));
+            }
+
+            let span = i.slice(..i.offset(&rest2));
+            lhs = fold(span, lhs, op, rhs);
+            rest = rest2;
+        }
+    }
+}
+
+/// Parses one or more operands separated by operators and folds them
+/// right-associative.
+///
+/// The fold gets the span of both operands and the operator, the left
+/// operand, the operator and the right operand.
+pub fn chainr1<PA, POp, FN, I, O, OOp, E>(
+    mut operand: PA,
+    mut operator: POp,
+    fold: FN,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>>
+where
+    I: Clone + InputLength + Offset + Slice<RangeTo<usize>>,
+    PA: Parser<I, O, E>,
+    POp: Parser<I, OOp, E>,
+    FN: Fn(I, O, OOp, O) -> O,
+    E: ParseError<I>,
+{
+    move |i: I| {
+        let (mut rest, first) = operand.parse(i.clone())?;
+
+        let mut operands = vec![(i, first)];
+        let mut operators = Vec::new();
+        loop {
+            let len = rest.input_len();
+
+            let (rest2, op) = match operator.parse(rest.clone()) {
+                Ok(v) => v,
+                Err(nom::Err::Error(_)) => break,
+                Err(e) => return Err(e),
+            };
+            let start = rest2.clone();
+            let (rest2, rhs) = operand.parse(rest2)?;
+
+            if rest2.input_len() == len {
+                return Err(nom::Err::Error(E::from_error_kind(rest2, ErrorKind::Many1)));
+            }
+
+            operators.push(op);
+            operands.push((start, rhs));
+            rest = rest2;
+        }
+
+        let (_, mut rhs) = operands.pop().expect("operand");
+        while let Some((start, lhs)) = operands.pop() {
+            let op = operators.pop().expect("operator");
+            let span = start.slice(..start.offset(&rest));
+            rhs = fold(span, lhs, op, rhs);
+        }
+
+        Ok((rest, rhs))
+    }
+}
+
+/// Parses the inner parser between the open and close delimiters and returns
+/// the output of the inner parser. Nesting works by calling balanced() again
+/// from the inner parser.
+///
+/// If the close delimiter is missing, the error has the given code and
+/// points at the place where the close delimiter was expected. The span of
+/// the unmatched open delimiter is added as expected hint with the same code.
+///
+/// ```rust
+/// use nom::branch::alt;
+/// use nom::bytes::complete::tag;
+/// use nom::character::complete::digit1;
+/// use kparse::combinators::balanced;
+/// use kparse::examples::{ExAthenB, ExParserResult, ExSpan};
+///
+/// fn parse_nested(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+///     alt((digit1, balanced(tag("("), tag(")"), parse_nested, ExAthenB)))(i)
+/// }
+/// ```
+pub fn balanced<PO, PC, PA, C, I, O1, O2, O>(
+    mut open: PO,
+    mut close: PC,
+    mut inner: PA,
+    code: C,
+) -> impl FnMut(I) -> ParserResult<C, I, O>
+where
+    PO: Parser<I, O1, ParserError<C, I>>,
+    PC: Parser<I, O2, ParserError<C, I>>,
+    PA: Parser<I, O, ParserError<C, I>>,
+    C: Code,
+    I: Clone + Offset + Slice<RangeTo<usize>>,
+{
+    move |i: I| {
+        let (rest, _) = open.parse(i.clone())?;
+        let open_span = i.slice(..i.offset(&rest));
+
+        let (rest, value) = inner.parse(rest)?;
+
+        match close.parse(rest.clone()) {
+            Ok((rest, _)) => Ok((rest, value)),
+            Err(nom::Err::Error(e)) => {
+                let mut err = ParserError::new(code, rest);
+                err.append_err(e);
+                err.expect(code, open_span);
+                Err(nom::Err::Error(err))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Runs the parser and if it fails, skips ahead to the next match of the
+/// synchronization parser and tries again. Use alt() for multiple
+/// synchronization parsers.
+///
+/// The skipped errors are recorded in the error list. Gives up after limit
+/// retries or if the synchronization parser doesn't match anywhere,
+/// in which case the last error is returned.
+///
+/// ```rust
+/// use core::cell::RefCell;
+/// use nom::bytes::complete::tag;
+/// use nom::character::complete::digit1;
+/// use nom::multi::separated_list1;
+/// use kparse::combinators::retry_with_sync;
+/// use kparse::examples::{ExParserError, ExParserResult, ExSpan};
+///
+/// fn parse_list<'s>(
+///     errors: &RefCell<Vec<ExParserError<'s>>>,
+///     i: ExSpan<'s>,
+/// ) -> ExParserResult<'s, Vec<ExSpan<'s>>> {
+///     separated_list1(tag(","), retry_with_sync(digit1, tag(","), 10, errors))(i)
+/// }
+/// ```
+pub fn retry_with_sync<'a, PA, PS, I, O, OS, E>(
+    mut parser: PA,
+    mut sync: PS,
+    limit: usize,
+    errors: &'a RefCell<Vec<E>>,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>> + 'a
+where
+    PA: Parser<I, O, E> + 'a,
+    PS: Parser<I, OS, E> + 'a,
+    I: Clone + InputIter + Slice<RangeFrom<usize>> + 'a,
+    O: 'a,
+    OS: 'a,
+{
+    move |mut i: I| {
+        let mut retries = 0;
+        loop {
+            let err = match parser.parse(i.clone()) {
+                Ok(v) => return Ok(v),
+                Err(nom::Err::Error(e)) => e,
+                Err(e) => return Err(e),
+            };
+
+            if retries == limit {
+                return Err(nom::Err::Error(err));
+            }
+
+            let mut synced = None;
+            for (off, _) in i.iter_indices() {
+                match sync.parse(i.slice(off..)) {
+                    Ok((rest, _)) => {
+                        synced = Some(rest);
+                        break;
+                    }
+                    Err(nom::Err::Error(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            match synced {
+                Some(rest) => {
+                    errors.borrow_mut().push(err);
+                    retries += 1;
+                    i = rest;
+                }
+                None => return Err(nom::Err::Error(err)),
+            }
+        }
+    }
+}
+
+/// Runs the parser and turns an error with a code in the set into a failure.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use kparse::code_set::CodeSet;
+/// use kparse::combinators::fail_if_code_in;
+/// use kparse::examples::{ExCode, ExParserResult, ExSpan, ExTagA};
+/// use kparse::KParser;
+///
+/// fn parse_a<'s>(starts: &CodeSet<ExCode>, i: ExSpan<'s>) -> ExParserResult<'s, ExSpan<'s>> {
+///     fail_if_code_in(tag("a").with_code(ExTagA), starts)(i)
+/// }
+/// ```
+pub fn fail_if_code_in<'a, PA, C, I, O, E>(
+    mut parser: PA,
+    set: &'a CodeSet<C>,
+) -> impl FnMut(I) -> Result<(I, O), nom::Err<E>> + 'a
+where
+    PA: Parser<I, O, E> + 'a,
+    C: Code,
+    E: KParseError<C, I>,
+{
+    move |i: I| match parser.parse(i) {
+        Err(nom::Err::Error(e)) if e.code().is_some_and(|v| set.contains(v)) => {
+            Err(nom::Err::Failure(e))
+        }
+        r => r,
+    }
+}
+
+/// Runs the parser and if it fails with a code of [Severity::Warning],
+/// records the error in the list and returns None without consuming input.
+/// Other errors are returned as is.
+///
+/// Lets codes like "deprecated syntax" flow through the error handling
+/// without stopping the parse.
+///
+/// ```rust
+/// use core::cell::RefCell;
+/// use nom::bytes::complete::tag;
+/// use kparse::combinators::recover_warning;
+/// use kparse::examples::{ExParserError, ExParserResult, ExSpan, ExTagA};
+/// use kparse::KParser;
+///
+/// fn parse_a<'s>(
+///     warnings: &RefCell<Vec<ExParserError<'s>>>,
+///     i: ExSpan<'s>,
+/// ) -> ExParserResult<'s, Option<ExSpan<'s>>> {
+///     recover_warning(tag("a").with_code(ExTagA), warnings)(i)
+/// }
+/// ```
+pub fn recover_warning<'a, PA, C, I, O, E>(
+    mut parser: PA,
+    warnings: &'a RefCell<Vec<E>>,
+) -> impl FnMut(I) -> Result<(I, Option<O>), nom::Err<E>> + 'a
+where
+    PA: Parser<I, O, E> + 'a,
+    C: Code,
+    I: Clone + 'a,
+    E: KParseError<C, I>,
+{
+    move |i: I| match parser.parse(i.clone()) {
+        Ok((rest, v)) => Ok((rest, Some(v))),
+        Err(nom::Err::Error(e)) if e.code().map(|v| v.severity()) == Some(Severity::Warning) => {
+            warnings.borrow_mut().push(e);
+            Ok((i, None))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Matches one of the given characters. Returns the span and the character,
+/// or fails with the code.
+#[inline]
+pub fn one_of_code<T, C, I, E>(
+    chars: T,
+    code: C,
+) -> impl Fn(I) -> Result<(I, (I, char)), nom::Err<E>>
+where
+    T: FindToken<char>,
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    char_class(move |c| chars.find_token(c), code)
+}
+
+/// Matches any character except the given ones. Returns the span and the
+/// character, or fails with the code.
+#[inline]
+pub fn none_of_code<T, C, I, E>(
+    chars: T,
+    code: C,
+) -> impl Fn(I) -> Result<(I, (I, char)), nom::Err<E>>
+where
+    T: FindToken<char>,
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    char_class(move |c| !chars.find_token(c), code)
+}
+
+#[inline]
+fn char_class<FN, C, I, E>(c_fn: FN, code: C) -> impl Fn(I) -> Result<(I, (I, char)), nom::Err<E>>
+where
+    FN: Fn(char) -> bool,
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    move |i: I| match i.iter_elements().next() {
+        Some(v) => {
+            let cc = v.as_char();
+            if c_fn(cc) {
+                // the length of the element, a byte is taken as one char.
+                Ok((i.slice(v.len()..), (i.slice(..v.len()), cc)))
+            } else {
+                Err(nom::Err::Error(E::from(code, i)))
+            }
+        }
+        None => Err(nom::Err::Error(E::from(code, i))),
+    }
+}
+
 /// Similiar to [nom::multi::separated_list0], but allows a trailing separator.
 pub fn separated_list_trailing0<PASep, PA, I, O1, O2, E>(
     mut sep: PASep,
@@ -357,3 +1363,112 @@ where
         }
     }
 }
+
+/// Memo for a left recursive rule.
+///
+/// Uses the seed-and-grow algorithm: The first call at some position fails,
+/// which leaves only the non left recursive branches to provide a seed.
+/// Then the rule is run again and again as long as it consumes more of
+/// the input, each time with the last result as result of the left
+/// recursive call.
+///
+/// ```rust
+/// use nom::branch::alt;
+/// use nom::bytes::complete::tag;
+/// use nom::character::complete::digit1;
+/// use nom::sequence::tuple;
+/// use nom::Parser;
+/// use kparse::combinators::LeftRec;
+/// use kparse::examples::{ExCode, ExNumber, ExParserResult, ExSpan};
+/// use kparse::KParser;
+///
+/// // expr := expr '-' number | number
+/// fn expr<'s>(
+///     memo: &LeftRec<ExCode, ExSpan<'s>, i64>,
+///     i: ExSpan<'s>,
+/// ) -> ExParserResult<'s, i64> {
+///     memo.parse(i, |i| {
+///         alt((
+///             tuple((|i| expr(memo, i), tag("-"), number)).map(|(a, _, b)| a - b),
+///             number,
+///         ))(i)
+///     })
+/// }
+///
+/// fn number(i: ExSpan<'_>) -> ExParserResult<'_, i64> {
+///     digit1.with_code(ExNumber).parse_from_str(ExNumber).parse(i)
+/// }
+/// ```
+pub struct LeftRec<C, I, O> {
+    code: C,
+    memo: RefCell<Vec<(usize, Option<(I, O)>)>>,
+}
+
+impl<C, I, O> LeftRec<C, I, O>
+where
+    C: Code,
+    I: Clone + InputLength,
+    O: Clone,
+{
+    /// New memo. The code is used for the error of the left recursive call.
+    pub fn new(code: C) -> Self {
+        Self {
+            code,
+            memo: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Runs the rule at this position.
+    ///
+    /// The rule must call itself via the same LeftRec.
+    pub fn parse<FN, E>(&self, input: I, mut rule: FN) -> Result<(I, O), nom::Err<E>>
+    where
+        FN: FnMut(I) -> Result<(I, O), nom::Err<E>>,
+        E: KParseError<C, I>,
+    {
+        // the position is identified by the remaining length.
+        let key = input.input_len();
+
+        if let Some((_, last)) = self.memo.borrow().iter().rev().find(|(k, _)| *k == key) {
+            return match last {
+                Some((rest, value)) => Ok((rest.clone(), value.clone())),
+                None => Err(nom::Err::Error(E::from(self.code, input))),
+            };
+        }
+
+        self.memo.borrow_mut().push((key, None));
+        let result = self.grow(key, input, &mut rule);
+        self.memo.borrow_mut().retain(|(k, _)| *k != key);
+
+        result
+    }
+
+    fn grow<FN, E>(&self, key: usize, input: I, rule: &mut FN) -> Result<(I, O), nom::Err<E>>
+    where
+        FN: FnMut(I) -> Result<(I, O), nom::Err<E>>,
+    {
+        // the first result is the seed, then grow while it gets longer.
+        let mut last = rule(input.clone())?;
+        loop {
+            self.memoize(key, &last);
+            match rule(input.clone()) {
+                Ok((rest, value)) => {
+                    if rest.input_len() >= last.0.input_len() {
+                        return Ok(last);
+                    }
+                    last = (rest, value);
+                }
+                Err(nom::Err::Error(_)) => return Ok(last),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn memoize(&self, key: usize, (rest, value): &(I, O)) {
+        for (k, v) in self.memo.borrow_mut().iter_mut() {
+            if *k == key {
+                *v = Some((rest.clone(), value.clone()));
+            }
+        }
+    }
+}
@@ -2,9 +2,9 @@ use crate::debug::{restrict, DebugWidth};
 use crate::parser_error::ParserError;
 use crate::spans::SpanFragment;
 use crate::Code;
+use core::fmt;
+use core::fmt::Debug;
 use nom::{InputIter, InputLength, InputTake};
-use std::fmt;
-use std::fmt::Debug;
 
 /// impl of debug for ParserError.
 pub(crate) fn debug_parse_error<C, I>(
@@ -23,10 +23,9 @@ where
         _ => {}
     }
 
-    #[cfg(debug_assertions)]
-    match f.width() {
-        Some(1) => write!(f, "{:#?}", err.backtrace)?,
-        _ => {}
+    #[cfg(all(debug_assertions, feature = "std"))]
+    if let (Some(1), Some(backtrace)) = (f.width(), err.backtrace()) {
+        write!(f, "{:#?}", backtrace)?;
     }
 
     Ok(())
@@ -51,6 +50,9 @@ where
     for v in err.iter_suggested() {
         write!(f, "suggest={:0?}, ", v)?;
     }
+    for v in err.iter_replacements() {
+        write!(f, "replace={:0?}, ", v)?;
+    }
     if let Some(cause) = err.cause() {
         write!(f, "cause={:0?}, ", cause)?;
     }
@@ -85,6 +87,18 @@ where
         indent(f, 1)?;
         writeln!(f, "{:1?}, ", v)?;
     }
+    if err.iter_replacements().next().is_some() {
+        writeln!(f, "replace ")?;
+    }
+    for v in err.iter_replacements() {
+        indent(f, 1)?;
+        writeln!(f, "{:1?}, ", v)?;
+    }
+    if let Some(kind) = err.nom() {
+        writeln!(f, "nom ")?;
+        indent(f, 1)?;
+        writeln!(f, "{:?}, ", kind)?;
+    }
     if let Some(cause) = err.cause() {
         writeln!(f, "cause ")?;
         indent(f, 1)?;
@@ -121,6 +135,18 @@ where
         indent(f, 1)?;
         writeln!(f, "{:2?}, ", v)?;
     }
+    if err.iter_replacements().next().is_some() {
+        writeln!(f, "replace ")?;
+    }
+    for v in err.iter_replacements() {
+        indent(f, 1)?;
+        writeln!(f, "{:2?}, ", v)?;
+    }
+    if let Some(kind) = err.nom() {
+        writeln!(f, "nom ")?;
+        indent(f, 1)?;
+        writeln!(f, "{:?}, ", kind)?;
+    }
     if let Some(cause) = err.cause() {
         writeln!(f, "cause ")?;
         indent(f, 1)?;
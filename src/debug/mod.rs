@@ -30,8 +30,9 @@ impl From<Option<usize>> for DebugWidth {
 }
 
 /// Cuts off the text at 20/40/60 characters.
-pub(crate) fn restrict_ref<T: AsBytes + Clone>(w: DebugWidth, text: &T) -> T
+pub(crate) fn restrict_ref<T>(w: DebugWidth, text: &T) -> T
 where
+    T: AsBytes + Clone,
     T: InputTake + InputLength + InputIter,
 {
     match w {
@@ -42,8 +43,9 @@ where
 }
 
 /// Cuts off the text at max_len characters.
-pub(crate) fn restrict_ref_n<T: AsBytes + Clone>(max_len: usize, text: &T) -> T
+pub(crate) fn restrict_ref_n<T>(max_len: usize, text: &T) -> T
 where
+    T: AsBytes + Clone,
     T: InputTake + InputLength + InputIter,
 {
     let mut n = 0;
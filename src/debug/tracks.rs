@@ -4,12 +4,15 @@
 
 use crate::debug::{restrict_ref, DebugWidth};
 use crate::provider::{TrackData, TrackedData};
-use crate::Code;
+use crate::{Code, CodeKind};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::ops::{RangeFrom, RangeTo};
 use nom::{AsBytes, InputIter, InputLength, InputTake, Offset, Slice};
 use nom_locate::LocatedSpan;
-use std::fmt;
-use std::fmt::Debug;
-use std::ops::{RangeFrom, RangeTo};
 
 fn indent(f: &mut impl fmt::Write, ind: usize) -> fmt::Result {
     write!(f, "{}", " ".repeat(ind * 2))?;
@@ -36,6 +39,10 @@ where
     writeln!(f, "trace")?;
 
     for t in tracks {
+        // the short trace leaves out the token level.
+        if w == DebugWidth::Short && t.func.kind() == CodeKind::Token {
+            continue;
+        }
         match t.track {
             TrackData::Enter(_, _) => {
                 ind += 1;
@@ -46,8 +53,9 @@ where
             TrackData::Info(_, _)
             | TrackData::Warn(_, _)
             | TrackData::Debug(_, _)
+            | TrackData::Value(_, _)
             | TrackData::Ok(_, _)
-            | TrackData::Err(_, _, _) => {
+            | TrackData::Err(_, _, _, _) => {
                 indent(f, ind)?;
                 debug_track(f, w, t)?;
                 writeln!(f)?;
@@ -60,12 +68,10 @@ where
     Ok(())
 }
 
-fn debug_track<T: AsBytes + Clone + Debug, C: Code>(
-    f: &mut impl fmt::Write,
-    w: DebugWidth,
-    v: &TrackedData<C, T>,
-) -> fmt::Result
+fn debug_track<T, C>(f: &mut impl fmt::Write, w: DebugWidth, v: &TrackedData<C, T>) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -78,13 +84,16 @@ where
         TrackData::Info(span, msg) => debug_info(f, w, v, span.clone(), msg),
         TrackData::Warn(span, msg) => debug_warn(f, w, v, span.clone(), msg),
         TrackData::Debug(span, msg) => debug_debug(f, w, v, span.clone(), msg.clone()),
+        TrackData::Value(span, value) => debug_value(f, w, v, span.clone(), value),
         TrackData::Ok(rest, parsed) => debug_ok(f, w, v, rest.clone(), parsed.clone()),
-        TrackData::Err(span, code, err) => debug_err(f, w, v, span.clone(), *code, err.clone()),
+        TrackData::Err(span, code, expected, err) => {
+            debug_err(f, w, v, span.clone(), *code, expected, err.clone())
+        }
         TrackData::Exit() => debug_exit(f, w, v),
     }
 }
 
-fn debug_enter<T: AsBytes + Clone + Debug, C: Code>(
+fn debug_enter<T, C>(
     f: &mut impl fmt::Write,
     w: DebugWidth,
     v: &TrackedData<C, T>,
@@ -92,6 +101,8 @@ fn debug_enter<T: AsBytes + Clone + Debug, C: Code>(
     span: LocatedSpan<T, ()>,
 ) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -120,7 +131,7 @@ where
     }
 }
 
-fn debug_info<T: AsBytes + Clone + Debug, C: Code>(
+fn debug_info<T, C>(
     f: &mut impl fmt::Write,
     w: DebugWidth,
     v: &TrackedData<C, T>,
@@ -128,6 +139,8 @@ fn debug_info<T: AsBytes + Clone + Debug, C: Code>(
     msg: &str,
 ) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -160,7 +173,7 @@ where
     }
 }
 
-fn debug_warn<T: AsBytes + Clone + Debug, C: Code>(
+fn debug_warn<T, C>(
     f: &mut impl fmt::Write,
     w: DebugWidth,
     v: &TrackedData<C, T>,
@@ -168,6 +181,8 @@ fn debug_warn<T: AsBytes + Clone + Debug, C: Code>(
     msg: &str,
 ) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -200,7 +215,7 @@ where
     }
 }
 
-fn debug_debug<T: AsBytes + Clone + Debug, C: Code>(
+fn debug_debug<T, C>(
     f: &mut impl fmt::Write,
     w: DebugWidth,
     v: &TrackedData<C, T>,
@@ -208,6 +223,8 @@ fn debug_debug<T: AsBytes + Clone + Debug, C: Code>(
     msg: String,
 ) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -221,7 +238,45 @@ where
     }
 }
 
-fn debug_ok<T: AsBytes + Clone + Debug, C: Code>(
+fn debug_value<T, C>(
+    f: &mut impl fmt::Write,
+    w: DebugWidth,
+    v: &TrackedData<C, T>,
+    span: LocatedSpan<T, ()>,
+    value: &str,
+) -> fmt::Result
+where
+    C: Code,
+    T: AsBytes + Clone + Debug,
+    T: Offset
+        + InputTake
+        + InputIter
+        + InputLength
+        + Slice<RangeFrom<usize>>
+        + Slice<RangeTo<usize>>,
+{
+    match w {
+        DebugWidth::Short | DebugWidth::Medium => write!(
+            f,
+            "{}: value {} for {}:{:?}",
+            v.func,
+            value.lines().map(str::trim).collect::<Vec<_>>().join(" "),
+            span.location_offset(),
+            restrict_ref(w, span.fragment())
+        ),
+        DebugWidth::Long => write!(
+            f,
+            "{}: value {} for {}:{:?} <<{:?}",
+            v.func,
+            value,
+            span.location_offset(),
+            restrict_ref(w, span.fragment()),
+            v.callstack
+        ),
+    }
+}
+
+fn debug_ok<T, C>(
     f: &mut impl fmt::Write,
     w: DebugWidth,
     v: &TrackedData<C, T>,
@@ -229,6 +284,8 @@ fn debug_ok<T: AsBytes + Clone + Debug, C: Code>(
     parsed: LocatedSpan<T, ()>,
 ) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -271,15 +328,18 @@ where
     Ok(())
 }
 
-fn debug_err<T: AsBytes + Clone + Debug, C: Code>(
+fn debug_err<T, C>(
     f: &mut impl fmt::Write,
     w: DebugWidth,
     v: &TrackedData<C, T>,
-    _span: LocatedSpan<T, ()>,
-    _code: C,
+    span: LocatedSpan<T, ()>,
+    code: C,
+    expected: &[(C, LocatedSpan<T, ()>)],
     err: String,
 ) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -287,18 +347,36 @@ where
         + Slice<RangeFrom<usize>>
         + Slice<RangeTo<usize>>,
 {
+    // without the error text, render code, span and the expected codes.
+    let err = if err.is_empty() {
+        let mut err = format!(
+            "{:?} for {}:{:?}",
+            code,
+            span.location_offset(),
+            restrict_ref(w, span.fragment())
+        );
+        for (code, span) in expected {
+            err.push_str(&format!(
+                " expect={:?}:{}:{:?},",
+                code,
+                span.location_offset(),
+                restrict_ref(w, span.fragment())
+            ));
+        }
+        err
+    } else {
+        err
+    };
     match w {
         DebugWidth::Short | DebugWidth::Medium => write!(f, "{}: err {} ", v.func, err),
         DebugWidth::Long => write!(f, "{}: err {} <<{:?}", v.func, err, v.callstack),
     }
 }
 
-fn debug_exit<T: AsBytes + Clone + Debug, C: Code>(
-    f: &mut impl fmt::Write,
-    w: DebugWidth,
-    v: &TrackedData<C, T>,
-) -> fmt::Result
+fn debug_exit<T, C>(f: &mut impl fmt::Write, w: DebugWidth, v: &TrackedData<C, T>) -> fmt::Result
 where
+    C: Code,
+    T: AsBytes + Clone + Debug,
     T: Offset
         + InputTake
         + InputIter
@@ -6,12 +6,11 @@
 #![allow(unreachable_pub)]
 
 use crate::prelude::*;
-use crate::token_error::TokenizerError;
-use crate::{ParserError, ParserResult, TokenizerResult};
-use std::fmt::{Display, Formatter};
+use core::fmt::{Display, Formatter};
 pub use ExCode::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ExCode {
     ExNomError,
 
@@ -28,7 +27,7 @@ pub enum ExCode {
 }
 
 impl Display for ExCode {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{}",
@@ -52,8 +51,18 @@ impl Code for ExCode {
     const NOM_ERROR: Self = Self::ExNomError;
 }
 
-define_span!(pub ExSpan = ExCode, str);
-pub type ExParserResult<'s, O> = ParserResult<ExCode, ExSpan<'s>, O>;
-pub type ExTokenizerResult<'s, O> = TokenizerResult<ExCode, ExSpan<'s>, O>;
-pub type ExParserError<'s> = ParserError<ExCode, ExSpan<'s>>;
-pub type ExTokenizerError<'s> = TokenizerError<ExCode, ExSpan<'s>>;
+impl CodeExplain for ExCode {
+    fn explain(&self) -> Option<&'static str> {
+        match self {
+            ExAorB => Some("Either an 'a' or a 'b' is required here."),
+            _ => None,
+        }
+    }
+}
+
+define_span!(pub ExSpan = ExCode, str;
+    ParserResult = ExParserResult,
+    TokenizerResult = ExTokenizerResult,
+    ParserError = ExParserError,
+    TokenizerError = ExTokenizerError,
+);
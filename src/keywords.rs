@@ -0,0 +1,306 @@
+//!
+//! Keyword table.
+//!
+//! Matches a set of keywords with a trie and returns the code of the
+//! longest matching keyword and its span.
+//!
+//! ```rust
+//! use kparse::examples::{ExAorB, ExCode, ExSpan, ExTagA, ExTagB, ExTokenizerResult};
+//! use kparse::keywords::KeywordSet;
+//!
+//! fn nom_keyword<'s>(
+//!     keywords: &KeywordSet<ExCode>,
+//!     i: ExSpan<'s>,
+//! ) -> ExTokenizerResult<'s, (ExCode, ExSpan<'s>)> {
+//!     keywords.parse(i)
+//! }
+//!
+//! let keywords = KeywordSet::new_ignore_case(ExAorB)
+//!     .keyword("a", ExTagA)
+//!     .keyword("b", ExTagB);
+//! ```
+//!
+//! With [KeywordSet::parse_suggest] a failed match gets the closest keyword
+//! as replacement for the word at the start of the input, so a typo like
+//! "Stichtak" results in "did you mean `Stichtag`?".
+//!
+
+use crate::spans::SpanFragment;
+use crate::{Code, KParseError, ParserError, ParserResult};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::ops::{RangeFrom, RangeTo};
+use nom::{AsChar, IResult, InputIter, InputLength, InputTake, Parser, Slice};
+
+#[derive(Debug)]
+struct Node<C> {
+    children: Vec<(char, usize)>,
+    code: Option<C>,
+    keyword: String,
+    order: usize,
+}
+
+impl<C> Node<C> {
+    fn new() -> Self {
+        Self {
+            children: Vec::new(),
+            code: None,
+            keyword: String::new(),
+            order: 0,
+        }
+    }
+
+    fn child(&self, c: char) -> Option<usize> {
+        self.children
+            .iter()
+            .find(|(cc, _)| *cc == c)
+            .map(|(_, idx)| *idx)
+    }
+}
+
+/// A set of keywords.
+///
+/// Acts as a parser that returns the code of the longest matching keyword
+/// and the matched span. Fails with the code of the set if no keyword matches.
+///
+/// A keyword that ends with an alphanumeric char or '_' must not be followed
+/// by one, "Stichtagg" doesn't match "Stichtag". See [KeywordSet::word_boundary].
+#[derive(Debug)]
+pub struct KeywordSet<C> {
+    code: C,
+    ignore_case: bool,
+    word_boundary: bool,
+    max_distance: usize,
+    count: usize,
+    nodes: Vec<Node<C>>,
+}
+
+impl<C> KeywordSet<C>
+where
+    C: Code,
+{
+    /// New, empty set. The code is used if no keyword matches.
+    pub fn new(code: C) -> Self {
+        Self {
+            code,
+            ignore_case: false,
+            word_boundary: true,
+            max_distance: 2,
+            count: 0,
+            nodes: vec![Node::new()],
+        }
+    }
+
+    /// New, empty set that matches case-insensitive.
+    pub fn new_ignore_case(code: C) -> Self {
+        Self {
+            code,
+            ignore_case: true,
+            word_boundary: true,
+            max_distance: 2,
+            count: 0,
+            nodes: vec![Node::new()],
+        }
+    }
+
+    /// Adds a keyword.
+    pub fn keyword(mut self, keyword: &str, code: C) -> Self {
+        self.add(keyword, code);
+        self
+    }
+
+    /// Adds all the keywords.
+    pub fn keywords<'a>(mut self, keywords: impl IntoIterator<Item = (&'a str, C)>) -> Self {
+        for (keyword, code) in keywords {
+            self.add(keyword, code);
+        }
+        self
+    }
+
+    /// Maximum edit distance for [KeywordSet::closest]. Defaults to 2,
+    /// 0 turns the suggestions off.
+    pub fn max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Requires a word boundary after a keyword. Defaults to true.
+    ///
+    /// With false the longest prefix matches, "Stichtagg" gives "Stichtag"
+    /// with the rest "g".
+    pub fn word_boundary(mut self, word_boundary: bool) -> Self {
+        self.word_boundary = word_boundary;
+        self
+    }
+
+    /// Adds a keyword.
+    pub fn add(&mut self, keyword: &str, code: C) {
+        let mut idx = 0;
+        for c in keyword.chars() {
+            let c = self.normalize(c);
+            idx = match self.nodes[idx].child(c) {
+                Some(next) => next,
+                None => {
+                    let next = self.nodes.len();
+                    self.nodes.push(Node::new());
+                    self.nodes[idx].children.push((c, next));
+                    next
+                }
+            };
+        }
+        self.nodes[idx].code = Some(code);
+        self.nodes[idx].keyword = keyword.into();
+        self.nodes[idx].order = self.count;
+        self.count += 1;
+    }
+
+    fn normalize(&self, c: char) -> char {
+        if self.ignore_case {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    }
+
+    /// Matches the longest keyword.
+    pub fn parse<I, E>(&self, input: I) -> IResult<I, (C, I), E>
+    where
+        I: Clone + InputIter + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+        <I as InputIter>::Item: AsChar + Copy,
+        E: KParseError<C, I>,
+    {
+        let mut idx = 0;
+        let mut found = None;
+        // a keyword is only found when the next char is known.
+        let mut pending = None;
+        for (off, c) in input.iter_indices() {
+            let ch = c.as_char();
+            if let Some((code, len, word)) = pending.take() {
+                if !(self.word_boundary && word && is_word_char(ch)) {
+                    found = Some((code, len));
+                }
+            }
+            match self.nodes[idx].child(self.normalize(ch)) {
+                Some(next) => idx = next,
+                None => break,
+            }
+            if let Some(code) = self.nodes[idx].code {
+                pending = Some((code, off + c.len(), is_word_char(ch)));
+            }
+        }
+        if let Some((code, len, _)) = pending {
+            found = Some((code, len));
+        }
+
+        match found {
+            Some((code, len)) => Ok((input.slice(len..), (code, input.slice(..len)))),
+            None => Err(nom::Err::Error(E::from(self.code, input))),
+        }
+    }
+
+    /// Matches the longest keyword.
+    ///
+    /// If no keyword matches, the closest keyword for the word at the start
+    /// of the input is added as replacement to the error.
+    /// A word consists of alphanumeric chars and '_'.
+    pub fn parse_suggest<I>(&self, input: I) -> ParserResult<C, I, (C, I)>
+    where
+        I: Clone + Debug + SpanFragment + InputTake + InputLength + InputIter,
+        I: Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+        <I as InputIter>::Item: AsChar + Copy,
+    {
+        match self.parse::<I, ParserError<C, I>>(input.clone()) {
+            Err(nom::Err::Error(mut e)) => {
+                let mut word = String::new();
+                let mut len = 0;
+                for (off, c) in input.iter_indices() {
+                    if !is_word_char(c.as_char()) {
+                        break;
+                    }
+                    word.push(c.as_char());
+                    len = off + c.len();
+                }
+                if let Some((code, keyword)) = self.closest(&word) {
+                    e.suggest_replacement(code, input.slice(..len), keyword);
+                }
+                Err(nom::Err::Error(e))
+            }
+            r => r,
+        }
+    }
+
+    /// Finds the keyword with the smallest edit distance to the word.
+    ///
+    /// Uses the Levenshtein distance and gives up beyond the max_distance.
+    /// For equal distances the keyword added first wins.
+    pub fn closest(&self, word: &str) -> Option<(C, &str)> {
+        if word.is_empty() || self.max_distance == 0 {
+            return None;
+        }
+
+        let word = word.chars().map(|c| self.normalize(c)).collect::<Vec<_>>();
+        let row = (0..=word.len()).collect::<Vec<_>>();
+        let mut best = None;
+        for (c, next) in &self.nodes[0].children {
+            self.closest_rec(*c, *next, &word, &row, &mut best);
+        }
+
+        best.and_then(|(_, _, idx)| {
+            let node = &self.nodes[idx];
+            node.code.map(|code| (code, node.keyword.as_str()))
+        })
+    }
+
+    // One row of the Levenshtein matrix per trie node.
+    fn closest_rec(
+        &self,
+        c: char,
+        idx: usize,
+        word: &[char],
+        prev: &[usize],
+        best: &mut Option<(usize, usize, usize)>,
+    ) {
+        let mut row = Vec::with_capacity(prev.len());
+        row.push(prev[0] + 1);
+        for i in 1..prev.len() {
+            let cost = usize::from(word[i - 1] != c);
+            row.push((row[i - 1] + 1).min(prev[i] + 1).min(prev[i - 1] + cost));
+        }
+
+        let dist = row[word.len()];
+        let order = self.nodes[idx].order;
+        if self.nodes[idx].code.is_some()
+            && dist <= self.max_distance
+            && best.is_none_or(|(v, o, _)| (dist, order) < (v, o))
+        {
+            *best = Some((dist, order, idx));
+        }
+
+        // an equal distance can still win with an earlier keyword.
+        let limit = best.map_or(self.max_distance, |(v, _, _)| v);
+        if row.iter().min().is_some_and(|v| *v <= limit) {
+            for (c, next) in &self.nodes[idx].children {
+                self.closest_rec(*c, *next, word, &row, best);
+            }
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl<C, I, E> Parser<I, (C, I), E> for &KeywordSet<C>
+where
+    C: Code,
+    I: Clone + InputIter + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, (C, I), E> {
+        KeywordSet::parse(self, input)
+    }
+}
@@ -19,11 +19,42 @@
 //!
 //! * Uses LocatedSpan for debug builds and replaces with plan `&str` or `&[u8]` for release
 //!   builds. Tracking is compiled away completely for release builds.
+//!   Or use a plain LocatedSpan in both and a thread-local tracker.
+//!
+//! * [TokenInput](token_input::TokenInput) runs the parser on the tokens of a
+//!   separate lexer. Errors and tracking map back to the source text.
+//!
+//! * [SharedStr](shared::SharedStr) is an input that owns its text, so parse
+//!   results and errors can outlive the function that read the text.
+//!
+//! * A [StateSpan] carries some user state next to the TrackProvider, see
+//!   [state].
+//!
+//! * [streaming] has streaming variants of the combinators and a
+//!   StreamBuffer that collects chunks and resumes the parser.
+//!
+//! * Without the default feature `std` the crate is `no_std` and uses `alloc`.
+//!   The test module, LocalTracker, recursion_guard(), the timeout of
+//!   StepLimit and the dump functions in report need `std`.
+//!
+//! * The feature `nom8` adds [nom8] to use the error types with nom 8 and
+//!   to convert parsers between nom 7 and nom 8.
+//!
+//! * The feature `winnow` adds [winnow] to use the error types, tracking and
+//!   the test framework with winnow parsers.
+//!
+//! * The feature `logos` adds [logos] to run the parser on the tokens of a
+//!   logos lexer.
+//!
+//! * The feature `ropey` adds [ropey] to run the parser on a ropey::Rope
+//!   without copying the text.
+//!
+//! * The feature `serde` implements Serialize for the tracking results.
 //!
 
 #![doc(html_root_url = "https://docs.rs/kparse")]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(absolute_paths_not_starting_with_crate)]
-#![allow(box_pointers)]
 #![warn(elided_lifetimes_in_paths)]
 #![warn(explicit_outlives_requirements)]
 #![warn(keyword_idents)]
@@ -33,7 +64,6 @@
 // #![warn(missing_docs)]
 #![warn(non_ascii_idents)]
 #![warn(noop_method_call)]
-#![warn(pointer_structural_match)]
 #![warn(semicolon_in_expressions_from_macros)]
 #![allow(single_use_lifetimes)]
 #![warn(trivial_casts)]
@@ -52,60 +82,212 @@
 #![allow(clippy::uninlined_format_args)]
 #![allow(clippy::type_complexity)]
 
+extern crate alloc;
+
+pub mod code_set;
 pub mod combinators;
 mod debug;
 pub mod examples;
+pub mod keywords;
+#[cfg(feature = "logos")]
+pub mod logos;
+#[cfg(feature = "nom8")]
+pub mod nom8;
 pub mod parser_error;
 mod parser_ext;
+pub mod pratt;
 pub mod provider;
+pub mod report;
+#[cfg(feature = "ropey")]
+pub mod ropey;
+pub mod shared;
 pub mod source;
 pub mod spans;
+pub mod state;
+pub mod streaming;
+#[cfg(feature = "std")]
 pub mod test;
 pub mod token_error;
+pub mod token_input;
+#[cfg(feature = "winnow")]
+pub mod winnow;
 
 pub use crate::parser_error::ParserError;
 pub use crate::token_error::TokenizerError;
-use std::borrow::Borrow;
+use core::borrow::Borrow;
 
 use crate::parser_ext::{
-    AllConsuming, Complete, Consumed, Cut, DelimitedBy, FromStrParser, IntoErr, MapRes,
-    OptPrecedes, Optional, OrElse, PNot, Peek, Precedes, Recognize, Terminated, Value, Verify,
-    WithCode, WithContext,
+    AllConsuming, AndThenSpan, Complete, Consumed, Cut, CutOn, DelimitedBy, FlatMap, FollowedBy,
+    FromStrParser, FromStrRadixParser, FromStrWith, IntoErr, MapParser, MapRes, NotFollowedBy,
+    OptOr, OptPrecedes, Optional, OrElse, PNot, Peek, Precedes, Recognize, Terminated, Value,
+    ValueWith, Verify, VerifyMap, WithCode, WithContext,
 };
+#[cfg(feature = "std")]
+use crate::provider::LocalTracker;
+#[cfg(all(debug_assertions, feature = "std"))]
+use crate::provider::{local_span, track_local};
 use crate::provider::{StdTracker, TrackData, TrackProvider};
+use crate::report::Severity;
 use crate::source::{SourceBytes, SourceStr};
-use nom::{AsBytes, InputIter, InputLength, InputTake, Offset, Parser, Slice};
+use crate::state::StateExtra;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display};
+use core::ops::RangeTo;
+use core::str::FromStr;
+use nom::character::complete::space0;
+use nom::error::ParseError;
+use nom::{
+    AsBytes, AsChar, IResult, InputIter, InputLength, InputTake, InputTakeAtPosition, Offset,
+    Parser, Slice,
+};
 use nom_locate::LocatedSpan;
-use std::fmt::{Debug, Display};
-use std::ops::RangeTo;
-use std::str::FromStr;
 
 /// Prelude for all traits.
 pub mod prelude {
     pub use crate::parser_error::AppendParserError;
     pub use crate::provider::TrackProvider;
     pub use crate::source::Source;
-    pub use crate::spans::{SpanFragment, SpanUnion};
+    pub use crate::spans::{SpanFragment, SpanGeometry, SpanRange, SpanResize, SpanUnion};
+    pub use crate::state::SpanState;
+    #[cfg(feature = "std")]
     pub use crate::test::Report;
     pub use crate::{
-        define_span, Code, ErrInto, ErrOrNomErr, KParseError, KParser, ParseSpan, Track,
-        TrackResult, TrackedSpan,
+        define_span, Code, CodeDisplay, CodeExplain, CodeKind, ErrInto, ErrOrNomErr, KParseError,
+        KParser, ParseSpan, StateSpan, Track, TrackResult, TrackedSpan,
     };
 }
 
 /// Standard input type. This is a LocatedSpan with a TrackProvider.
-pub type DynTrackProvider<'s, C, T> = &'s (dyn TrackProvider<C, T>);
+pub type DynTrackProvider<'s, C, T> = &'s dyn TrackProvider<C, T>;
 pub type ParseSpan<'s, C, T> = LocatedSpan<T, DynTrackProvider<'s, C, T>>;
+/// Input type with user state. This is a LocatedSpan with a TrackProvider and
+/// a reference to the state, see [state].
+pub type StateSpan<'s, C, T, S> = LocatedSpan<T, StateExtra<'s, C, T, S>>;
 
 /// Defines a type alias for the span type.
 /// Switches between ParseSpan<> in debug mode and plain type in release mode.
+///
+/// An optional `cfg(...)` replaces `debug_assertions` as the switch.
+/// Track::new_span() still follows debug_assertions, with another switch
+/// create the span with [TrackProvider::track_span] or use the plain text.
+///
+/// After a `;` follow aliases for the error and result types. Each one
+/// names the kparse type and the alias, any of ParserError, ParserResult,
+/// TokenizerError and TokenizerResult.
+///
+/// ```rust
+/// use kparse::define_span;
+/// use kparse::examples::ExCode;
+///
+/// define_span!(pub APSpan = ExCode, str, cfg(feature = "track");
+///     ParserError = APParserError,
+///     ParserResult = APParserResult,
+/// );
+///
+/// fn parse_a(i: APSpan<'_>) -> APParserResult<'_, APSpan<'_>> {
+///     nom::bytes::complete::tag("a")(i)
+/// }
+///
+/// let err: APParserError<'_> = match parse_a("b") {
+///     Err(nom::Err::Error(e)) => e,
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(err.code, ExCode::ExNomError);
+/// ```
 #[macro_export]
 macro_rules! define_span {
-    ($v:vis $name:ident = $code:ty, $typ:ty) => {
-        #[cfg(debug_assertions)]
-        $v type $name<'a> = ParseSpan<'a, $code, &'a $typ>;
-        #[cfg(not(debug_assertions))]
+    (@span [$($pred:tt)*] $v:vis $name:ident = $code:ty, $typ:ty
+        $(; $($kind:ident = $alias:ident),+ $(,)?)?
+    ) => {
+        #[cfg($($pred)*)]
+        $v type $name<'a> = $crate::ParseSpan<'a, $code, &'a $typ>;
+        #[cfg(not($($pred)*))]
         $v type $name<'a> = &'a $typ;
+        $($( $crate::define_span!(@alias $kind $v $alias = $code, $name); )+)?
+    };
+    (@alias ParserError $v:vis $alias:ident = $code:ty, $name:ident) => {
+        $v type $alias<'a> = $crate::ParserError<$code, $name<'a>>;
+    };
+    (@alias ParserResult $v:vis $alias:ident = $code:ty, $name:ident) => {
+        $v type $alias<'a, O> = $crate::ParserResult<$code, $name<'a>, O>;
+    };
+    (@alias TokenizerError $v:vis $alias:ident = $code:ty, $name:ident) => {
+        $v type $alias<'a> = $crate::TokenizerError<$code, $name<'a>>;
+    };
+    (@alias TokenizerResult $v:vis $alias:ident = $code:ty, $name:ident) => {
+        $v type $alias<'a, O> = $crate::TokenizerResult<$code, $name<'a>, O>;
+    };
+    ($v:vis $name:ident = $code:ty, $typ:ty, cfg($($pred:tt)*)
+        $(; $($kind:ident = $alias:ident),+ $(,)?)?
+    ) => {
+        $crate::define_span!(@span [$($pred)*] $v $name = $code, $typ
+            $(; $($kind = $alias),+)?);
+    };
+    ($v:vis $name:ident = $code:ty, $typ:ty
+        $(; $($kind:ident = $alias:ident),+ $(,)?)?
+    ) => {
+        $crate::define_span!(@span [debug_assertions] $v $name = $code, $typ
+            $(; $($kind = $alias),+)?);
+    };
+}
+
+/// Defines an error code enum with its Display and the Code impl.
+///
+/// The nom error code is added as the first variant, its Display is its name.
+///
+/// ```rust
+/// use kparse::codes;
+///
+/// codes! {
+///     #[derive(Hash)]
+///     pub APCode {
+///         APCName => "Name",
+///         APCMenge => "Menge",
+///         nom = APCNomError
+///     }
+/// }
+///
+/// assert_eq!(APCode::APCMenge.to_string(), "Menge");
+/// ```
+#[macro_export]
+macro_rules! codes {
+    (@munch [$($attr:tt)*] [$v:vis] $name:ident
+        [$( [$($vattr:tt)*] $variant:ident => $text:expr; )*]
+        nom = $nom:ident $(,)?
+    ) => {
+        $($attr)*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[allow(clippy::enum_variant_names)]
+        $v enum $name {
+            $nom,
+            $( $($vattr)* $variant, )*
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                let text = match self {
+                    $name::$nom => stringify!($nom),
+                    $( $name::$variant => $text, )*
+                };
+                write!(f, "{}", text)
+            }
+        }
+
+        impl $crate::Code for $name {
+            const NOM_ERROR: Self = $name::$nom;
+        }
+    };
+    (@munch [$($attr:tt)*] [$v:vis] $name:ident [$($acc:tt)*]
+        $(#[$vattr:meta])* $variant:ident => $text:expr, $($rest:tt)*
+    ) => {
+        $crate::codes!(@munch [$($attr)*] [$v] $name
+            [$($acc)* [$(#[$vattr])*] $variant => $text;] $($rest)*);
+    };
+    ($(#[$attr:meta])* $v:vis $name:ident { $($body:tt)* }) => {
+        $crate::codes!(@munch [$(#[$attr])*] [$v] $name [] $($body)*);
     };
 }
 
@@ -121,6 +303,83 @@ pub type TokenizerResult<C, I, O> = Result<(I, O), nom::Err<TokenizerError<C, I>
 pub trait Code: Copy + Display + Debug + Eq {
     /// Default error code for nom-errors.
     const NOM_ERROR: Self;
+
+    /// Category of the code. Defaults to [CodeKind::Syntax].
+    ///
+    /// The renderers in [report] and the short trace hide token level codes
+    /// unless they are asked to be verbose.
+    fn kind(&self) -> CodeKind {
+        CodeKind::Syntax
+    }
+
+    /// Stable numeric id of the code. Defaults to None.
+    ///
+    /// Tools can key on the id while the Display text is free to change.
+    /// [to_json_diagnostics](report::to_json_diagnostics) writes it if there
+    /// is one. [CodeSet](code_set::CodeSet) uses ids below 128 for its bitset.
+    fn id(&self) -> Option<u32> {
+        None
+    }
+
+    /// Default severity of the code. Defaults to [Severity::Error].
+    ///
+    /// Tracking an error with severity [Severity::Warning] records a warning
+    /// too, and the renderers in [report] show it as a warning. Use
+    /// [recover_warning](combinators::recover_warning) to continue parsing.
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+}
+
+/// Category of an error code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeKind {
+    /// Single token, e.g. a tag or a number.
+    Token,
+    /// Syntax rule.
+    Syntax,
+    /// Checks beyond the syntax.
+    Semantic,
+    /// Errors of the parser itself.
+    Internal,
+}
+
+/// Renders error codes for the user.
+///
+/// The renderers in [report] use this instead of the Display
+/// of the code. This way the codes can be shown in another language without
+/// a second enum. Implemented for any `Fn(C) -> String`.
+pub trait CodeDisplay<C> {
+    /// Text for the code.
+    fn display(&self, code: C) -> String;
+}
+
+/// Uses the Display of the code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdCodeDisplay;
+
+impl<C: Code> CodeDisplay<C> for StdCodeDisplay {
+    fn display(&self, code: C) -> String {
+        code.to_string()
+    }
+}
+
+impl<C, F> CodeDisplay<C> for F
+where
+    F: Fn(C) -> String,
+{
+    fn display(&self, code: C) -> String {
+        self(code)
+    }
+}
+
+/// Long form help text for error codes.
+///
+/// Used by [RustcStyle](crate::report::RustcStyle) to point to the explanation
+/// and by [explain](crate::report::explain) to render it.
+pub trait CodeExplain: Code {
+    /// The explanation of this code, if there is one.
+    fn explain(&self) -> Option<&'static str>;
 }
 
 /// This trait catches the essentials for an error type within this library.
@@ -147,6 +406,12 @@ pub trait KParseError<C, I> {
 
     /// Returns all the parts if self is `Result::Err` and it's not `nom::Err::Incomplete`.
     fn parts(&self) -> Option<(C, I, &Self::WrappedError)>;
+
+    /// Returns the expected codes with their spans, if the error keeps any.
+    /// The trace records them. Defaults to none.
+    fn expected(&self) -> Vec<(C, I)> {
+        Vec::new()
+    }
 }
 
 /// Analog function for err_into() working on a parser, but working on the Result instead.
@@ -184,6 +449,33 @@ pub trait ErrOrNomErr {
     fn wrap(self) -> nom::Err<Self::WrappedError>;
 }
 
+/// Analog to FromStr for conversions with a radix.
+///
+/// Implemented for all the primitive integer types.
+pub trait FromStrRadix: Sized {
+    /// Error type.
+    type Err;
+
+    /// Converts from a string in the given radix.
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, Self::Err>;
+}
+
+macro_rules! from_str_radix {
+    ($($t:ty)*) => {
+        $(
+        impl FromStrRadix for $t {
+            type Err = core::num::ParseIntError;
+
+            fn from_str_radix(src: &str, radix: u32) -> Result<Self, Self::Err> {
+                <$t>::from_str_radix(src, radix)
+            }
+        }
+        )*
+    };
+}
+
+from_str_radix!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
 /// Adds some common parser combinators as postfix operators to parser.
 pub trait KParser<I, O, E>
 where
@@ -213,6 +505,32 @@ where
     where
         TR: Fn(O) -> Result<O2, nom::Err<E>>;
 
+    /// Runs the inner parser on the output of this parser.
+    ///
+    /// The inner parser sees only the part recognized by the first one, errors
+    /// are reported with the inner parsers code and span.
+    fn map_parser<PB, O2>(self, inner: PB) -> MapParser<Self, PB, O>
+    where
+        PB: Parser<O, O2, E>;
+
+    /// Creates a second parser from the output of this parser and
+    /// runs it on the rest of the input.
+    ///
+    /// Errors of the second parser keep their codes.
+    fn flat_map_code<FN, PB, O2>(self, map: FN) -> FlatMap<Self, FN, O>
+    where
+        FN: Fn(O) -> PB,
+        PB: Parser<I, O2, E>;
+
+    /// Runs the second parser on the span recognized by this parser.
+    ///
+    /// The second parser only sees the recognized span, but as this is a slice
+    /// of the original input any error is reported with the original offsets.
+    fn and_then_span<PB, O2>(self, second: PB) -> AndThenSpan<Self, PB, O>
+    where
+        PB: Parser<I, O2, E>,
+        I: Clone + Slice<RangeTo<usize>> + Offset;
+
     /// Convert the output with the FromStr trait.
     fn parse_from_str<C, O2>(self, code: C) -> FromStrParser<Self, C, O, O2>
     where
@@ -221,11 +539,39 @@ where
         O2: FromStr,
         E: KParseError<C, I>;
 
+    /// Convert the output with the FromStrRadix trait.
+    /// Useful for hex or octal numbers.
+    fn parse_from_str_radix<C, O2>(self, code: C, radix: u32) -> FromStrRadixParser<Self, C, O, O2>
+    where
+        C: Code,
+        O: InputIter<Item = char>,
+        O2: FromStrRadix,
+        E: KParseError<C, O>;
+
+    /// Convert the output with the given function.
+    /// Any error of the conversion is replaced with the given code.
+    fn parse_from_str_with<C, FN, O2, E2>(
+        self,
+        code: C,
+        from_str: FN,
+    ) -> FromStrWith<Self, C, O, FN>
+    where
+        C: Code,
+        O: InputIter<Item = char>,
+        FN: Fn(&str) -> Result<O2, E2>,
+        E: KParseError<C, O>;
+
     /// Replace the output with the value.
     fn value<O2>(self, value: O2) -> Value<Self, O, O2>
     where
         O2: Clone;
 
+    /// Replace the output with the result of the function.
+    /// The value is only constructed if the parser succeeds.
+    fn value_with<FN, O2>(self, value: FN) -> ValueWith<Self, O, FN>
+    where
+        FN: Fn() -> O2;
+
     /// Fails if not everything has been processed.
     fn all_consuming<C>(self, code: C) -> AllConsuming<Self, C>
     where
@@ -243,9 +589,22 @@ where
     /// Convert from nom::Err::Error to nom::Err::Failure
     fn cut(self) -> Cut<Self>;
 
+    /// Convert from nom::Err::Error to nom::Err::Failure, but only if
+    /// the error has the given code.
+    fn cut_on<C>(self, code: C) -> CutOn<Self, C>
+    where
+        C: Code,
+        E: KParseError<C, I>;
+
     /// Optional parser.
     fn opt(self) -> Optional<Self>;
 
+    /// Optional parser, returns the default value if the parser fails.
+    fn opt_or(self, default: O) -> OptOr<Self, O>
+    where
+        I: Clone,
+        O: Clone;
+
     /// Run the parser and return the parsed input.
     fn recognize(self) -> Recognize<Self, O>
     where
@@ -280,6 +639,18 @@ where
     where
         PA: Parser<I, O2, E>;
 
+    /// Skips spaces and tabs before and after the parser.
+    fn padded(self) -> DelimitedBy<Self, fn(I) -> IResult<I, I, E>, I>
+    where
+        I: InputTakeAtPosition,
+        <I as InputTakeAtPosition>::Item: AsChar + Clone,
+        E: ParseError<I>;
+
+    /// Skips whitespace before and after the parser. Same as delimited_by().
+    fn padded_by<PW, O2>(self, ws: PW) -> DelimitedBy<Self, PW, O2>
+    where
+        PW: Parser<I, O2, E>;
+
     /// Runs the parser but doesn't change the input.
     fn peek(self) -> Peek<Self>
     where
@@ -292,6 +663,24 @@ where
         E: KParseError<C, I>,
         I: Clone;
 
+    /// Checks that the successor matches after this parser, but doesn't consume it.
+    /// Fails with the given code otherwise.
+    fn followed_by<PF, C, O2>(self, successor: PF, code: C) -> FollowedBy<Self, PF, C, O2>
+    where
+        PF: Parser<I, O2, E>,
+        C: Code,
+        E: KParseError<C, I>,
+        I: Clone;
+
+    /// Checks that the other parser doesn't match after this parser.
+    /// Fails with the given code otherwise.
+    fn not_followed_by<PF, C, O2>(self, other: PF, code: C) -> NotFollowedBy<Self, PF, C, O2>
+    where
+        PF: Parser<I, O2, E>,
+        C: Code,
+        E: KParseError<C, I>,
+        I: Clone;
+
     /// Or. Returns a `(Option<A>, Option<B>)`
     fn or_else<PE, OE>(self, other: PE) -> OrElse<Self, PE, OE>
     where
@@ -305,6 +694,14 @@ where
         O: Borrow<O2>,
         O2: ?Sized,
         E: KParseError<C, I>;
+
+    /// Runs a verify function on the parser result that converts the value too.
+    /// Fails with the given code if the function returns None.
+    fn verify_map<V, C, O2>(self, verify: V, code: C) -> VerifyMap<Self, V, C, O>
+    where
+        C: Code,
+        V: Fn(O) -> Option<O2>,
+        E: KParseError<C, I>;
 }
 
 impl<T, I, O, E> KParser<I, O, E> for T
@@ -358,6 +755,44 @@ where
         }
     }
 
+    #[inline]
+    fn map_parser<PB, O2>(self, inner: PB) -> MapParser<Self, PB, O>
+    where
+        PB: Parser<O, O2, E>,
+    {
+        MapParser {
+            parser: self,
+            inner,
+            _phantom: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn flat_map_code<FN, PB, O2>(self, map: FN) -> FlatMap<Self, FN, O>
+    where
+        FN: Fn(O) -> PB,
+        PB: Parser<I, O2, E>,
+    {
+        FlatMap {
+            parser: self,
+            map,
+            _phantom: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn and_then_span<PB, O2>(self, second: PB) -> AndThenSpan<Self, PB, O>
+    where
+        PB: Parser<I, O2, E>,
+        I: Clone + Slice<RangeTo<usize>> + Offset,
+    {
+        AndThenSpan {
+            parser: self,
+            second,
+            _phantom: Default::default(),
+        }
+    }
+
     #[inline]
     fn parse_from_str<C, O2>(self, code: C) -> FromStrParser<Self, C, O, O2>
     where
@@ -373,6 +808,42 @@ where
         }
     }
 
+    #[inline]
+    fn parse_from_str_radix<C, O2>(self, code: C, radix: u32) -> FromStrRadixParser<Self, C, O, O2>
+    where
+        C: Code,
+        O: InputIter<Item = char>,
+        O2: FromStrRadix,
+        E: KParseError<C, O>,
+    {
+        FromStrRadixParser {
+            parser: self,
+            code,
+            radix,
+            _phantom: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn parse_from_str_with<C, FN, O2, E2>(
+        self,
+        code: C,
+        from_str: FN,
+    ) -> FromStrWith<Self, C, O, FN>
+    where
+        C: Code,
+        O: InputIter<Item = char>,
+        FN: Fn(&str) -> Result<O2, E2>,
+        E: KParseError<C, O>,
+    {
+        FromStrWith {
+            parser: self,
+            code,
+            from_str,
+            _phantom: Default::default(),
+        }
+    }
+
     #[inline]
     fn value<O2>(self, value: O2) -> Value<Self, O, O2>
     where
@@ -385,6 +856,18 @@ where
         }
     }
 
+    #[inline]
+    fn value_with<FN, O2>(self, value: FN) -> ValueWith<Self, O, FN>
+    where
+        FN: Fn() -> O2,
+    {
+        ValueWith {
+            parser: self,
+            value,
+            _phantom: Default::default(),
+        }
+    }
+
     #[inline]
     fn all_consuming<C>(self, code: C) -> AllConsuming<Self, C>
     where
@@ -410,11 +893,32 @@ where
         Cut { parser: self }
     }
 
+    #[inline]
+    fn cut_on<C>(self, code: C) -> CutOn<Self, C>
+    where
+        C: Code,
+        E: KParseError<C, I>,
+    {
+        CutOn { parser: self, code }
+    }
+
     #[inline]
     fn opt(self) -> Optional<Self> {
         Optional { parser: self }
     }
 
+    #[inline]
+    fn opt_or(self, default: O) -> OptOr<Self, O>
+    where
+        I: Clone,
+        O: Clone,
+    {
+        OptOr {
+            parser: self,
+            default,
+        }
+    }
+
     #[inline]
     fn recognize(self) -> Recognize<Self, O>
     where
@@ -483,6 +987,32 @@ where
         }
     }
 
+    #[inline]
+    fn padded(self) -> DelimitedBy<Self, fn(I) -> IResult<I, I, E>, I>
+    where
+        I: InputTakeAtPosition,
+        <I as InputTakeAtPosition>::Item: AsChar + Clone,
+        E: ParseError<I>,
+    {
+        DelimitedBy {
+            parser: self,
+            delimiter: space0,
+            _phantom: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn padded_by<PW, O2>(self, ws: PW) -> DelimitedBy<Self, PW, O2>
+    where
+        PW: Parser<I, O2, E>,
+    {
+        DelimitedBy {
+            parser: self,
+            delimiter: ws,
+            _phantom: Default::default(),
+        }
+    }
+
     #[inline]
     fn peek(self) -> Peek<Self>
     where
@@ -500,6 +1030,38 @@ where
         }
     }
 
+    #[inline]
+    fn followed_by<PF, C, O2>(self, successor: PF, code: C) -> FollowedBy<Self, PF, C, O2>
+    where
+        PF: Parser<I, O2, E>,
+        C: Code,
+        E: KParseError<C, I>,
+        I: Clone,
+    {
+        FollowedBy {
+            parser: self,
+            successor,
+            code,
+            _phantom: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn not_followed_by<PF, C, O2>(self, other: PF, code: C) -> NotFollowedBy<Self, PF, C, O2>
+    where
+        PF: Parser<I, O2, E>,
+        C: Code,
+        E: KParseError<C, I>,
+        I: Clone,
+    {
+        NotFollowedBy {
+            parser: self,
+            other,
+            code,
+            _phantom: Default::default(),
+        }
+    }
+
     #[inline]
     fn or_else<PE, OE>(self, other: PE) -> OrElse<Self, PE, OE>
     where
@@ -528,6 +1090,21 @@ where
             _phantom: Default::default(),
         }
     }
+
+    #[inline]
+    fn verify_map<V, C, O2>(self, verify: V, code: C) -> VerifyMap<Self, V, C, O>
+    where
+        C: Code,
+        V: Fn(O) -> Option<O2>,
+        E: KParseError<C, I>,
+    {
+        VerifyMap {
+            parser: self,
+            verify,
+            code,
+            _phantom: Default::default(),
+        }
+    }
 }
 
 /// Central struct for tracking.
@@ -540,6 +1117,9 @@ where
 /// - Call the actual tracking functions:
 ///   - Track.enter(), Track.ok(), Track.err(), ...
 ///
+/// Alternatively ```Track::new_local_tracker()``` installs a tracker for the current
+/// thread. Then the span is a plain ```LocatedSpan<&str>``` in debug and release mode.
+///
 pub struct Track;
 
 impl Track {
@@ -553,6 +1133,16 @@ impl Track {
         StdTracker::new()
     }
 
+    /// Provider for tracking data that is looked up from a thread-local.
+    /// The spans are plain `LocatedSpan<T, ()>`, see [LocalTracker].
+    #[cfg(feature = "std")]
+    pub fn new_local_tracker<C>() -> LocalTracker<C>
+    where
+        C: Code + 'static,
+    {
+        LocalTracker::new()
+    }
+
     /// Create a tracking span for the given text and TrackProvider.
     #[cfg(debug_assertions)]
     pub fn new_span<'s, C, I>(
@@ -579,6 +1169,23 @@ impl Track {
         text
     }
 
+    /// Create a tracking span with user state for the given text and
+    /// TrackProvider. Other than new_span() this is a LocatedSpan in release
+    /// mode too, the TrackProvider is not used then.
+    pub fn new_state_span<'s, C, I, S>(
+        provider: &'s impl TrackProvider<C, I>,
+        state: &'s S,
+        text: I,
+    ) -> StateSpan<'s, C, I, S>
+    where
+        C: Code,
+        I: Clone + Debug + AsBytes,
+        I: InputTake + InputLength + InputIter,
+        I: 's,
+    {
+        LocatedSpan::new_extra(text, StateExtra::new(provider, state))
+    }
+
     /// Create a source text map for the given text.
     pub fn source_str(text: &str) -> SourceStr<'_> {
         SourceStr::new(text)
@@ -620,7 +1227,7 @@ impl Track {
         match err.parts() {
             None => Err(err.wrap()),
             Some((code, span, e)) => {
-                span.track_err(code, e);
+                span.track_err_expected(code, e, || err.expected());
                 span.track_exit();
                 Err(err.wrap())
             }
@@ -654,7 +1261,7 @@ impl Track {
         match err.parts() {
             None => {}
             Some((code, span, e)) => {
-                span.track_err(code, e);
+                span.track_err_expected(code, e, || err.expected());
             }
         }
     }
@@ -679,6 +1286,18 @@ impl Track {
         span.track_debug(debug);
     }
 
+    /// Track a parsed value, e.g. the AST node that was just built.
+    /// It shows up in the trace at the current position as its Debug output.
+    #[inline(always)]
+    pub fn value<C, I, V>(&self, span: I, value: &V)
+    where
+        C: Code,
+        I: TrackedSpan<C>,
+        V: Debug,
+    {
+        span.track_value(value);
+    }
+
     /// Track some other info.
     #[inline(always)]
     pub fn info<C, I>(&self, span: I, info: &'static str)
@@ -741,7 +1360,7 @@ where
             Err(e) => match e.parts() {
                 None => Err(e),
                 Some((code, span, err)) => {
-                    span.track_err(code, err);
+                    span.track_err_expected(code, err, || e.expected());
                     span.track_exit();
                     Err(e)
                 }
@@ -759,7 +1378,7 @@ where
                 match e.parts() {
                     None => Err(e),
                     Some((code, span, err)) => {
-                        span.track_err(code, err);
+                        span.track_err_expected(code, err, || e.expected());
                         span.track_exit();
                         Err(e)
                     }
@@ -782,6 +1401,11 @@ where
     /// Track some debug info.
     fn track_debug(&self, debug: String);
 
+    /// Track a parsed value. Only the pretty Debug output is kept.
+    ///
+    /// Does nothing by default.
+    fn track_value<V: Debug>(&self, _value: &V) {}
+
     /// Track some other info.
     fn track_info(&self, info: &'static str);
 
@@ -794,6 +1418,20 @@ where
     /// Calls exit_err() on the ParseContext. You might want to use err() instead.
     fn track_err<E: Debug>(&self, code: C, err: &E);
 
+    /// Calls exit_err() with the expected codes of the error.
+    ///
+    /// The trace keeps code, span and the expected codes, the error itself
+    /// is only formatted if the tracker keeps the text. The expected codes are
+    /// only collected if there is a tracker. By default they are ignored.
+    fn track_err_expected<E: Debug>(
+        &self,
+        code: C,
+        err: &E,
+        _expected: impl FnOnce() -> Vec<(C, Self)>,
+    ) {
+        self.track_err(code, err);
+    }
+
     /// Calls exit() on the ParseContext. You might want to use err() or ok() instead.
     fn track_exit(&self);
 }
@@ -813,6 +1451,12 @@ where
         self.extra.track(TrackData::Debug(clear_span(self), debug));
     }
 
+    #[inline(always)]
+    fn track_value<V: Debug>(&self, value: &V) {
+        self.extra
+            .track(TrackData::Value(clear_span(self), format!("{:#?}", value)));
+    }
+
     #[inline(always)]
     fn track_info(&self, info: &'static str) {
         self.extra.track(TrackData::Info(clear_span(self), info));
@@ -831,8 +1475,33 @@ where
 
     #[inline(always)]
     fn track_err<E: Debug>(&self, code: C, err: &E) {
+        self.track_err_expected(code, err, Vec::new);
+    }
+
+    #[inline(always)]
+    fn track_err_expected<E: Debug>(
+        &self,
+        code: C,
+        err: &E,
+        expected: impl FnOnce() -> Vec<(C, Self)>,
+    ) {
+        if code.severity() == Severity::Warning {
+            self.extra.track(TrackData::Warn(
+                clear_span(self),
+                "error with severity warning",
+            ));
+        }
+        let expected = expected()
+            .iter()
+            .map(|(code, span)| (*code, clear_span(span)))
+            .collect();
+        let text = if self.extra.err_text() {
+            format!("{:?}", err)
+        } else {
+            String::new()
+        };
         self.extra
-            .track(TrackData::Err(clear_span(self), code, format!("{:?}", err)));
+            .track(TrackData::Err(clear_span(self), code, expected, text));
     }
 
     #[inline(always)]
@@ -841,9 +1510,8 @@ where
     }
 }
 
-fn clear_span<C, T>(span: &LocatedSpan<T, DynTrackProvider<'_, C, T>>) -> LocatedSpan<T, ()>
+pub(crate) fn clear_span<T, X>(span: &LocatedSpan<T, X>) -> LocatedSpan<T, ()>
 where
-    C: Code,
     T: AsBytes + Clone,
 {
     unsafe {
@@ -856,11 +1524,85 @@ where
     }
 }
 
+/// Tracks with the thread-local [LocalTracker].
+#[cfg(all(debug_assertions, feature = "std"))]
 impl<C, T> TrackedSpan<C> for LocatedSpan<T, ()>
 where
     T: Clone + Debug,
     T: InputTake + InputLength + AsBytes,
-    C: Code,
+    C: Code + 'static,
+{
+    #[inline(always)]
+    fn track_enter(&self, func: C) {
+        track_local::<C>(|_| TrackData::Enter(func, local_span(self)));
+    }
+
+    #[inline(always)]
+    fn track_debug(&self, debug: String) {
+        track_local::<C>(|_| TrackData::Debug(local_span(self), debug));
+    }
+
+    #[inline(always)]
+    fn track_value<V: Debug>(&self, value: &V) {
+        track_local::<C>(|_| TrackData::Value(local_span(self), format!("{:#?}", value)));
+    }
+
+    #[inline(always)]
+    fn track_info(&self, info: &'static str) {
+        track_local::<C>(|_| TrackData::Info(local_span(self), info));
+    }
+
+    #[inline(always)]
+    fn track_warn(&self, warn: &'static str) {
+        track_local::<C>(|_| TrackData::Warn(local_span(self), warn));
+    }
+
+    #[inline(always)]
+    fn track_ok(&self, parsed: LocatedSpan<T, ()>) {
+        track_local::<C>(|_| TrackData::Ok(local_span(self), local_span(&parsed)));
+    }
+
+    #[inline(always)]
+    fn track_err<E: Debug>(&self, code: C, err: &E) {
+        self.track_err_expected(code, err, Vec::new);
+    }
+
+    #[inline(always)]
+    fn track_err_expected<E: Debug>(
+        &self,
+        code: C,
+        err: &E,
+        expected: impl FnOnce() -> Vec<(C, Self)>,
+    ) {
+        if code.severity() == Severity::Warning {
+            track_local::<C>(|_| TrackData::Warn(local_span(self), "error with severity warning"));
+        }
+        track_local::<C>(|err_text| {
+            let expected = expected()
+                .iter()
+                .map(|(code, span)| (*code, local_span(span)))
+                .collect();
+            let text = if err_text {
+                format!("{:?}", err)
+            } else {
+                String::new()
+            };
+            TrackData::Err(local_span(self), code, expected, text)
+        });
+    }
+
+    #[inline(always)]
+    fn track_exit(&self) {
+        track_local::<C>(|_| TrackData::Exit());
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "std")))]
+impl<C, T> TrackedSpan<C> for LocatedSpan<T, ()>
+where
+    T: Clone + Debug,
+    T: InputTake + InputLength + AsBytes,
+    C: Code + 'static,
 {
     #[inline(always)]
     fn track_enter(&self, _func: C) {}
@@ -884,7 +1626,7 @@ where
     fn track_exit(&self) {}
 }
 
-impl<'s, C> TrackedSpan<C> for &'s str
+impl<C> TrackedSpan<C> for &str
 where
     C: Code,
 {
@@ -910,7 +1652,7 @@ where
     fn track_exit(&self) {}
 }
 
-impl<'s, C> TrackedSpan<C> for &'s [u8]
+impl<C> TrackedSpan<C> for &[u8]
 where
     C: Code,
 {
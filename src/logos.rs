@@ -0,0 +1,146 @@
+//!
+//! Glue for a logos lexer. Enabled with the feature `logos`.
+//!
+//! [LogosTokens] runs a logos::Lexer and keeps the tokens with their spans.
+//! The parser runs on its [TokenInput], so errors and tracking refer to
+//! the source text.
+//!
+//! * Implement [TokenCode] to map each token kind to a Code.
+//! * [kind] matches a token of one kind and fails with the code of the kind.
+//! * A token the lexer can't recognize fails LogosTokens::new() with the
+//!   given code.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExNomError, ExNumber, ExTagA};
+//! use kparse::logos::{kind, LogosTokens, TokenCode};
+//! use kparse::token_input::TokenInput;
+//! use kparse::ParserError;
+//! use logos::Logos;
+//! use nom::multi::many1;
+//! use core::ops::Range;
+//!
+//! #[derive(Logos, Debug, Clone, PartialEq)]
+//! #[logos(skip r"[ \n]+")]
+//! enum Token {
+//!     #[token("a")]
+//!     A,
+//!     #[regex("[0-9]+", |lex| lex.slice().parse().ok())]
+//!     Number(u32),
+//! }
+//!
+//! impl TokenCode<ExCode> for Token {
+//!     fn code(&self) -> ExCode {
+//!         match self {
+//!             Token::A => ExTagA,
+//!             Token::Number(_) => ExNumber,
+//!         }
+//!     }
+//! }
+//!
+//! type Tokens<'s> = TokenInput<'s, (Token, Range<usize>)>;
+//!
+//! fn parse_numbers(
+//!     i: Tokens<'_>,
+//! ) -> nom::IResult<Tokens<'_>, Vec<&(Token, Range<usize>)>, ParserError<ExCode, Tokens<'_>>> {
+//!     many1(kind(Token::Number(0)))(i)
+//! }
+//!
+//! let tokens = LogosTokens::new(Token::lexer("1 2\na"), ExNomError).expect("lexed");
+//! let (rest, numbers) = parse_numbers(tokens.input()).expect("ok");
+//! assert_eq!(numbers.len(), 2);
+//!
+//! let err = match parse_numbers(rest) {
+//!     Err(nom::Err::Error(e)) => e.map_span(|v| v.source_span()),
+//!     _ => unreachable!(),
+//! };
+//! assert_eq!(err.code, ExNumber);
+//! assert_eq!(err.span, "a");
+//! ```
+//!
+
+use crate::token_input::{token, TokenInput};
+use crate::{Code, KParseError, ParserError};
+use alloc::vec::Vec;
+use core::mem::discriminant;
+use core::ops::Range;
+use logos::{Lexer, Logos};
+use nom::IResult;
+
+/// Maps the kind of a token to an error code.
+pub trait TokenCode<C>
+where
+    C: Code,
+{
+    /// Error code if this kind of token is expected.
+    fn code(&self) -> C;
+}
+
+/// The tokens of a logos lexer with their spans.
+#[derive(Debug, Clone)]
+pub struct LogosTokens<'s, T> {
+    text: &'s str,
+    tokens: Vec<(T, Range<usize>)>,
+}
+
+impl<'s, T> LogosTokens<'s, T>
+where
+    T: Logos<'s, Source = str>,
+{
+    /// Runs the lexer to the end.
+    ///
+    /// Fails with the code for a token the lexer can't recognize.
+    pub fn new<C>(mut lexer: Lexer<'s, T>, code: C) -> Result<Self, ParserError<C, &'s str>>
+    where
+        C: Code,
+    {
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next() {
+            match token {
+                Ok(token) => tokens.push((token, lexer.span())),
+                Err(_) => return Err(ParserError::new(code, lexer.slice())),
+            }
+        }
+        Ok(Self {
+            text: lexer.source(),
+            tokens,
+        })
+    }
+}
+
+impl<'s, T> LogosTokens<'s, T> {
+    /// Input for the parser.
+    pub fn input(&self) -> TokenInput<'_, (T, Range<usize>)> {
+        TokenInput::new(self.text, &self.tokens)
+    }
+
+    /// The source text.
+    pub fn text(&self) -> &'s str {
+        self.text
+    }
+
+    /// All tokens.
+    pub fn tokens(&self) -> &[(T, Range<usize>)] {
+        &self.tokens
+    }
+}
+
+/// Matches a token of the same kind. The data of the token is not
+/// compared, `kind(Token::Number(0))` matches any number.
+///
+/// Fails with the code of the kind.
+pub fn kind<'s, C, T, E>(
+    kind: T,
+) -> impl Fn(
+    TokenInput<'s, (T, Range<usize>)>,
+) -> IResult<TokenInput<'s, (T, Range<usize>)>, &'s (T, Range<usize>), E>
+where
+    C: Code,
+    T: TokenCode<C> + 's,
+    E: KParseError<C, TokenInput<'s, (T, Range<usize>)>>,
+{
+    let code = kind.code();
+    let kind = discriminant(&kind);
+    token(code, move |v: &(T, Range<usize>)| {
+        discriminant(&v.0) == kind
+    })
+}
@@ -0,0 +1,202 @@
+//!
+//! Compatibility with nom 8. Enabled with the feature `nom8`.
+//!
+//! kparse itself is still built on nom 7. This module makes the error types
+//! usable as nom 8 errors and converts parsers in both directions, so a
+//! grammar can be migrated one parser at a time.
+//!
+//! * ParserError and TokenizerError implement nom8::error::ParseError.
+//! * [from_nom8] turns a nom 8 parser into a nom 7 one. The result can use
+//!   all of [KParser](crate::KParser).
+//! * [to_nom8] turns a nom 7 parser into a nom 8 one.
+//!
+//! The input must implement the input traits of both versions. This holds
+//! for `&str` and `&[u8]`, but not for LocatedSpan, as nom_locate 4 only
+//! supports nom 7.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExTagA};
+//! use kparse::nom8::{from_nom8, to_nom8};
+//! use kparse::{KParser, ParserError};
+//!
+//! type Error<'s> = ParserError<ExCode, &'s str>;
+//!
+//! fn nom_a(i: &str) -> nom::IResult<&str, &str, Error<'_>> {
+//!     use nom::Parser;
+//!     from_nom8(nom8::bytes::complete::tag("a"))
+//!         .with_code(ExTagA)
+//!         .parse(i)
+//! }
+//!
+//! fn nom_many_a(i: &str) -> nom8::IResult<&str, Vec<&str>, Error<'_>> {
+//!     use nom8::Parser;
+//!     nom8::multi::many0(to_nom8(nom_a)).parse(i)
+//! }
+//!
+//! let (rest, many_a) = nom_many_a("aab").expect("ok");
+//! assert_eq!(rest, "b");
+//! assert_eq!(many_a, vec!["a", "a"]);
+//! ```
+//!
+
+use crate::{Code, ParserError, TokenizerError};
+use core::fmt::Debug;
+use nom::{InputIter, InputLength, InputTake};
+
+/// Maps the ErrorKind of nom 8 to nom 7. The kinds that are new in nom 8
+/// map to Fail.
+pub fn error_kind(kind: nom8::error::ErrorKind) -> nom::error::ErrorKind {
+    macro_rules! map_kind {
+        ($($kind:ident),*) => {
+            match kind {
+                $(nom8::error::ErrorKind::$kind => nom::error::ErrorKind::$kind,)*
+                _ => nom::error::ErrorKind::Fail,
+            }
+        };
+    }
+
+    map_kind!(
+        Tag,
+        MapRes,
+        MapOpt,
+        Alt,
+        IsNot,
+        IsA,
+        SeparatedList,
+        SeparatedNonEmptyList,
+        Many0,
+        Many1,
+        ManyTill,
+        Count,
+        TakeUntil,
+        LengthValue,
+        TagClosure,
+        Alpha,
+        Digit,
+        HexDigit,
+        OctDigit,
+        AlphaNumeric,
+        Space,
+        MultiSpace,
+        LengthValueFn,
+        Eof,
+        Switch,
+        TagBits,
+        OneOf,
+        NoneOf,
+        Char,
+        CrLf,
+        RegexpMatch,
+        RegexpMatches,
+        RegexpFind,
+        RegexpCapture,
+        RegexpCaptures,
+        TakeWhile1,
+        Complete,
+        Fix,
+        Escaped,
+        EscapedTransform,
+        NonEmpty,
+        ManyMN,
+        Not,
+        Permutation,
+        Verify,
+        TakeTill1,
+        TakeWhileMN,
+        TooLarge,
+        Many0Count,
+        Many1Count,
+        Float,
+        Satisfy,
+        Fail
+    )
+}
+
+/// Maps Needed of nom 8 to nom 7.
+pub fn needed(needed: nom8::Needed) -> nom::Needed {
+    match needed {
+        nom8::Needed::Unknown => nom::Needed::Unknown,
+        nom8::Needed::Size(n) => nom::Needed::Size(n),
+    }
+}
+
+/// Maps Needed of nom 7 to nom 8.
+pub fn needed_nom8(needed: nom::Needed) -> nom8::Needed {
+    match needed {
+        nom::Needed::Unknown => nom8::Needed::Unknown,
+        nom::Needed::Size(n) => nom8::Needed::Size(n),
+    }
+}
+
+/// Runs a nom 8 parser as nom 7 parser.
+#[inline]
+pub fn from_nom8<P, I, O, E>(mut parser: P) -> impl FnMut(I) -> nom::IResult<I, O, E>
+where
+    P: nom8::Parser<I, Output = O, Error = E>,
+{
+    move |i: I| match parser.parse(i) {
+        Ok(v) => Ok(v),
+        Err(nom8::Err::Error(e)) => Err(nom::Err::Error(e)),
+        Err(nom8::Err::Failure(e)) => Err(nom::Err::Failure(e)),
+        Err(nom8::Err::Incomplete(n)) => Err(nom::Err::Incomplete(needed(n))),
+    }
+}
+
+/// Runs a nom 7 parser as nom 8 parser.
+#[inline]
+pub fn to_nom8<P, I, O, E>(mut parser: P) -> impl FnMut(I) -> nom8::IResult<I, O, E>
+where
+    P: nom::Parser<I, O, E>,
+{
+    move |i: I| match parser.parse(i) {
+        Ok(v) => Ok(v),
+        Err(nom::Err::Error(e)) => Err(nom8::Err::Error(e)),
+        Err(nom::Err::Failure(e)) => Err(nom8::Err::Failure(e)),
+        Err(nom::Err::Incomplete(n)) => Err(nom8::Err::Incomplete(needed_nom8(n))),
+    }
+}
+
+impl<C, I> nom8::error::ParseError<I> for ParserError<C, I>
+where
+    C: Code,
+    I: Clone,
+{
+    fn from_error_kind(input: I, kind: nom8::error::ErrorKind) -> Self {
+        nom::error::ParseError::from_error_kind(input, error_kind(kind))
+    }
+
+    fn append(input: I, kind: nom8::error::ErrorKind, other: Self) -> Self {
+        nom::error::ParseError::append(input, error_kind(kind), other)
+    }
+
+    fn from_char(input: I, c: char) -> Self {
+        nom::error::ParseError::from_char(input, c)
+    }
+
+    fn or(self, other: Self) -> Self {
+        nom::error::ParseError::or(self, other)
+    }
+}
+
+impl<C, I> nom8::error::ParseError<I> for TokenizerError<C, I>
+where
+    C: Code,
+    I: Clone + Debug,
+    I: InputTake + InputLength + InputIter,
+{
+    fn from_error_kind(input: I, kind: nom8::error::ErrorKind) -> Self {
+        nom::error::ParseError::from_error_kind(input, error_kind(kind))
+    }
+
+    fn append(input: I, kind: nom8::error::ErrorKind, other: Self) -> Self {
+        nom::error::ParseError::append(input, error_kind(kind), other)
+    }
+
+    fn from_char(input: I, c: char) -> Self {
+        nom::error::ParseError::from_char(input, c)
+    }
+
+    fn or(self, other: Self) -> Self {
+        nom::error::ParseError::or(self, other)
+    }
+}
@@ -14,25 +14,68 @@ use crate::debug::error::debug_parse_error;
 use crate::debug::{restrict, DebugWidth};
 use crate::prelude::SpanFragment;
 use crate::{Code, ErrOrNomErr, KParseError};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Debug, Display};
 use nom::error::ErrorKind;
 use nom::{InputIter, InputLength, InputTake};
-use std::any::Any;
-#[cfg(debug_assertions)]
-use std::backtrace::Backtrace;
-use std::error::Error;
-use std::fmt;
-use std::fmt::{Debug, Display};
+#[cfg(any(feature = "miette", feature = "codespan-reporting"))]
+use nom_locate::LocatedSpan;
+#[cfg(all(debug_assertions, feature = "std"))]
+use std::backtrace::{Backtrace, BacktraceStatus};
 
 /// Parser error.
+///
+/// Only code and span are inline, the nom error kind, the hints and the
+/// backtrace are behind one pointer, so the error stays small when it's
+/// passed along. An error with only code and span doesn't allocate.
 pub struct ParserError<C, I> {
     /// Error code
     pub code: C,
     /// Error span
     pub span: I,
-    /// Extra information
-    pub hints: Vec<Hints<C, I>>,
-    #[cfg(debug_assertions)]
-    pub backtrace: Backtrace,
+    /// Everything else.
+    cold: Option<Box<Cold<C, I>>>,
+}
+
+/// The parts of a ParserError that are rarely needed.
+struct Cold<C, I> {
+    nom: Option<ErrorKind>,
+    hints: HintVec<C, I>,
+    #[cfg(all(debug_assertions, feature = "std"))]
+    backtrace: Backtrace,
+}
+
+impl<C, I> Cold<C, I> {
+    fn new() -> Box<Self> {
+        Box::new(Self {
+            nom: None,
+            hints: HintVec::new(),
+            #[cfg(all(debug_assertions, feature = "std"))]
+            backtrace: Backtrace::capture(),
+        })
+    }
+
+    /// Only allocates if RUST_BACKTRACE or RUST_LIB_BACKTRACE
+    /// asks for a backtrace. Always None in release builds.
+    fn backtrace() -> Option<Box<Self>> {
+        #[cfg(all(debug_assertions, feature = "std"))]
+        {
+            let backtrace = Backtrace::capture();
+            if backtrace.status() == BacktraceStatus::Captured {
+                return Some(Box::new(Self {
+                    nom: None,
+                    hints: HintVec::new(),
+                    backtrace,
+                }));
+            }
+        }
+        None
+    }
 }
 
 /// Extra information added to a ParserError.
@@ -41,6 +84,8 @@ pub enum Hints<C, I> {
     Expect(SpanAndCode<C, I>),
     /// Suggestions from the parser.
     Suggest(SpanAndCode<C, I>),
+    /// Suggested replacement text.
+    Replace(Replacement<C, I>),
     /// External cause for the error.
     Cause(Box<dyn Error>),
     /// Extra user context.
@@ -104,6 +149,9 @@ where
     fn parts(&self) -> Option<(C, I, &Self::WrappedError)> {
         Some((self.code, self.span.clone(), self))
     }
+    fn expected(&self) -> Vec<(C, I)> {
+        self.iter_expected().map(|v| (v.code, v.span)).collect()
+    }
 }
 
 impl<C, I> KParseError<C, I> for nom::Err<ParserError<C, I>>
@@ -157,6 +205,12 @@ where
             nom::Err::Failure(e) => Some((e.code, e.span.clone(), e)),
         }
     }
+    fn expected(&self) -> Vec<(C, I)> {
+        match self.err() {
+            Some(e) => e.expected(),
+            None => Vec::new(),
+        }
+    }
 }
 
 impl<C, I, O> KParseError<C, I> for Result<(I, O), nom::Err<ParserError<C, I>>>
@@ -215,6 +269,13 @@ where
             Err(nom::Err::Incomplete(_)) => None,
         }
     }
+
+    fn expected(&self) -> Vec<(C, I)> {
+        match KParseError::err(self) {
+            Some(e) => e.expected(),
+            None => Vec::new(),
+        }
+    }
 }
 
 /// Combines two ParserErrors.
@@ -338,14 +399,10 @@ where
     C: Code,
     I: Clone,
 {
-    fn from_error_kind(input: I, _kind: ErrorKind) -> Self {
-        ParserError {
-            code: C::NOM_ERROR,
-            span: input,
-            hints: Default::default(),
-            #[cfg(debug_assertions)]
-            backtrace: Backtrace::capture(),
-        }
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        let mut err = ParserError::new(C::NOM_ERROR, input);
+        err.cold_mut().nom = Some(kind);
+        err
     }
 
     fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
@@ -353,13 +410,7 @@ where
     }
 
     fn from_char(input: I, _ch: char) -> Self {
-        ParserError {
-            code: C::NOM_ERROR,
-            span: input,
-            hints: Default::default(),
-            #[cfg(debug_assertions)]
-            backtrace: Backtrace::capture(),
-        }
+        Self::from_error_kind(input, ErrorKind::Char)
     }
 
     /// Combines two parser errors.
@@ -433,6 +484,7 @@ where
         match self {
             Hints::Expect(v) => write!(f, "Expect {:?} ", v),
             Hints::Suggest(v) => write!(f, "Suggest {:?} ", v),
+            Hints::Replace(v) => write!(f, "Replace {:?} ", v),
             Hints::Cause(v) => write!(f, "Cause {:?}", v),
             Hints::UserData(v) => write!(f, "UserData {:?}", v),
         }
@@ -445,9 +497,8 @@ where
     I: Clone + Debug + SpanFragment,
     I: InputTake + InputLength + InputIter,
 {
-    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
-        self.hints
-            .iter()
+    fn source(&self) -> Option<&(dyn ::core::error::Error + 'static)> {
+        self.hints()
             .find(|v| matches!(v, Hints::Cause(_)))
             .and_then(|v| {
                 if let Hints::Cause(e) = v {
@@ -459,6 +510,111 @@ where
     }
 }
 
+/// Storage for the hints of a ParserError.
+///
+/// Lives in the boxed part of the error. The first hint is stored inline,
+/// only the following ones need a second allocation.
+pub struct HintVec<C, I> {
+    first: Option<Hints<C, I>>,
+    more: Vec<Hints<C, I>>,
+}
+
+impl<C, I> Default for HintVec<C, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, I> From<Hints<C, I>> for HintVec<C, I> {
+    fn from(hint: Hints<C, I>) -> Self {
+        Self {
+            first: Some(hint),
+            more: Vec::new(),
+        }
+    }
+}
+
+impl<C, I> HintVec<C, I> {
+    /// Empty, doesn't allocate.
+    pub const fn new() -> Self {
+        Self {
+            first: None,
+            more: Vec::new(),
+        }
+    }
+
+    /// Adds a hint.
+    pub fn push(&mut self, hint: Hints<C, I>) {
+        match self.first {
+            None => self.first = Some(hint),
+            Some(_) => self.more.push(hint),
+        }
+    }
+
+    /// Number of hints.
+    pub fn len(&self) -> usize {
+        self.first.iter().count() + self.more.len()
+    }
+
+    /// No hints.
+    pub fn is_empty(&self) -> bool {
+        self.first.is_none()
+    }
+
+    /// The hints didn't fit inline and needed a second allocation.
+    pub fn spilled(&self) -> bool {
+        self.more.capacity() > 0
+    }
+
+    /// Number of hints that fit without a second allocation.
+    pub fn capacity(&self) -> usize {
+        1 + self.more.capacity()
+    }
+
+    /// Iterates the hints in the order they were added.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Hints<C, I>> + '_ {
+        self.into_iter()
+    }
+}
+
+impl<C, I> Extend<Hints<C, I>> for HintVec<C, I> {
+    fn extend<T: IntoIterator<Item = Hints<C, I>>>(&mut self, iter: T) {
+        for hint in iter {
+            self.push(hint);
+        }
+    }
+}
+
+impl<C, I> FromIterator<Hints<C, I>> for HintVec<C, I> {
+    fn from_iter<T: IntoIterator<Item = Hints<C, I>>>(iter: T) -> Self {
+        let mut hints = HintVec::new();
+        hints.extend(iter);
+        hints
+    }
+}
+
+impl<C, I> IntoIterator for HintVec<C, I> {
+    type Item = Hints<C, I>;
+    type IntoIter =
+        core::iter::Chain<core::option::IntoIter<Hints<C, I>>, alloc::vec::IntoIter<Hints<C, I>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.first.into_iter().chain(self.more)
+    }
+}
+
+impl<'a, C, I> IntoIterator for &'a HintVec<C, I> {
+    type Item = &'a Hints<C, I>;
+    type IntoIter = core::iter::Chain<
+        core::option::IntoIter<&'a Hints<C, I>>,
+        core::slice::Iter<'a, Hints<C, I>>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.first.as_ref().into_iter().chain(self.more.iter())
+    }
+}
+
 /// Contains a error code and the span.
 #[derive(Clone, Copy)]
 pub struct SpanAndCode<C, I> {
@@ -486,6 +642,36 @@ where
     }
 }
 
+/// Suggested replacement text for a span.
+#[derive(Clone)]
+pub struct Replacement<C, I> {
+    /// Code of the replacement
+    pub code: C,
+    /// Span to replace
+    pub span: I,
+    /// Replacement text
+    pub text: String,
+}
+
+impl<C, I> Debug for Replacement<C, I>
+where
+    C: Code,
+    I: Clone + Debug + SpanFragment,
+    I: InputTake + InputLength + InputIter,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let w = f.width().into();
+        write!(
+            f,
+            "{:?}:{:?} -> {:?}",
+            self.code,
+            restrict(w, self.span.clone()).fragment(),
+            self.text
+        )?;
+        Ok(())
+    }
+}
+
 impl<C, I> ParserError<C, I>
 where
     C: Code,
@@ -496,18 +682,45 @@ where
         Self {
             code,
             span,
-            hints: Vec::new(),
-            #[cfg(debug_assertions)]
-            backtrace: Backtrace::capture(),
+            cold: Cold::backtrace(),
         }
     }
 
+    fn cold_mut(&mut self) -> &mut Cold<C, I> {
+        self.cold.get_or_insert_with(Cold::new)
+    }
+
+    /// Iterates the hints in the order they were added.
+    pub fn hints(&self) -> impl DoubleEndedIterator<Item = &Hints<C, I>> + '_ {
+        self.cold.iter().flat_map(|v| v.hints.iter())
+    }
+
+    /// Storage of the hints. None if nothing was allocated for the error.
+    pub fn hint_vec(&self) -> Option<&HintVec<C, I>> {
+        self.cold.as_ref().map(|v| &v.hints)
+    }
+
+    /// Adds a hint.
+    pub fn push_hint(&mut self, hint: Hints<C, I>) {
+        self.cold_mut().hints.push(hint);
+    }
+
+    /// The backtrace, if RUST_BACKTRACE or RUST_LIB_BACKTRACE asked for one
+    /// when the error was created.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.cold
+            .as_ref()
+            .map(|v| &v.backtrace)
+            .filter(|v| v.status() == BacktraceStatus::Captured)
+    }
+
     /// With a cause.
     pub fn with_cause<E>(mut self, err: E) -> Self
     where
         E: Error + 'static,
     {
-        self.hints.push(Hints::Cause(Box::new(err)));
+        self.push_hint(Hints::Cause(Box::new(err)));
         self
     }
 
@@ -516,14 +729,13 @@ where
     where
         Y: 'static,
     {
-        self.hints.push(Hints::UserData(Box::new(user_data)));
+        self.push_hint(Hints::UserData(Box::new(user_data)));
         self
     }
 
     /// Finds the first (single) cause.
     pub fn cause(&self) -> Option<&dyn Error> {
-        self.hints
-            .iter()
+        self.hints()
             .find(|v| matches!(v, Hints::Cause(_)))
             .and_then(|v| match v {
                 Hints::Cause(e) => Some(e.as_ref()),
@@ -533,8 +745,7 @@ where
 
     /// Finds the first (single) user data.
     pub fn user_data<Y: 'static>(&self) -> Option<&Y> {
-        self.hints
-            .iter()
+        self.hints()
             .find(|v| matches!(v, Hints::UserData(_)))
             .and_then(|v| match v {
                 Hints::UserData(e) => e.downcast_ref::<Y>(),
@@ -560,8 +771,10 @@ where
         if other.code != C::NOM_ERROR {
             self.expect(other.code, other.span);
         }
-        for hint in other.hints {
-            self.hints.push(hint);
+        if let Some(cold) = other.cold {
+            if !cold.hints.is_empty() {
+                self.cold_mut().hints.extend(cold.hints);
+            }
         }
     }
 
@@ -569,7 +782,7 @@ where
     /// If the old one differs, it is added to the expect list.
     pub fn with_code(mut self, code: C) -> Self {
         if self.code != code && self.code != C::NOM_ERROR {
-            self.hints.push(Hints::Expect(SpanAndCode {
+            self.push_hint(Hints::Expect(SpanAndCode {
                 code: self.code,
                 span: self.span.clone(),
             }));
@@ -584,7 +797,7 @@ where
         if self.code == code {
             return true;
         }
-        for exp in &self.hints {
+        for exp in self.hints() {
             if let Hints::Expect(v) = exp {
                 if v.code == code {
                     return true;
@@ -596,13 +809,13 @@ where
 
     /// Add an expected code.
     pub fn expect(&mut self, code: C, span: I) {
-        self.hints.push(Hints::Expect(SpanAndCode { code, span }))
+        self.push_hint(Hints::Expect(SpanAndCode { code, span }))
     }
 
     /// Adds some expected codes.
     pub fn append_expected(&mut self, exp_iter: impl Iterator<Item = SpanAndCode<C, I>>) {
         for exp in exp_iter {
-            self.hints.push(Hints::Expect(exp));
+            self.push_hint(Hints::Expect(exp));
         }
     }
 
@@ -612,7 +825,7 @@ where
     ///
     /// The main error code is not included here.
     pub fn iter_expected(&self) -> impl Iterator<Item = SpanAndCode<C, I>> + '_ {
-        self.hints.iter().rev().filter_map(|v| match v {
+        self.hints().rev().filter_map(|v| match v {
             Hints::Expect(v) => Some(v.clone()),
             _ => None,
         })
@@ -620,13 +833,13 @@ where
 
     /// Add an suggested code.
     pub fn suggest(&mut self, code: C, span: I) {
-        self.hints.push(Hints::Suggest(SpanAndCode { code, span }))
+        self.push_hint(Hints::Suggest(SpanAndCode { code, span }))
     }
 
     /// Was this one of the expected errors.
     /// The main error code is one of the tested values.
     pub fn is_suggested(&self, code: C) -> bool {
-        for exp in &self.hints {
+        for exp in self.hints() {
             if let Hints::Suggest(v) = exp {
                 if v.code == code {
                     return true;
@@ -639,15 +852,185 @@ where
     /// Adds some suggested codes.
     pub fn append_suggested(&mut self, sug_iter: impl Iterator<Item = SpanAndCode<C, I>>) {
         for exp in sug_iter {
-            self.hints.push(Hints::Suggest(exp));
+            self.push_hint(Hints::Suggest(exp));
         }
     }
 
     /// Returns the suggested codes.
     pub fn iter_suggested(&self) -> impl Iterator<Item = SpanAndCode<C, I>> + '_ {
-        self.hints.iter().rev().filter_map(|v| match v {
+        self.hints().rev().filter_map(|v| match v {
             Hints::Suggest(v) => Some(v.clone()),
             _ => None,
         })
     }
+
+    /// Adds a suggested replacement text for the span.
+    pub fn suggest_replacement(&mut self, code: C, span: I, text: impl Into<String>) {
+        self.push_hint(Hints::Replace(Replacement {
+            code,
+            span,
+            text: text.into(),
+        }))
+    }
+
+    /// Returns the suggested replacements.
+    pub fn iter_replacements(&self) -> impl Iterator<Item = &Replacement<C, I>> + '_ {
+        self.hints().rev().filter_map(|v| match v {
+            Hints::Replace(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    /// Returns the error kind of the nom error that was converted into this one.
+    ///
+    /// This is the innermost nom parser that failed, the error kinds
+    /// nom appends on the way out are not kept.
+    pub fn nom(&self) -> Option<ErrorKind> {
+        self.cold.as_ref().and_then(|v| v.nom)
+    }
+
+    /// Converts all the spans of the error.
+    ///
+    /// Can be used to detach the error from the lifetime of the parsed text,
+    /// e.g. by converting the spans to byte ranges with
+    /// [SpanRange](crate::spans::SpanRange).
+    pub fn map_span<J, F>(self, mut f: F) -> ParserError<C, J>
+    where
+        F: FnMut(I) -> J,
+    {
+        let cold = self.cold.map(|cold| {
+            let cold = *cold;
+            Box::new(Cold {
+                nom: cold.nom,
+                hints: cold
+                    .hints
+                    .into_iter()
+                    .map(|v| match v {
+                        Hints::Expect(v) => Hints::Expect(SpanAndCode {
+                            code: v.code,
+                            span: f(v.span),
+                        }),
+                        Hints::Suggest(v) => Hints::Suggest(SpanAndCode {
+                            code: v.code,
+                            span: f(v.span),
+                        }),
+                        Hints::Replace(v) => Hints::Replace(Replacement {
+                            code: v.code,
+                            span: f(v.span),
+                            text: v.text,
+                        }),
+                        Hints::Cause(v) => Hints::Cause(v),
+                        Hints::UserData(v) => Hints::UserData(v),
+                    })
+                    .collect(),
+                #[cfg(all(debug_assertions, feature = "std"))]
+                backtrace: cold.backtrace,
+            })
+        });
+        ParserError {
+            code: self.code,
+            span: f(self.span),
+            cold,
+        }
+    }
+}
+
+/// Renders the error with miette.
+///
+/// Only for LocatedSpan, the labels need the offset into the source.
+/// The source code must be attached to the report, e.g. with
+/// `miette::Report::new(err).with_source_code(text)`.
+#[cfg(feature = "miette")]
+impl<C, T, X> miette::Diagnostic for ParserError<C, LocatedSpan<T, X>>
+where
+    C: Code,
+    LocatedSpan<T, X>: Clone + Debug + SpanFragment,
+    LocatedSpan<T, X>: InputTake + InputLength + InputIter,
+{
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let suggested = self
+            .iter_suggested()
+            .map(|v| v.code.to_string())
+            .collect::<Vec<_>>();
+        let replaced = self
+            .iter_replacements()
+            .map(|v| format!("did you mean `{}`?", v.text))
+            .collect::<Vec<_>>();
+        let mut help = Vec::new();
+        if !suggested.is_empty() {
+            help.push(format!("suggested {}", suggested.join(", ")));
+        }
+        help.extend(replaced);
+        if help.is_empty() {
+            None
+        } else {
+            Some(Box::new(help.join("\n")))
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let primary = miette::LabeledSpan::new_primary_with_span(
+            Some(self.code.to_string()),
+            (self.span.location_offset(), 0),
+        );
+        let expected = self.iter_expected().map(|v| {
+            miette::LabeledSpan::new_with_span(
+                Some(format!("expected {}", v.code)),
+                (v.span.location_offset(), 0),
+            )
+        });
+        Some(Box::new(Some(primary).into_iter().chain(expected)))
+    }
+}
+
+/// Converts the error to a codespan-reporting diagnostic.
+///
+/// Only for LocatedSpan, the labels need the offset into the source.
+/// The primary label shows the error code, the expected and suggested
+/// codes get a secondary label each.
+#[cfg(feature = "codespan-reporting")]
+pub fn to_codespan<C, T, X, F>(
+    err: &ParserError<C, LocatedSpan<T, X>>,
+    file_id: F,
+) -> codespan_reporting::diagnostic::Diagnostic<F>
+where
+    C: Code,
+    LocatedSpan<T, X>: Clone,
+    F: Copy,
+{
+    use codespan_reporting::diagnostic::{Diagnostic, Label};
+
+    let offset = err.span.location_offset();
+    let mut labels =
+        vec![Label::primary(file_id, offset..offset).with_message(err.code.to_string())];
+    for exp in err.iter_expected() {
+        let offset = exp.span.location_offset();
+        labels.push(
+            Label::secondary(file_id, offset..offset)
+                .with_message(format!("expected {}", exp.code)),
+        );
+    }
+    for sug in err.iter_suggested() {
+        let offset = sug.span.location_offset();
+        labels.push(
+            Label::secondary(file_id, offset..offset)
+                .with_message(format!("suggested {}", sug.code)),
+        );
+    }
+    for rep in err.iter_replacements() {
+        let offset = rep.span.location_offset();
+        labels.push(
+            Label::secondary(file_id, offset..offset)
+                .with_message(format!("did you mean `{}`?", rep.text)),
+        );
+    }
+
+    Diagnostic::error()
+        .with_code(err.code.to_string())
+        .with_message(err.code.to_string())
+        .with_labels(labels)
 }
@@ -3,13 +3,14 @@
 //!
 
 use crate::parser_error::AppendParserError;
-use crate::{Code, KParseError, ParserError};
+use crate::{Code, FromStrRadix, KParseError, ParserError};
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::error::Error;
+use core::marker::PhantomData;
+use core::ops::RangeTo;
+use core::str::FromStr;
 use nom::{IResult, InputIter, InputLength, Offset, Parser, Slice};
-use std::borrow::Borrow;
-use std::error::Error;
-use std::marker::PhantomData;
-use std::ops::RangeTo;
-use std::str::FromStr;
 
 /// Convert the error.
 pub struct IntoErr<PA, O, E1, E2> {
@@ -76,6 +77,69 @@ where
     }
 }
 
+/// Runs a second parser on the output of the first one.
+pub struct MapParser<PA, PB, O1> {
+    pub(crate) parser: PA,
+    pub(crate) inner: PB,
+    pub(crate) _phantom: PhantomData<O1>,
+}
+
+impl<PA, PB, I, O1, O2, E> Parser<I, O2, E> for MapParser<PA, PB, O1>
+where
+    PA: Parser<I, O1, E>,
+    PB: Parser<O1, O2, E>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        let (rest, token) = self.parser.parse(input)?;
+        let (_, value) = self.inner.parse(token)?;
+        Ok((rest, value))
+    }
+}
+
+/// Chooses the follow-up parser from the output of the first one.
+pub struct FlatMap<PA, FN, O1> {
+    pub(crate) parser: PA,
+    pub(crate) map: FN,
+    pub(crate) _phantom: PhantomData<O1>,
+}
+
+impl<PA, FN, PB, I, O1, O2, E> Parser<I, O2, E> for FlatMap<PA, FN, O1>
+where
+    PA: Parser<I, O1, E>,
+    FN: Fn(O1) -> PB,
+    PB: Parser<I, O2, E>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        let (rest, token) = self.parser.parse(input)?;
+        (self.map)(token).parse(rest)
+    }
+}
+
+/// Runs a second parser on the span recognized by the first one.
+pub struct AndThenSpan<PA, PB, O1> {
+    pub(crate) parser: PA,
+    pub(crate) second: PB,
+    pub(crate) _phantom: PhantomData<O1>,
+}
+
+impl<PA, PB, I, O1, O2, E> Parser<I, O2, E> for AndThenSpan<PA, PB, O1>
+where
+    PA: Parser<I, O1, E>,
+    PB: Parser<I, O2, E>,
+    I: Clone + Slice<RangeTo<usize>> + Offset,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        let (tail, _) = self.parser.parse(input.clone())?;
+        let index = input.offset(&tail);
+        // the slice keeps the offsets of the original input.
+        let (_, value) = self.second.parse(input.slice(..index))?;
+        Ok((tail, value))
+    }
+}
+
 /// Add some context.
 pub struct WithContext<PA, C, E, Y> {
     pub(crate) parser: PA,
@@ -141,6 +205,68 @@ where
     }
 }
 
+/// Convert the output with the FromStrRadix trait.
+pub struct FromStrRadixParser<PA, C, O1, O2> {
+    pub(crate) parser: PA,
+    pub(crate) code: C,
+    pub(crate) radix: u32,
+    pub(crate) _phantom: PhantomData<(O1, O2)>,
+}
+
+impl<PA, C, I, O1, O2, E> Parser<I, O2, E> for FromStrRadixParser<PA, C, O1, O2>
+where
+    PA: Parser<I, O1, E>,
+    O1: InputIter<Item = char>,
+    O2: FromStrRadix,
+    C: Code,
+    E: KParseError<C, O1>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        match self.parser.parse(input) {
+            Ok((rest, token)) => {
+                let txt: String = token.iter_elements().collect();
+                match O2::from_str_radix(txt.as_ref(), self.radix) {
+                    Ok(value) => Ok((rest, value)),
+                    Err(_) => Err(nom::Err::Error(E::from(self.code, token))),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Convert the output with a conversion function.
+pub struct FromStrWith<PA, C, O1, FN> {
+    pub(crate) parser: PA,
+    pub(crate) code: C,
+    pub(crate) from_str: FN,
+    pub(crate) _phantom: PhantomData<O1>,
+}
+
+impl<PA, C, FN, I, O1, O2, E, E2> Parser<I, O2, E> for FromStrWith<PA, C, O1, FN>
+where
+    PA: Parser<I, O1, E>,
+    O1: InputIter<Item = char>,
+    FN: Fn(&str) -> Result<O2, E2>,
+    C: Code,
+    E: KParseError<C, O1>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        match self.parser.parse(input) {
+            Ok((rest, token)) => {
+                let txt: String = token.iter_elements().collect();
+                match (self.from_str)(txt.as_ref()) {
+                    Ok(value) => Ok((rest, value)),
+                    Err(_) => Err(nom::Err::Error(E::from(self.code, token))),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
 /// Replace the output with the value.
 pub struct Value<PA, O1, O2> {
     pub(crate) parser: PA,
@@ -162,6 +288,27 @@ where
     }
 }
 
+/// Replace the output with the result of a function.
+pub struct ValueWith<PA, O1, FN> {
+    pub(crate) parser: PA,
+    pub(crate) value: FN,
+    pub(crate) _phantom: PhantomData<O1>,
+}
+
+impl<PA, FN, I, O1, O2, E> Parser<I, O2, E> for ValueWith<PA, O1, FN>
+where
+    PA: Parser<I, O1, E>,
+    FN: Fn() -> O2,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        match self.parser.parse(input) {
+            Ok((r, _)) => Ok((r, (self.value)())),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 /// Fails if not everything has been processed.
 pub struct AllConsuming<PA, C> {
     pub(crate) parser: PA,
@@ -232,6 +379,28 @@ where
     }
 }
 
+/// Convert from nom::Err::Error to nom::Err::Failure if the error code matches.
+pub struct CutOn<PA, C> {
+    pub(crate) parser: PA,
+    pub(crate) code: C,
+}
+
+impl<PA, C, I, O, E> Parser<I, O, E> for CutOn<PA, C>
+where
+    PA: Parser<I, O, E>,
+    C: Code,
+    E: KParseError<C, I>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O, E> {
+        match self.parser.parse(input) {
+            Err(nom::Err::Error(e)) if e.code() == Some(self.code) => Err(nom::Err::Failure(e)),
+            Ok((r, v)) => Ok((r, v)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 /// Optional parser.
 pub struct Optional<PA> {
     pub(crate) parser: PA,
@@ -252,6 +421,28 @@ where
     }
 }
 
+/// Optional parser with a default value.
+pub struct OptOr<PA, O> {
+    pub(crate) parser: PA,
+    pub(crate) default: O,
+}
+
+impl<PA, I, O, E> Parser<I, O, E> for OptOr<PA, O>
+where
+    PA: Parser<I, O, E>,
+    I: Clone,
+    O: Clone,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O, E> {
+        match self.parser.parse(input.clone()) {
+            Ok((r, v)) => Ok((r, v)),
+            Err(nom::Err::Error(_)) => Ok((input, self.default.clone())),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 /// Run the parser and return the parsed input.
 pub struct Recognize<PA, O> {
     pub(crate) parser: PA,
@@ -434,6 +625,60 @@ where
     }
 }
 
+/// Checks that the successor matches after the parser, but doesn't consume it.
+pub struct FollowedBy<PA, PF, C, O2> {
+    pub(crate) parser: PA,
+    pub(crate) successor: PF,
+    pub(crate) code: C,
+    pub(crate) _phantom: PhantomData<O2>,
+}
+
+impl<PA, PF, C, I, O1, O2, E> Parser<I, O1, E> for FollowedBy<PA, PF, C, O2>
+where
+    PA: Parser<I, O1, E>,
+    PF: Parser<I, O2, E>,
+    C: Code,
+    E: KParseError<C, I>,
+    I: Clone,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O1, E> {
+        let (rest, val) = self.parser.parse(input)?;
+        match self.successor.parse(rest.clone()) {
+            Ok(_) => Ok((rest, val)),
+            Err(nom::Err::Error(_)) => Err(nom::Err::Error(E::from(self.code, rest))),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Checks that the other parser doesn't match after the parser.
+pub struct NotFollowedBy<PA, PF, C, O2> {
+    pub(crate) parser: PA,
+    pub(crate) other: PF,
+    pub(crate) code: C,
+    pub(crate) _phantom: PhantomData<O2>,
+}
+
+impl<PA, PF, C, I, O1, O2, E> Parser<I, O1, E> for NotFollowedBy<PA, PF, C, O2>
+where
+    PA: Parser<I, O1, E>,
+    PF: Parser<I, O2, E>,
+    C: Code,
+    E: KParseError<C, I>,
+    I: Clone,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O1, E> {
+        let (rest, val) = self.parser.parse(input)?;
+        match self.other.parse(rest.clone()) {
+            Ok(_) => Err(nom::Err::Error(E::from(self.code, rest))),
+            Err(nom::Err::Error(_)) => Ok((rest, val)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 /// Or-Else parser.
 pub struct OrElse<PA, PE, OE> {
     pub(crate) parser: PA,
@@ -493,3 +738,30 @@ where
         }
     }
 }
+
+/// Runs a function on the parser result that verifies and converts it.
+pub struct VerifyMap<PA, V, C, O1> {
+    pub(crate) parser: PA,
+    pub(crate) verify: V,
+    pub(crate) code: C,
+    pub(crate) _phantom: PhantomData<O1>,
+}
+
+impl<PA, V, C, I, O1, O2, E> Parser<I, O2, E> for VerifyMap<PA, V, C, O1>
+where
+    PA: Parser<I, O1, E>,
+    C: Code,
+    V: Fn(O1) -> Option<O2>,
+    E: KParseError<C, I>,
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O2, E> {
+        match self.parser.parse(input) {
+            Ok((rest, val)) => match (self.verify)(val) {
+                Some(val) => Ok((rest, val)),
+                None => Err(nom::Err::Error(E::from(self.code, rest))),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
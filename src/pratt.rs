@@ -0,0 +1,285 @@
+//!
+//! Operator precedence parser.
+//!
+//! Parses expressions with prefix, infix and postfix operators by binding power.
+//! Each operator gets its own error code, and a missing operand after an operator
+//! results in an error with the code of the expression and an expect hint that
+//! points back at the operator.
+//!
+//! ```rust
+//! use nom::bytes::complete::tag;
+//! use nom::character::complete::digit1;
+//! use nom::Parser;
+//! use kparse::examples::{ExNumber, ExParserResult, ExSpan, ExTagA, ExTagB, ExAthenB};
+//! use kparse::pratt::{pratt, Assoc};
+//! use kparse::KParser;
+//!
+//! fn expr(i: ExSpan<'_>) -> ExParserResult<'_, i64> {
+//!     pratt(ExAthenB, digit1.with_code(ExNumber).parse_from_str(ExNumber))
+//!         .infix(ExTagA, tag("+"), 1, Assoc::Left, |_span, a, _op, b| a + b)
+//!         .infix(ExTagB, tag("*"), 2, Assoc::Left, |_span, a, _op, b| a * b)
+//!         .prefix(ExTagA, tag("-"), 3, |_span, _op, a: i64| -a)
+//!         .parse(i)
+//! }
+//! ```
+//!
+
+use crate::spans::SpanFragment;
+use crate::{Code, ParserError, ParserResult, TrackedSpan};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::ops::RangeTo;
+use nom::{InputIter, InputLength, InputTake, Offset, Parser, Slice};
+
+/// Associativity of an infix operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    /// `a + b + c` is parsed as `(a + b) + c`.
+    Left,
+    /// `a ^ b ^ c` is parsed as `a ^ (b ^ c)`.
+    Right,
+}
+
+/// Operator parser. Returns the recognized span of the operator.
+type OpParser<'a, C, I> = Box<dyn FnMut(I) -> ParserResult<C, I, I> + 'a>;
+
+/// Fold for a prefix operator. Gets the span of the whole expression, the operator
+/// and the operand.
+type PrefixFold<'a, I, O> = Box<dyn Fn(I, I, O) -> O + 'a>;
+/// Fold for an infix operator. Gets the span of the whole expression, the left operand,
+/// the operator and the right operand.
+type InfixFold<'a, I, O> = Box<dyn Fn(I, O, I, O) -> O + 'a>;
+/// Fold for a postfix operator. Gets the span of the whole expression, the operand
+/// and the operator.
+type PostfixFold<'a, I, O> = Box<dyn Fn(I, O, I) -> O + 'a>;
+
+struct Op<'a, C, I, F> {
+    code: C,
+    parser: OpParser<'a, C, I>,
+    lbp: u32,
+    rbp: u32,
+    fold: F,
+}
+
+/// Operator precedence parser. Create with [pratt].
+pub struct Pratt<'a, C, I, O, PA> {
+    code: C,
+    operand: PA,
+    prefix: Vec<Op<'a, C, I, PrefixFold<'a, I, O>>>,
+    infix: Vec<Op<'a, C, I, InfixFold<'a, I, O>>>,
+    postfix: Vec<Op<'a, C, I, PostfixFold<'a, I, O>>>,
+}
+
+/// Creates an operator precedence parser for the given operand parser.
+///
+/// The code is used for tracking and for the error if an operand is missing.
+pub fn pratt<'a, C, I, O, PA>(code: C, operand: PA) -> Pratt<'a, C, I, O, PA>
+where
+    C: Code,
+    PA: Parser<I, O, ParserError<C, I>>,
+{
+    Pratt {
+        code,
+        operand,
+        prefix: Vec::new(),
+        infix: Vec::new(),
+        postfix: Vec::new(),
+    }
+}
+
+fn op_parser<'a, C, I, O2, PO>(mut op: PO) -> OpParser<'a, C, I>
+where
+    C: 'a,
+    I: Clone + Offset + Slice<RangeTo<usize>> + 'a,
+    O2: 'a,
+    PO: Parser<I, O2, ParserError<C, I>> + 'a,
+{
+    Box::new(move |input: I| {
+        let (rest, _) = op.parse(input.clone())?;
+        let index = input.offset(&rest);
+        Ok((rest, input.slice(..index)))
+    })
+}
+
+fn match_op<C, I, F>(
+    ops: &mut [Op<'_, C, I, F>],
+    input: I,
+) -> Result<Option<(I, usize, I)>, nom::Err<ParserError<C, I>>>
+where
+    I: Clone,
+{
+    for (idx, op) in ops.iter_mut().enumerate() {
+        match (op.parser)(input.clone()) {
+            Ok((rest, token)) => return Ok(Some((rest, idx, token))),
+            Err(nom::Err::Error(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+fn span<I>(start: &I, rest: &I) -> I
+where
+    I: Offset + Slice<RangeTo<usize>>,
+{
+    start.slice(..start.offset(rest))
+}
+
+impl<'a, C, I, O, PA> Pratt<'a, C, I, O, PA>
+where
+    C: Code + 'a,
+    I: Clone + Offset + Slice<RangeTo<usize>> + 'a,
+    PA: Parser<I, O, ParserError<C, I>>,
+{
+    /// Adds a prefix operator.
+    ///
+    /// The binding power is the precedence level, higher levels bind tighter.
+    pub fn prefix<PO, O2, FN>(mut self, code: C, op: PO, bp: u32, fold: FN) -> Self
+    where
+        PO: Parser<I, O2, ParserError<C, I>> + 'a,
+        O2: 'a,
+        FN: Fn(I, I, O) -> O + 'a,
+    {
+        self.prefix.push(Op {
+            code,
+            parser: op_parser(op),
+            lbp: 0,
+            rbp: 2 * bp,
+            fold: Box::new(fold),
+        });
+        self
+    }
+
+    /// Adds an infix operator.
+    ///
+    /// The binding power is the precedence level, higher levels bind tighter.
+    pub fn infix<PO, O2, FN>(mut self, code: C, op: PO, bp: u32, assoc: Assoc, fold: FN) -> Self
+    where
+        PO: Parser<I, O2, ParserError<C, I>> + 'a,
+        O2: 'a,
+        FN: Fn(I, O, I, O) -> O + 'a,
+    {
+        self.infix.push(Op {
+            code,
+            parser: op_parser(op),
+            lbp: 2 * bp,
+            rbp: match assoc {
+                Assoc::Left => 2 * bp + 1,
+                Assoc::Right => 2 * bp,
+            },
+            fold: Box::new(fold),
+        });
+        self
+    }
+
+    /// Adds a postfix operator.
+    ///
+    /// The binding power is the precedence level, higher levels bind tighter.
+    pub fn postfix<PO, O2, FN>(mut self, code: C, op: PO, bp: u32, fold: FN) -> Self
+    where
+        PO: Parser<I, O2, ParserError<C, I>> + 'a,
+        O2: 'a,
+        FN: Fn(I, O, I) -> O + 'a,
+    {
+        self.postfix.push(Op {
+            code,
+            parser: op_parser(op),
+            lbp: 2 * bp,
+            rbp: 0,
+            fold: Box::new(fold),
+        });
+        self
+    }
+
+    fn expr(&mut self, input: I, min_bp: u32) -> ParserResult<C, I, O> {
+        let start = input.clone();
+
+        let (mut rest, mut lhs) = match match_op(&mut self.prefix, input.clone())? {
+            Some((rest, idx, op)) => {
+                let (code, rbp) = (self.prefix[idx].code, self.prefix[idx].rbp);
+                let (rest, rhs) = self.operand_after(rest, code, op.clone(), rbp)?;
+                let lhs = (self.prefix[idx].fold)(span(&start, &rest), op, rhs);
+                (rest, lhs)
+            }
+            None => self.operand.parse(input)?,
+        };
+
+        loop {
+            if let Some((rest2, idx, op)) = match_op(&mut self.postfix, rest.clone())? {
+                if self.postfix[idx].lbp < min_bp {
+                    break;
+                }
+                lhs = (self.postfix[idx].fold)(span(&start, &rest2), lhs, op);
+                rest = rest2;
+                continue;
+            }
+
+            if let Some((rest2, idx, op)) = match_op(&mut self.infix, rest.clone())? {
+                if self.infix[idx].lbp < min_bp {
+                    break;
+                }
+                let (code, rbp) = (self.infix[idx].code, self.infix[idx].rbp);
+                let (rest2, rhs) = self.operand_after(rest2, code, op.clone(), rbp)?;
+                lhs = (self.infix[idx].fold)(span(&start, &rest2), lhs, op, rhs);
+                rest = rest2;
+                continue;
+            }
+
+            break;
+        }
+
+        Ok((rest, lhs))
+    }
+
+    fn operand_after(&mut self, input: I, code: C, op: I, min_bp: u32) -> ParserResult<C, I, O> {
+        match self.expr(input.clone(), min_bp) {
+            Ok(v) => Ok(v),
+            Err(nom::Err::Error(e)) => {
+                let mut err = ParserError::new(self.code, input);
+                err.expect(code, op);
+                err.append_err(e);
+                Err(nom::Err::Error(err))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a, C, I, O, PA> Parser<I, O, ParserError<C, I>> for Pratt<'a, C, I, O, PA>
+where
+    C: Code + 'a,
+    I: Clone + Debug + Offset + Slice<RangeTo<usize>> + TrackedSpan<C> + 'a,
+    I: SpanFragment + InputTake + InputLength + InputIter,
+    PA: Parser<I, O, ParserError<C, I>>,
+{
+    fn parse(&mut self, input: I) -> ParserResult<C, I, O> {
+        input.track_enter(self.code);
+        match self.expr(input.clone(), 0) {
+            Ok((rest, value)) => {
+                rest.track_ok(input);
+                rest.track_exit();
+                Ok((rest, value))
+            }
+            Err(nom::Err::Error(e)) => {
+                e.span.track_err_expected(e.code, &e, || {
+                    e.iter_expected().map(|v| (v.code, v.span)).collect()
+                });
+                e.span.track_exit();
+                Err(nom::Err::Error(e))
+            }
+            Err(nom::Err::Failure(e)) => {
+                e.span.track_err_expected(e.code, &e, || {
+                    e.iter_expected().map(|v| (v.code, v.span)).collect()
+                });
+                e.span.track_exit();
+                Err(nom::Err::Failure(e))
+            }
+            Err(nom::Err::Incomplete(e)) => {
+                input.track_info("incomplete");
+                input.track_exit();
+                Err(nom::Err::Incomplete(e))
+            }
+        }
+    }
+}
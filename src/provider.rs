@@ -1,10 +1,20 @@
 use crate::debug::tracks::debug_tracks;
 use crate::{Code, DynTrackProvider};
+#[cfg(feature = "std")]
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::any::Any;
+use core::cell::{Cell, Ref, RefCell};
+use core::fmt::{Debug, Formatter};
+#[cfg(feature = "std")]
+use core::ops::Range;
+use core::ops::{RangeFrom, RangeTo};
 use nom::{AsBytes, InputIter, InputLength, InputTake, Offset, Slice};
 use nom_locate::LocatedSpan;
-use std::cell::RefCell;
-use std::fmt::{Debug, Formatter};
-use std::ops::{RangeFrom, RangeTo};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeStruct, SerializeStructVariant, Serializer};
 
 /// Data packet for the Tracker.
 #[derive(Debug)]
@@ -18,14 +28,17 @@ where
     Exit(),
     /// Ok result
     Ok(LocatedSpan<T, ()>, LocatedSpan<T, ()>),
-    /// Err result
-    Err(LocatedSpan<T, ()>, C, String),
+    /// Err result. Span, code and the expected codes of the error. The Debug
+    /// output of the error, if the provider keeps it, see [TrackProvider::err_text].
+    Err(LocatedSpan<T, ()>, C, Vec<(C, LocatedSpan<T, ()>)>, String),
     /// Warning
     Warn(LocatedSpan<T, ()>, &'static str),
     /// General info
     Info(LocatedSpan<T, ()>, &'static str),
     /// Debug info
     Debug(LocatedSpan<T, ()>, String),
+    /// Parsed value. The pretty Debug output of the value.
+    Value(LocatedSpan<T, ()>, String),
 }
 
 /// Provides the tracking functionality backend.
@@ -44,24 +57,36 @@ where
 
     /// Collects the tracking data. Use Track.xxx()
     fn track(&self, data: TrackData<C, T>);
+
+    /// Keep the Debug output of tracked errors.
+    ///
+    /// Formatting the error is expensive and often the trace is never
+    /// printed. Without it the trace shows code, span and the expected codes.
+    fn err_text(&self) -> bool {
+        true
+    }
 }
 
 impl<'c, C, T> Debug for DynTrackProvider<'c, C, T>
 where
     C: Code,
 {
-    fn fmt(&self, _: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, _: &mut Formatter<'_>) -> core::fmt::Result {
         Ok(())
     }
 }
 
+/// One recorded event.
 #[derive(Debug)]
 pub struct TrackedData<C, I>
 where
     C: Code,
 {
+    /// Current function.
     pub func: C,
+    /// All functions entered so far, the current one last.
     pub callstack: Vec<C>,
+    /// The event.
     pub track: TrackData<C, I>,
 }
 
@@ -80,24 +105,143 @@ where
         + Slice<RangeFrom<usize>>
         + Slice<RangeTo<usize>>,
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         debug_tracks(f, f.width().into(), &self.0)
     }
 }
 
+/// Serializes all events in order. Each span becomes its offset, line and
+/// fragment.
+#[cfg(feature = "serde")]
+impl<C, I> Serialize for TrackedDataVec<C, I>
+where
+    C: Code + Serialize,
+    I: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<C, I> Serialize for TrackedData<C, I>
+where
+    C: Code + Serialize,
+    I: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("TrackedData", 3)?;
+        s.serialize_field("func", &self.func)?;
+        s.serialize_field("callstack", &self.callstack)?;
+        s.serialize_field("track", &self.track)?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<C, I> Serialize for TrackData<C, I>
+where
+    C: Code + Serialize,
+    I: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TrackData::Enter(func, span) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 0, "Enter", 2)?;
+                s.serialize_field("func", func)?;
+                s.serialize_field("span", &SerSpan(span))?;
+                s.end()
+            }
+            TrackData::Exit() => serializer.serialize_unit_variant("TrackData", 1, "Exit"),
+            TrackData::Ok(rest, input) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 2, "Ok", 2)?;
+                s.serialize_field("rest", &SerSpan(rest))?;
+                s.serialize_field("input", &SerSpan(input))?;
+                s.end()
+            }
+            TrackData::Err(span, code, expected, err) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 3, "Err", 4)?;
+                s.serialize_field("span", &SerSpan(span))?;
+                s.serialize_field("code", code)?;
+                s.serialize_field("expected", &SerExpected(expected))?;
+                s.serialize_field("err", err)?;
+                s.end()
+            }
+            TrackData::Warn(span, warn) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 4, "Warn", 2)?;
+                s.serialize_field("span", &SerSpan(span))?;
+                s.serialize_field("warn", warn)?;
+                s.end()
+            }
+            TrackData::Info(span, info) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 5, "Info", 2)?;
+                s.serialize_field("span", &SerSpan(span))?;
+                s.serialize_field("info", info)?;
+                s.end()
+            }
+            TrackData::Debug(span, debug) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 6, "Debug", 2)?;
+                s.serialize_field("span", &SerSpan(span))?;
+                s.serialize_field("debug", debug)?;
+                s.end()
+            }
+            TrackData::Value(span, value) => {
+                let mut s = serializer.serialize_struct_variant("TrackData", 7, "Value", 2)?;
+                s.serialize_field("span", &SerSpan(span))?;
+                s.serialize_field("value", value)?;
+                s.end()
+            }
+        }
+    }
+}
+
+/// Span as offset, line and fragment.
+#[cfg(feature = "serde")]
+struct SerSpan<'a, T>(&'a LocatedSpan<T, ()>);
+
+/// Expected codes as a list of (code, span).
+#[cfg(feature = "serde")]
+struct SerExpected<'a, C, T>(&'a [(C, LocatedSpan<T, ()>)]);
+
+#[cfg(feature = "serde")]
+impl<C, T> Serialize for SerExpected<'_, C, T>
+where
+    C: Serialize,
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|(code, span)| (code, SerSpan(span))))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Serialize for SerSpan<'_, T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Span", 3)?;
+        s.serialize_field("offset", &self.0.location_offset())?;
+        s.serialize_field("line", &self.0.location_line())?;
+        s.serialize_field("fragment", self.0.fragment())?;
+        s.end()
+    }
+}
+
 #[derive(Debug)]
 pub struct StdTracker<C, T>
 where
-    T: AsBytes + Clone,
+    T: Clone,
     C: Code,
 {
     data: RefCell<StdTracks<C, T>>,
+    err_text: Cell<bool>,
 }
 
 #[derive(Debug)]
 struct StdTracks<C, T>
 where
-    T: AsBytes + Clone,
+    T: Clone,
     C: Code,
 {
     func: Vec<C>,
@@ -106,16 +250,28 @@ where
 
 impl<C, T> StdTracker<C, T>
 where
-    T: AsBytes + Clone,
+    T: Clone,
     C: Code,
 {
     /// Creates a context for a given span.
+    ///
+    /// Keeps the Debug output of errors, see with_err_text().
     pub fn new() -> Self {
         Self {
             data: Default::default(),
+            err_text: Cell::new(true),
         }
     }
 
+    /// Keep the Debug output of the tracked errors for the trace.
+    ///
+    /// Without it the error isn't formatted while parsing, the trace
+    /// shows code, span and the expected codes instead.
+    pub fn with_err_text(self, err_text: bool) -> Self {
+        self.err_text.set(err_text);
+        self
+    }
+
     // enter function
     fn push_func(&self, func: C) {
         self.data.borrow_mut().func.push(func);
@@ -136,6 +292,28 @@ where
             .expect("Vec<FnCode> is empty. forgot to trace.enter()")
     }
 
+    /// The warnings so far, with the code of the function that issued them.
+    ///
+    /// Leaves the tracking data in place.
+    pub fn warnings(&self) -> Vec<(C, &'static str)> {
+        self.data
+            .borrow()
+            .track
+            .iter()
+            .filter_map(|v| match v.track {
+                TrackData::Warn(_, msg) => Some((v.func, msg)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The tracking data so far.
+    ///
+    /// Leaves the tracking data in place.
+    pub fn tracks(&self) -> Ref<'_, [TrackedData<C, T>]> {
+        Ref::map(self.data.borrow(), |v| v.track.as_slice())
+    }
+
     fn callstack(&self) -> Vec<C> {
         self.data.borrow().func.clone()
     }
@@ -153,7 +331,7 @@ where
 
 impl<C, T> TrackProvider<C, T> for StdTracker<C, T>
 where
-    T: AsBytes + Clone,
+    T: Clone,
     C: Code,
 {
     /// Create a new Span from this context using the original str.
@@ -171,6 +349,10 @@ where
         TrackedDataVec(self.data.replace(StdTracks::default()).track)
     }
 
+    fn err_text(&self) -> bool {
+        self.err_text.get()
+    }
+
     fn track(&self, data: TrackData<C, T>) {
         match &data {
             TrackData::Enter(func, _) => {
@@ -182,10 +364,11 @@ where
                 self.pop_func();
             }
             TrackData::Ok(_, _)
-            | TrackData::Err(_, _, _)
+            | TrackData::Err(_, _, _, _)
             | TrackData::Warn(_, _)
             | TrackData::Info(_, _)
-            | TrackData::Debug(_, _) => {
+            | TrackData::Debug(_, _)
+            | TrackData::Value(_, _) => {
                 self.append_track(data);
             }
         }
@@ -194,7 +377,7 @@ where
 
 impl<C, T> Default for StdTracker<C, T>
 where
-    T: AsBytes + Clone,
+    T: Clone,
     C: Code,
 {
     fn default() -> Self {
@@ -204,7 +387,7 @@ where
 
 impl<C, T> Default for StdTracks<C, T>
 where
-    T: AsBytes + Clone,
+    T: Clone,
     C: Code,
 {
     fn default() -> Self {
@@ -214,3 +397,182 @@ where
         }
     }
 }
+
+#[cfg(feature = "std")]
+thread_local! {
+    static LOCAL_TRACKER: RefCell<Option<Rc<dyn Any>>> = const { RefCell::new(None) };
+}
+
+/// Tracker that is looked up from a thread-local instead of being carried
+/// in the span.
+///
+/// With this the span is a plain `LocatedSpan<&str, ()>` in debug and release
+/// builds. Creating the tracker installs it for the current thread, dropping
+/// it reinstates the previous one. Tracking only happens in debug builds.
+///
+/// The events only record offset, line and length of the spans, results()
+/// needs the original text to restore them.
+///
+/// ```rust
+/// use kparse::examples::ExCode;
+/// use kparse::prelude::*;
+/// use kparse::ParserError;
+/// use nom_locate::LocatedSpan;
+///
+/// let tracker = Track::new_local_tracker::<ExCode>();
+/// let span = LocatedSpan::new("1 + 2");
+/// Track.enter(ExCode::ExNumber, span);
+/// Track.ok::<ExCode, _, _, ParserError<ExCode, _>>(span, span, ()).unwrap();
+/// println!("{:?}", tracker.results("1 + 2"));
+/// ```
+///
+/// Needs the feature std.
+#[cfg(feature = "std")]
+pub struct LocalTracker<C>
+where
+    C: Code + 'static,
+{
+    tracker: Rc<StdTracker<C, usize>>,
+    prev: Option<Rc<dyn Any>>,
+}
+
+#[cfg(feature = "std")]
+impl<C> LocalTracker<C>
+where
+    C: Code + 'static,
+{
+    /// Creates the tracker and installs it for the current thread.
+    ///
+    /// Keeps the Debug output of errors, see with_err_text().
+    pub fn new() -> Self {
+        let tracker = Rc::new(StdTracker::new());
+        let local: Rc<dyn Any> = tracker.clone();
+        let prev = LOCAL_TRACKER.with(|v| v.replace(Some(local)));
+        Self { tracker, prev }
+    }
+
+    /// Keep the Debug output of the tracked errors for the trace.
+    ///
+    /// Without it the error isn't formatted while parsing, the trace
+    /// shows code, span and the expected codes instead.
+    pub fn with_err_text(self, err_text: bool) -> Self {
+        self.tracker.err_text.set(err_text);
+        self
+    }
+
+    /// The warnings so far, with the code of the function that issued them.
+    ///
+    /// Leaves the tracking data in place.
+    pub fn warnings(&self) -> Vec<(C, &'static str)> {
+        self.tracker.warnings()
+    }
+
+    /// Extract the tracking results. The text must be the one the
+    /// spans were created from.
+    ///
+    /// Removes the result from the context.
+    pub fn results<T>(&self, text: T) -> TrackedDataVec<C, T>
+    where
+        T: Clone + Slice<Range<usize>>,
+    {
+        let span = |v: LocatedSpan<usize, ()>| {
+            let start = v.location_offset();
+            let end = start + *v.fragment();
+            // Safety: offset and line are taken from a span of the same text.
+            unsafe {
+                LocatedSpan::new_from_raw_offset(
+                    start,
+                    v.location_line(),
+                    text.slice(start..end),
+                    (),
+                )
+            }
+        };
+
+        TrackedDataVec(
+            self.tracker
+                .results()
+                .0
+                .into_iter()
+                .map(|v| TrackedData {
+                    func: v.func,
+                    callstack: v.callstack,
+                    track: match v.track {
+                        TrackData::Enter(func, s) => TrackData::Enter(func, span(s)),
+                        TrackData::Exit() => TrackData::Exit(),
+                        TrackData::Ok(s, p) => TrackData::Ok(span(s), span(p)),
+                        TrackData::Err(s, code, exp, err) => TrackData::Err(
+                            span(s),
+                            code,
+                            exp.into_iter().map(|(c, s)| (c, span(s))).collect(),
+                            err,
+                        ),
+                        TrackData::Warn(s, warn) => TrackData::Warn(span(s), warn),
+                        TrackData::Info(s, info) => TrackData::Info(span(s), info),
+                        TrackData::Debug(s, debug) => TrackData::Debug(span(s), debug),
+                        TrackData::Value(s, value) => TrackData::Value(span(s), value),
+                    },
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C> Default for LocalTracker<C>
+where
+    C: Code + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C> Drop for LocalTracker<C>
+where
+    C: Code + 'static,
+{
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        LOCAL_TRACKER.with(|v| v.replace(prev));
+    }
+}
+
+/// Sends the event to the thread-local tracker, if there is one for
+/// this code type. The flag tells whether the tracker keeps the error text.
+#[cfg(all(debug_assertions, feature = "std"))]
+pub(crate) fn track_local<C>(data: impl FnOnce(bool) -> TrackData<C, usize>)
+where
+    C: Code + 'static,
+{
+    LOCAL_TRACKER.with(|v| {
+        if let Some(tracker) = v
+            .borrow()
+            .as_ref()
+            .and_then(|v| v.downcast_ref::<StdTracker<C, usize>>())
+        {
+            tracker.track(data(tracker.err_text.get()));
+        }
+    });
+}
+
+/// Offset, line and length of the span.
+#[cfg(all(debug_assertions, feature = "std"))]
+pub(crate) fn local_span<T>(span: &LocatedSpan<T, ()>) -> LocatedSpan<usize, ()>
+where
+    T: InputLength,
+{
+    location_span(
+        span.location_offset(),
+        span.location_line(),
+        span.fragment().input_len(),
+    )
+}
+
+/// Span for the thread-local tracker from offset, line and length.
+#[cfg(all(debug_assertions, feature = "std"))]
+pub(crate) fn location_span(offset: usize, line: u32, len: usize) -> LocatedSpan<usize, ()> {
+    // Safety: only offset and line are kept, the fragment is the length.
+    unsafe { LocatedSpan::new_from_raw_offset(offset, line, len, ()) }
+}
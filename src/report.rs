@@ -0,0 +1,1056 @@
+//!
+//! Diagnostics for a ParserError.
+//!
+//! [Diagnostics] renders the error with the lines around it, a caret for each
+//! expected code, the suggestions and the nom error kind. It can render all the
+//! errors collected during error recovery in one go, too.
+//!
+//! [RustcStyle] renders the error in the style of rustc.
+//!
+//! [explain] renders the long form help text of a [CodeExplain].
+//!
+//! [to_json_diagnostics] converts the error to JSON for other tools.
+//!
+//! [to_json_bundle] packs the errors, the recorded tracks and the source
+//! lines around the errors in one JSON for a trace viewer or a bug report.
+//!
+//! ```rust
+//! use kparse::examples::{ExAorB, ExTagA};
+//! use kparse::report::Diagnostics;
+//! use kparse::ParserError;
+//! use kparse::Track;
+//!
+//! let text = "first\nsecond x";
+//! let txt = Track::source_str(text);
+//!
+//! let mut err = ParserError::new(ExAorB, &text[13..]);
+//! err.expect(ExTagA, &text[13..]);
+//!
+//! Diagnostics::new()
+//!     .prefix_error("warning")
+//!     .dump(&txt, "plan.txt", &err, "");
+//! ```
+//!
+
+use crate::provider::{TrackData, TrackedData};
+use crate::source::{Source, SourceStr};
+use crate::{Code, CodeDisplay, CodeExplain, CodeKind, ParserError, StdCodeDisplay};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Write};
+use nom::AsBytes;
+use nom_locate::LocatedSpan;
+#[cfg(feature = "std")]
+use std::env;
+#[cfg(feature = "std")]
+use std::io::{self, IsTerminal};
+
+/// Severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Error
+    Error,
+    /// Warning
+    Warning,
+    /// Information
+    Info,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
+
+/// Settings for rendering a ParserError.
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostics<'a, D = StdCodeDisplay> {
+    stderr: bool,
+    context: usize,
+    error: &'a str,
+    warning: &'a str,
+    expected: &'a str,
+    suggested: &'a str,
+    nom: &'a str,
+    max_errors: usize,
+    verbose: bool,
+    codes: D,
+}
+
+impl<'a> Default for Diagnostics<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Diagnostics<'a> {
+    /// Writes to stdout with 3 lines of context.
+    pub fn new() -> Self {
+        Self {
+            stderr: false,
+            context: 3,
+            error: "error",
+            warning: "warning",
+            expected: "expected",
+            suggested: "hint",
+            nom: "nom",
+            max_errors: 10,
+            verbose: false,
+            codes: StdCodeDisplay,
+        }
+    }
+}
+
+impl<'a, D> Diagnostics<'a, D> {
+    /// Renders the codes with this instead of their Display.
+    pub fn code_display<D2>(self, codes: D2) -> Diagnostics<'a, D2> {
+        Diagnostics {
+            stderr: self.stderr,
+            context: self.context,
+            error: self.error,
+            warning: self.warning,
+            expected: self.expected,
+            suggested: self.suggested,
+            nom: self.nom,
+            max_errors: self.max_errors,
+            verbose: self.verbose,
+            codes,
+        }
+    }
+
+    /// Writes to stderr instead of stdout.
+    pub fn stderr(mut self, stderr: bool) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    /// Number of lines before and after the error.
+    pub fn context(mut self, n: usize) -> Self {
+        self.context = n;
+        self
+    }
+
+    /// Prefix for the first line. Use something like "warning" to report
+    /// an error that doesn't stop the processing.
+    pub fn prefix_error(mut self, prefix: &'a str) -> Self {
+        self.error = prefix;
+        self
+    }
+
+    /// Prefix for warnings in [format_all](Diagnostics::format_all).
+    pub fn prefix_warning(mut self, prefix: &'a str) -> Self {
+        self.warning = prefix;
+        self
+    }
+
+    /// Prefix for each expected code.
+    pub fn prefix_expected(mut self, prefix: &'a str) -> Self {
+        self.expected = prefix;
+        self
+    }
+
+    /// Prefix for each suggested code.
+    pub fn prefix_suggested(mut self, prefix: &'a str) -> Self {
+        self.suggested = prefix;
+        self
+    }
+
+    /// Prefix for the nom error kind.
+    pub fn prefix_nom(mut self, prefix: &'a str) -> Self {
+        self.nom = prefix;
+        self
+    }
+
+    /// Shows the expected codes of [CodeKind::Token] too.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Maximum number of errors and warnings for
+    /// [format_all](Diagnostics::format_all). Defaults to 10.
+    pub fn max_errors(mut self, n: usize) -> Self {
+        self.max_errors = n;
+        self
+    }
+
+    /// Writes the diagnostics to stdout or stderr.
+    ///
+    /// The name is the name of the source, e.g. the file name. If the message is
+    /// empty the error code is used instead.
+    #[cfg(feature = "std")]
+    pub fn dump<C, I, S>(&self, source: &S, name: &str, err: &ParserError<C, I>, msg: &str)
+    where
+        C: Code,
+        I: Clone,
+        S: Source<I>,
+        S::Result: AsBytes,
+        D: CodeDisplay<C>,
+    {
+        let txt = self.format(source, name, err, msg);
+        if self.stderr {
+            eprint!("{}", txt);
+        } else {
+            print!("{}", txt);
+        }
+    }
+
+    /// Renders the diagnostics.
+    ///
+    /// The name is the name of the source, e.g. the file name. If the message is
+    /// empty the error code is used instead.
+    pub fn format<C, I, S>(
+        &self,
+        source: &S,
+        name: &str,
+        err: &ParserError<C, I>,
+        msg: &str,
+    ) -> String
+    where
+        C: Code,
+        I: Clone,
+        S: Source<I>,
+        S::Result: AsBytes,
+        D: CodeDisplay<C>,
+    {
+        let mut buf = String::new();
+        let _ = self.write(&mut buf, source, name, err, msg);
+        buf
+    }
+
+    /// Writes the diagnostics for all errors and warnings to stdout or stderr.
+    ///
+    /// See [format_all](Diagnostics::format_all).
+    #[cfg(feature = "std")]
+    pub fn dump_all<C, I, S>(
+        &self,
+        source: &S,
+        name: &str,
+        errors: &[ParserError<C, I>],
+        warnings: &[ParserError<C, I>],
+    ) where
+        C: Code,
+        I: Clone,
+        S: Source<I>,
+        S::Result: AsBytes,
+        D: CodeDisplay<C>,
+    {
+        let txt = self.format_all(source, name, errors, warnings);
+        if self.stderr {
+            eprint!("{}", txt);
+        } else {
+            print!("{}", txt);
+        }
+    }
+
+    /// Renders the diagnostics for all errors and warnings, e.g. the ones
+    /// collected by [retry_with_sync](crate::combinators::retry_with_sync).
+    ///
+    /// They are sorted by position, and the lines around them are shown once
+    /// for all. Errors with a code of [Severity::Warning] count as warnings. Renders at most [max_errors](Diagnostics::max_errors) of them
+    /// and ends with "and M more" for the rest.
+    pub fn format_all<C, I, S>(
+        &self,
+        source: &S,
+        name: &str,
+        errors: &[ParserError<C, I>],
+        warnings: &[ParserError<C, I>],
+    ) -> String
+    where
+        C: Code,
+        I: Clone,
+        S: Source<I>,
+        S::Result: AsBytes,
+        D: CodeDisplay<C>,
+    {
+        let mut buf = String::new();
+        let _ = self.write_all(&mut buf, source, name, errors, warnings);
+        buf
+    }
+
+    fn write_all<C, I, S>(
+        &self,
+        f: &mut impl Write,
+        source: &S,
+        name: &str,
+        errors: &[ParserError<C, I>],
+        warnings: &[ParserError<C, I>],
+    ) -> core::fmt::Result
+    where
+        C: Code,
+        I: Clone,
+        S: Source<I>,
+        S::Result: AsBytes,
+        D: CodeDisplay<C>,
+    {
+        let mut all = errors
+            .iter()
+            .map(|v| match v.code.severity() {
+                Severity::Warning => (self.warning, v),
+                _ => (self.error, v),
+            })
+            .chain(warnings.iter().map(|v| (self.warning, v)))
+            .map(|(prefix, v)| (source.offset(v.span.clone()), prefix, v))
+            .collect::<Vec<_>>();
+        // stable, errors stay before warnings at the same offset.
+        all.sort_by_key(|(offset, _, _)| *offset);
+        let more = all.len().saturating_sub(self.max_errors);
+        all.truncate(self.max_errors);
+
+        // line -> text, and line -> (column, message) for the marks below.
+        let mut lines = BTreeMap::new();
+        let mut marks = BTreeMap::<usize, Vec<(usize, String)>>::new();
+        for (_, prefix, err) in &all {
+            let loc = source.location(err.span.clone());
+            for line in loc.line.saturating_sub(self.context).max(1)..=loc.line + self.context {
+                if let Some(text) = source.line_span(line) {
+                    lines.insert(line, text);
+                }
+            }
+
+            let mut msg = format!("{}: {}", prefix, self.codes.display(err.code));
+            for sug in err.iter_suggested() {
+                let _ = write!(
+                    msg,
+                    "\n{}: {}",
+                    self.suggested,
+                    self.codes.display(sug.code)
+                );
+            }
+            for rep in err.iter_replacements() {
+                let _ = write!(msg, "\n{}: did you mean `{}`?", self.suggested, rep.text);
+            }
+            marks.entry(loc.line).or_default().push((loc.column, msg));
+
+            for exp in err
+                .iter_expected()
+                .filter(|v| self.verbose || v.code.kind() != CodeKind::Token)
+            {
+                let loc = source.location(exp.span);
+                if let Some(text) = source.line_span(loc.line) {
+                    lines.insert(loc.line, text);
+                }
+                marks.entry(loc.line).or_default().push((
+                    loc.column,
+                    format!("{}: {}", self.expected, self.codes.display(exp.code)),
+                ));
+            }
+        }
+
+        writeln!(f, "{}:", name)?;
+        let mut last_line = None;
+        for (line, text) in lines {
+            if let Some(last_line) = last_line {
+                if line > last_line + 1 {
+                    writeln!(f, "...")?;
+                }
+            }
+            last_line = Some(line);
+
+            let text = String::from_utf8_lossy(text.as_bytes());
+            let text = text.trim_end_matches(['\n', '\r']);
+            match marks.get_mut(&line) {
+                Some(marks) => {
+                    writeln!(f, "*{:04} {}", line, text)?;
+                    marks.sort_by_key(|(column, _)| *column);
+                    for (column, msg) in marks.iter() {
+                        writeln!(f, "      {}^", " ".repeat(*column))?;
+                        writeln!(f, "{}", msg)?;
+                    }
+                }
+                None => {
+                    writeln!(f, " {:04} {}", line, text)?;
+                }
+            }
+        }
+
+        if more > 0 {
+            writeln!(f, "and {} more", more)?;
+        }
+
+        Ok(())
+    }
+
+    fn write<C, I, S>(
+        &self,
+        f: &mut impl Write,
+        source: &S,
+        name: &str,
+        err: &ParserError<C, I>,
+        msg: &str,
+    ) -> core::fmt::Result
+    where
+        C: Code,
+        I: Clone,
+        S: Source<I>,
+        S::Result: AsBytes,
+        D: CodeDisplay<C>,
+    {
+        if msg.is_empty() {
+            writeln!(
+                f,
+                "{}: {}: {}",
+                self.error,
+                name,
+                self.codes.display(err.code)
+            )?;
+        } else {
+            writeln!(f, "{}: {}: {}", self.error, name, msg)?;
+        }
+
+        let err_line = source.line(err.span.clone());
+        let expected = err
+            .iter_expected()
+            .filter(|v| self.verbose || v.code.kind() != CodeKind::Token)
+            .map(|v| {
+                let loc = source.location(v.span);
+                (loc.line, loc.column, v.code)
+            })
+            .collect::<Vec<_>>();
+
+        for (line, text, _) in source.get_numbered_lines_around(err.span.clone(), self.context) {
+            let line = line as usize;
+            let text = String::from_utf8_lossy(text.as_bytes());
+            let text = text.trim_end_matches(['\n', '\r']);
+            if line == err_line {
+                writeln!(f, "*{:04} {}", line, text)?;
+            } else {
+                writeln!(f, " {:04} {}", line, text)?;
+            }
+
+            if expected.is_empty() && line == err_line {
+                let column = source.column(err.span.clone());
+                writeln!(f, "      {}^", " ".repeat(column))?;
+                if msg.is_empty() {
+                    writeln!(f, "{}: {}", self.expected, self.codes.display(err.code))?;
+                } else {
+                    writeln!(f, "{}: {}", self.expected, msg)?;
+                }
+            }
+            for (exp_line, exp_column, exp_code) in &expected {
+                if *exp_line == line {
+                    writeln!(f, "      {}^", " ".repeat(*exp_column))?;
+                    writeln!(f, "{}: {}", self.expected, self.codes.display(*exp_code))?;
+                }
+            }
+        }
+
+        for sug in err.iter_suggested() {
+            writeln!(f, "{}: {}", self.suggested, self.codes.display(sug.code))?;
+        }
+        for rep in err.iter_replacements() {
+            writeln!(f, "{}: did you mean `{}`?", self.suggested, rep.text)?;
+        }
+        if let Some(kind) = err.nom() {
+            let loc = source.location(err.span.clone());
+            writeln!(f, "{}: {:?} at {}:{}", self.nom, kind, loc.line, loc.column)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the error in the style of rustc.
+///
+/// ```text
+/// error[ExAorB]: A | B
+///  --> plan.txt:2:8
+///   |
+/// 2 | second x
+///   |        ^ A | B
+///   |        - expected a
+///   |
+///   = help: suggested b
+/// ```
+///
+/// The error and each expected code get a label below their line.
+/// Uses ANSI colors unless the environment variable NO_COLOR is set or
+/// stderr is no terminal. Tabs are not expanded.
+#[derive(Debug, Clone, Copy)]
+pub struct RustcStyle<D = StdCodeDisplay> {
+    severity: Option<Severity>,
+    color: bool,
+    explain_cmd: &'static str,
+    verbose: bool,
+    codes: D,
+}
+
+impl Default for RustcStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+fn use_color() -> bool {
+    env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal()
+}
+
+#[cfg(not(feature = "std"))]
+fn use_color() -> bool {
+    false
+}
+
+impl RustcStyle {
+    /// Renders an error.
+    pub fn new() -> Self {
+        Self {
+            severity: None,
+            color: use_color(),
+            explain_cmd: "",
+            verbose: false,
+            codes: StdCodeDisplay,
+        }
+    }
+}
+
+impl<D> RustcStyle<D> {
+    const RESET: &'static str = "\x1b[0m";
+    const BOLD: &'static str = "\x1b[1m";
+    const RED: &'static str = "\x1b[1;31m";
+    const YELLOW: &'static str = "\x1b[1;33m";
+    const GREEN: &'static str = "\x1b[1;32m";
+    const BLUE: &'static str = "\x1b[1;34m";
+
+    /// Renders the codes with this instead of their Display.
+    pub fn code_display<D2>(self, codes: D2) -> RustcStyle<D2> {
+        RustcStyle {
+            severity: self.severity,
+            color: self.color,
+            explain_cmd: self.explain_cmd,
+            verbose: self.verbose,
+            codes,
+        }
+    }
+
+    /// Shows the expected codes of [CodeKind::Token] too.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Changes the severity. Defaults to the severity of the error code.
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Switches the colors on or off.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The command that shows the explanation of a code, used by format_explained().
+    pub fn explain_cmd(mut self, cmd: &'static str) -> Self {
+        self.explain_cmd = cmd;
+        self
+    }
+
+    fn paint(&self, color: &'static str) -> &'static str {
+        if self.color {
+            color
+        } else {
+            ""
+        }
+    }
+
+    /// Writes the error to stderr.
+    #[cfg(feature = "std")]
+    pub fn dump<'s, C, I>(&self, source: &SourceStr<'s>, name: &str, err: &ParserError<C, I>)
+    where
+        C: Code,
+        I: Clone,
+        SourceStr<'s>: Source<I>,
+        D: CodeDisplay<C>,
+    {
+        eprint!("{}", self.format(source, name, err));
+    }
+
+    /// Renders the error.
+    pub fn format<'s, C, I>(
+        &self,
+        source: &SourceStr<'s>,
+        name: &str,
+        err: &ParserError<C, I>,
+    ) -> String
+    where
+        C: Code,
+        I: Clone,
+        SourceStr<'s>: Source<I>,
+        D: CodeDisplay<C>,
+    {
+        let mut buf = String::new();
+        let _ = self.write(&mut buf, source, name, err);
+        buf
+    }
+
+    /// Writes the error to stderr, with a hint to the explanation of the code.
+    #[cfg(feature = "std")]
+    pub fn dump_explained<'s, C, I>(
+        &self,
+        source: &SourceStr<'s>,
+        name: &str,
+        err: &ParserError<C, I>,
+    ) where
+        C: CodeExplain,
+        I: Clone,
+        SourceStr<'s>: Source<I>,
+        D: CodeDisplay<C>,
+    {
+        eprint!("{}", self.format_explained(source, name, err));
+    }
+
+    /// Renders the error. If there is an explanation for the code, adds a hint
+    /// how to show it.
+    ///
+    /// ```text
+    ///
+    /// For more information about this error, try `plan --explain ExAorB`.
+    /// ```
+    pub fn format_explained<'s, C, I>(
+        &self,
+        source: &SourceStr<'s>,
+        name: &str,
+        err: &ParserError<C, I>,
+    ) -> String
+    where
+        C: CodeExplain,
+        I: Clone,
+        SourceStr<'s>: Source<I>,
+        D: CodeDisplay<C>,
+    {
+        let mut buf = self.format(source, name, err);
+        if err.code.explain().is_some() {
+            let cmd = if self.explain_cmd.is_empty() {
+                String::new()
+            } else {
+                format!("{} ", self.explain_cmd)
+            };
+            let _ = writeln!(
+                buf,
+                "\n{}For more information about this error, try `{}--explain {:?}`.{}",
+                self.paint(Self::BOLD),
+                cmd,
+                err.code,
+                self.paint(Self::RESET)
+            );
+        }
+        buf
+    }
+
+    fn write<'s, C, I>(
+        &self,
+        f: &mut impl Write,
+        source: &SourceStr<'s>,
+        name: &str,
+        err: &ParserError<C, I>,
+    ) -> core::fmt::Result
+    where
+        C: Code,
+        I: Clone,
+        SourceStr<'s>: Source<I>,
+        D: CodeDisplay<C>,
+    {
+        let level = self.severity.unwrap_or(err.code.severity());
+        let severity = match level {
+            Severity::Error => self.paint(Self::RED),
+            Severity::Warning => self.paint(Self::YELLOW),
+            Severity::Info => self.paint(Self::GREEN),
+        };
+        let bold = self.paint(Self::BOLD);
+        let blue = self.paint(Self::BLUE);
+        let reset = self.paint(Self::RESET);
+
+        // (line, column, primary, label)
+        let loc = source.location(err.span.clone());
+        let mut labels = vec![(loc.line, loc.column, true, self.codes.display(err.code))];
+        for exp in err
+            .iter_expected()
+            .filter(|v| self.verbose || v.code.kind() != CodeKind::Token)
+        {
+            let exp_loc = source.location(exp.span);
+            labels.push((
+                exp_loc.line,
+                exp_loc.column,
+                false,
+                format!("expected {}", self.codes.display(exp.code)),
+            ));
+        }
+        let mut lines = labels.iter().map(|v| v.0).collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        let width = lines.last().copied().unwrap_or(1).to_string().len();
+        let gutter = " ".repeat(width);
+
+        writeln!(
+            f,
+            "{}{}[{:?}]{}{}: {}{}",
+            severity,
+            level,
+            err.code,
+            reset,
+            bold,
+            self.codes.display(err.code),
+            reset
+        )?;
+        writeln!(
+            f,
+            "{}{}--> {}{}:{}:{}",
+            gutter,
+            blue,
+            reset,
+            name,
+            loc.line,
+            loc.column + 1
+        )?;
+        writeln!(f, "{}{} |{}", gutter, blue, reset)?;
+
+        let mut last_line = None;
+        for line in lines {
+            if matches!(last_line, Some(last) if last + 1 < line) {
+                writeln!(f, "{}...{}", blue, reset)?;
+            }
+            last_line = Some(line);
+
+            let text = <SourceStr<'s> as Source<&str>>::line_span(source, line).unwrap_or("");
+            let text = text.trim_end_matches(['\n', '\r']);
+            writeln!(
+                f,
+                "{}{:>width$} |{} {}",
+                blue,
+                line,
+                reset,
+                text,
+                width = width
+            )?;
+
+            let mut line_labels = labels.iter().filter(|v| v.0 == line).collect::<Vec<_>>();
+            line_labels.sort_by_key(|v| (v.1, !v.2));
+            for (_, column, primary, label) in line_labels {
+                let (mark, color) = if *primary {
+                    ("^", severity)
+                } else {
+                    ("-", blue)
+                };
+                writeln!(
+                    f,
+                    "{}{} |{} {}{}{} {}{}",
+                    gutter,
+                    blue,
+                    reset,
+                    " ".repeat(*column),
+                    color,
+                    mark,
+                    label,
+                    reset
+                )?;
+            }
+        }
+
+        if err.iter_suggested().next().is_some() || err.iter_replacements().next().is_some() {
+            writeln!(f, "{}{} |{}", gutter, blue, reset)?;
+        }
+        for sug in err.iter_suggested() {
+            writeln!(
+                f,
+                "{}{} = {}{}help{}: suggested {}",
+                gutter,
+                blue,
+                reset,
+                bold,
+                reset,
+                self.codes.display(sug.code)
+            )?;
+        }
+        for rep in err.iter_replacements() {
+            writeln!(
+                f,
+                "{}{} = {}{}help{}: did you mean `{}`?",
+                gutter, blue, reset, bold, reset, rep.text
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the explanation of the code.
+///
+/// Returns None if there is none.
+pub fn explain<C: CodeExplain>(code: C) -> Option<String> {
+    code.explain()
+        .map(|text| format!("{:?}: {}\n\n{}\n", code, code, text.trim_end()))
+}
+
+/// Finds the code by its name, as given to `--explain`, and renders the explanation.
+///
+/// The names are compared with the Debug output of the codes.
+pub fn explain_name<C: CodeExplain>(name: &str, codes: &[C]) -> Option<String> {
+    codes
+        .iter()
+        .find(|v| format!("{:?}", v) == name)
+        .and_then(|v| explain(*v))
+}
+
+/// Converts the error to a single line of JSON.
+///
+/// The format is stable and doesn't depend on the internal types:
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "severity": "error" | "warning" | "info",
+///   "code": string,       // Debug of the code, e.g. "ExAorB"
+///   "id": number,         // Code::id(), only if there is one
+///   "message": string,    // Display of the code
+///   "span": Span,
+///   "expected": [ { "code": string, "id": number, "message": string, "span": Span } ],
+///   "suggested": [ { "code": string, "id": number, "message": string, "span": Span } ]
+/// }
+///
+/// Span = { "offset": number, "line": number, "column": number }
+/// ```
+///
+/// The offset is in bytes. Lines start at 1, columns at 0, as with [Source].
+pub fn to_json_diagnostics<C, I, S>(
+    source: &S,
+    err: &ParserError<C, I>,
+    severity: Severity,
+) -> String
+where
+    C: Code,
+    I: Clone,
+    S: Source<I>,
+{
+    to_json_diagnostics_with(source, err, severity, &StdCodeDisplay)
+}
+
+/// Same as [to_json_diagnostics], the messages come from the [CodeDisplay].
+pub fn to_json_diagnostics_with<C, I, S, D>(
+    source: &S,
+    err: &ParserError<C, I>,
+    severity: Severity,
+    codes: &D,
+) -> String
+where
+    C: Code,
+    I: Clone,
+    S: Source<I>,
+    D: CodeDisplay<C>,
+{
+    let hint = |code: C, span: I| {
+        format!(
+            "{{\"code\":{},{}\"message\":{},\"span\":{}}}",
+            json_str(Some(&format!("{:?}", code))),
+            json_id(code),
+            json_str(Some(&codes.display(code))),
+            json_span(source, span)
+        )
+    };
+    let expected = err
+        .iter_expected()
+        .map(|v| hint(v.code, v.span))
+        .collect::<Vec<_>>();
+    let suggested = err
+        .iter_suggested()
+        .map(|v| hint(v.code, v.span))
+        .collect::<Vec<_>>();
+
+    format!(
+        "{{\"version\":1,\"severity\":\"{}\",\"code\":{},{}\"message\":{},\"span\":{},\"expected\":[{}],\"suggested\":[{}]}}",
+        severity,
+        json_str(Some(&format!("{:?}", err.code))),
+        json_id(err.code),
+        json_str(Some(&codes.display(err.code))),
+        json_span(source, err.span.clone()),
+        expected.join(","),
+        suggested.join(",")
+    )
+}
+
+/// Packs the errors, the tracks and the source lines around the errors
+/// into one JSON.
+///
+/// ```json
+/// {"format":"kparse-bundle","version":1,"name":"plan.txt",
+///  "errors":[...],
+///  "tracks":[{"event":"enter","func":"ExAorB","depth":1,"offset":0,"line":1},...],
+///  "source":[{"line":1,"offset":0,"text":"first\u000a"},...]}
+/// ```
+///
+/// The errors are the same as with [to_json_diagnostics], with the severity
+/// of their code. The tracks are the
+/// recorded events in order, the depth is the length of the callstack.
+/// Events have an "offset" and a "line" except for "exit", an "ok" has the
+/// "len" of the parsed span, an "err" the "code", a "message" and the
+/// "expected" codes if there are any, and all others a "message".
+/// The source contains the line of each error and the given number of
+/// lines before and after it, with the line end.
+///
+/// The tracks can be taken from [Test::tracks](crate::test::Test::tracks)
+/// or [StdTracker::tracks](crate::provider::StdTracker::tracks).
+pub fn to_json_bundle<C, I, S, T>(
+    source: &S,
+    name: &str,
+    errors: &[ParserError<C, I>],
+    tracks: &[TrackedData<C, T>],
+    context: usize,
+) -> String
+where
+    C: Code,
+    I: Clone,
+    S: Source<I>,
+    S::Result: AsBytes,
+    T: AsBytes,
+{
+    let json_errors = errors
+        .iter()
+        .map(|v| to_json_diagnostics(source, v, v.code.severity()))
+        .collect::<Vec<_>>();
+
+    let json_tracks = tracks.iter().map(json_track).collect::<Vec<_>>();
+
+    let mut lines = BTreeMap::new();
+    for err in errors {
+        let line = source.line(err.span.clone());
+        for (n, text, range) in source.get_numbered_lines_around(err.span.clone(), context) {
+            if n as usize <= line + context {
+                lines.insert(n, (range.start, text));
+            }
+        }
+    }
+    let json_source = lines
+        .into_iter()
+        .map(|(n, (offset, text))| {
+            format!(
+                "{{\"line\":{},\"offset\":{},\"text\":{}}}",
+                n,
+                offset,
+                json_str(Some(&String::from_utf8_lossy(text.as_bytes())))
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "{{\"format\":\"kparse-bundle\",\"version\":1,\"name\":{},\"errors\":[{}],\"tracks\":[{}],\"source\":[{}]}}",
+        json_str(Some(name)),
+        json_errors.join(","),
+        json_tracks.join(","),
+        json_source.join(",")
+    )
+}
+
+fn json_track<C: Code, T: AsBytes>(track: &TrackedData<C, T>) -> String {
+    let pos = |span: &LocatedSpan<T, ()>| {
+        format!(
+            ",\"offset\":{},\"line\":{}",
+            span.location_offset(),
+            span.location_line()
+        )
+    };
+    let (event, rest) = match &track.track {
+        TrackData::Enter(_, span) => ("enter", pos(span)),
+        TrackData::Exit() => ("exit", String::new()),
+        TrackData::Ok(_, parsed) => (
+            "ok",
+            format!(
+                "{},\"len\":{}",
+                pos(parsed),
+                parsed.fragment().as_bytes().len()
+            ),
+        ),
+        TrackData::Err(span, code, expected, msg) => (
+            "err",
+            format!(
+                "{},\"code\":{},\"message\":{}{}",
+                pos(span),
+                json_str(Some(&format!("{:?}", code))),
+                json_str(Some(msg)),
+                if expected.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        ",\"expected\":[{}]",
+                        expected
+                            .iter()
+                            .map(|(code, span)| format!(
+                                "{{\"code\":{}{}}}",
+                                json_str(Some(&format!("{:?}", code))),
+                                pos(span)
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    )
+                }
+            ),
+        ),
+        TrackData::Warn(span, msg) => (
+            "warn",
+            format!("{},\"message\":{}", pos(span), json_str(Some(msg))),
+        ),
+        TrackData::Info(span, msg) => (
+            "info",
+            format!("{},\"message\":{}", pos(span), json_str(Some(msg))),
+        ),
+        TrackData::Debug(span, msg) => (
+            "debug",
+            format!("{},\"message\":{}", pos(span), json_str(Some(msg))),
+        ),
+        TrackData::Value(span, value) => (
+            "value",
+            format!("{},\"message\":{}", pos(span), json_str(Some(value))),
+        ),
+    };
+    format!(
+        "{{\"event\":\"{}\",\"func\":{},\"depth\":{}{}}}",
+        event,
+        json_str(Some(&format!("{:?}", track.func))),
+        track.callstack.len(),
+        rest
+    )
+}
+
+/// The id field with a trailing comma, or nothing.
+fn json_id<C: Code>(code: C) -> String {
+    match code.id() {
+        Some(id) => format!("\"id\":{},", id),
+        None => String::new(),
+    }
+}
+
+fn json_span<I, S: Source<I>>(source: &S, span: I) -> String {
+    let loc = source.location(span);
+    format!(
+        "{{\"offset\":{},\"line\":{},\"column\":{}}}",
+        loc.offset, loc.line, loc.column
+    )
+}
+
+/// JSON string or null.
+pub(crate) fn json_str(v: Option<&str>) -> String {
+    match v {
+        Some(v) => {
+            let mut buf = String::from("\"");
+            for c in v.chars() {
+                match c {
+                    '"' => buf.push_str("\\\""),
+                    '\\' => buf.push_str("\\\\"),
+                    c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => buf.push(c),
+                }
+            }
+            buf.push('"');
+            buf
+        }
+        None => "null".into(),
+    }
+}
@@ -0,0 +1,743 @@
+//!
+//! Input from a ropey::Rope. Enabled with the feature `ropey`.
+//!
+//! An editor that keeps its buffer as a rope can run the parser without
+//! copying the text into one String first.
+//!
+//! * [RopeSpan] is the input for the parser. It works with the nom
+//!   combinators for text, tag, take, char, take_while, digit1, etc.
+//!   Offsets and lengths are in bytes, as for `&str`.
+//! * [SourceRope] implements [Source] for it.
+//! * RopeSpan tracks with the [LocalTracker](crate::provider::LocalTracker).
+//!   The results need the text as `&str`, flatten the rope for the trace.
+//!
+//! The lines are the lines of ropey. With the default features of ropey
+//! there are more line breaks than "\n", "\r\n" and "\r", and
+//! [with_separator](Source::with_separator) can't change that.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExNumber, ExTagA};
+//! use kparse::prelude::*;
+//! use kparse::ropey::{RopeSpan, SourceRope};
+//! use kparse::ParserError;
+//! use nom::bytes::complete::tag;
+//! use nom::character::complete::digit1;
+//! use nom::{Parser, Slice};
+//! use ropey::Rope;
+//!
+//! type Error<'r> = ParserError<ExCode, RopeSpan<'r>>;
+//!
+//! fn parse_a_num(i: RopeSpan<'_>) -> nom::IResult<RopeSpan<'_>, u32, Error<'_>> {
+//!     let (rest, _) = tag("a").with_code(ExTagA).parse(i)?;
+//!     digit1.parse_from_str(ExNumber).parse(rest)
+//! }
+//!
+//! let rope = Rope::from_str("a12\na3x");
+//! let (rest, v) = parse_a_num(RopeSpan::new(rope.slice(..))).expect("ok");
+//! assert_eq!(v, 12);
+//!
+//! let err = parse_a_num(rest).expect_err("err");
+//! assert_eq!(err.code(), Some(ExTagA));
+//!
+//! let source = SourceRope::new(rope.slice(..));
+//! assert_eq!(source.line(rest), 1);
+//! assert_eq!(source.line(rest.slice(1..)), 2);
+//! ```
+//!
+
+#[cfg(debug_assertions)]
+use crate::provider::{location_span, track_local, TrackData};
+#[cfg(debug_assertions)]
+use crate::report::Severity;
+use crate::source::{Source, SourceLocation};
+use crate::spans::SpanFragment;
+use crate::{Code, TrackedSpan};
+#[cfg(debug_assertions)]
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+use core::iter::Rev;
+use core::ops::{Range, RangeFrom, RangeFull, RangeTo};
+use core::str::FromStr;
+use nom::{
+    Compare, CompareResult, FindSubstring, FindToken, InputIter, InputLength, InputTake, Needed,
+    Offset, ParseTo, Slice, UnspecializedInput,
+};
+#[cfg(debug_assertions)]
+use nom_locate::LocatedSpan;
+use ropey::iter::Chars;
+use ropey::{Rope, RopeSlice};
+
+/// Input for a parser over a rope.
+///
+/// Holds the complete text and the part of it that is still to parse.
+#[derive(Clone, Copy)]
+pub struct RopeSpan<'r> {
+    rope: RopeSlice<'r>,
+    offset: usize,
+    fragment: RopeSlice<'r>,
+}
+
+impl<'r> RopeSpan<'r> {
+    /// New input for the whole text.
+    pub fn new(rope: RopeSlice<'r>) -> Self {
+        Self {
+            rope,
+            offset: 0,
+            fragment: rope,
+        }
+    }
+
+    /// The complete text.
+    pub fn rope(&self) -> RopeSlice<'r> {
+        self.rope
+    }
+
+    /// Byte offset within the complete text.
+    pub fn location_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Line within the complete text. Lines start at 1.
+    pub fn location_line(&self) -> u32 {
+        self.rope.byte_to_line(self.offset) as u32 + 1
+    }
+
+    /// Offset, line and length for the tracker.
+    #[cfg(debug_assertions)]
+    fn local_span(&self) -> LocatedSpan<usize, ()> {
+        location_span(self.offset, self.location_line(), self.input_len())
+    }
+}
+
+impl<'r> From<&'r Rope> for RopeSpan<'r> {
+    fn from(rope: &'r Rope) -> Self {
+        Self::new(rope.slice(..))
+    }
+}
+
+impl PartialEq for RopeSpan<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset && self.fragment == other.fragment
+    }
+}
+
+impl Debug for RopeSpan<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{:?}", self.offset, self.fragment.to_string())
+    }
+}
+
+impl Display for RopeSpan<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.fragment, f)
+    }
+}
+
+impl<'r> SpanFragment for RopeSpan<'r> {
+    type Result = RopeSlice<'r>;
+
+    fn fragment(&self) -> &Self::Result {
+        &self.fragment
+    }
+}
+
+impl InputLength for RopeSpan<'_> {
+    fn input_len(&self) -> usize {
+        self.fragment.len_bytes()
+    }
+}
+
+impl InputTake for RopeSpan<'_> {
+    fn take(&self, count: usize) -> Self {
+        self.slice(..count)
+    }
+
+    fn take_split(&self, count: usize) -> (Self, Self) {
+        (self.slice(count..), self.slice(..count))
+    }
+}
+
+/// Chars with their byte offset.
+#[derive(Debug, Clone)]
+pub struct RopeCharIndices<'r> {
+    chars: Chars<'r>,
+    pos: usize,
+}
+
+impl Iterator for RopeCharIndices<'_> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.chars.next()?;
+        let pos = self.pos;
+        self.pos += c.len_utf8();
+        Some((pos, c))
+    }
+}
+
+impl<'r> InputIter for RopeSpan<'r> {
+    type Item = char;
+    type Iter = RopeCharIndices<'r>;
+    type IterElem = Chars<'r>;
+
+    fn iter_indices(&self) -> Self::Iter {
+        RopeCharIndices {
+            chars: self.fragment.chars(),
+            pos: 0,
+        }
+    }
+
+    fn iter_elements(&self) -> Self::IterElem {
+        self.fragment.chars()
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.iter_indices()
+            .find(|(_, c)| predicate(*c))
+            .map(|(pos, _)| pos)
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        let mut cnt = 0;
+        for (index, _) in self.iter_indices() {
+            if cnt == count {
+                return Ok(index);
+            }
+            cnt += 1;
+        }
+        if cnt == count {
+            return Ok(self.input_len());
+        }
+        Err(Needed::Unknown)
+    }
+}
+
+impl UnspecializedInput for RopeSpan<'_> {}
+
+impl Offset for RopeSpan<'_> {
+    fn offset(&self, second: &Self) -> usize {
+        second.offset - self.offset
+    }
+}
+
+/// Byte ranges, same as for `&str`.
+///
+/// # Panics
+/// If the range is out of bounds or doesn't fall on char boundaries.
+impl Slice<Range<usize>> for RopeSpan<'_> {
+    fn slice(&self, range: Range<usize>) -> Self {
+        Self {
+            rope: self.rope,
+            offset: self.offset + range.start,
+            fragment: self.fragment.byte_slice(range),
+        }
+    }
+}
+
+impl Slice<RangeFrom<usize>> for RopeSpan<'_> {
+    fn slice(&self, range: RangeFrom<usize>) -> Self {
+        self.slice(range.start..self.input_len())
+    }
+}
+
+impl Slice<RangeTo<usize>> for RopeSpan<'_> {
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        self.slice(0..range.end)
+    }
+}
+
+impl Slice<RangeFull> for RopeSpan<'_> {
+    fn slice(&self, _range: RangeFull) -> Self {
+        *self
+    }
+}
+
+impl<'b> Compare<&'b str> for RopeSpan<'_> {
+    fn compare(&self, t: &'b str) -> CompareResult {
+        let mut bytes = self.fragment.bytes();
+        for b in t.bytes() {
+            match bytes.next() {
+                Some(v) if v == b => {}
+                Some(_) => return CompareResult::Error,
+                None => return CompareResult::Incomplete,
+            }
+        }
+        CompareResult::Ok
+    }
+
+    fn compare_no_case(&self, t: &'b str) -> CompareResult {
+        if self
+            .fragment
+            .chars()
+            .zip(t.chars())
+            .any(|(a, b)| a.to_lowercase().ne(b.to_lowercase()))
+        {
+            CompareResult::Error
+        } else if self.input_len() < t.len() {
+            CompareResult::Incomplete
+        } else {
+            CompareResult::Ok
+        }
+    }
+}
+
+impl<'b> FindSubstring<&'b str> for RopeSpan<'_> {
+    fn find_substring(&self, substr: &'b str) -> Option<usize> {
+        if substr.is_empty() {
+            Some(0)
+        } else {
+            find_from(self.fragment, substr.as_bytes(), 0)
+        }
+    }
+}
+
+impl FindToken<char> for RopeSpan<'_> {
+    fn find_token(&self, token: char) -> bool {
+        self.fragment.chars().any(|c| c == token)
+    }
+}
+
+impl<R> ParseTo<R> for RopeSpan<'_>
+where
+    R: FromStr,
+{
+    fn parse_to(&self) -> Option<R> {
+        self.fragment.to_string().parse().ok()
+    }
+}
+
+/// First occurrence of the needle at or after start.
+fn find_from(hay: RopeSlice<'_>, needle: &[u8], start: usize) -> Option<usize> {
+    let first = *needle.first()?;
+    let mut bytes = hay.bytes_at(start);
+    let mut pos = start;
+    while pos + needle.len() <= hay.len_bytes() {
+        let rest = bytes.clone();
+        if bytes.next()? == first && rest.take(needle.len()).eq(needle.iter().copied()) {
+            return Some(pos);
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Tracks with the thread-local [LocalTracker](crate::provider::LocalTracker).
+#[cfg(debug_assertions)]
+impl<C> TrackedSpan<C> for RopeSpan<'_>
+where
+    C: Code + 'static,
+{
+    #[inline(always)]
+    fn track_enter(&self, func: C) {
+        track_local::<C>(|_| TrackData::Enter(func, self.local_span()));
+    }
+
+    #[inline(always)]
+    fn track_debug(&self, debug: String) {
+        track_local::<C>(|_| TrackData::Debug(self.local_span(), debug));
+    }
+
+    #[inline(always)]
+    fn track_value<V: Debug>(&self, value: &V) {
+        track_local::<C>(|_| TrackData::Value(self.local_span(), format!("{:#?}", value)));
+    }
+
+    #[inline(always)]
+    fn track_info(&self, info: &'static str) {
+        track_local::<C>(|_| TrackData::Info(self.local_span(), info));
+    }
+
+    #[inline(always)]
+    fn track_warn(&self, warn: &'static str) {
+        track_local::<C>(|_| TrackData::Warn(self.local_span(), warn));
+    }
+
+    #[inline(always)]
+    fn track_ok(&self, parsed: Self) {
+        track_local::<C>(|_| TrackData::Ok(self.local_span(), parsed.local_span()));
+    }
+
+    #[inline(always)]
+    fn track_err<E: Debug>(&self, code: C, err: &E) {
+        self.track_err_expected(code, err, Vec::new);
+    }
+
+    #[inline(always)]
+    fn track_err_expected<E: Debug>(
+        &self,
+        code: C,
+        err: &E,
+        expected: impl FnOnce() -> Vec<(C, Self)>,
+    ) {
+        if code.severity() == Severity::Warning {
+            track_local::<C>(|_| TrackData::Warn(self.local_span(), "error with severity warning"));
+        }
+        track_local::<C>(|err_text| {
+            let expected = expected()
+                .iter()
+                .map(|(code, span)| (*code, span.local_span()))
+                .collect();
+            let text = if err_text {
+                format!("{:?}", err)
+            } else {
+                String::new()
+            };
+            TrackData::Err(self.local_span(), code, expected, text)
+        });
+    }
+
+    #[inline(always)]
+    fn track_exit(&self) {
+        track_local::<C>(|_| TrackData::Exit());
+    }
+}
+
+#[cfg(not(debug_assertions))]
+impl<C> TrackedSpan<C> for RopeSpan<'_>
+where
+    C: Code + 'static,
+{
+    #[inline(always)]
+    fn track_enter(&self, _func: C) {}
+
+    #[inline(always)]
+    fn track_debug(&self, _debug: String) {}
+
+    #[inline(always)]
+    fn track_info(&self, _info: &'static str) {}
+
+    #[inline(always)]
+    fn track_warn(&self, _warn: &'static str) {}
+
+    #[inline(always)]
+    fn track_ok(&self, _parsed: Self) {}
+
+    #[inline(always)]
+    fn track_err<E>(&self, _func: C, _err: &E) {}
+
+    #[inline(always)]
+    fn track_exit(&self) {}
+}
+
+/// Source for a rope. Uses the line index of the rope.
+#[derive(Debug, Clone, Copy)]
+pub struct SourceRope<'r> {
+    ascii: bool,
+    tab: usize,
+    rope: RopeSlice<'r>,
+}
+
+impl<'r> SourceRope<'r> {
+    /// New source for the text.
+    pub fn new(rope: RopeSlice<'r>) -> Self {
+        Self {
+            ascii: false,
+            tab: 1,
+            rope,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rope.len_bytes()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rope.len_bytes() == 0
+    }
+
+    /// Returns line/column for an offset into the rope.
+    ///
+    /// # Panics
+    /// If the offset is beyond the end of the rope.
+    pub fn position(&self, offset: usize) -> SourceLocation {
+        let line = self.rope.byte_to_line(offset);
+        SourceLocation {
+            offset,
+            line: line + 1,
+            column: self.width(self.rope.line_to_byte(line)..offset),
+        }
+    }
+
+    /// Returns the offset for a line/column. Lines start at 1, columns at 0.
+    ///
+    /// Returns None if the line doesn't exist or the column is beyond the end of the line.
+    pub fn offset_of(&self, line: usize, column: usize) -> Option<usize> {
+        if line == 0 || line > self.rope.len_lines() {
+            return None;
+        }
+        let start = self.rope.line_to_byte(line - 1);
+        // the last byte of the line end, same as SourceStr.
+        let end = if line < self.rope.len_lines() {
+            self.rope.line_to_byte(line) - 1
+        } else {
+            self.rope.len_bytes()
+        };
+
+        let mut col = 0;
+        for (i, b) in self.rope.byte_slice(start..end).bytes().enumerate() {
+            // skip utf8 continuation bytes.
+            if !self.ascii && b & 0xC0 == 0x80 {
+                continue;
+            }
+            // a column within an expanded tab maps to the tab.
+            let next = self.next_column(col, b);
+            if column < next {
+                return Some(start + i);
+            }
+            col = next;
+        }
+        if col == column {
+            Some(end)
+        } else {
+            None
+        }
+    }
+
+    /// Number of lines. There is no empty last line after a trailing line end.
+    fn line_count(&self) -> usize {
+        let lines = self.rope.len_lines();
+        if self.rope.line(lines - 1).len_bytes() == 0 {
+            lines - 1
+        } else {
+            lines
+        }
+    }
+
+    /// Line index starting at 0 of the offset.
+    fn line_index(&self, offset: usize) -> usize {
+        self.rope.byte_to_line(offset)
+    }
+
+    /// Byte range of the line including the line end.
+    fn line_range(&self, line_idx: usize) -> Range<usize> {
+        self.rope.line_to_byte(line_idx)..self.rope.line_to_byte(line_idx + 1)
+    }
+
+    /// Line index starting at 0 after the end of the fragment.
+    fn end_line_index(&self, fragment: &RopeSpan<'_>) -> usize {
+        self.line_index(fragment.offset + fragment.input_len())
+    }
+
+    fn span(&self, range: Range<usize>) -> RopeSpan<'r> {
+        RopeSpan::new(self.rope).slice(range)
+    }
+
+    fn lines(&self, lines: Range<usize>) -> RopeLines<'r> {
+        RopeLines {
+            source: *self,
+            next: lines.start,
+            end: lines.end.min(self.line_count()),
+        }
+    }
+
+    /// Width of the text in columns.
+    fn width(&self, range: Range<usize>) -> usize {
+        let mut col = 0;
+        for b in self.rope.byte_slice(range).bytes() {
+            // skip utf8 continuation bytes.
+            if !self.ascii && b & 0xC0 == 0x80 {
+                continue;
+            }
+            col = self.next_column(col, b);
+        }
+        col
+    }
+
+    /// Column after the byte.
+    fn next_column(&self, col: usize, b: u8) -> usize {
+        if b == b'\t' && self.tab > 1 {
+            col + self.tab - col % self.tab
+        } else {
+            col + 1
+        }
+    }
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'r, 'i> Source<RopeSpan<'i>> for SourceRope<'r> {
+    type Result = RopeSpan<'r>;
+
+    /// The lines of a rope are fixed.
+    ///
+    /// # Panics
+    /// For any separator other than '\n'.
+    fn with_separator(self, sep: u8) -> Self {
+        assert_eq!(sep, b'\n');
+        self
+    }
+
+    fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    fn with_tab_width(mut self, tab: usize) -> Self {
+        self.tab = tab;
+        self
+    }
+
+    fn offset(&self, fragment: RopeSpan<'i>) -> usize {
+        fragment.offset
+    }
+
+    fn line(&self, fragment: RopeSpan<'i>) -> usize {
+        self.line_index(fragment.offset) + 1
+    }
+
+    fn column(&self, fragment: RopeSpan<'i>) -> usize {
+        self.position(fragment.offset).column
+    }
+
+    fn location(&self, fragment: RopeSpan<'i>) -> SourceLocation {
+        self.position(fragment.offset)
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<RopeSpan<'r>> {
+        self.offset_of(line, column).map(|v| self.span(v..v))
+    }
+
+    fn get_lines_around(&self, fragment: RopeSpan<'i>, n: usize) -> Vec<RopeSpan<'r>> {
+        let mut buf: Vec<_> = self.backward_from(fragment).take(n).collect();
+        buf.reverse();
+        buf.extend(self.current(fragment));
+        buf.extend(self.forward_from(fragment).take(n));
+
+        buf
+    }
+
+    fn get_numbered_lines_around(
+        &self,
+        fragment: RopeSpan<'i>,
+        n: usize,
+    ) -> Vec<(u32, RopeSpan<'r>, Range<usize>)> {
+        let first = self.line_index(fragment.offset);
+        let last = if fragment.input_len() == 0 {
+            first
+        } else {
+            self.line_index(fragment.offset + fragment.input_len() - 1)
+        };
+        let max_line = self.line_count().max(last + 1);
+
+        (first.saturating_sub(n)..(last + n + 1).min(max_line))
+            .map(|line| {
+                let range = self.line_range(line);
+                (line as u32 + 1, self.span(range.clone()), range)
+            })
+            .collect()
+    }
+
+    fn start(&self, fragment: RopeSpan<'i>) -> RopeSpan<'r> {
+        self.span(self.line_range(self.line_index(fragment.offset)))
+    }
+
+    fn end(&self, fragment: RopeSpan<'i>) -> RopeSpan<'r> {
+        self.span(self.line_range(self.end_line_index(&fragment)))
+    }
+
+    fn span_for(&self, range: Range<usize>) -> RopeSpan<'r> {
+        self.span(range)
+    }
+
+    fn line_span(&self, line: usize) -> Option<RopeSpan<'r>> {
+        if line >= 1 && line <= self.line_count() {
+            Some(self.span(self.line_range(line - 1)))
+        } else {
+            None
+        }
+    }
+
+    fn split_lines(&self, fragment: RopeSpan<'i>) -> Vec<RopeSpan<'r>> {
+        let start = fragment.offset;
+        let end = start + fragment.input_len();
+        let first = self.line_index(start);
+        let last = if start == end {
+            first
+        } else {
+            self.line_index(end - 1)
+        };
+
+        (first..=last)
+            .map(|line| {
+                let bounds = self.line_range(line);
+                self.span(bounds.start.max(start)..bounds.end.min(end))
+            })
+            .collect()
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<RopeSpan<'r>> {
+        let is_char_boundary = |v: usize| v == self.len() || self.rope.byte(v) & 0xC0 != 0x80;
+
+        let mut found = Vec::new();
+        let mut pos = 0;
+        while let Some(start) = find_from(self.rope, needle, pos) {
+            pos = start + needle.len();
+            if is_char_boundary(start) && is_char_boundary(pos) {
+                found.push(self.span(start..pos));
+            }
+        }
+        found
+    }
+
+    type SpanIter<'it>
+        = RopeLines<'r>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = Rev<RopeLines<'r>>
+    where
+        Self: 'it;
+
+    fn current<'a>(&'a self, fragment: RopeSpan<'i>) -> Self::SpanIter<'a> {
+        self.lines(self.line_index(fragment.offset)..self.end_line_index(&fragment) + 1)
+    }
+
+    fn iter<'a>(&'a self) -> Self::SpanIter<'a> {
+        self.lines(0..self.line_count())
+    }
+
+    fn forward_from<'a>(&'a self, fragment: RopeSpan<'i>) -> Self::SpanIter<'a> {
+        self.lines(self.end_line_index(&fragment) + 1..self.line_count())
+    }
+
+    fn backward_from<'a>(&'a self, fragment: RopeSpan<'i>) -> Self::RSpanIter<'a> {
+        self.lines(0..self.line_index(fragment.offset)).rev()
+    }
+}
+
+/// Iterates lines.
+#[doc(hidden)]
+pub struct RopeLines<'r> {
+    source: SourceRope<'r>,
+    next: usize,
+    end: usize,
+}
+
+impl<'r> Iterator for RopeLines<'r> {
+    type Item = RopeSpan<'r>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            let line = self.next;
+            self.next += 1;
+            Some(self.source.span(self.source.line_range(line)))
+        } else {
+            None
+        }
+    }
+}
+
+impl DoubleEndedIterator for RopeLines<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            self.end -= 1;
+            Some(self.source.span(self.source.line_range(self.end)))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,559 @@
+//!
+//! Owned input.
+//!
+//! [SharedStr] is a `Arc<str>` and a byte range of it. It can be used
+//! wherever a `&str` is used as input, `LocatedSpan<SharedStr>` included.
+//! Slicing only clones the Arc, so parse results and errors can keep their
+//! spans and still be returned from a function that read the text itself.
+//!
+//! * [Track::new_span](crate::Track::new_span) takes a SharedStr as text.
+//!   Use [map_extra](LocatedSpan::map_extra) to drop the TrackProvider from the
+//!   spans before they leave the function, or use a plain LocatedSpan and the
+//!   [LocalTracker](crate::provider::LocalTracker).
+//! * [SourceStr] implements [Source] for SharedStr and `LocatedSpan<SharedStr>`.
+//!   Create it with the text of the same SharedStr, the spans it returns
+//!   borrow from the SourceStr.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExNumber, ExTagA};
+//! use kparse::prelude::*;
+//! use kparse::shared::SharedStr;
+//! use kparse::source::SourceStr;
+//! use kparse::ParserError;
+//! use nom::bytes::complete::tag;
+//! use nom::character::complete::digit1;
+//! use nom::Parser;
+//! use nom_locate::LocatedSpan;
+//!
+//! type Span = LocatedSpan<SharedStr>;
+//!
+//! fn parse_a_num(i: Span) -> nom::IResult<Span, Span, ParserError<ExCode, Span>> {
+//!     let (rest, _) = tag("a").with_code(ExTagA).parse(i)?;
+//!     digit1.with_code(ExNumber).parse(rest)
+//! }
+//!
+//! fn read(text: String) -> Result<Span, ParserError<ExCode, Span>> {
+//!     match parse_a_num(Span::new(SharedStr::from(text))) {
+//!         Ok((_, v)) => Ok(v),
+//!         Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(e),
+//!         Err(nom::Err::Incomplete(_)) => unreachable!(),
+//!     }
+//! }
+//!
+//! let num = read("a12".to_string()).expect("ok");
+//! assert_eq!(num.fragment().as_str(), "12");
+//!
+//! let err = read("ab".to_string()).expect_err("err");
+//! assert_eq!(err.code, ExNumber);
+//! let text = err.span.fragment().text();
+//! let source = SourceStr::new(&text);
+//! assert_eq!(source.column(err.span), 1);
+//! ```
+//!
+
+use crate::source::{
+    LocatedSpanStrIter, RLocatedSpanStrIter, RStrIter, Source, SourceLocation, SourceStr, StrIter,
+};
+use crate::spans::SpanFragment;
+use crate::{Code, TrackedSpan};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::ops::{Range, RangeFrom, RangeFull, RangeTo};
+use core::str::FromStr;
+use nom::{
+    AsBytes, Compare, CompareResult, FindSubstring, FindToken, InputIter, InputLength, InputTake,
+    Needed, Offset, ParseTo, Slice, UnspecializedInput,
+};
+use nom_locate::LocatedSpan;
+
+/// Input that owns its text.
+///
+/// Holds the complete text and the byte range that is still to parse.
+/// Compares, hashes and prints like the `&str` of that range.
+#[derive(Clone)]
+pub struct SharedStr {
+    text: Arc<str>,
+    start: usize,
+    end: usize,
+}
+
+impl SharedStr {
+    /// New input for the whole text.
+    pub fn new(text: Arc<str>) -> Self {
+        Self {
+            start: 0,
+            end: text.len(),
+            text,
+        }
+    }
+
+    /// The part of the text.
+    pub fn as_str(&self) -> &str {
+        &self.text[self.start..self.end]
+    }
+
+    /// The complete text.
+    pub fn text(&self) -> Arc<str> {
+        Arc::clone(&self.text)
+    }
+
+    /// Byte range within the complete text.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl From<Arc<str>> for SharedStr {
+    fn from(text: Arc<str>) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(text: String) -> Self {
+        Self::new(Arc::from(text))
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(text: &str) -> Self {
+        Self::new(Arc::from(text))
+    }
+}
+
+impl AsRef<str> for SharedStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for SharedStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SharedStr {}
+
+impl PartialEq<&str> for SharedStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for SharedStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl Debug for SharedStr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for SharedStr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl SpanFragment for SharedStr {
+    type Result = SharedStr;
+
+    fn fragment(&self) -> &Self::Result {
+        self
+    }
+}
+
+impl AsBytes for SharedStr {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl InputLength for SharedStr {
+    fn input_len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+impl InputTake for SharedStr {
+    fn take(&self, count: usize) -> Self {
+        self.slice(..count)
+    }
+
+    fn take_split(&self, count: usize) -> (Self, Self) {
+        (self.slice(count..), self.slice(..count))
+    }
+}
+
+/// Chars with their byte offset.
+#[derive(Debug, Clone)]
+pub struct SharedCharIndices {
+    text: SharedStr,
+    pos: usize,
+}
+
+impl Iterator for SharedCharIndices {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.text.as_str()[self.pos..].chars().next()?;
+        let pos = self.pos;
+        self.pos += c.len_utf8();
+        Some((pos, c))
+    }
+}
+
+/// Chars.
+#[derive(Debug, Clone)]
+pub struct SharedChars(SharedCharIndices);
+
+impl Iterator for SharedChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, c)| c)
+    }
+}
+
+impl InputIter for SharedStr {
+    type Item = char;
+    type Iter = SharedCharIndices;
+    type IterElem = SharedChars;
+
+    fn iter_indices(&self) -> Self::Iter {
+        SharedCharIndices {
+            text: self.clone(),
+            pos: 0,
+        }
+    }
+
+    fn iter_elements(&self) -> Self::IterElem {
+        SharedChars(self.iter_indices())
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.as_str().position(predicate)
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        self.as_str().slice_index(count)
+    }
+}
+
+impl UnspecializedInput for SharedStr {}
+
+impl Offset for SharedStr {
+    fn offset(&self, second: &Self) -> usize {
+        second.start - self.start
+    }
+}
+
+/// Byte ranges, same as for `&str`.
+///
+/// # Panics
+/// If the range is out of bounds or doesn't fall on char boundaries.
+impl Slice<Range<usize>> for SharedStr {
+    fn slice(&self, range: Range<usize>) -> Self {
+        // checks the range.
+        let _ = &self.as_str()[range.clone()];
+        Self {
+            text: Arc::clone(&self.text),
+            start: self.start + range.start,
+            end: self.start + range.end,
+        }
+    }
+}
+
+impl Slice<RangeFrom<usize>> for SharedStr {
+    fn slice(&self, range: RangeFrom<usize>) -> Self {
+        self.slice(range.start..self.input_len())
+    }
+}
+
+impl Slice<RangeTo<usize>> for SharedStr {
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        self.slice(0..range.end)
+    }
+}
+
+impl Slice<RangeFull> for SharedStr {
+    fn slice(&self, _range: RangeFull) -> Self {
+        self.clone()
+    }
+}
+
+impl<'b> Compare<&'b str> for SharedStr {
+    fn compare(&self, t: &'b str) -> CompareResult {
+        self.as_str().compare(t)
+    }
+
+    fn compare_no_case(&self, t: &'b str) -> CompareResult {
+        self.as_str().compare_no_case(t)
+    }
+}
+
+impl<'b> FindSubstring<&'b str> for SharedStr {
+    fn find_substring(&self, substr: &'b str) -> Option<usize> {
+        self.as_str().find_substring(substr)
+    }
+}
+
+impl FindToken<char> for SharedStr {
+    fn find_token(&self, token: char) -> bool {
+        self.as_str().find_token(token)
+    }
+}
+
+impl<R> ParseTo<R> for SharedStr
+where
+    R: FromStr,
+{
+    fn parse_to(&self) -> Option<R> {
+        self.as_str().parse().ok()
+    }
+}
+
+/// No tracking, same as `&str`.
+impl<C> TrackedSpan<C> for SharedStr
+where
+    C: Code,
+{
+    #[inline(always)]
+    fn track_enter(&self, _func: C) {}
+
+    #[inline(always)]
+    fn track_debug(&self, _debug: String) {}
+
+    #[inline(always)]
+    fn track_info(&self, _info: &'static str) {}
+
+    #[inline(always)]
+    fn track_warn(&self, _warn: &'static str) {}
+
+    #[inline(always)]
+    fn track_ok(&self, _input: Self) {}
+
+    #[inline(always)]
+    fn track_err<E>(&self, _func: C, _err: &E) {}
+
+    #[inline(always)]
+    fn track_exit(&self) {}
+}
+
+/// The fragment as `LocatedSpan<&str>`. SourceStr only looks at the
+/// address of the fragment, offset and line are not needed.
+fn located_str<Y>(span: &LocatedSpan<SharedStr, Y>) -> LocatedSpan<&str, ()> {
+    LocatedSpan::new(span.fragment().as_str())
+}
+
+/// Delegates to the `&str` implementation.
+#[allow(clippy::needless_lifetimes)]
+impl<'s> Source<SharedStr> for SourceStr<'s> {
+    type Result = &'s str;
+
+    fn with_separator(self, sep: u8) -> Self {
+        Source::<&str>::with_separator(self, sep)
+    }
+
+    fn with_ascii(self, ascii: bool) -> Self {
+        Source::<&str>::with_ascii(self, ascii)
+    }
+
+    fn with_tab_width(self, tab: usize) -> Self {
+        Source::<&str>::with_tab_width(self, tab)
+    }
+
+    fn offset(&self, fragment: SharedStr) -> usize {
+        Source::<&str>::offset(self, fragment.as_str())
+    }
+
+    fn line(&self, fragment: SharedStr) -> usize {
+        Source::<&str>::line(self, fragment.as_str())
+    }
+
+    fn column(&self, fragment: SharedStr) -> usize {
+        Source::<&str>::column(self, fragment.as_str())
+    }
+
+    fn location(&self, fragment: SharedStr) -> SourceLocation {
+        Source::<&str>::location(self, fragment.as_str())
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<&'s str> {
+        Source::<&str>::span_at(self, line, column)
+    }
+
+    fn get_lines_around(&self, fragment: SharedStr, n: usize) -> Vec<&'s str> {
+        Source::<&str>::get_lines_around(self, fragment.as_str(), n)
+    }
+
+    fn get_numbered_lines_around(
+        &self,
+        fragment: SharedStr,
+        n: usize,
+    ) -> Vec<(u32, &'s str, Range<usize>)> {
+        Source::<&str>::get_numbered_lines_around(self, fragment.as_str(), n)
+    }
+
+    fn start(&self, fragment: SharedStr) -> &'s str {
+        Source::<&str>::start(self, fragment.as_str())
+    }
+
+    fn end(&self, fragment: SharedStr) -> &'s str {
+        Source::<&str>::end(self, fragment.as_str())
+    }
+
+    fn span_for(&self, range: Range<usize>) -> &'s str {
+        Source::<&str>::span_for(self, range)
+    }
+
+    fn line_span(&self, line: usize) -> Option<&'s str> {
+        Source::<&str>::line_span(self, line)
+    }
+
+    fn split_lines(&self, fragment: SharedStr) -> Vec<&'s str> {
+        Source::<&str>::split_lines(self, fragment.as_str())
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<&'s str> {
+        Source::<&str>::find_all(self, needle)
+    }
+
+    type SpanIter<'it>
+        = StrIter<'s>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = RStrIter<'s>
+    where
+        Self: 'it;
+
+    fn current<'a>(&'a self, fragment: SharedStr) -> Self::SpanIter<'a> {
+        Source::<&str>::current(self, fragment.as_str())
+    }
+
+    fn iter<'a>(&'a self) -> Self::SpanIter<'a> {
+        Source::<&str>::iter(self)
+    }
+
+    fn forward_from<'a>(&'a self, fragment: SharedStr) -> Self::SpanIter<'a> {
+        Source::<&str>::forward_from(self, fragment.as_str())
+    }
+
+    fn backward_from<'a>(&'a self, fragment: SharedStr) -> Self::RSpanIter<'a> {
+        Source::<&str>::backward_from(self, fragment.as_str())
+    }
+}
+
+/// Delegates to the `LocatedSpan<&str>` implementation.
+#[allow(clippy::needless_lifetimes)]
+impl<'s, Y> Source<LocatedSpan<SharedStr, Y>> for SourceStr<'s> {
+    type Result = LocatedSpan<&'s str, ()>;
+
+    fn with_separator(self, sep: u8) -> Self {
+        Source::<LocatedSpan<&str, ()>>::with_separator(self, sep)
+    }
+
+    fn with_ascii(self, ascii: bool) -> Self {
+        Source::<LocatedSpan<&str, ()>>::with_ascii(self, ascii)
+    }
+
+    fn with_tab_width(self, tab: usize) -> Self {
+        Source::<LocatedSpan<&str, ()>>::with_tab_width(self, tab)
+    }
+
+    fn offset(&self, fragment: LocatedSpan<SharedStr, Y>) -> usize {
+        self.offset(located_str(&fragment))
+    }
+
+    fn line(&self, fragment: LocatedSpan<SharedStr, Y>) -> usize {
+        self.line(located_str(&fragment))
+    }
+
+    fn column(&self, fragment: LocatedSpan<SharedStr, Y>) -> usize {
+        self.column(located_str(&fragment))
+    }
+
+    fn location(&self, fragment: LocatedSpan<SharedStr, Y>) -> SourceLocation {
+        self.location(located_str(&fragment))
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<LocatedSpan<&'s str, ()>> {
+        Source::<LocatedSpan<&str, ()>>::span_at(self, line, column)
+    }
+
+    fn get_lines_around(
+        &self,
+        fragment: LocatedSpan<SharedStr, Y>,
+        n: usize,
+    ) -> Vec<LocatedSpan<&'s str, ()>> {
+        self.get_lines_around(located_str(&fragment), n)
+    }
+
+    fn get_numbered_lines_around(
+        &self,
+        fragment: LocatedSpan<SharedStr, Y>,
+        n: usize,
+    ) -> Vec<(u32, LocatedSpan<&'s str, ()>, Range<usize>)> {
+        self.get_numbered_lines_around(located_str(&fragment), n)
+    }
+
+    fn start(&self, fragment: LocatedSpan<SharedStr, Y>) -> LocatedSpan<&'s str, ()> {
+        self.start(located_str(&fragment))
+    }
+
+    fn end(&self, fragment: LocatedSpan<SharedStr, Y>) -> LocatedSpan<&'s str, ()> {
+        self.end(located_str(&fragment))
+    }
+
+    fn span_for(&self, range: Range<usize>) -> LocatedSpan<&'s str, ()> {
+        Source::<LocatedSpan<&str, ()>>::span_for(self, range)
+    }
+
+    fn line_span(&self, line: usize) -> Option<LocatedSpan<&'s str, ()>> {
+        Source::<LocatedSpan<&str, ()>>::line_span(self, line)
+    }
+
+    fn split_lines(&self, fragment: LocatedSpan<SharedStr, Y>) -> Vec<LocatedSpan<&'s str, ()>> {
+        self.split_lines(located_str(&fragment))
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<LocatedSpan<&'s str, ()>> {
+        Source::<LocatedSpan<&str, ()>>::find_all(self, needle)
+    }
+
+    type SpanIter<'it>
+        = LocatedSpanStrIter<'it, 's>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = RLocatedSpanStrIter<'it, 's>
+    where
+        Self: 'it;
+
+    fn current<'a>(&'a self, fragment: LocatedSpan<SharedStr, Y>) -> Self::SpanIter<'a> {
+        self.current(located_str(&fragment))
+    }
+
+    fn iter<'a>(&'a self) -> Self::SpanIter<'a> {
+        Source::<LocatedSpan<&str, ()>>::iter(self)
+    }
+
+    fn forward_from<'a>(&'a self, fragment: LocatedSpan<SharedStr, Y>) -> Self::SpanIter<'a> {
+        self.forward_from(located_str(&fragment))
+    }
+
+    fn backward_from<'a>(&'a self, fragment: LocatedSpan<SharedStr, Y>) -> Self::RSpanIter<'a> {
+        self.backward_from(located_str(&fragment))
+    }
+}
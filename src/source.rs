@@ -1,3 +1,6 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
 use nom::AsBytes;
 use nom_locate::LocatedSpan;
 
@@ -13,6 +16,10 @@ pub struct SourceLocation {
 }
 
 /// Source span.
+///
+/// With the default separator '\n' a line ends with "\n", "\r\n" or a lone "\r".
+/// Lines are returned including their line end, and the last line may have none.
+/// Lines start at 1, columns at 0.
 #[allow(clippy::needless_lifetimes)]
 pub trait Source<I> {
     type Result;
@@ -25,6 +32,9 @@ pub trait Source<I> {
     /// Assume the content is plain ASCII and use a simplified
     /// column calculation.
     fn with_ascii(self, ascii: bool) -> Self;
+    /// Expands tabs to the next multiple of the tab width for the column.
+    /// With a tab width of 0 or 1 a tab counts as a single column.
+    fn with_tab_width(self, tab: usize) -> Self;
 
     /// Returns the offset of the fragment.
     fn offset(&self, fragment: I) -> usize;
@@ -34,16 +44,48 @@ pub trait Source<I> {
     fn column(&self, fragment: I) -> usize;
     /// Returns offset/line/column of the fragment.
     fn location(&self, fragment: I) -> SourceLocation;
+    /// Returns an empty span at the line/column, the inverse of [location](Source::location).
+    /// Honors the ASCII and tab width settings for the column.
+    ///
+    /// Returns None if the line doesn't exist or the column is beyond the end of the line.
+    fn span_at(&self, line: usize, column: usize) -> Option<Self::Result>;
 
     /// Return n lines before and after the fragment, and place the lines of the fragment
     /// between them.
     fn get_lines_around(&self, fragment: I, n: usize) -> Vec<Self::Result>;
 
+    /// Return n lines before and after the fragment, and the lines of the fragment
+    /// between them. Each line comes with its line number and its byte range.
+    fn get_numbered_lines_around(
+        &self,
+        fragment: I,
+        n: usize,
+    ) -> Vec<(u32, Self::Result, Range<usize>)>;
+
     /// First full line for the fragment.
     fn start(&self, fragment: I) -> Self::Result;
     /// Last full line for the fragment.
     fn end(&self, fragment: I) -> Self::Result;
 
+    /// Returns the span for a byte range of the buffer.
+    ///
+    /// # Panics
+    /// If the range is out of bounds or doesn't fall on char boundaries.
+    fn span_for(&self, range: Range<usize>) -> Self::Result;
+
+    /// Returns the line with the given line number, including its line end.
+    /// Lines start at 1. Use [iter](Source::iter) to get all lines.
+    fn line_span(&self, line: usize) -> Option<Self::Result>;
+
+    /// Splits the fragment at the line ends. Each part keeps its line end.
+    fn split_lines(&self, fragment: I) -> Vec<Self::Result>;
+
+    /// Finds all non-overlapping occurrences of the needle.
+    ///
+    /// An empty needle finds nothing. For text, matches that don't fall on char
+    /// boundaries are skipped.
+    fn find_all(&self, needle: &[u8]) -> Vec<Self::Result>;
+
     /// Forward iterator.
     type SpanIter<'it>: Iterator<Item = Self::Result>
     where
@@ -69,6 +111,7 @@ pub trait Source<I> {
 pub struct SourceBytes<'s> {
     sep: u8,
     ascii: bool,
+    tab: usize,
     buf: &'s [u8],
     idx: Vec<usize>,
 }
@@ -79,6 +122,7 @@ impl<'s> SourceBytes<'s> {
         Self {
             sep: b'\n',
             ascii: false,
+            tab: 1,
             buf,
             idx: raw::index_lines(buf, b'\n'),
         }
@@ -91,6 +135,120 @@ impl<'s> SourceBytes<'s> {
     pub fn is_empty(&self) -> bool {
         self.buf.is_empty()
     }
+
+    /// Returns line/column for an offset into the buffer.
+    ///
+    /// # Panics
+    /// If the offset is beyond the end of the buffer.
+    pub fn position(&self, offset: usize) -> SourceLocation {
+        raw::position(self.buf, &self.idx, offset, self.ascii, self.tab)
+    }
+
+    /// Returns the offset for a line/column. Lines start at 1, columns at 0.
+    ///
+    /// Returns None if the line doesn't exist or the column is beyond the end of the line.
+    pub fn offset_of(&self, line: usize, column: usize) -> Option<usize> {
+        raw::offset_of(self.buf, &self.idx, line, column, self.ascii, self.tab)
+    }
+}
+
+/// Encoding detected from the byte order mark.
+#[cfg(feature = "bom")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// No BOM, assumed to be UTF-8.
+    Utf8,
+    /// UTF-8 with BOM.
+    Utf8Bom,
+    /// UTF-16 little endian.
+    Utf16Le,
+    /// UTF-16 big endian.
+    Utf16Be,
+}
+
+/// Text decoded from a [SourceBytes].
+///
+/// Keeps a mapping back to the offsets of the original bytes.
+#[cfg(feature = "bom")]
+#[derive(Debug)]
+pub struct DecodedText<'s> {
+    encoding: Encoding,
+    text: alloc::borrow::Cow<'s, str>,
+    /// Offset of each char in the original bytes. Only for UTF-16.
+    raw_offsets: Vec<usize>,
+}
+
+#[cfg(feature = "bom")]
+impl<'s> SourceBytes<'s> {
+    /// Detects the BOM and decodes the text.
+    ///
+    /// UTF-8 is borrowed without the BOM, and fails for invalid UTF-8.
+    /// UTF-16 is decoded lossy, invalid surrogates are replaced with U+FFFD.
+    pub fn decode(&self) -> Result<DecodedText<'s>, core::str::Utf8Error> {
+        let buf = self.buf;
+        match buf {
+            [0xEF, 0xBB, 0xBF, text @ ..] => Ok(DecodedText {
+                encoding: Encoding::Utf8Bom,
+                text: alloc::borrow::Cow::Borrowed(core::str::from_utf8(text)?),
+                raw_offsets: Vec::new(),
+            }),
+            [0xFF, 0xFE, ..] => Ok(raw::decode_utf16(
+                buf,
+                Encoding::Utf16Le,
+                u16::from_le_bytes,
+            )),
+            [0xFE, 0xFF, ..] => Ok(raw::decode_utf16(
+                buf,
+                Encoding::Utf16Be,
+                u16::from_be_bytes,
+            )),
+            text => Ok(DecodedText {
+                encoding: Encoding::Utf8,
+                text: alloc::borrow::Cow::Borrowed(core::str::from_utf8(text)?),
+                raw_offsets: Vec::new(),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "bom")]
+impl<'s> DecodedText<'s> {
+    /// Detected encoding.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Decoded text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Source for the decoded text.
+    pub fn source_str(&self) -> SourceStr<'_> {
+        SourceStr::new(&self.text)
+    }
+
+    /// Maps an offset into the decoded text back to the offset in the original bytes.
+    ///
+    /// # Panics
+    /// If the offset is beyond the end of the text.
+    pub fn raw_offset(&self, offset: usize) -> usize {
+        assert!(offset <= self.text.len());
+        match self.encoding {
+            Encoding::Utf8 => offset,
+            Encoding::Utf8Bom => offset + 3,
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                // index of the char that starts at or before the offset.
+                let idx = num_chars_before(&self.text, offset);
+                self.raw_offsets[idx]
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bom")]
+fn num_chars_before(text: &str, offset: usize) -> usize {
+    bytecount::num_chars(&text.as_bytes()[..offset])
 }
 
 #[allow(clippy::needless_lifetimes)]
@@ -112,6 +270,11 @@ where
         self
     }
 
+    fn with_tab_width(mut self, tab: usize) -> Self {
+        self.tab = tab;
+        self
+    }
+
     fn offset(&self, fragment: LocatedSpan<&'i [u8], Y>) -> usize {
         raw::offset_from(self.buf, fragment.as_bytes())
     }
@@ -121,22 +284,26 @@ where
     }
 
     fn column(&self, fragment: LocatedSpan<&'i [u8], Y>) -> usize {
-        if self.ascii {
-            raw::ascii_column(self.buf, fragment.as_bytes(), self.sep)
-        } else {
-            raw::utf8_column(self.buf, fragment.as_bytes(), self.sep)
-        }
+        raw::column(
+            self.buf,
+            &self.idx,
+            fragment.as_bytes(),
+            self.ascii,
+            self.tab,
+        )
     }
 
     fn location(&self, fragment: LocatedSpan<&'i [u8], Y>) -> SourceLocation {
         SourceLocation {
             offset: raw::offset_from(self.buf, fragment.as_bytes()),
             line: raw::line_index(&self.idx, raw::offset_from(self.buf, fragment.as_bytes())),
-            column: if self.ascii {
-                raw::ascii_column(self.buf, fragment.as_bytes(), self.sep)
-            } else {
-                raw::utf8_column(self.buf, fragment.as_bytes(), self.sep)
-            },
+            column: raw::column(
+                self.buf,
+                &self.idx,
+                fragment.as_bytes(),
+                self.ascii,
+                self.tab,
+            ),
         }
     }
 
@@ -149,6 +316,23 @@ where
         buf
     }
 
+    fn get_numbered_lines_around(
+        &self,
+        fragment: LocatedSpan<&'i [u8], Y>,
+        n: usize,
+    ) -> Vec<(u32, Self::Result, Range<usize>)> {
+        raw::lines_around(self.buf, &self.idx, fragment.as_bytes(), n)
+            .into_iter()
+            .map(|(line, range)| {
+                (
+                    line,
+                    raw::range_frame(self.buf, range.clone()).as_span_bytes(&self.idx),
+                    range,
+                )
+            })
+            .collect()
+    }
+
     fn start(&self, fragment: LocatedSpan<&'i [u8], Y>) -> Self::Result {
         raw::start_frame(self.buf, fragment.as_bytes(), self.sep).as_span_bytes(&self.idx)
     }
@@ -157,10 +341,42 @@ where
         raw::end_frame(self.buf, fragment.as_bytes(), self.sep).as_span_bytes(&self.idx)
     }
 
-    type SpanIter<'it> = LocatedSpanBytesIter<'it, 's>
-    where Self: 'it;
-    type RSpanIter<'it> = RLocatedSpanBytesIter<'it, 's>
-    where Self: 'it;
+    fn span_for(&self, range: Range<usize>) -> Self::Result {
+        raw::range_frame(self.buf, range).as_span_bytes(&self.idx)
+    }
+
+    fn line_span(&self, line: usize) -> Option<Self::Result> {
+        raw::line_range(self.buf, &self.idx, line)
+            .map(|v| raw::range_frame(self.buf, v).as_span_bytes(&self.idx))
+    }
+
+    fn split_lines(&self, fragment: LocatedSpan<&'i [u8], Y>) -> Vec<Self::Result> {
+        raw::split_lines(self.buf, &self.idx, fragment.as_bytes())
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_span_bytes(&self.idx))
+            .collect()
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<Self::Result> {
+        raw::find_all(self.buf, needle)
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_span_bytes(&self.idx))
+            .collect()
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<Self::Result> {
+        raw::offset_of(self.buf, &self.idx, line, column, self.ascii, self.tab)
+            .map(|v| raw::range_frame(self.buf, v..v).as_span_bytes(&self.idx))
+    }
+
+    type SpanIter<'it>
+        = LocatedSpanBytesIter<'it, 's>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = RLocatedSpanBytesIter<'it, 's>
+    where
+        Self: 'it;
 
     fn current<'a>(&'a self, fragment: LocatedSpan<&'i [u8], Y>) -> Self::SpanIter<'a> {
         let frag = raw::complete_fragment(self.buf, fragment.as_bytes(), self.sep);
@@ -256,6 +472,11 @@ impl<'i, 's> Source<&'i [u8]> for SourceBytes<'s> {
         self
     }
 
+    fn with_tab_width(mut self, tab: usize) -> Self {
+        self.tab = tab;
+        self
+    }
+
     fn offset(&self, fragment: &'i [u8]) -> usize {
         raw::offset_from(self.buf, fragment.as_bytes())
     }
@@ -265,22 +486,14 @@ impl<'i, 's> Source<&'i [u8]> for SourceBytes<'s> {
     }
 
     fn column(&self, fragment: &'i [u8]) -> usize {
-        if self.ascii {
-            raw::ascii_column(self.buf, fragment, self.sep)
-        } else {
-            raw::utf8_column(self.buf, fragment, self.sep)
-        }
+        raw::column(self.buf, &self.idx, fragment, self.ascii, self.tab)
     }
 
     fn location(&self, fragment: &'i [u8]) -> SourceLocation {
         SourceLocation {
             offset: raw::offset_from(self.buf, fragment),
             line: raw::line_index(&self.idx, raw::offset_from(self.buf, fragment.as_bytes())),
-            column: if self.ascii {
-                raw::ascii_column(self.buf, fragment, self.sep)
-            } else {
-                raw::utf8_column(self.buf, fragment, self.sep)
-            },
+            column: raw::column(self.buf, &self.idx, fragment, self.ascii, self.tab),
         }
     }
 
@@ -293,6 +506,23 @@ impl<'i, 's> Source<&'i [u8]> for SourceBytes<'s> {
         buf
     }
 
+    fn get_numbered_lines_around(
+        &self,
+        fragment: &'i [u8],
+        n: usize,
+    ) -> Vec<(u32, &'s [u8], Range<usize>)> {
+        raw::lines_around(self.buf, &self.idx, fragment, n)
+            .into_iter()
+            .map(|(line, range)| {
+                (
+                    line,
+                    raw::range_frame(self.buf, range.clone()).as_bytes(),
+                    range,
+                )
+            })
+            .collect()
+    }
+
     fn start(&self, fragment: &'i [u8]) -> &'s [u8] {
         raw::start_frame(self.buf, fragment, self.sep).as_bytes()
     }
@@ -301,10 +531,41 @@ impl<'i, 's> Source<&'i [u8]> for SourceBytes<'s> {
         raw::end_frame(self.buf, fragment, self.sep).as_bytes()
     }
 
-    type SpanIter<'it> = BytesIter<'s>
-    where Self: 'it;
-    type RSpanIter<'it> = RBytesIter<'s>
-    where Self: 'it;
+    fn span_for(&self, range: Range<usize>) -> &'s [u8] {
+        raw::range_frame(self.buf, range).as_bytes()
+    }
+
+    fn line_span(&self, line: usize) -> Option<&'s [u8]> {
+        raw::line_range(self.buf, &self.idx, line).map(|v| raw::range_frame(self.buf, v).as_bytes())
+    }
+
+    fn split_lines(&self, fragment: &'i [u8]) -> Vec<&'s [u8]> {
+        raw::split_lines(self.buf, &self.idx, fragment)
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_bytes())
+            .collect()
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<&'s [u8]> {
+        raw::find_all(self.buf, needle)
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_bytes())
+            .collect()
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<&'s [u8]> {
+        raw::offset_of(self.buf, &self.idx, line, column, self.ascii, self.tab)
+            .map(|v| raw::range_frame(self.buf, v..v).as_bytes())
+    }
+
+    type SpanIter<'it>
+        = BytesIter<'s>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = RBytesIter<'s>
+    where
+        Self: 'it;
 
     fn current<'a>(&'a self, fragment: &'i [u8]) -> Self::SpanIter<'a> {
         let frag = raw::complete_fragment(self.buf, fragment, self.sep);
@@ -379,10 +640,60 @@ impl<'s> Iterator for RBytesIter<'s> {
     }
 }
 
+/// Memory mapped file.
+///
+/// Hands out a [SourceBytes] or [SourceStr] that borrow the mapped memory,
+/// so large files can be parsed and diagnosed without reading them into a String.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct SourceMmap {
+    mmap: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl SourceMmap {
+    /// Maps the file.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped.
+    /// See [memmap2::Mmap::map].
+    pub unsafe fn open(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(Self {
+            mmap: memmap2::Mmap::map(&file)?,
+        })
+    }
+
+    /// The mapped bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// The mapped bytes as str, if they are valid UTF8.
+    pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(&self.mmap)
+    }
+
+    /// Source for the mapped bytes.
+    ///
+    /// Creates the line index, so better hold on to the result.
+    pub fn source_bytes(&self) -> SourceBytes<'_> {
+        SourceBytes::new(&self.mmap)
+    }
+
+    /// Source for the mapped text, if it is valid UTF8.
+    ///
+    /// Creates the line index, so better hold on to the result.
+    pub fn source_str(&self) -> Result<SourceStr<'_>, core::str::Utf8Error> {
+        Ok(SourceStr::new(self.as_str()?))
+    }
+}
+
 #[derive(Debug)]
 pub struct SourceStr<'s> {
     sep: u8,
     ascii: bool,
+    tab: usize,
     buf: &'s [u8],
     idx: Vec<usize>,
 }
@@ -393,6 +704,7 @@ impl<'s> SourceStr<'s> {
         Self {
             sep: b'\n',
             ascii: false,
+            tab: 1,
             buf: buf.as_bytes(),
             idx: raw::index_lines(buf.as_bytes(), b'\n'),
         }
@@ -405,6 +717,52 @@ impl<'s> SourceStr<'s> {
     pub fn is_empty(&self) -> bool {
         self.buf.is_empty()
     }
+
+    /// Returns line/column for an offset into the buffer.
+    ///
+    /// # Panics
+    /// If the offset is beyond the end of the buffer.
+    pub fn position(&self, offset: usize) -> SourceLocation {
+        raw::position(self.buf, &self.idx, offset, self.ascii, self.tab)
+    }
+
+    /// Returns the offset for a line/column. Lines start at 1, columns at 0.
+    ///
+    /// Returns None if the line doesn't exist or the column is beyond the end of the line.
+    pub fn offset_of(&self, line: usize, column: usize) -> Option<usize> {
+        raw::offset_of(self.buf, &self.idx, line, column, self.ascii, self.tab)
+    }
+
+    /// Column of the fragment counted in grapheme clusters.
+    ///
+    /// This is the number of user-perceived characters before the fragment,
+    /// which is what's needed to align a caret in a terminal.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn column_graphemes<F: AsBytes>(&self, fragment: F) -> usize {
+        raw::grapheme_column(self.buf, &self.idx, fragment.as_bytes())
+    }
+
+    /// Renders the lines of the fragment with n lines of context before and after.
+    ///
+    /// Each line is prefixed with its line number, the lines of the fragment are
+    /// marked with a '*' and the fragment is underlined with `^~~~`.
+    ///
+    /// ```text
+    ///  0001 first line
+    /// *0002 second line
+    ///              ^~~~
+    ///  0003 third line
+    /// ```
+    pub fn snippet<F: AsBytes>(&self, fragment: F, n: usize) -> String {
+        raw::snippet(
+            self.buf,
+            &self.idx,
+            fragment.as_bytes(),
+            n,
+            self.ascii,
+            self.tab,
+        )
+    }
 }
 
 #[allow(clippy::needless_lifetimes)]
@@ -426,6 +784,11 @@ where
         self
     }
 
+    fn with_tab_width(mut self, tab: usize) -> Self {
+        self.tab = tab;
+        self
+    }
+
     fn offset(&self, fragment: LocatedSpan<&'i str, Y>) -> usize {
         raw::offset_from(self.buf, fragment.as_bytes())
     }
@@ -435,22 +798,26 @@ where
     }
 
     fn column(&self, fragment: LocatedSpan<&'i str, Y>) -> usize {
-        if self.ascii {
-            raw::ascii_column(self.buf, fragment.as_bytes(), self.sep)
-        } else {
-            raw::utf8_column(self.buf, fragment.as_bytes(), self.sep)
-        }
+        raw::column(
+            self.buf,
+            &self.idx,
+            fragment.as_bytes(),
+            self.ascii,
+            self.tab,
+        )
     }
 
     fn location(&self, fragment: LocatedSpan<&'i str, Y>) -> SourceLocation {
         SourceLocation {
             offset: raw::offset_from(self.buf, fragment.as_bytes()),
             line: raw::line_index(&self.idx, raw::offset_from(self.buf, fragment.as_bytes())),
-            column: if self.ascii {
-                raw::ascii_column(self.buf, fragment.as_bytes(), self.sep)
-            } else {
-                raw::utf8_column(self.buf, fragment.as_bytes(), self.sep)
-            },
+            column: raw::column(
+                self.buf,
+                &self.idx,
+                fragment.as_bytes(),
+                self.ascii,
+                self.tab,
+            ),
         }
     }
 
@@ -467,6 +834,23 @@ where
         buf
     }
 
+    fn get_numbered_lines_around(
+        &self,
+        fragment: LocatedSpan<&'i str, Y>,
+        n: usize,
+    ) -> Vec<(u32, LocatedSpan<&'s str, ()>, Range<usize>)> {
+        raw::lines_around(self.buf, &self.idx, fragment.as_bytes(), n)
+            .into_iter()
+            .map(|(line, range)| {
+                (
+                    line,
+                    raw::range_frame(self.buf, range.clone()).as_span_str(&self.idx),
+                    range,
+                )
+            })
+            .collect()
+    }
+
     fn start(&self, fragment: LocatedSpan<&'i str, Y>) -> LocatedSpan<&'s str, ()> {
         raw::start_frame(self.buf, fragment.as_bytes(), self.sep).as_span_str(&self.idx)
     }
@@ -475,10 +859,42 @@ where
         raw::end_frame(self.buf, fragment.as_bytes(), self.sep).as_span_str(&self.idx)
     }
 
-    type SpanIter<'it> = LocatedSpanStrIter<'it, 's>
-    where Self: 'it;
-    type RSpanIter<'it> = RLocatedSpanStrIter<'it, 's>
-    where Self: 'it;
+    fn span_for(&self, range: Range<usize>) -> LocatedSpan<&'s str, ()> {
+        raw::range_frame_str(self.buf, range).as_span_str(&self.idx)
+    }
+
+    fn line_span(&self, line: usize) -> Option<LocatedSpan<&'s str, ()>> {
+        raw::line_range(self.buf, &self.idx, line)
+            .map(|v| raw::range_frame(self.buf, v).as_span_str(&self.idx))
+    }
+
+    fn split_lines(&self, fragment: LocatedSpan<&'i str, Y>) -> Vec<LocatedSpan<&'s str, ()>> {
+        raw::split_lines(self.buf, &self.idx, fragment.as_bytes())
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_span_str(&self.idx))
+            .collect()
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<LocatedSpan<&'s str, ()>> {
+        raw::find_all_str(self.buf, needle)
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_span_str(&self.idx))
+            .collect()
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<LocatedSpan<&'s str, ()>> {
+        raw::offset_of(self.buf, &self.idx, line, column, self.ascii, self.tab)
+            .map(|v| raw::range_frame(self.buf, v..v).as_span_str(&self.idx))
+    }
+
+    type SpanIter<'it>
+        = LocatedSpanStrIter<'it, 's>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = RLocatedSpanStrIter<'it, 's>
+    where
+        Self: 'it;
 
     fn current<'a>(&'a self, fragment: LocatedSpan<&'i str, Y>) -> Self::SpanIter<'a> {
         let frag = raw::complete_fragment(self.buf, fragment.as_bytes(), self.sep);
@@ -574,6 +990,11 @@ impl<'i, 's> Source<&'i str> for SourceStr<'s> {
         self
     }
 
+    fn with_tab_width(mut self, tab: usize) -> Self {
+        self.tab = tab;
+        self
+    }
+
     fn offset(&self, fragment: &'i str) -> usize {
         raw::offset_from(self.buf, fragment.as_bytes())
     }
@@ -583,22 +1004,26 @@ impl<'i, 's> Source<&'i str> for SourceStr<'s> {
     }
 
     fn column(&self, fragment: &'i str) -> usize {
-        if self.ascii {
-            raw::ascii_column(self.buf.as_bytes(), fragment.as_bytes(), self.sep)
-        } else {
-            raw::utf8_column(self.buf.as_bytes(), fragment.as_bytes(), self.sep)
-        }
+        raw::column(
+            self.buf.as_bytes(),
+            &self.idx,
+            fragment.as_bytes(),
+            self.ascii,
+            self.tab,
+        )
     }
 
     fn location(&self, fragment: &'i str) -> SourceLocation {
         SourceLocation {
             offset: raw::offset_from(self.buf.as_bytes(), fragment.as_bytes()),
             line: raw::line_index(&self.idx, raw::offset_from(self.buf, fragment.as_bytes())),
-            column: if self.ascii {
-                raw::ascii_column(self.buf.as_bytes(), fragment.as_bytes(), self.sep)
-            } else {
-                raw::utf8_column(self.buf.as_bytes(), fragment.as_bytes(), self.sep)
-            },
+            column: raw::column(
+                self.buf.as_bytes(),
+                &self.idx,
+                fragment.as_bytes(),
+                self.ascii,
+                self.tab,
+            ),
         }
     }
 
@@ -611,6 +1036,23 @@ impl<'i, 's> Source<&'i str> for SourceStr<'s> {
         buf
     }
 
+    fn get_numbered_lines_around(
+        &self,
+        fragment: &'i str,
+        n: usize,
+    ) -> Vec<(u32, &'s str, Range<usize>)> {
+        raw::lines_around(self.buf, &self.idx, fragment.as_bytes(), n)
+            .into_iter()
+            .map(|(line, range)| {
+                (
+                    line,
+                    raw::range_frame(self.buf, range.clone()).as_str(),
+                    range,
+                )
+            })
+            .collect()
+    }
+
     fn start(&self, fragment: &'i str) -> &'s str {
         raw::start_frame(self.buf.as_bytes(), fragment.as_bytes(), self.sep).as_str()
     }
@@ -619,10 +1061,41 @@ impl<'i, 's> Source<&'i str> for SourceStr<'s> {
         raw::end_frame(self.buf.as_bytes(), fragment.as_bytes(), self.sep).as_str()
     }
 
-    type SpanIter<'it> = StrIter<'s>
-    where Self: 'it;
-    type RSpanIter<'it> = RStrIter<'s>
-    where Self: 'it;
+    fn span_for(&self, range: Range<usize>) -> &'s str {
+        raw::range_frame_str(self.buf, range).as_str()
+    }
+
+    fn line_span(&self, line: usize) -> Option<&'s str> {
+        raw::line_range(self.buf, &self.idx, line).map(|v| raw::range_frame(self.buf, v).as_str())
+    }
+
+    fn split_lines(&self, fragment: &'i str) -> Vec<&'s str> {
+        raw::split_lines(self.buf, &self.idx, fragment.as_bytes())
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_str())
+            .collect()
+    }
+
+    fn find_all(&self, needle: &[u8]) -> Vec<&'s str> {
+        raw::find_all_str(self.buf, needle)
+            .into_iter()
+            .map(|v| raw::range_frame(self.buf, v).as_str())
+            .collect()
+    }
+
+    fn span_at(&self, line: usize, column: usize) -> Option<&'s str> {
+        raw::offset_of(self.buf, &self.idx, line, column, self.ascii, self.tab)
+            .map(|v| raw::range_frame(self.buf, v..v).as_str())
+    }
+
+    type SpanIter<'it>
+        = StrIter<'s>
+    where
+        Self: 'it;
+    type RSpanIter<'it>
+        = RStrIter<'s>
+    where
+        Self: 'it;
 
     fn current<'a>(&'a self, fragment: &'i str) -> Self::SpanIter<'a> {
         let frag = raw::complete_fragment(self.buf.as_bytes(), fragment.as_bytes(), self.sep);
@@ -698,8 +1171,15 @@ impl<'s> Iterator for RStrIter<'s> {
 }
 
 mod raw {
+    use crate::source::SourceLocation;
+    #[cfg(feature = "bom")]
+    use crate::source::{DecodedText, Encoding};
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
     use bytecount::num_chars;
-    use memchr::{memchr, memchr_iter, memrchr};
+    use core::ops::Range;
+    use memchr::{memchr, memchr2, memchr2_iter, memchr_iter, memmem, memrchr, memrchr2};
     use nom_locate::LocatedSpan;
 
     #[derive(Debug)]
@@ -713,12 +1193,12 @@ mod raw {
 
     impl<'a> MemFragment<'a> {
         pub(crate) fn as_str(&self) -> &'a str {
-            unsafe { std::str::from_utf8_unchecked(self.span) }
+            unsafe { core::str::from_utf8_unchecked(self.span) }
         }
 
         pub(crate) fn as_iter_str(&self) -> Option<&'a str> {
             self.iter_span
-                .map(|v| unsafe { std::str::from_utf8_unchecked(v) })
+                .map(|v| unsafe { core::str::from_utf8_unchecked(v) })
         }
 
         pub(crate) fn as_span_str(&self, line_idx: &[usize]) -> LocatedSpan<&'a str, ()> {
@@ -726,7 +1206,7 @@ mod raw {
                 LocatedSpan::new_from_raw_offset(
                     self.start,
                     line_index(line_idx, self.start) as u32,
-                    std::str::from_utf8_unchecked(self.span),
+                    core::str::from_utf8_unchecked(self.span),
                     (),
                 )
             }
@@ -740,7 +1220,7 @@ mod raw {
                 LocatedSpan::new_from_raw_offset(
                     self.start,
                     line_index(line_idx, self.start) as u32,
-                    std::str::from_utf8_unchecked(v),
+                    core::str::from_utf8_unchecked(v),
                     (),
                 )
             })
@@ -780,8 +1260,58 @@ mod raw {
         }
     }
 
+    /// Is there a line end at this position?
+    ///
+    /// With the default separator '\n' a line ends with "\n", "\r\n" or a lone "\r".
+    /// The position of a line end is always that of its last byte.
+    pub(crate) fn is_line_end(complete: &[u8], pos: usize, sep: u8) -> bool {
+        match complete[pos] {
+            b if b == sep => true,
+            b'\r' if sep == b'\n' => complete.get(pos + 1) != Some(&b'\n'),
+            _ => false,
+        }
+    }
+
+    /// Finds the first line end at or after start.
+    pub(crate) fn find_line_end(complete: &[u8], start: usize, sep: u8) -> Option<usize> {
+        if sep == b'\n' {
+            let mut start = start;
+            while let Some(o) = memchr2(b'\n', b'\r', &complete[start..]) {
+                if is_line_end(complete, start + o, sep) {
+                    return Some(start + o);
+                }
+                start += o + 1;
+            }
+            None
+        } else {
+            memchr(sep, &complete[start..]).map(|o| start + o)
+        }
+    }
+
+    /// Finds the last line end before end.
+    pub(crate) fn rfind_line_end(complete: &[u8], end: usize, sep: u8) -> Option<usize> {
+        if sep == b'\n' {
+            let mut end = end;
+            while let Some(o) = memrchr2(b'\n', b'\r', &complete[..end]) {
+                if is_line_end(complete, o, sep) {
+                    return Some(o);
+                }
+                end = o;
+            }
+            None
+        } else {
+            memrchr(sep, &complete[..end])
+        }
+    }
+
     pub(crate) fn index_lines(complete: &[u8], sep: u8) -> Vec<usize> {
-        memchr_iter(sep, complete).collect()
+        if sep == b'\n' {
+            memchr2_iter(b'\n', b'\r', complete)
+                .filter(|v| is_line_end(complete, *v, sep))
+                .collect()
+        } else {
+            memchr_iter(sep, complete).collect()
+        }
     }
 
     pub(crate) fn line_index(line_idx: &[usize], offset: usize) -> usize {
@@ -797,44 +1327,370 @@ mod raw {
     //     memchr_iter(sep, &complete[..offset]).count() + 1
     // }
 
-    /// Assumes ASCII text and gives a column.
-    pub(crate) fn ascii_column(complete: &[u8], fragment: &[u8], sep: u8) -> usize {
-        let frag = frame_prefix(complete, fragment, sep);
-        frag.span.len()
+    /// Start offset of the line containing the offset.
+    pub(crate) fn line_start(line_idx: &[usize], offset: usize) -> usize {
+        let v = match line_idx.binary_search(&offset) {
+            Ok(v) => v,
+            Err(v) => v,
+        };
+        if v == 0 {
+            0
+        } else {
+            line_idx[v - 1] + 1
+        }
     }
 
-    /// Gives a column for UTF8 text.
-    pub(crate) fn utf8_column(complete: &[u8], fragment: &[u8], sep: u8) -> usize {
-        let frag = frame_prefix(complete, fragment, sep);
-        num_chars(frag.span)
+    /// Width of the text in columns.
+    ///
+    /// Counts bytes for ASCII and chars for UTF8 text, and expands tabs if
+    /// the tab width is greater than 1.
+    pub(crate) fn width(text: &[u8], ascii: bool, tab: usize) -> usize {
+        if tab > 1 && memchr(b'\t', text).is_some() {
+            let mut col = 0;
+            for b in text {
+                // skip utf8 continuation bytes.
+                if !ascii && *b & 0xC0 == 0x80 {
+                    continue;
+                }
+                col = next_column(col, *b, tab);
+            }
+            col
+        } else if ascii {
+            text.len()
+        } else {
+            num_chars(text)
+        }
     }
 
-    /// Returns the part of the frame from the last separator up to the start of the
-    /// fragment.
-    #[allow(clippy::needless_lifetimes)]
-    pub(crate) fn frame_prefix<'s, 'a>(
-        complete: &'s [u8],
-        fragment: &'a [u8],
-        sep: u8,
-    ) -> MemFragment<'s> {
+    /// Column after the byte.
+    fn next_column(col: usize, b: u8, tab: usize) -> usize {
+        if b == b'\t' && tab > 1 {
+            col + tab - col % tab
+        } else {
+            col + 1
+        }
+    }
+
+    /// Gives a column.
+    pub(crate) fn column(
+        complete: &[u8],
+        line_idx: &[usize],
+        fragment: &[u8],
+        ascii: bool,
+        tab: usize,
+    ) -> usize {
         let offset = offset_from(complete, fragment);
         assert!(offset <= complete.len());
+        width(&complete[line_start(line_idx, offset)..offset], ascii, tab)
+    }
 
-        let self_bytes = complete;
+    /// Line/column for an offset.
+    pub(crate) fn position(
+        complete: &[u8],
+        line_idx: &[usize],
+        offset: usize,
+        ascii: bool,
+        tab: usize,
+    ) -> SourceLocation {
+        assert!(offset <= complete.len());
+        let line_start = line_start(line_idx, offset);
+        SourceLocation {
+            offset,
+            line: line_index(line_idx, offset),
+            column: width(&complete[line_start..offset], ascii, tab),
+        }
+    }
 
-        let start = match memrchr(sep, &self_bytes[..offset]) {
-            None => 0,
-            Some(o) => o + 1,
+    /// Offset for a line/column.
+    pub(crate) fn offset_of(
+        complete: &[u8],
+        line_idx: &[usize],
+        line: usize,
+        column: usize,
+        ascii: bool,
+        tab: usize,
+    ) -> Option<usize> {
+        if line == 0 || line > line_idx.len() + 1 {
+            return None;
+        }
+        let start = if line == 1 { 0 } else { line_idx[line - 2] + 1 };
+        let end = if line <= line_idx.len() {
+            line_idx[line - 1]
+        } else {
+            complete.len()
+        };
+
+        if ascii && tab <= 1 {
+            if start + column <= end {
+                Some(start + column)
+            } else {
+                None
+            }
+        } else {
+            let mut col = 0;
+            for (i, b) in complete[start..end].iter().enumerate() {
+                // skip utf8 continuation bytes.
+                if !ascii && *b & 0xC0 == 0x80 {
+                    continue;
+                }
+                // a column within an expanded tab maps to the tab.
+                let next = next_column(col, *b, tab);
+                if column < next {
+                    return Some(start + i);
+                }
+                col = next;
+            }
+            if col == column {
+                Some(end)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Gives a column in grapheme clusters for UTF8 text.
+    #[cfg(feature = "unicode-segmentation")]
+    pub(crate) fn grapheme_column(complete: &[u8], line_idx: &[usize], fragment: &[u8]) -> usize {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let offset = offset_from(complete, fragment);
+        assert!(offset <= complete.len());
+        let line_start = line_start(line_idx, offset);
+        // complete is always a str here.
+        let prefix = unsafe { core::str::from_utf8_unchecked(&complete[line_start..offset]) };
+        prefix.graphemes(true).count()
+    }
+
+    /// Line numbers and ranges for n lines before and after the fragment and the
+    /// lines of the fragment itself.
+    pub(crate) fn lines_around(
+        complete: &[u8],
+        line_idx: &[usize],
+        fragment: &[u8],
+        n: usize,
+    ) -> Vec<(u32, Range<usize>)> {
+        let offset = offset_from(complete, fragment);
+        assert!(offset + fragment.len() <= complete.len());
+
+        let first = line_index(line_idx, offset);
+        let last = if fragment.is_empty() {
+            first
+        } else {
+            line_index(line_idx, offset + fragment.len() - 1)
+        };
+        // no extra empty line after a trailing line end.
+        let max_line = if line_idx.last().map(|v| v + 1) == Some(complete.len()) {
+            line_idx.len().max(last)
+        } else {
+            line_idx.len() + 1
+        };
+
+        (first.saturating_sub(n).max(1)..=(last + n).min(max_line))
+            .map(|line| (line as u32, line_bounds(complete, line_idx, line)))
+            .collect()
+    }
+
+    /// Parts of the fragment per line.
+    pub(crate) fn split_lines(
+        complete: &[u8],
+        line_idx: &[usize],
+        fragment: &[u8],
+    ) -> Vec<Range<usize>> {
+        let start = offset_from(complete, fragment);
+        let end = start + fragment.len();
+        assert!(end <= complete.len());
+
+        let first = line_index(line_idx, start);
+        let last = if fragment.is_empty() {
+            first
+        } else {
+            line_index(line_idx, end - 1)
         };
 
+        (first..=last)
+            .map(|line| {
+                let bounds = line_bounds(complete, line_idx, line);
+                bounds.start.max(start)..bounds.end.min(end)
+            })
+            .collect()
+    }
+
+    /// Decodes UTF-16 after the BOM and records the raw offset of each char.
+    #[cfg(feature = "bom")]
+    pub(crate) fn decode_utf16<'s>(
+        buf: &[u8],
+        encoding: Encoding,
+        to_u16: fn([u8; 2]) -> u16,
+    ) -> DecodedText<'s> {
+        let units = buf[2..].chunks(2).map(|v| match v {
+            [a, b] => to_u16([*a, *b]),
+            _ => 0xFFFD,
+        });
+
+        let mut text = String::new();
+        let mut raw_offsets = Vec::new();
+        let mut raw_offset = 2;
+        for c in char::decode_utf16(units) {
+            let (c, len) = match c {
+                Ok(c) => (c, c.len_utf16() * 2),
+                Err(_) => (char::REPLACEMENT_CHARACTER, 2),
+            };
+            text.push(c);
+            raw_offsets.push(raw_offset);
+            raw_offset += len;
+        }
+        // offset for the end of the text.
+        raw_offsets.push(buf.len());
+
+        DecodedText {
+            encoding,
+            text: alloc::borrow::Cow::Owned(text),
+            raw_offsets,
+        }
+    }
+
+    /// Ranges of all occurrences of the needle.
+    pub(crate) fn find_all(complete: &[u8], needle: &[u8]) -> Vec<Range<usize>> {
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        memmem::find_iter(complete, needle)
+            .map(|v| v..v + needle.len())
+            .collect()
+    }
+
+    /// Ranges of all occurrences of the needle that fall on char boundaries.
+    pub(crate) fn find_all_str(complete: &[u8], needle: &[u8]) -> Vec<Range<usize>> {
+        let is_char_boundary = |v: usize| v == complete.len() || complete[v] & 0xC0 != 0x80;
+        let mut found = find_all(complete, needle);
+        found.retain(|v| is_char_boundary(v.start) && is_char_boundary(v.end));
+        found
+    }
+
+    /// Range of the line including the line end. Doesn't check the line number.
+    fn line_bounds(complete: &[u8], line_idx: &[usize], line: usize) -> Range<usize> {
+        let start = if line == 1 { 0 } else { line_idx[line - 2] + 1 };
+        let end = if line <= line_idx.len() {
+            line_idx[line - 1] + 1
+        } else {
+            complete.len()
+        };
+        start..end
+    }
+
+    /// Range of the line including the line end.
+    ///
+    /// There is no empty last line after a trailing line end, same as for
+    /// the line iterators.
+    pub(crate) fn line_range(
+        complete: &[u8],
+        line_idx: &[usize],
+        line: usize,
+    ) -> Option<Range<usize>> {
+        let trailing = line_idx.last().map(|v| v + 1) == Some(complete.len());
+        let count = if trailing || complete.is_empty() {
+            line_idx.len()
+        } else {
+            line_idx.len() + 1
+        };
+        if line >= 1 && line <= count {
+            Some(line_bounds(complete, line_idx, line))
+        } else {
+            None
+        }
+    }
+
+    /// Renders numbered lines around the fragment and underlines the fragment.
+    pub(crate) fn snippet(
+        complete: &[u8],
+        line_idx: &[usize],
+        fragment: &[u8],
+        n: usize,
+        ascii: bool,
+        tab: usize,
+    ) -> String {
+        let start = offset_from(complete, fragment);
+        let end = start + fragment.len();
+        let first = line_index(line_idx, start);
+        let last = if fragment.is_empty() {
+            first
+        } else {
+            line_index(line_idx, end - 1)
+        };
+
+        let mut buf = String::new();
+        for (line, range) in lines_around(complete, line_idx, fragment, n) {
+            let text = &complete[range.clone()];
+            let text = match text {
+                [v @ .., b'\r', b'\n'] => v,
+                [v @ .., b'\n'] | [v @ .., b'\r'] => v,
+                v => v,
+            };
+            let text_end = range.start + text.len();
+            let is_marked = (first..=last).contains(&(line as usize));
+
+            buf.push(if is_marked { '*' } else { ' ' });
+            buf.push_str(&format!("{:04} ", line));
+            if tab > 1 {
+                let mut col = 0;
+                for c in String::from_utf8_lossy(text).chars() {
+                    if c == '\t' {
+                        let next = col + tab - col % tab;
+                        buf.push_str(&" ".repeat(next - col));
+                        col = next;
+                    } else {
+                        buf.push(c);
+                        col += 1;
+                    }
+                }
+            } else {
+                buf.push_str(&String::from_utf8_lossy(text));
+            }
+            buf.push('\n');
+
+            if is_marked {
+                let from = start.max(range.start).min(text_end);
+                let to = end.min(text_end).max(from);
+                let col = width(&complete[range.start..from], ascii, tab);
+                let len = width(&complete[range.start..to], ascii, tab) - col;
+
+                let underline = if line as usize == first {
+                    format!("^{}", "~".repeat(len.saturating_sub(1)))
+                } else {
+                    "~".repeat(len)
+                };
+                if !underline.is_empty() {
+                    buf.push_str(&" ".repeat(6 + col));
+                    buf.push_str(&underline);
+                    buf.push('\n');
+                }
+            }
+        }
+        buf
+    }
+
+    /// Frame for a byte range.
+    pub(crate) fn range_frame(complete: &[u8], range: Range<usize>) -> MemFragment<'_> {
+        assert!(range.start <= range.end);
+        assert!(range.end <= complete.len());
+
         MemFragment {
-            start,
-            end: offset,
-            span: &complete[start..offset],
+            start: range.start,
+            end: range.end,
+            span: &complete[range],
             iter_span: None,
         }
     }
 
+    /// Frame for a byte range. Checks for char boundaries too.
+    pub(crate) fn range_frame_str(complete: &[u8], range: Range<usize>) -> MemFragment<'_> {
+        let is_char_boundary = |v: usize| v == complete.len() || complete[v] & 0xC0 != 0x80;
+        assert!(range.end <= complete.len());
+        assert!(is_char_boundary(range.start));
+        assert!(is_char_boundary(range.end));
+        range_frame(complete, range)
+    }
+
     /// Empty span at the beginning of the fragment.
     #[allow(clippy::needless_lifetimes)]
     pub(crate) fn empty_frame<'s, 'a>(complete: &'s [u8], fragment: &'a [u8]) -> MemFragment<'s> {
@@ -864,13 +1720,13 @@ mod raw {
         // no skip_lines, already correct.
 
         let self_bytes = complete;
-        let start = match memrchr(sep, &self_bytes[..offset]) {
+        let start = match rfind_line_end(self_bytes, offset, sep) {
             None => 0,
             Some(v) => v + 1,
         };
-        let end = match memchr(sep, &self_bytes[offset..]) {
+        let end = match find_line_end(self_bytes, offset, sep) {
             None => complete.len(),
-            Some(v) => offset + v + 1,
+            Some(v) => v + 1,
         };
 
         MemFragment {
@@ -894,13 +1750,13 @@ mod raw {
         assert!(offset <= complete.len());
 
         let self_bytes = complete;
-        let start = match memrchr(sep, &self_bytes[..offset]) {
+        let start = match rfind_line_end(self_bytes, offset, sep) {
             None => 0,
             Some(v) => v + 1,
         };
-        let end = match memchr(sep, &self_bytes[offset..]) {
+        let end = match find_line_end(self_bytes, offset, sep) {
             None => complete.len(),
-            Some(v) => offset + v + 1,
+            Some(v) => v + 1,
         };
 
         MemFragment {
@@ -928,13 +1784,13 @@ mod raw {
 
         // fill up front and back
         let self_bytes = complete;
-        let start = match memrchr(sep, &self_bytes[..start]) {
+        let start = match rfind_line_end(self_bytes, start, sep) {
             None => 0,
             Some(o) => o + 1,
         };
-        let end = match memchr(sep, &self_bytes[end..]) {
+        let end = match find_line_end(self_bytes, end, sep) {
             None => complete.len(),
-            Some(o) => end + o + 1,
+            Some(o) => o + 1,
         };
 
         MemFragment {
@@ -969,9 +1825,9 @@ mod raw {
         let is_terminal = start == complete.len();
 
         let self_bytes = complete;
-        let end = match memchr(sep, &self_bytes[start..]) {
+        let end = match find_line_end(self_bytes, start, sep) {
             None => complete.len(),
-            Some(o) => start + o + 1,
+            Some(o) => o + 1,
         };
 
         let span = &complete[start..end];
@@ -1008,14 +1864,14 @@ mod raw {
         // immediately preceeding separator.
         let self_bytes = complete;
         #[allow(clippy::bool_to_int_with_if)]
-        let skip_lines = if !is_terminal && self_bytes[end - 1] == sep {
+        let skip_lines = if !is_terminal && is_line_end(self_bytes, end - 1, sep) {
             1
         } else {
             0
         };
 
         // find separator
-        let start = match memrchr(sep, &self_bytes[..end - skip_lines]) {
+        let start = match rfind_line_end(self_bytes, end - skip_lines, sep) {
             None => 0,
             Some(n) => n + 1,
         };
@@ -1066,35 +1922,17 @@ mod tests_spanbytes {
     }
 
     #[test]
-    fn test_frame_prefix() {
+    fn test_line_start() {
         fn run(txt: &[u8], occ: &[usize]) {
-            // println!("--{:?}--", txt);
             let bounds = test_bounds(txt, occ);
+            let idx = raw::index_lines(txt, SEP);
 
             for i in 0..=txt.len() {
-                for j in i..=txt.len() {
-                    let mut cb = check_bounds_complete_fragment(txt, i, i, &bounds);
-                    // always override end.
-                    cb.1 = i;
-                    // println!("    <{}:{}> -> <{}:{}>", i, j, cb.0, cb.1);
-                    let cmp = mk_fragment(txt, cb.0, cb.1);
-
-                    let frag = mk_fragment(txt, i, j);
-                    let prefix = raw::frame_prefix(&txt, &frag, SEP);
-
-                    // println!(
-                    //     "    {}:{}:{:?} -> {}:{:?} <> {}:{:?}",
-                    //     SpanBytes::offset_from(txt, frag),
-                    //     SpanBytes::offset_from(txt, frag) + frag.len(),
-                    //     frag,
-                    //     SpanBytes::offset_from(txt, prefix),
-                    //     prefix,
-                    //     SpanBytes::offset_from(txt, cmp),
-                    //     cmp
-                    // );
+                let cb = check_bounds_complete_fragment(txt, i, i, &bounds);
+                assert_eq!(raw::line_start(&idx, i), cb.0);
 
-                    assert_eq!(prefix.span, cmp);
-                }
+                let frag = mk_fragment(txt, i, i);
+                assert_eq!(raw::column(txt, &idx, frag, true, 1), i - cb.0);
             }
         }
 
@@ -1120,10 +1958,10 @@ mod tests_spanbytes {
                 for j in i..=txt.len() {
                     let cb = check_bounds_complete_fragment(txt, i, i, &bounds);
                     // println!("    <{}:{}> -> <{}:{}>", i, j, cb.0, cb.1);
-                    let cmp = mk_fragment(&txt, cb.0, cb.1);
+                    let cmp = mk_fragment(txt, cb.0, cb.1);
 
-                    let frag = mk_fragment(&txt, i, j);
-                    let next = raw::start_frame(&txt, &frag, SEP);
+                    let frag = mk_fragment(txt, i, j);
+                    let next = raw::start_frame(txt, frag, SEP);
 
                     // println!(
                     //     "    {}:{}:{:?} -> {}:{:?} <> {}:{:?}",
@@ -1163,10 +2001,10 @@ mod tests_spanbytes {
                 for j in i..=txt.len() {
                     let cb = check_bounds_complete_fragment(txt, j, j, &bounds);
                     // println!("    <{}:{}> -> <{}:{}>", i, j, cb.0, cb.1);
-                    let cmp = mk_fragment(&txt, cb.0, cb.1);
+                    let cmp = mk_fragment(txt, cb.0, cb.1);
 
-                    let frag = mk_fragment(&txt, i, j);
-                    let next = raw::end_frame(&txt, &frag, SEP);
+                    let frag = mk_fragment(txt, i, j);
+                    let next = raw::end_frame(txt, frag, SEP);
 
                     // println!(
                     //     "    {}:{}:{:?} -> {}:{:?} <> {}:{:?}",
@@ -1200,12 +2038,12 @@ mod tests_spanbytes {
         txt: &[u8],
         start: usize,
         end: usize,
-        bounds: &Vec<[usize; 2]>,
+        bounds: &[[usize; 2]],
     ) -> (usize, usize) {
         let btxt = txt;
 
         let start_0 = 'loop_val: {
-            for (_idx, b) in bounds.iter().enumerate() {
+            for b in bounds.iter() {
                 if b[0] <= start && start < b[1] {
                     break 'loop_val b[0];
                 } else if b[0] <= start && start == b[1] {
@@ -1247,10 +2085,10 @@ mod tests_spanbytes {
                 for j in i..=txt.len() {
                     let cb = check_bounds_complete_fragment(txt, i, j, &bounds);
                     // println!("    <{}:{}> -> <{}:{}>", i, j, cb.0, cb.1);
-                    let cmp = mk_fragment(&txt, cb.0, cb.1);
+                    let cmp = mk_fragment(txt, cb.0, cb.1);
 
-                    let frag = mk_fragment(&txt, i, j);
-                    let next = raw::complete_fragment(&txt, &frag, SEP);
+                    let frag = mk_fragment(txt, i, j);
+                    let next = raw::complete_fragment(txt, frag, SEP);
 
                     // println!(
                     //     "    {}:{}:{:?} -> {}:{:?} <> {}:{:?}",
@@ -1280,7 +2118,7 @@ mod tests_spanbytes {
         run(b"\naaaa\nbbbb\ncccc\ndddd\neeee\n", &[0, 5, 10, 15, 20, 25]);
     }
 
-    fn check_bounds_next_fragment(pos: usize, bounds: &Vec<[usize; 2]>) -> (usize, usize) {
+    fn check_bounds_next_fragment(pos: usize, bounds: &[[usize; 2]]) -> (usize, usize) {
         for (idx, b) in bounds.iter().enumerate() {
             if b[0] <= pos && pos < b[1] {
                 return (pos, b[1]);
@@ -1306,10 +2144,10 @@ mod tests_spanbytes {
                 for j in i..=txt.len() {
                     let cb = check_bounds_next_fragment(j, &bounds);
                     // println!("    <{}:{}> -> <{}:{}>", i, j, cb.0, cb.1);
-                    let cmp = mk_fragment(&txt, cb.0, cb.1);
+                    let cmp = mk_fragment(txt, cb.0, cb.1);
 
-                    let frag = mk_fragment(&txt, i, j);
-                    let next = raw::next_fragment(&txt, &frag, SEP);
+                    let frag = mk_fragment(txt, i, j);
+                    let next = raw::next_fragment(txt, frag, SEP);
 
                     // println!(
                     //     "    {}:{}:{:?} -> {}:{:?} <> {}:{:?}",
@@ -1339,22 +2177,17 @@ mod tests_spanbytes {
         run(b"\naaaa\nbbbb\ncccc\ndddd\neeee\n", &[0, 5, 10, 15, 20, 25]);
     }
 
-    fn check_bounds_prev_fragment(
-        txt: &[u8],
-        pos: usize,
-        bounds: &Vec<[usize; 2]>,
-    ) -> (usize, usize) {
+    fn check_bounds_prev_fragment(txt: &[u8], pos: usize, bounds: &[[usize; 2]]) -> (usize, usize) {
         let btxt = txt;
 
         for b in bounds {
             if b[0] <= pos && pos < b[1] {
                 return (b[0], pos);
-            } else if b[0] <= pos && pos == b[1] && b[1] > 0 && btxt[b[1] - 1] == SEP {
+            } else if b[0] <= pos
+                && pos == b[1]
+                && (b[1] > 0 && btxt[b[1] - 1] == SEP || pos == txt.len())
+            {
                 return (b[0], b[1]);
-            } else if b[0] <= pos && pos == b[1] {
-                if pos == txt.len() {
-                    return (b[0], b[1]);
-                }
             }
         }
         panic!();
@@ -1366,16 +2199,14 @@ mod tests_spanbytes {
             // println!("--{:?}--", txt);
             let bounds = test_bounds(txt, occ);
 
-            let txt = txt;
-
             for i in 0..=txt.len() {
                 for j in i..=txt.len() {
                     let cb = check_bounds_prev_fragment(txt, i, &bounds);
                     // println!("    <{}:{}> -> <{}:{}>", i, j, cb.0, cb.1);
-                    let cmp = mk_fragment(&txt, cb.0, cb.1);
+                    let cmp = mk_fragment(txt, cb.0, cb.1);
 
-                    let frag = mk_fragment(&txt, i, j);
-                    let prev = raw::prev_fragment(&txt, &frag, SEP);
+                    let frag = mk_fragment(txt, i, j);
+                    let prev = raw::prev_fragment(txt, frag, SEP);
 
                     // println!(
                     //     "    {}:{}:{:?} -> {}:{:?} <> {}:{:?}",
@@ -1438,4 +2269,320 @@ mod tests_spanbytes {
         run(b"\n\n\n\n");
         run(b"\n\n\n\n\n");
     }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn test_grapheme_column() {
+        use crate::source::{Source, SourceStr};
+
+        // a + combining diaeresis, superscript two, then x.
+        let txt = "line\na\u{0308}\u{00B2}x";
+        let src = SourceStr::new(txt);
+        let frag = &txt[txt.len() - 1..];
+
+        assert_eq!(Source::<&str>::column(&src, frag), 3);
+        assert_eq!(src.column_graphemes(frag), 2);
+        assert_eq!(src.column_graphemes(&txt[5..]), 0);
+    }
+
+    #[test]
+    fn test_position() {
+        use crate::source::SourceStr;
+
+        let txt = "abc\näöü\n\nx";
+        let src = SourceStr::new(txt);
+
+        for (offset, line, column) in [
+            (0, 1, 0),
+            (3, 1, 3),
+            (4, 2, 0),
+            (6, 2, 1),
+            (10, 2, 3),
+            (11, 3, 0),
+            (12, 4, 0),
+            (13, 4, 1),
+        ] {
+            let pos = src.position(offset);
+            assert_eq!((pos.line, pos.column), (line, column), "offset {}", offset);
+            assert_eq!(src.offset_of(line, column), Some(offset));
+        }
+
+        assert_eq!(src.offset_of(0, 0), None);
+        assert_eq!(src.offset_of(1, 4), None);
+        assert_eq!(src.offset_of(3, 1), None);
+        assert_eq!(src.offset_of(5, 0), None);
+    }
+
+    #[test]
+    fn test_line_ends() {
+        use crate::source::{Source, SourceBytes};
+
+        let txt = b"ab\r\ncd\ref\ngh";
+        let src = SourceBytes::new(txt);
+
+        for (offset, line, column) in [
+            (0, 1, 0),
+            (2, 1, 2),
+            (3, 1, 3),
+            (4, 2, 0),
+            (6, 2, 2),
+            (7, 3, 0),
+            (9, 3, 2),
+            (10, 4, 0),
+            (12, 4, 2),
+        ] {
+            let frag = &txt[offset..];
+            assert_eq!(Source::<&[u8]>::line(&src, frag), line, "offset {}", offset);
+            assert_eq!(
+                Source::<&[u8]>::column(&src, frag),
+                column,
+                "offset {}",
+                offset
+            );
+        }
+
+        let lines: Vec<&[u8]> = Source::<&[u8]>::iter(&src).collect();
+        assert_eq!(lines, [&b"ab\r\n"[..], b"cd\r", b"ef\n", b"gh"]);
+
+        let lines = src.get_lines_around(&txt[8..9], 1);
+        assert_eq!(lines, [&b"cd\r"[..], b"ef\n", b"gh"]);
+        let lines = src.get_lines_around(&txt[2..3], 1);
+        assert_eq!(lines, [&b"ab\r\n"[..], b"cd\r"]);
+    }
+
+    #[test]
+    fn test_numbered_lines_around() {
+        use crate::source::{Source, SourceStr};
+
+        let txt = "one\ntwo\nthree\nfour\n";
+        let src = SourceStr::new(txt);
+
+        let lines = Source::<&str>::get_numbered_lines_around(&src, &txt[5..10], 1);
+        assert_eq!(
+            lines,
+            [
+                (1, "one\n", 0..4),
+                (2, "two\n", 4..8),
+                (3, "three\n", 8..14),
+                (4, "four\n", 14..19)
+            ]
+        );
+
+        let lines = Source::<&str>::get_numbered_lines_around(&src, &txt[0..4], 0);
+        assert_eq!(lines, [(1, "one\n", 0..4)]);
+
+        let lines = Source::<&str>::get_numbered_lines_around(&src, &txt[19..], 1);
+        assert_eq!(lines, [(4, "four\n", 14..19), (5, "", 19..19)]);
+    }
+
+    #[test]
+    fn test_snippet() {
+        use crate::source::SourceStr;
+
+        let txt = "one\ntwo three\nfour\nfive\n";
+        let src = SourceStr::new(txt);
+
+        assert_eq!(
+            src.snippet(&txt[8..13], 1),
+            concat!(
+                " 0001 one\n",
+                "*0002 two three\n",
+                "          ^~~~~\n",
+                " 0003 four\n",
+            )
+        );
+        assert_eq!(
+            src.snippet(&txt[8..16], 0),
+            concat!(
+                "*0002 two three\n",
+                "          ^~~~~\n",
+                "*0003 four\n",
+                "      ~~\n",
+            )
+        );
+        assert_eq!(
+            src.snippet(&txt[0..0], 0),
+            concat!("*0001 one\n", "      ^\n")
+        );
+    }
+
+    #[test]
+    fn test_tab_width() {
+        use crate::source::{Source, SourceStr};
+
+        let txt = "a\tb\n\t\tc";
+        let src = Source::<&str>::with_tab_width(SourceStr::new(txt), 4);
+
+        assert_eq!(Source::<&str>::column(&src, &txt[2..]), 4);
+        assert_eq!(Source::<&str>::column(&src, &txt[6..]), 8);
+        assert_eq!(src.position(1).column, 1);
+        assert_eq!(src.offset_of(1, 4), Some(2));
+        assert_eq!(src.offset_of(1, 2), Some(1));
+        assert_eq!(src.offset_of(2, 8), Some(6));
+        assert_eq!(src.offset_of(2, 9), Some(7));
+        assert_eq!(src.offset_of(2, 10), None);
+
+        assert_eq!(
+            src.snippet(&txt[6..7], 0),
+            concat!("*0002         c\n", "              ^\n")
+        );
+
+        let src = SourceStr::new(txt);
+        assert_eq!(Source::<&str>::column(&src, &txt[6..]), 2);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap() {
+        use crate::source::{Source, SourceMmap};
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("kparse_mmap_{}.txt", std::process::id()));
+        let mut file = std::fs::File::create(&path).expect("create");
+        file.write_all(b"one\ntwo\nthree").expect("write");
+        drop(file);
+
+        let mmap = unsafe { SourceMmap::open(&path) }.expect("mmap");
+        let txt = mmap.as_str().expect("utf8");
+        let src = mmap.source_str().expect("utf8");
+        assert_eq!(Source::<&str>::line(&src, &txt[4..]), 2);
+        assert_eq!(Source::<&str>::start(&src, &txt[5..6]), "two\n");
+
+        let src = mmap.source_bytes();
+        let bytes = mmap.as_bytes();
+        assert_eq!(Source::<&[u8]>::line(&src, &bytes[8..]), 3);
+
+        drop(mmap);
+        std::fs::remove_file(&path).expect("remove");
+    }
+
+    #[test]
+    fn test_line_span() {
+        use crate::source::{Source, SourceStr};
+        use nom_locate::LocatedSpan;
+
+        let txt = "one\ntwo\nthree\n";
+        let src = SourceStr::new(txt);
+
+        assert_eq!(Source::<&str>::line_span(&src, 0), None);
+        assert_eq!(Source::<&str>::line_span(&src, 1), Some("one\n"));
+        assert_eq!(Source::<&str>::line_span(&src, 3), Some("three\n"));
+        assert_eq!(Source::<&str>::line_span(&src, 4), None);
+
+        let line = Source::<LocatedSpan<&str>>::line_span(&src, 2).expect("line");
+        assert_eq!(line.location_offset(), 4);
+        assert_eq!(line.location_line(), 2);
+
+        let src = SourceStr::new("one");
+        assert_eq!(Source::<&str>::line_span(&src, 1), Some("one"));
+        let src = SourceStr::new("");
+        assert_eq!(Source::<&str>::line_span(&src, 1), None);
+    }
+
+    #[test]
+    fn test_split_lines() {
+        use crate::source::{Source, SourceStr};
+        use nom_locate::LocatedSpan;
+
+        let txt = "one\r\ntwo\nthree\n";
+        let src = SourceStr::new(txt);
+
+        assert_eq!(
+            Source::<&str>::split_lines(&src, &txt[1..12]),
+            ["ne\r\n", "two\n", "thr"]
+        );
+        assert_eq!(Source::<&str>::split_lines(&src, &txt[5..9]), ["two\n"]);
+        assert_eq!(Source::<&str>::split_lines(&src, &txt[6..6]), [""]);
+
+        let parts = Source::<LocatedSpan<&str>>::split_lines(&src, LocatedSpan::new(&txt[1..12]));
+        assert_eq!(parts[2].location_offset(), 9);
+        assert_eq!(parts[2].location_line(), 3);
+    }
+
+    #[test]
+    fn test_find_all() {
+        use crate::source::{Source, SourceBytes, SourceStr};
+        use nom_locate::LocatedSpan;
+
+        let txt = "let a = b;\na = a + 1;\n";
+        let src = SourceStr::new(txt);
+
+        let found = Source::<LocatedSpan<&str>>::find_all(&src, b"a");
+        let found: Vec<_> = found
+            .iter()
+            .map(|v| (v.location_line(), v.location_offset()))
+            .collect();
+        assert_eq!(found, [(1, 4), (2, 11), (2, 15)]);
+
+        assert!(Source::<&str>::find_all(&src, b"").is_empty());
+
+        // continuation byte of 'ä'
+        let src = SourceStr::new("ä");
+        assert!(Source::<&str>::find_all(&src, &[0xA4]).is_empty());
+        let src = SourceBytes::new("ä".as_bytes());
+        assert_eq!(Source::<&[u8]>::find_all(&src, &[0xA4]).len(), 1);
+    }
+
+    #[test]
+    fn test_span_at() {
+        use crate::source::{Source, SourceStr};
+        use nom_locate::LocatedSpan;
+
+        let txt = "one\ntwö\nthree";
+        let src = SourceStr::new(txt);
+
+        let span = Source::<LocatedSpan<&str>>::span_at(&src, 2, 3).expect("span");
+        assert_eq!(span.location_offset(), 8);
+        assert_eq!(span.location_line(), 2);
+        assert_eq!(*span.fragment(), "");
+
+        let loc = Source::<&str>::location(&src, &txt[10..]);
+        let span = Source::<&str>::span_at(&src, loc.line, loc.column).expect("span");
+        assert_eq!(span.as_ptr(), txt[10..].as_ptr());
+
+        assert!(Source::<&str>::span_at(&src, 4, 0).is_none());
+    }
+
+    #[cfg(feature = "bom")]
+    #[test]
+    fn test_decode() {
+        use crate::source::{Encoding, Source, SourceBytes};
+
+        let src = SourceBytes::new(b"\xEF\xBB\xBFab\ncd");
+        let text = src.decode().expect("utf8");
+        assert_eq!(text.encoding(), Encoding::Utf8Bom);
+        assert_eq!(text.as_str(), "ab\ncd");
+        assert_eq!(text.raw_offset(3), 6);
+
+        let src = SourceBytes::new(b"ab");
+        let text = src.decode().expect("utf8");
+        assert_eq!(text.encoding(), Encoding::Utf8);
+        assert_eq!(text.raw_offset(1), 1);
+
+        // a, newline, ä, 𝄞 (surrogate pair), b
+        let mut raw = vec![0xFF, 0xFE];
+        for v in "a\nä\u{1D11E}b".encode_utf16() {
+            raw.extend(v.to_le_bytes());
+        }
+        let src = SourceBytes::new(&raw);
+        let text = src.decode().expect("utf16");
+        assert_eq!(text.encoding(), Encoding::Utf16Le);
+        assert_eq!(text.as_str(), "a\nä\u{1D11E}b");
+        assert_eq!(text.raw_offset(0), 2);
+        assert_eq!(text.raw_offset(2), 6);
+        assert_eq!(text.raw_offset(4), 8);
+        assert_eq!(text.raw_offset(8), 12);
+        assert_eq!(text.raw_offset(text.as_str().len()), raw.len());
+
+        let source = text.source_str();
+        let b = &text.as_str()[8..];
+        assert_eq!(Source::<&str>::line(&source, b), 2);
+
+        let mut raw = vec![0xFE, 0xFF];
+        for v in "xy".encode_utf16() {
+            raw.extend(v.to_be_bytes());
+        }
+        let src = SourceBytes::new(&raw);
+        assert_eq!(src.decode().expect("utf16").as_str(), "xy");
+    }
 }
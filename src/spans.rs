@@ -2,10 +2,11 @@
 //! Additions to LocatedSpan, str and \[u8\]
 //!
 
-use nom::{AsBytes, InputLength, Slice};
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use core::ops::Range;
+use nom::{AsBytes, InputLength, Offset, Slice};
 use nom_locate::LocatedSpan;
-use std::fmt::Debug;
-use std::ops::Range;
 
 /// Extension trait for Spans.
 pub trait SpanUnion {
@@ -17,68 +18,40 @@ pub trait SpanUnion {
     fn span_union<'a>(&self, first: &'a Self, second: &'a Self) -> Self;
 }
 
-impl<'s> SpanUnion for &'s str {
+impl SpanUnion for &str {
     /// Can be implemented reasonably sane for &str.
+    ///
+    /// Parts of the spans that are not contained in self are cut off.
     fn span_union<'a>(&self, first: &'a Self, second: &'a Self) -> Self {
-        let self_ptr = self.as_ptr();
-
-        let offset_1 = unsafe { first.as_ptr().offset_from(self_ptr) };
-        let offset_2 = unsafe { second.as_ptr().offset_from(self_ptr) };
-
-        let offset_1 = if offset_1 >= 0 { offset_1 as usize } else { 0 };
-        let offset_2 = if offset_2 >= 0 { offset_2 as usize } else { 0 };
-
-        let (offset, len) = if offset_1 <= offset_2 {
-            (offset_1, offset_2 - offset_1 + second.len())
-        } else {
-            (offset_2, offset_1 - offset_2 + first.len())
-        };
-
-        let offset = if offset > self.len() {
-            self.len()
-        } else {
-            offset
-        };
-        let len = if offset + len > self.len() {
-            self.len() - offset
-        } else {
-            len
-        };
-
-        &self[offset..offset + len]
+        let range = union_range(self.as_bytes(), first.as_bytes(), second.as_bytes());
+        &self[range]
     }
 }
 
-impl<'s> SpanUnion for &'s [u8] {
+impl SpanUnion for &[u8] {
     /// Can be implemented reasonably sane for &\[u8\].
+    ///
+    /// Parts of the spans that are not contained in self are cut off.
     fn span_union<'a>(&self, first: &'a Self, second: &'a Self) -> Self {
-        let self_ptr = self.as_ptr();
-
-        let offset_1 = unsafe { first.as_ptr().offset_from(self_ptr) };
-        let offset_2 = unsafe { second.as_ptr().offset_from(self_ptr) };
-
-        let offset_1 = if offset_1 >= 0 { offset_1 as usize } else { 0 };
-        let offset_2 = if offset_2 >= 0 { offset_2 as usize } else { 0 };
+        let range = union_range(self, first, second);
+        &self[range]
+    }
+}
 
-        let (offset, len) = if offset_1 <= offset_2 {
-            (offset_1, offset_2 - offset_1 + second.len())
-        } else {
-            (offset_2, offset_1 - offset_2 + first.len())
-        };
+/// Range of the union of both spans relative to complete.
+///
+/// Only compares addresses, so it's fine if the spans don't point into complete.
+/// Both spans are clamped to the bounds of complete before the union is taken.
+fn union_range(complete: &[u8], first: &[u8], second: &[u8]) -> Range<usize> {
+    let base = complete.as_ptr() as usize;
+    let clamp = |addr: usize| addr.saturating_sub(base).min(complete.len());
 
-        let offset = if offset > self.len() {
-            self.len()
-        } else {
-            offset
-        };
-        let len = if offset + len > self.len() {
-            self.len() - offset
-        } else {
-            len
-        };
+    let start_1 = clamp(first.as_ptr() as usize);
+    let end_1 = clamp(first.as_ptr() as usize + first.len());
+    let start_2 = clamp(second.as_ptr() as usize);
+    let end_2 = clamp(second.as_ptr() as usize + second.len());
 
-        &self[offset..offset + len]
-    }
+    start_1.min(start_2)..end_1.max(end_2)
 }
 
 impl<T, X> SpanUnion for LocatedSpan<T, X>
@@ -129,6 +102,297 @@ where
     }
 }
 
+/// Byte range of a span.
+pub trait SpanRange {
+    /// Returns the byte range of the span relative to the complete span.
+    ///
+    /// The range can be stored and later be turned into a span again with
+    /// [Source::span_for](crate::source::Source::span_for).
+    ///
+    /// # Panics
+    /// If the span is not contained in the complete span.
+    fn to_range(&self, complete: &Self) -> Range<usize>;
+}
+
+impl SpanRange for &str {
+    fn to_range(&self, complete: &Self) -> Range<usize> {
+        slice_range(complete.as_bytes(), self.as_bytes())
+    }
+}
+
+impl SpanRange for &[u8] {
+    fn to_range(&self, complete: &Self) -> Range<usize> {
+        slice_range(complete, self)
+    }
+}
+
+impl<T, X> SpanRange for LocatedSpan<T, X>
+where
+    T: AsBytes + InputLength,
+{
+    fn to_range(&self, complete: &Self) -> Range<usize> {
+        assert!(self.location_offset() >= complete.location_offset());
+        let start = self.location_offset() - complete.location_offset();
+        let end = start + self.input_len();
+        assert!(end <= complete.input_len());
+        start..end
+    }
+}
+
+/// Range of the fragment relative to complete.
+fn slice_range(complete: &[u8], fragment: &[u8]) -> Range<usize> {
+    let base = complete.as_ptr() as usize;
+    let addr = fragment.as_ptr() as usize;
+    assert!(addr >= base);
+    let start = addr - base;
+    let end = start + fragment.len();
+    assert!(end <= complete.len());
+    start..end
+}
+
+/// Geometry of spans of the same source.
+pub trait SpanGeometry: Sized {
+    /// Does this span contain the other one?
+    fn contains_span(&self, other: &Self) -> bool;
+
+    /// Do both spans overlap?
+    ///
+    /// Adjacent spans don't intersect, but an empty span intersects with a span
+    /// that contains it.
+    fn intersects(&self, other: &Self) -> bool;
+
+    /// The overlapping part of both spans.
+    fn intersection(&self, other: &Self) -> Option<Self>;
+
+    /// Orders by start offset, and by end offset if both start at the same offset.
+    fn ordering_by_offset(&self, other: &Self) -> Ordering;
+}
+
+impl SpanGeometry for &str {
+    fn contains_span(&self, other: &Self) -> bool {
+        contains_range(addr_range(self.as_bytes()), addr_range(other.as_bytes()))
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        intersect_range(addr_range(self.as_bytes()), addr_range(other.as_bytes())).is_some()
+    }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let base = self.as_ptr() as usize;
+        intersect_range(addr_range(self.as_bytes()), addr_range(other.as_bytes()))
+            .map(|v| &self[v.start - base..v.end - base])
+    }
+
+    fn ordering_by_offset(&self, other: &Self) -> Ordering {
+        order_range(addr_range(self.as_bytes()), addr_range(other.as_bytes()))
+    }
+}
+
+impl SpanGeometry for &[u8] {
+    fn contains_span(&self, other: &Self) -> bool {
+        contains_range(addr_range(self), addr_range(other))
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        intersect_range(addr_range(self), addr_range(other)).is_some()
+    }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let base = self.as_ptr() as usize;
+        intersect_range(addr_range(self), addr_range(other))
+            .map(|v| &self[v.start - base..v.end - base])
+    }
+
+    fn ordering_by_offset(&self, other: &Self) -> Ordering {
+        order_range(addr_range(self), addr_range(other))
+    }
+}
+
+impl<T, X> SpanGeometry for LocatedSpan<T, X>
+where
+    T: AsBytes + InputLength,
+    LocatedSpan<T, X>: Slice<Range<usize>>,
+{
+    fn contains_span(&self, other: &Self) -> bool {
+        contains_range(offset_range(self), offset_range(other))
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        intersect_range(offset_range(self), offset_range(other)).is_some()
+    }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let base = self.location_offset();
+        intersect_range(offset_range(self), offset_range(other))
+            .map(|v| self.slice(v.start - base..v.end - base))
+    }
+
+    fn ordering_by_offset(&self, other: &Self) -> Ordering {
+        order_range(offset_range(self), offset_range(other))
+    }
+}
+
+fn addr_range(span: &[u8]) -> Range<usize> {
+    let addr = span.as_ptr() as usize;
+    addr..addr + span.len()
+}
+
+fn offset_range<T: AsBytes + InputLength, X>(span: &LocatedSpan<T, X>) -> Range<usize> {
+    span.location_offset()..span.location_offset() + span.input_len()
+}
+
+fn contains_range(outer: Range<usize>, inner: Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+fn intersect_range(first: Range<usize>, second: Range<usize>) -> Option<Range<usize>> {
+    let start = first.start.max(second.start);
+    let end = first.end.min(second.end);
+    if start < end || start == end && (first.is_empty() || second.is_empty()) {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+fn order_range(first: Range<usize>, second: Range<usize>) -> Ordering {
+    first
+        .start
+        .cmp(&second.start)
+        .then(first.end.cmp(&second.end))
+}
+
+/// Shrink and extend spans without unsafe code.
+pub trait SpanResize: Sized {
+    /// Removes leading whitespace.
+    fn trim_start_ws(&self) -> Self;
+
+    /// Removes trailing whitespace.
+    fn trim_end_ws(&self) -> Self;
+
+    /// Sub-span by char index.
+    ///
+    /// # Panics
+    /// If the range is beyond the end of the span.
+    fn slice_chars(&self, range: Range<usize>) -> Self;
+
+    /// Extends the span up to the end of the other span.
+    ///
+    /// # Panics
+    /// If the result is not contained in the complete span, or the other
+    /// span ends before this one starts.
+    fn extend_to(&self, complete: &Self, other: &Self) -> Self;
+}
+
+impl SpanResize for &str {
+    fn trim_start_ws(&self) -> Self {
+        self.trim_start()
+    }
+
+    fn trim_end_ws(&self) -> Self {
+        self.trim_end()
+    }
+
+    fn slice_chars(&self, range: Range<usize>) -> Self {
+        &self[char_range(self.as_bytes(), range)]
+    }
+
+    fn extend_to(&self, complete: &Self, other: &Self) -> Self {
+        &complete[extend_range(complete.as_bytes(), self.as_bytes(), other.as_bytes())]
+    }
+}
+
+impl SpanResize for &[u8] {
+    fn trim_start_ws(&self) -> Self {
+        let n = self.iter().take_while(|v| v.is_ascii_whitespace()).count();
+        &self[n..]
+    }
+
+    fn trim_end_ws(&self) -> Self {
+        let n = self
+            .iter()
+            .rev()
+            .take_while(|v| v.is_ascii_whitespace())
+            .count();
+        &self[..self.len() - n]
+    }
+
+    fn slice_chars(&self, range: Range<usize>) -> Self {
+        &self[char_range(self, range)]
+    }
+
+    fn extend_to(&self, complete: &Self, other: &Self) -> Self {
+        &complete[extend_range(complete, self, other)]
+    }
+}
+
+impl<T, X> SpanResize for LocatedSpan<T, X>
+where
+    T: SpanResize + AsBytes + InputLength + Offset,
+    LocatedSpan<T, X>: Slice<Range<usize>>,
+{
+    fn trim_start_ws(&self) -> Self {
+        let trim = self.fragment().trim_start_ws();
+        let start = self.fragment().offset(&trim);
+        self.slice(start..self.input_len())
+    }
+
+    fn trim_end_ws(&self) -> Self {
+        let trim = self.fragment().trim_end_ws();
+        self.slice(0..trim.input_len())
+    }
+
+    fn slice_chars(&self, range: Range<usize>) -> Self {
+        let sub = self.fragment().slice_chars(range);
+        let start = self.fragment().offset(&sub);
+        self.slice(start..start + sub.input_len())
+    }
+
+    fn extend_to(&self, complete: &Self, other: &Self) -> Self {
+        assert!(self.location_offset() >= complete.location_offset());
+        let start = self.location_offset() - complete.location_offset();
+        let end = (other.location_offset() + other.input_len())
+            .checked_sub(complete.location_offset())
+            .expect("other span before complete");
+        assert!(start <= end);
+        assert!(end <= complete.input_len());
+        complete.slice(start..end)
+    }
+}
+
+/// Byte range for a char range. Counts UTF8 char starts.
+fn char_range(text: &[u8], range: Range<usize>) -> Range<usize> {
+    let mut starts = text
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b & 0xC0 != 0x80)
+        .map(|(i, _)| i)
+        .chain(Some(text.len()));
+    let start = starts.nth(range.start).expect("char range out of bounds");
+    let end = if range.end > range.start {
+        starts
+            .nth(range.end - range.start - 1)
+            .expect("char range out of bounds")
+    } else {
+        start
+    };
+    start..end
+}
+
+/// Range from the start of the first span to the end of the second.
+fn extend_range(complete: &[u8], first: &[u8], second: &[u8]) -> Range<usize> {
+    let base = complete.as_ptr() as usize;
+    let start = (first.as_ptr() as usize)
+        .checked_sub(base)
+        .expect("span before complete");
+    let end = (second.as_ptr() as usize + second.len())
+        .checked_sub(base)
+        .expect("other span before complete");
+    assert!(start <= end);
+    assert!(end <= complete.len());
+    start..end
+}
+
 /// Get the fragment from a span.
 pub trait SpanFragment {
     /// Type of the fragment.
@@ -0,0 +1,235 @@
+//!
+//! User state that travels with the span.
+//!
+//! The `extra` of a [ParseSpan](crate::ParseSpan) is taken by the
+//! TrackProvider. A [StateSpan](crate::StateSpan) has a [StateExtra] instead, which holds the
+//! TrackProvider and a reference to some user state. Tables for interned
+//! strings, symbol counters and the like can be reached with
+//! [state()](SpanState::state) instead of a thread-local.
+//!
+//! The state is shared by all copies of the span, use a Cell or RefCell for
+//! anything that changes while parsing.
+//!
+//! Unlike ParseSpan the StateSpan is the same type in debug and release
+//! mode, only the tracking is compiled away.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExTagA};
+//! use kparse::prelude::*;
+//! use kparse::{StateSpan, TokenizerError};
+//! use nom::bytes::complete::tag;
+//! use nom::Parser;
+//! use std::cell::Cell;
+//!
+//! type Span<'s> = StateSpan<'s, ExCode, &'s str, Cell<u32>>;
+//!
+//! fn count_a(i: Span<'_>) -> nom::IResult<Span<'_>, Span<'_>, TokenizerError<ExCode, Span<'_>>> {
+//!     let (rest, a) = tag("a").with_code(ExTagA).parse(i)?;
+//!     rest.state().set(rest.state().get() + 1);
+//!     Ok((rest, a))
+//! }
+//!
+//! let counter = Cell::new(0);
+//! let tracker = Track::new_tracker::<ExCode, _>();
+//! let span = Track::new_state_span(&tracker, &counter, "aab");
+//!
+//! let (rest, _) = count_a(span).expect("ok");
+//! let (rest, _) = count_a(rest).expect("ok");
+//! assert!(count_a(rest).is_err());
+//! assert_eq!(counter.get(), 2);
+//! ```
+//!
+
+#[cfg(debug_assertions)]
+use crate::provider::TrackData;
+#[cfg(debug_assertions)]
+use crate::report::Severity;
+use crate::{Code, DynTrackProvider, TrackedSpan};
+#[cfg(debug_assertions)]
+use alloc::format;
+use alloc::string::String;
+#[cfg(debug_assertions)]
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+#[cfg(not(debug_assertions))]
+use core::marker::PhantomData;
+#[cfg(debug_assertions)]
+use nom::{AsBytes, InputLength, InputTake};
+use nom_locate::LocatedSpan;
+
+/// Extra for a [StateSpan](crate::StateSpan). Holds the TrackProvider and
+/// the user state.
+pub struct StateExtra<'s, C, T, S> {
+    #[cfg(debug_assertions)]
+    provider: DynTrackProvider<'s, C, T>,
+    #[cfg(not(debug_assertions))]
+    provider: PhantomData<DynTrackProvider<'s, C, T>>,
+    state: &'s S,
+}
+
+impl<'s, C, T, S> StateExtra<'s, C, T, S> {
+    /// Combines the TrackProvider and the state.
+    #[cfg(debug_assertions)]
+    pub fn new(provider: DynTrackProvider<'s, C, T>, state: &'s S) -> Self {
+        Self { provider, state }
+    }
+
+    /// Combines the TrackProvider and the state.
+    /// The TrackProvider is not used in release mode.
+    #[cfg(not(debug_assertions))]
+    pub fn new(_provider: DynTrackProvider<'s, C, T>, state: &'s S) -> Self {
+        Self {
+            provider: PhantomData,
+            state,
+        }
+    }
+
+    /// The user state.
+    pub fn state(&self) -> &'s S {
+        self.state
+    }
+}
+
+impl<C, T, S> Clone for StateExtra<'_, C, T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, T, S> Copy for StateExtra<'_, C, T, S> {}
+
+impl<C, T, S> Debug for StateExtra<'_, C, T, S> {
+    fn fmt(&self, _: &mut Formatter<'_>) -> core::fmt::Result {
+        Ok(())
+    }
+}
+
+/// Access to the user state of a span.
+pub trait SpanState<S> {
+    /// The user state.
+    fn state(&self) -> &S;
+}
+
+impl<'s, C, T, S> SpanState<S> for LocatedSpan<T, StateExtra<'s, C, T, S>> {
+    fn state(&self) -> &S {
+        self.extra.state
+    }
+}
+
+#[cfg(debug_assertions)]
+impl<C, T, S> TrackedSpan<C> for LocatedSpan<T, StateExtra<'_, C, T, S>>
+where
+    C: Code,
+    T: Clone + Debug + AsBytes + InputTake + InputLength,
+{
+    #[inline(always)]
+    fn track_enter(&self, func: C) {
+        self.extra
+            .provider
+            .track(TrackData::Enter(func, crate::clear_span(self)));
+    }
+
+    #[inline(always)]
+    fn track_debug(&self, debug: String) {
+        self.extra
+            .provider
+            .track(TrackData::Debug(crate::clear_span(self), debug));
+    }
+
+    #[inline(always)]
+    fn track_value<V: Debug>(&self, value: &V) {
+        self.extra.provider.track(TrackData::Value(
+            crate::clear_span(self),
+            format!("{:#?}", value),
+        ));
+    }
+
+    #[inline(always)]
+    fn track_info(&self, info: &'static str) {
+        self.extra
+            .provider
+            .track(TrackData::Info(crate::clear_span(self), info));
+    }
+
+    #[inline(always)]
+    fn track_warn(&self, warn: &'static str) {
+        self.extra
+            .provider
+            .track(TrackData::Warn(crate::clear_span(self), warn));
+    }
+
+    #[inline(always)]
+    fn track_ok(&self, parsed: Self) {
+        self.extra.provider.track(TrackData::Ok(
+            crate::clear_span(self),
+            crate::clear_span(&parsed),
+        ));
+    }
+
+    #[inline(always)]
+    fn track_err<E: Debug>(&self, code: C, err: &E) {
+        self.track_err_expected(code, err, Vec::new);
+    }
+
+    #[inline(always)]
+    fn track_err_expected<E: Debug>(
+        &self,
+        code: C,
+        err: &E,
+        expected: impl FnOnce() -> Vec<(C, Self)>,
+    ) {
+        if code.severity() == Severity::Warning {
+            self.extra.provider.track(TrackData::Warn(
+                crate::clear_span(self),
+                "error with severity warning",
+            ));
+        }
+        let expected = expected()
+            .iter()
+            .map(|(code, span)| (*code, crate::clear_span(span)))
+            .collect();
+        let text = if self.extra.provider.err_text() {
+            format!("{:?}", err)
+        } else {
+            String::new()
+        };
+        self.extra.provider.track(TrackData::Err(
+            crate::clear_span(self),
+            code,
+            expected,
+            text,
+        ));
+    }
+
+    #[inline(always)]
+    fn track_exit(&self) {
+        self.extra.provider.track(TrackData::Exit());
+    }
+}
+
+#[cfg(not(debug_assertions))]
+impl<C, T, S> TrackedSpan<C> for LocatedSpan<T, StateExtra<'_, C, T, S>>
+where
+    C: Code,
+{
+    #[inline(always)]
+    fn track_enter(&self, _func: C) {}
+
+    #[inline(always)]
+    fn track_debug(&self, _debug: String) {}
+
+    #[inline(always)]
+    fn track_info(&self, _info: &'static str) {}
+
+    #[inline(always)]
+    fn track_warn(&self, _warn: &'static str) {}
+
+    #[inline(always)]
+    fn track_ok(&self, _parsed: Self) {}
+
+    #[inline(always)]
+    fn track_err<E>(&self, _func: C, _err: &E) {}
+
+    #[inline(always)]
+    fn track_exit(&self) {}
+}
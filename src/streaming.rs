@@ -0,0 +1,304 @@
+//!
+//! Parsing input that arrives in chunks.
+//!
+//! nom signals with nom::Err::Incomplete that a parser ran out of input
+//! and could continue with more. This module has the streaming variants of
+//! the combinators that can run out of input, and a [StreamBuffer] that
+//! collects the chunks and reruns the parser once more data has arrived.
+//!
+//! The streaming combinators never succeed at the end of the input, as
+//! there might be more. After [StreamBuffer::close] the rest can be parsed
+//! with the complete combinators, or use [complete](crate::KParser::complete)
+//! to turn the Incomplete into an error.
+//!
+//! [track](crate::combinators::track) records an Incomplete as info
+//! "incomplete" and closes the function, so the trace shows how far the
+//! parser got.
+//!
+//! ```rust
+//! use kparse::examples::ExCode;
+//! use kparse::streaming::{take_till_newline, StreamBuffer};
+//! use kparse::TokenizerError;
+//! use nom::bytes::streaming::tag;
+//! use nom::sequence::terminated;
+//!
+//! fn record(i: &str) -> nom::IResult<&str, &str, TokenizerError<ExCode, &str>> {
+//!     terminated(take_till_newline, tag("\n"))(i)
+//! }
+//!
+//! let mut buf = StreamBuffer::new();
+//! let mut records = Vec::new();
+//! for chunk in ["fir", "st\nsec", "ond\n"] {
+//!     buf.push(chunk.as_bytes());
+//!     while let Some(r) = buf.parse_str(record).expect("ok") {
+//!         records.push(r.to_string());
+//!     }
+//! }
+//! buf.close();
+//! assert!(buf.is_empty());
+//! assert_eq!(records, vec!["first", "second"]);
+//! ```
+//!
+
+use crate::combinators::ascii_ws_len;
+use crate::{Code, KParseError};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ops::{RangeFrom, RangeTo};
+use nom::error::{ErrorKind, ParseError};
+use nom::{AsBytes, AsChar, IResult, InputIter, InputLength, Needed, Slice};
+
+/// Skips ASCII whitespace: space, tab, '\r' and '\n'.
+///
+/// Streaming variant of [combinators::take_while_ascii_ws](crate::combinators::take_while_ascii_ws).
+/// Returns Incomplete if the whitespace reaches the end of the input.
+#[inline]
+pub fn take_while_ascii_ws<I, E>(i: I) -> IResult<I, I, E>
+where
+    I: AsBytes + InputLength + Slice<RangeTo<usize>> + Slice<RangeFrom<usize>>,
+    E: ParseError<I>,
+{
+    let len = ascii_ws_len(i.as_bytes());
+    if len == i.input_len() {
+        Err(nom::Err::Incomplete(Needed::new(1)))
+    } else {
+        Ok((i.slice(len..), i.slice(..len)))
+    }
+}
+
+/// Takes everything up to the next '\n'.
+/// The line break, '\n' or '\r\n', stays in the rest.
+///
+/// Streaming variant of [combinators::take_till_newline](crate::combinators::take_till_newline).
+/// Returns Incomplete if there is no '\n' in the input.
+#[inline]
+pub fn take_till_newline<I, E>(i: I) -> IResult<I, I, E>
+where
+    I: AsBytes + InputLength + Slice<RangeTo<usize>> + Slice<RangeFrom<usize>>,
+    E: ParseError<I>,
+{
+    let bytes = i.as_bytes();
+    let len = match memchr::memchr(b'\n', bytes) {
+        Some(nl) if nl > 0 && bytes[nl - 1] == b'\r' => nl - 1,
+        Some(nl) => nl,
+        None => return Err(nom::Err::Incomplete(Needed::new(1))),
+    };
+    Ok((i.slice(len..), i.slice(..len)))
+}
+
+/// Same as nom::bytes::streaming::take_while_m_n but with an error code.
+///
+/// Streaming variant of [combinators::take_while_m_n](crate::combinators::take_while_m_n).
+/// Returns Incomplete if all of the input matches and there could be more.
+#[inline]
+pub fn take_while_m_n<FN, C, I, E>(
+    min: usize,
+    max: usize,
+    pred: FN,
+    code: C,
+) -> impl Fn(I) -> Result<(I, I), nom::Err<E>>
+where
+    FN: Fn(char) -> bool,
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    move |i: I| {
+        let mut n = 0;
+        let mut len = 0;
+        let mut end = true;
+        for (off, c) in i.iter_indices() {
+            if n == max || !pred(c.as_char()) {
+                end = false;
+                break;
+            }
+            n += 1;
+            len = off + c.len();
+        }
+
+        if end && n < max {
+            Err(nom::Err::Incomplete(Needed::new(
+                min.saturating_sub(n).max(1),
+            )))
+        } else if n < min {
+            Err(nom::Err::Error(E::from(code, i.slice(..len))))
+        } else {
+            Ok((i.slice(len..), i.slice(..len)))
+        }
+    }
+}
+
+/// Takes exactly n characters or fails with the code.
+///
+/// Streaming variant of [combinators::chars](crate::combinators::chars).
+#[inline]
+pub fn chars<C, I, E>(n: usize, code: C) -> impl Fn(I) -> Result<(I, I), nom::Err<E>>
+where
+    C: Code,
+    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputIter,
+    <I as InputIter>::Item: AsChar + Copy,
+    E: KParseError<C, I>,
+{
+    take_while_m_n(n, n, |_| true, code)
+}
+
+/// Collects the chunks of a stream and runs a parser on them.
+///
+/// Each call to parse() runs the parser on the data that is not yet
+/// parsed. If the parser succeeds, the parsed part is dropped before the
+/// next call. If the parser returns Incomplete, parse() returns None and
+/// the parser runs again from the same place after the next push().
+///
+/// Once the stream has ended call close(). Then an Incomplete is returned
+/// as the error it is.
+#[derive(Debug)]
+pub struct StreamBuffer {
+    buf: Vec<u8>,
+    parsed: Cell<usize>,
+    offset: usize,
+    line: u32,
+    closed: bool,
+}
+
+impl Default for StreamBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamBuffer {
+    /// Empty buffer.
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            parsed: Cell::new(0),
+            offset: 0,
+            line: 1,
+            closed: false,
+        }
+    }
+
+    /// Appends a chunk of the stream.
+    ///
+    /// # Panics
+    /// After close().
+    pub fn push(&mut self, chunk: &[u8]) {
+        assert!(!self.closed, "push after close");
+        self.drop_parsed();
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// The stream has ended.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// Has the stream ended?
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Length of the data that is not yet parsed.
+    pub fn len(&self) -> usize {
+        self.buf.len() - self.parsed.get()
+    }
+
+    /// Everything parsed?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Offset of the data that is not yet parsed within the whole stream.
+    pub fn offset(&self) -> usize {
+        self.offset + self.parsed.get()
+    }
+
+    /// Line of the data that is not yet parsed within the whole stream.
+    /// Lines start at 1.
+    pub fn line(&self) -> u32 {
+        let parsed = &self.buf[..self.parsed.get()];
+        self.line + bytecount::count(parsed, b'\n') as u32
+    }
+
+    /// The data that is not yet parsed.
+    pub fn unparsed(&self) -> &[u8] {
+        &self.buf[self.parsed.get()..]
+    }
+
+    /// Runs the parser on the data that is not yet parsed.
+    ///
+    /// Returns None if the parser needs more data.
+    pub fn parse<'a, O, E>(
+        &'a mut self,
+        parser: impl FnOnce(&'a [u8]) -> IResult<&'a [u8], O, E>,
+    ) -> Result<Option<O>, nom::Err<E>> {
+        self.drop_parsed();
+        let this: &'a Self = self;
+
+        this.finish(parser(&this.buf), this.buf.len())
+    }
+
+    /// Runs the parser on the data that is not yet parsed.
+    ///
+    /// The parser gets all the complete UTF-8 text. A char that is split
+    /// between two chunks waits for the next chunk. Invalid UTF-8 is a
+    /// nom::Err::Failure with ErrorKind::Char after the valid part.
+    ///
+    /// Returns None if the parser needs more data.
+    pub fn parse_str<'a, O, E>(
+        &'a mut self,
+        parser: impl FnOnce(&'a str) -> IResult<&'a str, O, E>,
+    ) -> Result<Option<O>, nom::Err<E>>
+    where
+        E: ParseError<&'a str>,
+    {
+        self.drop_parsed();
+        let this: &'a Self = self;
+
+        let (text, invalid) = match core::str::from_utf8(&this.buf) {
+            Ok(text) => (text, false),
+            Err(e) => {
+                // Safety: valid_up_to() is the end of the valid UTF-8.
+                let text = unsafe { core::str::from_utf8_unchecked(&this.buf[..e.valid_up_to()]) };
+                (text, e.error_len().is_some() || this.closed)
+            }
+        };
+
+        match this.finish(parser(text), text.len()) {
+            Ok(None) | Err(nom::Err::Incomplete(_)) if invalid => Err(nom::Err::Failure(
+                E::from_error_kind(&text[text.len()..], ErrorKind::Char),
+            )),
+            r => r,
+        }
+    }
+
+    /// Marks the parsed part and maps the result.
+    fn finish<I, O, E>(
+        &self,
+        result: IResult<I, O, E>,
+        len: usize,
+    ) -> Result<Option<O>, nom::Err<E>>
+    where
+        I: InputLength,
+    {
+        match result {
+            Ok((rest, o)) => {
+                self.parsed.set(len - rest.input_len());
+                Ok(Some(o))
+            }
+            Err(nom::Err::Incomplete(_)) if !self.closed => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Removes the parsed part from the buffer.
+    fn drop_parsed(&mut self) {
+        let parsed = self.parsed.replace(0);
+        if parsed > 0 {
+            self.line += bytecount::count(&self.buf[..parsed], b'\n') as u32;
+            self.offset += parsed;
+            self.buf.drain(..parsed);
+        }
+    }
+}
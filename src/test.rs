@@ -21,16 +21,33 @@
 
 use crate::debug::{restrict, DebugWidth};
 use crate::provider::StdTracker;
+use crate::provider::TrackProvider;
+#[cfg(debug_assertions)]
+use crate::provider::TrackedData;
+use crate::source::SourceBytes;
 use crate::spans::SpanFragment;
+#[cfg(feature = "winnow")]
+use crate::winnow::nom_err;
 use crate::{Code, KParseError, ParserError};
-#[cfg(debug_assertions)]
 use crate::{ParseSpan, Track};
-use nom::{AsBytes, InputIter, InputLength, InputTake};
+#[cfg(debug_assertions)]
+use nom::Slice;
+use nom::{AsBytes, InputIter, InputLength, InputTake, Needed, Offset};
 pub use report::*;
-use std::cell::Cell;
+#[cfg(debug_assertions)]
+use std::cell::Ref;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, IsTerminal, Write};
+#[cfg(debug_assertions)]
+use std::ops::{RangeFrom, RangeTo};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use std::vec::Vec;
+use std::{env, fs};
+#[cfg(feature = "winnow")]
+use winnow::{LocatingSlice, ModalResult};
 
 /// Value comparison.
 pub type TestEqFn<O, V> = for<'a> fn(parsed: &'a O, test: V) -> bool;
@@ -47,16 +64,81 @@ pub struct Test<'s, P, I, O, E> {
     pub result: Result<(I, O), nom::Err<E>>,
     /// Test duration
     pub duration: Duration,
+    /// Durations of all runs. Only str_timing() runs the parser more than once.
+    pub samples: Vec<Duration>,
     /// Any check failed
     pub failed: Cell<bool>,
+    /// Name of the source for diagnostics.
+    pub name: RefCell<Option<String>>,
+}
+
+/// A test failed. Returned by finish().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFailure {
+    /// Name of the source if one was set.
+    pub name: Option<String>,
+}
+
+impl Display for TestFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "test failed for {}", name),
+            None => write!(f, "test failed"),
+        }
+    }
 }
 
+impl Error for TestFailure {}
+
 /// Result reporting.
 pub trait Report<T> {
     /// Report something.
     fn report(&self, test: &T);
 }
 
+/// Chains reports.
+///
+/// The reports run in order. Reports like CheckTrace panic if a test failed,
+/// so they should come last.
+///
+/// ```rust
+/// use kparse::test::{str_parse, CheckTrace, Timing, ReportAnd};
+/// # use nom::bytes::complete::tag;
+/// # use kparse::combinators::with_code;
+/// # use kparse::examples::{ExSpan, ExTagB, ExTokenizerResult};
+/// # fn nom_parse_b(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+/// #     with_code(tag("b"), ExTagB)(i)
+/// # }
+///
+/// str_parse(&mut None, "b", nom_parse_b)
+///     .ok_any()
+///     .q(Timing(1).and(CheckTrace));
+/// ```
+pub trait ReportAnd: Sized {
+    /// Runs this report and then the other one.
+    fn and<R>(self, other: R) -> And<Self, R> {
+        And(self, other)
+    }
+}
+
+/// Two reports in a row. Create with [ReportAnd::and].
+#[derive(Clone, Copy)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<A, B> ReportAnd for And<A, B> {}
+
+impl<T, A, B> Report<T> for And<A, B>
+where
+    A: Report<T>,
+    B: Report<T>,
+{
+    #[track_caller]
+    fn report(&self, test: &T) {
+        self.0.report(test);
+        self.1.report(test);
+    }
+}
+
 /// Not an error code.
 #[doc(hidden)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -105,7 +187,9 @@ where
         context,
         result,
         duration,
+        samples: vec![duration],
         failed: Cell::new(false),
+        name: RefCell::new(None),
     }
 }
 
@@ -132,7 +216,415 @@ pub fn str_parse<'s, O, E>(
         context: &(),
         result,
         duration,
+        samples: vec![duration],
+        failed: Cell::new(false),
+        name: RefCell::new(None),
+    }
+}
+
+/// Runs a winnow parser for &str and records the results.
+/// Use ok(), err(), ... to check specifics.
+/// Finish the test with q().
+///
+/// The parser gets a LocatingSlice, so [track](crate::winnow::track)
+/// can find the position. Install a
+/// [LocalTracker](crate::provider::LocalTracker) to collect the trace.
+/// The rest and the error spans are plain &str.
+#[must_use]
+#[cfg(feature = "winnow")]
+pub fn winnow_parse<'s, O, E>(
+    text: &'s str,
+    mut fn_test: impl FnMut(&mut LocatingSlice<&'s str>) -> ModalResult<O, E>,
+) -> Test<'s, (), &'s str, O, E> {
+    let mut input = LocatingSlice::new(text);
+
+    let now = Instant::now();
+    let result = fn_test(&mut input);
+    let duration = now.elapsed();
+
+    Test {
+        span: text,
+        context: &(),
+        result: match result {
+            Ok(o) => Ok((*input.as_ref(), o)),
+            Err(e) => Err(nom_err(e)),
+        },
+        duration,
+        samples: vec![duration],
+        failed: Cell::new(false),
+        name: RefCell::new(None),
+    }
+}
+
+/// Expands a table of test cases into one test function per row.
+///
+/// Each row runs str_parse() with the parser, applies the checks and
+/// finishes with q() and the report.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use kparse::combinators::with_code;
+/// use kparse::examples::{ExSpan, ExTagB, ExTokenizerResult};
+/// use kparse::kparse_cases;
+/// use kparse::test::CheckDump;
+///
+/// fn nom_parse_b(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     with_code(tag("b"), ExTagB)(i)
+/// }
+///
+/// kparse_cases! {
+///     nom_parse_b, CheckDump;
+///     b_ok: "b" => ok_any();
+///     b_rest: "bb" => ok_any().rest("b");
+///     b_err: "a" => err(ExTagB);
+/// }
+/// ```
+#[macro_export]
+macro_rules! kparse_cases {
+    ($parser:expr, $report:expr;
+     $($name:ident: $input:expr => $($check:ident ( $($arg:expr),* $(,)? )).+ ;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                $crate::test::str_parse(&mut None, $input, $parser)
+                    $(.$check($($arg),*))+
+                    .q($report);
+            }
+        )*
+    };
+}
+
+/// Runs a parser for &str several times and records the durations.
+/// The result is the one of the last run. Use with the Statistics report.
+///
+/// The first warmup runs are not recorded. In debug build the tracking
+/// is part of the measurement and the tracking data is cleared after each run,
+/// except for the last.
+#[must_use]
+#[cfg(debug_assertions)]
+pub fn str_timing<'s, C, O, E>(
+    buf: &'s mut Option<StdTracker<C, &'s str>>,
+    text: &'s str,
+    warmup: u32,
+    samples: u32,
+    fn_test: impl Fn(ParseSpan<'s, C, &'s str>) -> Result<(ParseSpan<'s, C, &'s str>, O), nom::Err<E>>,
+) -> Test<'s, StdTracker<C, &'s str>, ParseSpan<'s, C, &'s str>, O, E>
+where
+    C: Code,
+{
+    buf.replace(Track::new_tracker());
+    let context = buf.as_ref().expect("yes");
+
+    let span = Track::new_span(context, text);
+
+    for _ in 0..warmup {
+        let _ = fn_test(span);
+        let _ = context.results();
+    }
+
+    let mut durations = Vec::new();
+    let mut result;
+    loop {
+        let now = Instant::now();
+        result = fn_test(span);
+        durations.push(now.elapsed());
+        if durations.len() >= samples as usize {
+            break;
+        }
+        let _ = context.results();
+    }
+
+    Test {
+        span,
+        context,
+        result,
+        duration: durations[0],
+        samples: durations,
+        failed: Cell::new(false),
+        name: RefCell::new(None),
+    }
+}
+
+/// Runs a parser for &str several times and records the durations.
+/// The result is the one of the last run. Use with the Statistics report.
+///
+/// The first warmup runs are not recorded. In debug build the tracking
+/// is part of the measurement and the tracking data is cleared after each run,
+/// except for the last.
+#[must_use]
+#[cfg(not(debug_assertions))]
+pub fn str_timing<'s, O, E>(
+    _buf: &'s mut Option<StdTracker<NoCode, &'s str>>,
+    text: &'s str,
+    warmup: u32,
+    samples: u32,
+    fn_test: impl Fn(&'s str) -> Result<(&'s str, O), nom::Err<E>>,
+) -> Test<'s, (), &'s str, O, E> {
+    for _ in 0..warmup {
+        let _ = fn_test(text);
+    }
+
+    let mut durations = Vec::new();
+    let mut result;
+    loop {
+        let now = Instant::now();
+        result = fn_test(text);
+        durations.push(now.elapsed());
+        if durations.len() >= samples as usize {
+            break;
+        }
+    }
+
+    Test {
+        span: text,
+        context: &(),
+        result,
+        duration: durations[0],
+        samples: durations,
         failed: Cell::new(false),
+        name: RefCell::new(None),
+    }
+}
+
+/// Runs the same parser with and without tracking and prints the
+/// median durations and the overhead factor of the tracking.
+///
+/// The parser must be generic over the span type, fn_tracked is the
+/// instance for a ParseSpan and fn_plain the one for &str.
+/// Returns the overhead factor.
+pub fn str_tracking_overhead<'s, C, O1, O2, E1, E2>(
+    buf: &'s mut Option<StdTracker<C, &'s str>>,
+    text: &'s str,
+    samples: u32,
+    fn_tracked: impl Fn(
+        ParseSpan<'s, C, &'s str>,
+    ) -> Result<(ParseSpan<'s, C, &'s str>, O1), nom::Err<E1>>,
+    fn_plain: impl Fn(&'s str) -> Result<(&'s str, O2), nom::Err<E2>>,
+) -> f64
+where
+    C: Code,
+{
+    buf.replace(Track::new_tracker());
+    let context = buf.as_ref().expect("yes");
+    let span = context.track_span(text);
+
+    let tracked = median(samples, || {
+        let _ = fn_tracked(span);
+        let _ = context.results();
+    });
+    let plain = median(samples, || {
+        let _ = fn_plain(text);
+    });
+
+    let factor = tracked.as_secs_f64() / plain.as_secs_f64().max(f64::MIN_POSITIVE);
+
+    println!(
+        "when parsing {:?} tracked {:?} untracked {:?} => overhead x{:.2}",
+        restrict(DebugWidth::Medium, text),
+        tracked,
+        plain,
+        factor
+    );
+
+    factor
+}
+
+/// Median duration of n runs.
+fn median(samples: u32, mut f: impl FnMut()) -> Duration {
+    let mut durations = (0..samples.max(1))
+        .map(|_| {
+            let now = Instant::now();
+            f();
+            now.elapsed()
+        })
+        .collect::<Vec<_>>();
+    durations.sort();
+    durations[durations.len() / 2]
+}
+
+/// Input files for str_fixtures().
+#[derive(Debug)]
+pub struct Fixtures {
+    /// All fixtures.
+    pub files: Vec<Fixture>,
+}
+
+/// One input file and the expected output.
+#[derive(Debug)]
+pub struct Fixture {
+    /// Input file.
+    pub path: PathBuf,
+    /// Content of the input file.
+    pub text: String,
+    /// Expected Debug output of the parsed value. Taken from a file with the
+    /// same name and the extension `expected`.
+    pub expected: Option<String>,
+}
+
+impl Fixtures {
+    /// Reads all files with the given extension from a directory.
+    pub fn read(dir: impl AsRef<Path>, ext: &str) -> io::Result<Self> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().map(|v| v == ext).unwrap_or(false) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut files = Vec::new();
+        for path in paths {
+            let text = fs::read_to_string(&path)?;
+            let expected = match fs::read_to_string(path.with_extension("expected")) {
+                Ok(v) => Some(v),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+            files.push(Fixture {
+                path,
+                text,
+                expected,
+            });
+        }
+
+        Ok(Self { files })
+    }
+}
+
+/// Runs the parser for each fixture. Each parser must succeed and consume all of
+/// the input. If the fixture has an expected output it's compared with the Debug
+/// output of the parsed value.
+///
+/// If the environment variable `KPARSE_UPDATE_SNAPSHOTS` is set the expected
+/// output is written instead.
+///
+/// Dumps every failed fixture and panics at the end if any failed.
+///
+/// This method changes behaviour between debug and release build like str_parse().
+#[track_caller]
+#[cfg(debug_assertions)]
+pub fn str_fixtures<'s, C, O, E>(
+    buf: &'s mut Option<StdTracker<C, &'s str>>,
+    fixtures: &'s Fixtures,
+    fn_test: impl Fn(ParseSpan<'s, C, &'s str>) -> Result<(ParseSpan<'s, C, &'s str>, O), nom::Err<E>>,
+) where
+    C: Code,
+    O: Debug,
+    E: Debug,
+{
+    buf.replace(Track::new_tracker());
+    let context = buf.as_ref().expect("yes");
+
+    let mut failed = 0;
+    for fixture in &fixtures.files {
+        let _ = context.results();
+        let span = Track::new_span(context, fixture.text.as_str());
+
+        let now = Instant::now();
+        let result = fn_test(span);
+        let duration = now.elapsed();
+
+        let test = Test {
+            span,
+            context,
+            result,
+            duration,
+            samples: vec![duration],
+            failed: Cell::new(false),
+            name: RefCell::new(Some(fixture.path.display().to_string())),
+        };
+        if !check_fixture(&test, fixture) {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        panic!("{} of {} fixtures failed", failed, fixtures.files.len());
+    }
+}
+
+/// Runs the parser for each fixture. Each parser must succeed and consume all of
+/// the input. If the fixture has an expected output it's compared with the Debug
+/// output of the parsed value.
+///
+/// If the environment variable `KPARSE_UPDATE_SNAPSHOTS` is set the expected
+/// output is written instead.
+///
+/// Dumps every failed fixture and panics at the end if any failed.
+///
+/// This method changes behaviour between debug and release build like str_parse().
+#[track_caller]
+#[cfg(not(debug_assertions))]
+pub fn str_fixtures<'s, O, E>(
+    _buf: &'s mut Option<StdTracker<NoCode, &'s str>>,
+    fixtures: &'s Fixtures,
+    fn_test: impl Fn(&'s str) -> Result<(&'s str, O), nom::Err<E>>,
+) where
+    O: Debug,
+    E: Debug,
+{
+    let mut failed = 0;
+    for fixture in &fixtures.files {
+        let now = Instant::now();
+        let result = fn_test(fixture.text.as_str());
+        let duration = now.elapsed();
+
+        let test = Test {
+            span: fixture.text.as_str(),
+            context: &(),
+            result,
+            duration,
+            samples: vec![duration],
+            failed: Cell::new(false),
+            name: RefCell::new(Some(fixture.path.display().to_string())),
+        };
+        if !check_fixture(&test, fixture) {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        panic!("{} of {} fixtures failed", failed, fixtures.files.len());
+    }
+}
+
+/// Checks one fixture and dumps the result if it fails.
+fn check_fixture<P, I, O, E>(test: &Test<'_, P, I, O, E>, fixture: &Fixture) -> bool
+where
+    I: AsBytes + Clone + Debug + PartialEq,
+    I: InputTake + InputLength + InputIter + Offset,
+    O: Debug,
+    E: Debug,
+{
+    println!("fixture {:?}", fixture.path);
+
+    let _ = test.ok_any().rest_offset(fixture.text.len());
+    if let Ok((_, value)) = &test.result {
+        let actual = format!("{:#?}\n", value);
+        let path = fixture.path.with_extension("expected");
+        if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+            if let Err(e) = fs::write(&path, &actual) {
+                println!("FAIL: Can't write expected output {:?}: {}", path, e);
+                test.flag_fail();
+            }
+        } else if let Some(expected) = &fixture.expected {
+            if *expected != actual {
+                println!("FAIL: Expected output mismatch {:?}", path);
+                println!("expected");
+                println!("{}", expected);
+                println!("actual");
+                println!("{}", actual);
+                test.flag_fail();
+            }
+        }
+    }
+
+    if test.failed.get() {
+        Dump.report(test);
+        false
+    } else {
+        true
     }
 }
 
@@ -167,7 +659,9 @@ where
         context,
         result,
         duration,
+        samples: vec![duration],
         failed: Cell::new(false),
+        name: RefCell::new(None),
     }
 }
 
@@ -194,7 +688,9 @@ pub fn byte_parse<'s, O, E>(
         context: &(),
         result,
         duration,
+        samples: vec![duration],
         failed: Cell::new(false),
+        name: RefCell::new(None),
     }
 }
 
@@ -203,7 +699,7 @@ pub fn byte_parse<'s, O, E>(
 impl<'s, P, I, O, E> Test<'s, P, I, O, E>
 where
     I: AsBytes + Clone + Debug + PartialEq + 's,
-    I: InputTake + InputLength + InputIter,
+    I: InputTake + InputLength + InputIter + Offset,
     O: Debug,
     E: Debug,
 {
@@ -212,6 +708,34 @@ where
         self.failed.set(true);
     }
 
+    /// Sets a name for the source, e.g. the file name. Used when
+    /// printing the location of a failure.
+    #[must_use]
+    pub fn named(&self, name: impl Into<String>) -> &Self {
+        self.name.replace(Some(name.into()));
+        self
+    }
+
+    fn print_location(&self, span: Option<I>) {
+        if let Some(span) = span {
+            println!("  at {}", self.location(&span));
+        }
+    }
+
+    /// Location of the span as name:line:column, both starting with 1.
+    ///
+    /// Lines end with "\n", "\r\n" or a lone "\r", as for [SourceBytes].
+    pub fn location(&self, span: &I) -> String {
+        let bytes = self.span.as_bytes();
+        let offset = self.span.offset(span).min(bytes.len());
+        let loc = SourceBytes::new(bytes).position(offset);
+
+        match self.name.borrow().as_ref() {
+            Some(name) => format!("{}:{}:{}", name, loc.line, loc.column + 1),
+            None => format!("{}:{}", loc.line, loc.column + 1),
+        }
+    }
+
     /// Always fails.
     ///
     /// Finish the test with q().
@@ -240,12 +764,9 @@ where
     /// Finish the test with q()
     #[must_use]
     pub fn err_any(&self) -> &Self {
-        match &self.result {
-            Ok(_) => {
-                println!("FAIL: Expected error, but was ok!");
-                self.flag_fail();
-            }
-            Err(_) => {}
+        if self.result.is_ok() {
+            println!("FAIL: Expected error, but was ok!");
+            self.flag_fail();
         }
         self
     }
@@ -261,6 +782,21 @@ where
         r.report(self);
     }
 
+    /// Alternative to q() that doesn't panic. Runs the Report only if
+    /// any of the tests signaled a failure condition and returns an error.
+    ///
+    /// Use a Report that doesn't panic itself like Dump or Trace.
+    pub fn finish<R: Report<Self> + Clone>(&self, r: R) -> Result<(), TestFailure> {
+        if self.failed.get() {
+            r.report(self);
+            Err(TestFailure {
+                name: self.name.borrow().clone(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Checks for ok results.
     ///
     /// This takes a TestEqFn to convert the parser result to a type which can be compared
@@ -268,7 +804,7 @@ where
     ///
     /// Finish the test with q()
     #[must_use]
-    pub fn ok<V>(&'s self, eq: TestEqFn<O, V>, test: V) -> &Self
+    pub fn ok<V>(&'s self, eq: TestEqFn<O, V>, test: V) -> &'s Self
     where
         V: Debug + Clone,
         O: Debug,
@@ -276,7 +812,14 @@ where
         match &self.result {
             Ok((_, token)) => {
                 if !eq(token, test.clone()) {
-                    println!("FAIL: Value mismatch: {:?} <> {:?}", token, test);
+                    println!("FAIL: Value mismatch");
+                    let expected = format!("{:#?}", test);
+                    let actual = format!("{:#?}", token);
+                    if expected.contains('\n') || actual.contains('\n') {
+                        print_line_diff(&expected, &actual);
+                    } else {
+                        print_diff(&expected, &actual);
+                    }
                     self.flag_fail();
                 }
             }
@@ -300,11 +843,51 @@ where
         match &self.result {
             Ok((rest, _)) => {
                 if rest.fragment() != &test {
-                    println!(
-                        "FAIL: Rest mismatch {:?} <> {:?}",
-                        restrict(DebugWidth::Medium, rest.clone()),
-                        test
-                    );
+                    println!("FAIL: Rest mismatch");
+                    print_diff(&format!("{:?}", test), &format!("{:?}", rest.fragment()));
+                    self.print_location(Some(rest.clone()));
+                    self.flag_fail();
+                }
+            }
+            Err(_) => {
+                println!("FAIL: Expect ok, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Tests the remaining bytes after parsing. The bytes are given as hex
+    /// digits, whitespace is ignored.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn rest_hex(&self, hex: &str) -> &Self {
+        let test = match from_hex(hex) {
+            Some(v) => v,
+            None => {
+                println!("FAIL: Invalid hex {:?}", hex);
+                self.flag_fail();
+                return self;
+            }
+        };
+
+        match &self.result {
+            Ok((rest, _)) => {
+                let bytes = rest.as_bytes();
+                if bytes != test.as_slice() {
+                    let pos = bytes
+                        .iter()
+                        .zip(test.iter())
+                        .position(|(a, b)| a != b)
+                        .unwrap_or_else(|| bytes.len().min(test.len()));
+                    println!("FAIL: Rest mismatch");
+                    println!("expected");
+                    print!("{}", hexdump(&test));
+                    println!("actual");
+                    print!("{}", hexdump(bytes));
+                    println!("first difference at offset {}", pos);
+                    self.print_location(Some(rest.clone()));
                     self.flag_fail();
                 }
             }
@@ -316,105 +899,927 @@ where
         self
     }
 
-    /// Checks for an error.
-    ///
-    /// Finish the test with q()
-    #[must_use]
-    pub fn err<C>(&self, code: C) -> &Self
-    where
+    /// Checks for nom::Err::Incomplete.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn incomplete(&self) -> &Self {
+        match &self.result {
+            Err(nom::Err::Incomplete(_)) => {}
+            Ok(_) => {
+                println!("FAIL: Expected incomplete, but was ok!");
+                self.flag_fail();
+            }
+            Err(_) => {
+                println!("FAIL: Expected incomplete, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks for nom::Err::Incomplete with the given number of needed bytes.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn needed(&self, n: usize) -> &Self {
+        match &self.result {
+            Err(nom::Err::Incomplete(Needed::Size(v))) => {
+                if v.get() != n {
+                    println!("FAIL: Needed mismatch {} <> {}", v.get(), n);
+                    self.flag_fail();
+                }
+            }
+            Err(nom::Err::Incomplete(Needed::Unknown)) => {
+                println!("FAIL: Needed mismatch Unknown <> {}", n);
+                self.flag_fail();
+            }
+            Ok(_) => {
+                println!("FAIL: Expected incomplete, but was ok!");
+                self.flag_fail();
+            }
+            Err(_) => {
+                println!("FAIL: Expected incomplete, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks the offset where the rest starts, relative to the test input.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn rest_offset(&self, offset: usize) -> &Self {
+        match &self.result {
+            Ok((rest, _)) => {
+                let rest_offset = self.span.offset(rest);
+                if rest_offset != offset {
+                    println!("FAIL: Rest offset mismatch {} <> {}", rest_offset, offset);
+                    self.print_location(Some(rest.clone()));
+                    self.flag_fail();
+                }
+            }
+            Err(_) => {
+                println!("FAIL: Expect ok, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks the length of the parsed input.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn consumed(&self, len: usize) -> &Self {
+        match &self.result {
+            Ok((rest, _)) => {
+                let consumed = self.span.input_len() - rest.input_len();
+                if consumed != len {
+                    println!("FAIL: Consumed length mismatch {} <> {}", consumed, len);
+                    self.flag_fail();
+                }
+            }
+            Err(_) => {
+                println!("FAIL: Expect ok, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks that parsing took no longer than the given duration.
+    ///
+    /// With str_timing() this uses the median of all runs, otherwise the
+    /// duration of the single run. Debug builds include the tracking.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn max_duration(&self, max: Duration) -> &Self {
+        let mut samples = self.samples.clone();
+        samples.sort();
+        let duration = samples
+            .get(samples.len() / 2)
+            .copied()
+            .unwrap_or(self.duration);
+        if duration > max {
+            println!(
+                "FAIL: Parsing took {:?}, longer than the maximum {:?}",
+                duration, max
+            );
+            self.flag_fail();
+        }
+        self
+    }
+
+    /// Checks for an error.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn err<C>(&self, code: C) -> &Self
+    where
+        C: Code,
+        E: KParseError<C, I>,
+    {
+        match &self.result {
+            Ok(_) => {
+                println!("FAIL: Expected error, but was ok!");
+                self.flag_fail();
+            }
+            Err(nom::Err::Error(e)) => {
+                if e.code() != Some(code) {
+                    println!("ERROR: {:?} <> {:?}", e.code(), code);
+                    self.print_location(e.span());
+                    self.flag_fail();
+                } else {
+                    cover_code(code);
+                }
+            }
+            Err(nom::Err::Failure(e)) => {
+                if e.code() != Some(code) {
+                    println!("FAILURE: {:?} <> {:?}", e.code(), code);
+                    self.print_location(e.span());
+                    self.flag_fail();
+                } else {
+                    cover_code(code);
+                }
+            }
+            Err(nom::Err::Incomplete(e)) => {
+                println!("INCOMPLETE: {:?}", e);
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Prints the parsed value, parses the printed text again and compares
+    /// the result with the first value.
+    ///
+    /// The reparse function gets the printed text and must create its own
+    /// tracker if the parser needs one.
+    ///
+    /// ```rust
+    /// use nom::character::complete::digit1;
+    /// use nom::Parser;
+    /// use kparse::prelude::*;
+    /// use kparse::examples::{ExCode, ExNumber, ExSpan, ExTokenizerResult};
+    /// use kparse::test::{str_parse, CheckTrace};
+    /// use kparse::Track;
+    ///
+    /// fn number(i: ExSpan<'_>) -> ExTokenizerResult<'_, u32> {
+    ///     digit1.parse_from_str(ExNumber).parse(i)
+    /// }
+    ///
+    /// str_parse(&mut None, "0042", number)
+    ///     .roundtrip(
+    ///         |v| v.to_string(),
+    ///         |txt| {
+    ///             let tracker = Track::new_tracker::<ExCode, _>();
+    ///             number(Track::new_span(&tracker, txt)).ok().map(|(_, v)| v)
+    ///         },
+    ///     )
+    ///     .q(CheckTrace);
+    /// ```
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn roundtrip(
+        &self,
+        printer: impl FnOnce(&O) -> String,
+        reparse: impl FnOnce(&str) -> Option<O>,
+    ) -> &Self
+    where
+        O: PartialEq,
+    {
+        match &self.result {
+            Ok((_, token)) => {
+                let printed = printer(token);
+                match reparse(printed.as_str()) {
+                    Some(reparsed) => {
+                        if reparsed != *token {
+                            println!("FAIL: Roundtrip mismatch for {:?}", printed);
+                            println!("parsed");
+                            println!("{:#?}", token);
+                            println!("reparsed");
+                            println!("{:#?}", reparsed);
+                            self.flag_fail();
+                        }
+                    }
+                    None => {
+                        println!("FAIL: Can't reparse {:?}", printed);
+                        self.flag_fail();
+                    }
+                }
+            }
+            Err(_) => {
+                println!("FAIL: Expect ok, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Compares the Debug output of the parsed value with a golden file.
+    ///
+    /// If the file doesn't exist or the environment variable
+    /// `KPARSE_UPDATE_SNAPSHOTS` is set, the file is written instead.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn ok_snapshot(&self, path: impl AsRef<Path>) -> &Self {
+        match &self.result {
+            Ok((_, token)) => {
+                self.check_snapshot(path.as_ref(), &format!("{:#?}\n", token));
+            }
+            Err(_) => {
+                println!("FAIL: Expect ok, but was an error!");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Compares the text with the golden file, or writes the golden file.
+    fn check_snapshot(&self, path: &Path, actual: &str) {
+        if env::var_os(UPDATE_SNAPSHOTS).is_some() || !path.exists() {
+            if let Some(dir) = path.parent() {
+                if let Err(e) = fs::create_dir_all(dir) {
+                    println!("FAIL: Can't create {:?}: {}", dir, e);
+                    self.flag_fail();
+                    return;
+                }
+            }
+            if let Err(e) = fs::write(path, actual) {
+                println!("FAIL: Can't write snapshot {:?}: {}", path, e);
+                self.flag_fail();
+            }
+            return;
+        }
+
+        match fs::read_to_string(path) {
+            Ok(expected) => {
+                if expected != actual {
+                    println!("FAIL: Snapshot mismatch {:?}", path);
+                    println!("expected");
+                    println!("{}", expected);
+                    println!("actual");
+                    println!("{}", actual);
+                    println!("set {} to update the snapshot.", UPDATE_SNAPSHOTS);
+                    self.flag_fail();
+                }
+            }
+            Err(e) => {
+                println!("FAIL: Can't read snapshot {:?}: {}", path, e);
+                self.flag_fail();
+            }
+        }
+    }
+}
+
+/// Environment variable that updates the snapshots instead of comparing them.
+pub const UPDATE_SNAPSHOTS: &str = "KPARSE_UPDATE_SNAPSHOTS";
+
+/// Environment variable with the path of the coverage file.
+///
+/// If it is set, each successful err() check appends its code to this file.
+/// Run the tests with it and then call code_coverage() to find the error codes
+/// that no test checks.
+pub const CODE_COVERAGE: &str = "KPARSE_CODE_COVERAGE";
+
+/// Records the code in the coverage file, if there is one.
+fn cover_code<C: Code>(code: C) {
+    if let Some(path) = env::var_os(CODE_COVERAGE) {
+        let r = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| writeln!(f, "{:?}", code));
+        if let Err(e) = r {
+            println!("Can't write code coverage {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Returns the codes that were never checked with err() and prints them.
+///
+/// Reads the coverage file given by the environment variable `KPARSE_CODE_COVERAGE`.
+/// The codes are compared by their Debug output.
+pub fn code_coverage<C: Code>(codes: &[C]) -> Vec<C> {
+    let covered = match env::var_os(CODE_COVERAGE) {
+        Some(path) => fs::read_to_string(path).unwrap_or_default(),
+        None => {
+            println!("No code coverage, {} is not set.", CODE_COVERAGE);
+            String::new()
+        }
+    };
+    let covered = covered.lines().collect::<Vec<_>>();
+
+    let uncovered = codes
+        .iter()
+        .copied()
+        .filter(|v| !covered.contains(&format!("{:?}", v).as_str()))
+        .collect::<Vec<_>>();
+    if !uncovered.is_empty() {
+        println!("Error codes without test: {:?}", uncovered);
+    }
+    uncovered
+}
+
+#[cfg(debug_assertions)]
+impl<'s, C, T, O, E> Test<'s, StdTracker<C, T>, ParseSpan<'s, C, T>, O, E>
+where
+    T: AsBytes + Clone + Debug + PartialEq + 's,
+    T: Offset
+        + InputTake
+        + InputIter
+        + InputLength
+        + Slice<RangeFrom<usize>>
+        + Slice<RangeTo<usize>>,
+    C: Code,
+    O: Debug,
+    E: Debug,
+{
+    /// Compares the parser trace with a golden file.
+    ///
+    /// If the file doesn't exist or the environment variable
+    /// `KPARSE_UPDATE_SNAPSHOTS` is set, the file is written instead.
+    ///
+    /// This takes the trace from the tracker, so a following q(Trace) has
+    /// nothing to show.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn trace_snapshot(&self, path: impl AsRef<Path>) -> &Self {
+        let tracks = self.context.results();
+        self.check_snapshot(path.as_ref(), &format!("{:?}", tracks));
+        self
+    }
+
+    /// The events recorded by the tracker.
+    ///
+    /// Leaves the tracking data in place, so reports still can show the trace.
+    /// Drop the result before calling q().
+    pub fn tracks(&self) -> Ref<'_, [TrackedData<C, T>]> {
+        self.context.tracks()
+    }
+
+    /// Checks that the function with this code issued a warning.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn warns(&self, code: C) -> &Self {
+        let warnings = self.context.warnings();
+        if !warnings.iter().any(|(func, _)| *func == code) {
+            println!("FAIL: No warning for {:?}. {:?}", code, warnings);
+            self.flag_fail();
+        }
+        self
+    }
+
+    /// Checks that there were no warnings.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn no_warnings(&self) -> &Self {
+        let warnings = self.context.warnings();
+        if !warnings.is_empty() {
+            println!("FAIL: Unexpected warnings {:?}", warnings);
+            self.flag_fail();
+        }
+        self
+    }
+}
+
+impl<'s, I, O, E> Test<'s, (), I, O, E>
+where
+    I: AsBytes + Clone + Debug + PartialEq + 's,
+    I: InputTake + InputLength + InputIter,
+    O: Debug,
+    E: Debug,
+{
+    /// There is no trace without tracking, so this does nothing.
+    #[must_use]
+    pub fn trace_snapshot(&self, _path: impl AsRef<Path>) -> &Self {
+        self
+    }
+
+    /// There are no warnings without tracking, so this does nothing.
+    #[must_use]
+    pub fn warns<C: Code>(&self, _code: C) -> &Self {
+        self
+    }
+
+    /// There are no warnings without tracking, so this does nothing.
+    #[must_use]
+    pub fn no_warnings(&self) -> &Self {
+        self
+    }
+}
+
+// works for any NomFn.
+impl<'s, P, I, O> Test<'s, P, I, O, nom::error::Error<I>>
+where
+    I: AsBytes + Clone + Debug + PartialEq + 's,
+    I: InputTake + InputLength + InputIter + Offset,
+    O: Debug,
+{
+    /// Test for a nom error that occurred.
+    #[must_use]
+    pub fn nom_err(&self, kind: nom::error::ErrorKind) -> &Self {
+        match &self.result {
+            Ok(_) => {
+                println!("FAIL: Expected error, but was ok!");
+                self.flag_fail();
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                if e.code != kind {
+                    println!("FAIL: {:?} <> {:?}", e.code, kind);
+                    self.flag_fail();
+                }
+            }
+            Err(nom::Err::Incomplete(_)) => {
+                println!("FAIL: nom::Err::Incomplete");
+                self.flag_fail();
+            }
+        }
+        self
+    }
+}
+
+impl<'s, P, C, I, O> Test<'s, P, I, O, ParserError<C, I>>
+where
+    I: AsBytes + Clone + SpanFragment + Debug + PartialEq + 's,
+    I: InputTake + InputLength + InputIter + Offset,
+    C: Code,
+    O: Debug,
+{
+    /// Checks for an expect value.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn expect(&self, code: C) -> &Self {
+        match &self.result {
+            Ok(_) => {
+                println!("FAIL: {:?} was ok not an error.", code,);
+                self.flag_fail();
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                if !e.is_expected(code) {
+                    println!(
+                        "FAIL: {:?} is not an expected token. {:?}",
+                        code,
+                        e.iter_expected().collect::<Vec<_>>()
+                    );
+                    self.flag_fail();
+                }
+            }
+            Err(nom::Err::Incomplete(e)) => {
+                println!("FAIL: {:?} was incomplete not an error. {:?}", code, e);
+                self.flag_fail();
+            }
+        }
+
+        self
+    }
+
+    /// Checks that the code is one of the expect hints of the error.
+    ///
+    /// Different to expect() this doesn't accept the main error code.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn err_expected(&self, code: C) -> &Self {
+        if let Some(e) = self.parser_error() {
+            if !e.iter_expected().any(|v| v.code == code) {
+                println!(
+                    "FAIL: {:?} is not an expected token. {:?}",
+                    code,
+                    e.iter_expected().collect::<Vec<_>>()
+                );
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks that the code is one of the expect hints of the error,
+    /// and that its span starts at the given offset of the test input.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn err_expected_at(&self, code: C, offset: usize) -> &Self {
+        if let Some(e) = self.parser_error() {
+            if !e
+                .iter_expected()
+                .any(|v| v.code == code && self.span.offset(&v.span) == offset)
+            {
+                println!(
+                    "FAIL: {:?} at {} is not an expected token. {:?}",
+                    code,
+                    offset,
+                    e.iter_expected()
+                        .map(|v| (v.code, self.span.offset(&v.span)))
+                        .collect::<Vec<_>>()
+                );
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks that the expect hints of the error are exactly the given codes.
+    /// The order doesn't matter.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn err_expected_all(&self, codes: &[C]) -> &Self {
+        if let Some(e) = self.parser_error() {
+            let expected = e.iter_expected().map(|v| v.code).collect::<Vec<_>>();
+            let missing = codes.iter().any(|c| !expected.contains(c));
+            let extra = expected.iter().any(|c| !codes.contains(c));
+            if missing || extra {
+                println!("FAIL: Expected tokens {:?} <> {:?}", expected, codes);
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Test for a nom error that occurred.
+    ///
+    /// Same as for a plain nom error, but checks the error kind that was
+    /// kept when the nom error was converted, see [ParserError::nom].
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn nom_err(&self, kind: nom::error::ErrorKind) -> &Self {
+        if let Some(e) = self.parser_error() {
+            if e.nom() != Some(kind) {
+                println!("FAIL: {:?} <> {:?}", e.nom(), kind);
+                self.flag_fail();
+            }
+        }
+        self
+    }
+
+    /// Checks the errors collected during error recovery, e.g. by retry_with_sync().
+    ///
+    /// Compares code and offset relative to the test input of each error,
+    /// in order. Dumps all errors if there is a mismatch.
+    ///
+    /// Finish the test with q()
+    #[must_use]
+    pub fn errs(&self, errors: &[ParserError<C, I>], expected: &[(C, usize)]) -> &Self {
+        let found = errors
+            .iter()
+            .map(|e| (e.code, self.span.offset(&e.span)))
+            .collect::<Vec<_>>();
+        if found != expected {
+            println!("FAIL: Errors mismatch {:?} <> {:?}", found, expected);
+            for e in errors {
+                println!("{:?}", e);
+            }
+            self.flag_fail();
+        }
+        self
+    }
+
+    /// Returns the error or flags a failure.
+    fn parser_error(&self) -> Option<&ParserError<C, I>> {
+        match &self.result {
+            Ok(_) => {
+                println!("FAIL: Expected error, but was ok!");
+                self.flag_fail();
+                None
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Some(e),
+            Err(nom::Err::Incomplete(e)) => {
+                println!("FAIL: Expected error, but was incomplete. {:?}", e);
+                self.flag_fail();
+                None
+            }
+        }
+    }
+}
+
+/// Bytes from hex digits. Whitespace is ignored.
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits = hex
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|v| v as u8))
+        .collect::<Option<Vec<_>>>()?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(digits.chunks(2).map(|v| v[0] << 4 | v[1]).collect())
+}
+
+/// Hexdump with offset, 16 bytes per line and the ascii representation.
+/// Shows at most 16 lines.
+fn hexdump(bytes: &[u8]) -> String {
+    const MAX_LINES: usize = 16;
+
+    let mut buf = String::new();
+    for (n, line) in bytes.chunks(16).enumerate() {
+        if n == MAX_LINES {
+            buf.push_str(&format!("    ... {} bytes\n", bytes.len()));
+            break;
+        }
+        buf.push_str(&format!("    {:04x} ", n * 16));
+        for i in 0..16 {
+            match line.get(i) {
+                Some(b) => buf.push_str(&format!(" {:02x}", b)),
+                None => buf.push_str("   "),
+            }
+        }
+        buf.push_str("  |");
+        for b in line {
+            if b.is_ascii_graphic() || *b == b' ' {
+                buf.push(*b as char);
+            } else {
+                buf.push('.');
+            }
+        }
+        buf.push_str("|\n");
+    }
+    if bytes.is_empty() {
+        buf.push_str("    empty\n");
+    }
+    buf
+}
+
+/// Prints expected and actual value with the first difference highlighted.
+///
+/// Uses ANSI colors unless the environment variable NO_COLOR is set or
+/// stdout is no terminal.
+fn print_diff(expected: &str, actual: &str) {
+    let color = env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+    print!("{}", diff(expected, actual, color));
+}
+
+/// Prints a line-wise diff of expected and actual value.
+fn print_line_diff(expected: &str, actual: &str) {
+    let color = env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+    print!("{}", line_diff(expected, actual, color));
+}
+
+/// Line-wise diff of expected and actual value. Lines only in expected
+/// start with '-', lines only in actual with '+'. Runs of equal lines are
+/// shortened to some context.
+fn line_diff(expected: &str, actual: &str, color: bool) -> String {
+    const CONTEXT: usize = 3;
+
+    let e = expected.lines().collect::<Vec<_>>();
+    let a = actual.lines().collect::<Vec<_>>();
+
+    // longest common subsequence, lcs[i][j] for e[i..] and a[j..]
+    let mut lcs = vec![vec![0usize; a.len() + 1]; e.len() + 1];
+    for i in (0..e.len()).rev() {
+        for j in (0..a.len()).rev() {
+            lcs[i][j] = if e[i] == a[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < e.len() || j < a.len() {
+        if i < e.len() && j < a.len() && e[i] == a[j] {
+            ops.push((' ', e[i]));
+            i += 1;
+            j += 1;
+        } else if i < e.len() && (j == a.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', e[i]));
+            i += 1;
+        } else {
+            ops.push(('+', a[j]));
+            j += 1;
+        }
+    }
+
+    let near_change = |idx: usize| {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(ops.len());
+        ops[start..end].iter().any(|(op, _)| *op != ' ')
+    };
+
+    let mut buf = String::new();
+    let mut skipped = false;
+    for (idx, (op, line)) in ops.iter().enumerate() {
+        if *op == ' ' && !near_change(idx) {
+            if !skipped {
+                buf.push_str("  ...\n");
+                skipped = true;
+            }
+            continue;
+        }
+        skipped = false;
+        match (*op, color) {
+            ('-', true) => buf.push_str(&format!("\x1b[32m- {}\x1b[0m\n", line)),
+            ('+', true) => buf.push_str(&format!("\x1b[31m+ {}\x1b[0m\n", line)),
+            (op, _) => buf.push_str(&format!("{} {}\n", op, line)),
+        }
+    }
+    buf
+}
+
+/// Expected and actual value with the first difference highlighted.
+/// Only some context around the difference is shown.
+fn diff(expected: &str, actual: &str, color: bool) -> String {
+    const BEFORE: usize = 20;
+    const AFTER: usize = 60;
+
+    let pos = expected
+        .char_indices()
+        .zip(actual.chars())
+        .find(|((_, e), a)| e != a)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+
+    let show = |txt: &str, ansi: &str| {
+        let mut start = pos.saturating_sub(BEFORE);
+        while !txt.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = (pos + AFTER).min(txt.len());
+        while !txt.is_char_boundary(end) {
+            end += 1;
+        }
+        let head = if start > 0 { "..." } else { "" };
+        let tail = if end < txt.len() { "..." } else { "" };
+        if color {
+            format!(
+                "{}{}{}{}\x1b[0m{}",
+                head,
+                &txt[start..pos],
+                ansi,
+                &txt[pos..end],
+                tail
+            )
+        } else {
+            format!("{}{}[{}]{}", head, &txt[start..pos], &txt[pos..end], tail)
+        }
+    };
+
+    format!(
+        "expected {}\nactual   {}\nfirst difference at offset {}\n",
+        show(expected, "\x1b[32m"),
+        show(actual, "\x1b[31m"),
+        pos
+    )
+}
+
+/// Helpers for fuzzing and property tests.
+///
+/// check_str() runs a parser and asserts some invariants that hold for any
+/// input. It panics if one is violated, which is what cargo-fuzz and proptest
+/// expect. A panic of the parser itself is reported by them too.
+///
+/// With feature `arbitrary` fuzz_str() converts the raw bytes of a fuzz target
+/// to the input text.
+///
+/// ```rust
+/// use nom::bytes::complete::tag;
+/// use nom::Parser;
+/// use kparse::prelude::*;
+/// use kparse::examples::{ExSpan, ExTagA, ExTokenizerResult};
+/// use kparse::test::fuzz::check_str;
+///
+/// fn nom_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+///     tag("a").with_code(ExTagA).parse(i)
+/// }
+///
+/// // e.g. called from proptest with a generated string.
+/// check_str(&mut None, "abc", nom_a);
+/// ```
+pub mod fuzz {
+    use crate::provider::StdTracker;
+    #[cfg(not(debug_assertions))]
+    use crate::test::NoCode;
+    use crate::test::{str_parse, Test};
+    #[cfg(debug_assertions)]
+    use crate::ParseSpan;
+    use crate::{Code, KParseError};
+    use nom::{InputLength, Offset};
+
+    /// Runs the parser and checks the invariants.
+    ///
+    /// * The rest is a suffix of the input.
+    /// * The span of an error is within the input.
+    ///
+    /// Panics if any of these is violated.
+    #[track_caller]
+    #[cfg(debug_assertions)]
+    pub fn check_str<'s, C, O, E>(
+        buf: &'s mut Option<StdTracker<C, &'s str>>,
+        text: &'s str,
+        fn_test: impl Fn(
+            ParseSpan<'s, C, &'s str>,
+        ) -> Result<(ParseSpan<'s, C, &'s str>, O), nom::Err<E>>,
+    ) where
+        C: Code,
+        E: KParseError<C, ParseSpan<'s, C, &'s str>>,
+    {
+        check_invariants(&str_parse(buf, text, fn_test));
+    }
+
+    /// Runs the parser and checks the invariants.
+    ///
+    /// * The rest is a suffix of the input.
+    /// * The span of an error is within the input.
+    ///
+    /// Panics if any of these is violated.
+    #[track_caller]
+    #[cfg(not(debug_assertions))]
+    pub fn check_str<'s, C, O, E>(
+        buf: &'s mut Option<StdTracker<NoCode, &'s str>>,
+        text: &'s str,
+        fn_test: impl Fn(&'s str) -> Result<(&'s str, O), nom::Err<E>>,
+    ) where
+        C: Code,
+        E: KParseError<C, &'s str>,
+    {
+        check_invariants(&str_parse(buf, text, fn_test));
+    }
+
+    /// Converts the raw bytes from the fuzzer to a &str and runs check_str().
+    #[track_caller]
+    #[cfg(all(feature = "arbitrary", debug_assertions))]
+    pub fn fuzz_str<'s, C, O, E>(
+        buf: &'s mut Option<StdTracker<C, &'s str>>,
+        data: &'s [u8],
+        fn_test: impl Fn(
+            ParseSpan<'s, C, &'s str>,
+        ) -> Result<(ParseSpan<'s, C, &'s str>, O), nom::Err<E>>,
+    ) where
         C: Code,
-        E: KParseError<C, I>,
+        E: KParseError<C, ParseSpan<'s, C, &'s str>>,
     {
-        match &self.result {
-            Ok(_) => {
-                println!("FAIL: Expected error, but was ok!");
-                self.flag_fail();
-            }
-            Err(nom::Err::Error(e)) => {
-                if e.code() != Some(code) {
-                    println!("ERROR: {:?} <> {:?}", e.code(), code);
-                    self.flag_fail();
-                }
-            }
-            Err(nom::Err::Failure(e)) => {
-                if e.code() != Some(code) {
-                    println!("FAILURE: {:?} <> {:?}", e.code(), code);
-                    self.flag_fail();
-                }
-            }
-            Err(nom::Err::Incomplete(e)) => {
-                println!("INCOMPLETE: {:?}", e);
-                self.flag_fail();
-            }
+        if let Some(text) = arbitrary_str(data) {
+            check_str(buf, text, fn_test);
         }
-        self
     }
-}
 
-// works for any NomFn.
-impl<'s, P, I, O> Test<'s, P, I, O, nom::error::Error<I>>
-where
-    I: AsBytes + Clone + Debug + PartialEq + 's,
-    I: InputTake + InputLength + InputIter,
-    O: Debug,
-{
-    /// Test for a nom error that occurred.
-    #[must_use]
-    pub fn nom_err(&self, kind: nom::error::ErrorKind) -> &Self {
-        match &self.result {
-            Ok(_) => {
-                println!("FAIL: Expected error, but was ok!");
-                self.flag_fail();
-            }
-            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
-                if e.code != kind {
-                    println!("FAIL: {:?} <> {:?}", e.code, kind);
-                    self.flag_fail();
-                }
-            }
-            Err(nom::Err::Incomplete(_)) => {
-                println!("FAIL: nom::Err::Incomplete");
-                self.flag_fail();
-            }
+    /// Converts the raw bytes from the fuzzer to a &str and runs check_str().
+    #[track_caller]
+    #[cfg(all(feature = "arbitrary", not(debug_assertions)))]
+    pub fn fuzz_str<'s, C, O, E>(
+        buf: &'s mut Option<StdTracker<NoCode, &'s str>>,
+        data: &'s [u8],
+        fn_test: impl Fn(&'s str) -> Result<(&'s str, O), nom::Err<E>>,
+    ) where
+        C: Code,
+        E: KParseError<C, &'s str>,
+    {
+        if let Some(text) = arbitrary_str(data) {
+            check_str(buf, text, fn_test);
         }
-        self
     }
-}
 
-impl<'s, P, C, I, O> Test<'s, P, I, O, ParserError<C, I>>
-where
-    I: AsBytes + Clone + SpanFragment + Debug + PartialEq + 's,
-    I: InputTake + InputLength + InputIter,
-    C: Code,
-    O: Debug,
-{
-    /// Checks for an expect value.
-    ///
-    /// Finish the test with q()
-    #[must_use]
-    pub fn expect(&self, code: C) -> &Self {
-        match &self.result {
-            Ok(_) => {
-                println!("FAIL: {:?} was ok not an error.", code,);
-                self.flag_fail();
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_str(data: &[u8]) -> Option<&str> {
+        use arbitrary::{Arbitrary, Unstructured};
+        <&str>::arbitrary_take_rest(Unstructured::new(data)).ok()
+    }
+
+    #[track_caller]
+    fn check_invariants<P, C, I, O, E>(test: &Test<'_, P, I, O, E>)
+    where
+        C: Code,
+        I: Offset + InputLength,
+        E: KParseError<C, I>,
+    {
+        let len = test.span.input_len();
+        match &test.result {
+            Ok((rest, _)) => {
+                let offset = test.span.offset(rest);
+                assert!(
+                    offset <= len && offset + rest.input_len() == len,
+                    "rest at {}..{} is not a suffix of the input 0..{}",
+                    offset,
+                    offset + rest.input_len(),
+                    len
+                );
             }
             Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
-                if !e.is_expected(code) {
-                    println!(
-                        "FAIL: {:?} is not an expected token. {:?}",
-                        code,
-                        e.iter_expected().collect::<Vec<_>>()
+                if let Some(span) = e.span() {
+                    let offset = test.span.offset(&span);
+                    assert!(
+                        offset <= len && offset + span.input_len() <= len,
+                        "error span at {}..{} is outside of the input 0..{}",
+                        offset,
+                        offset + span.input_len(),
+                        len
                     );
-                    self.flag_fail();
                 }
             }
-            Err(nom::Err::Incomplete(e)) => {
-                println!("FAIL: {:?} was incomplete not an error. {:?}", code, e);
-                self.flag_fail();
-            }
+            Err(nom::Err::Incomplete(_)) => {}
         }
-
-        self
     }
 }
 
@@ -422,17 +1827,33 @@ mod report {
     use crate::debug::{restrict, restrict_ref, DebugWidth};
     use crate::prelude::*;
     use crate::provider::StdTracker;
-    use crate::test::{Report, Test};
-    use crate::{Code, ParseSpan};
+    use crate::report::json_str;
+    use crate::source::SourceStr;
+    use crate::test::{hexdump, Report, ReportAnd, Test};
+    use crate::token_error::TokenizerError;
+    use crate::{Code, KParseError, ParseSpan, ParserError, Track};
     use nom::{AsBytes, InputIter, InputLength, InputTake, Offset, Slice};
     use nom_locate::LocatedSpan;
     use std::fmt::Debug;
+    use std::fs::OpenOptions;
+    use std::io::Write;
     use std::ops::{RangeFrom, RangeTo};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// Name of the source followed by a space, if any.
+    fn source_name<P, I, O, E>(test: &Test<'_, P, I, O, E>) -> String {
+        match test.name.borrow().as_ref() {
+            Some(name) => format!("{} ", name),
+            None => String::new(),
+        }
+    }
 
     /// Do nothing report.
     #[derive(Clone, Copy)]
     pub struct NoReport;
 
+    impl ReportAnd for NoReport {}
+
     impl<'s, P, I, O, E> Report<Test<'s, P, I, O, E>> for NoReport {
         fn report(&self, _: &Test<'s, P, I, O, E>) {}
     }
@@ -441,6 +1862,8 @@ mod report {
     #[derive(Clone, Copy)]
     pub struct CheckDump;
 
+    impl ReportAnd for CheckDump {}
+
     impl<'s, P, I, O, E> Report<Test<'s, P, I, O, E>> for CheckDump
     where
         I: AsBytes + Clone + Debug,
@@ -467,6 +1890,8 @@ mod report {
     #[derive(Clone, Copy)]
     pub struct Timing(pub u32);
 
+    impl ReportAnd for Timing {}
+
     impl<'s, P, I, O, E> Report<Test<'s, P, I, O, E>> for Timing
     where
         I: AsBytes + Clone + Debug,
@@ -476,7 +1901,8 @@ mod report {
     {
         fn report(&self, test: &Test<'s, P, I, O, E>) {
             println!(
-                "when parsing {:?} in {:?} =>",
+                "when parsing {}{:?} in {:?} =>",
+                source_name(test),
                 restrict(DebugWidth::Medium, test.span.clone()),
                 test.duration / self.0
             );
@@ -491,10 +1917,280 @@ mod report {
         }
     }
 
+    /// Timing statistics for the samples of str_timing().
+    ///
+    /// Prints min, mean, median and 95th percentile of the durations and the
+    /// throughput for the median.
+    #[derive(Clone, Copy)]
+    pub struct Statistics;
+
+    impl ReportAnd for Statistics {}
+
+    impl<'s, P, I, O, E> Report<Test<'s, P, I, O, E>> for Statistics
+    where
+        I: AsBytes + Clone + Debug,
+        I: InputTake + InputLength + InputIter,
+        O: Debug,
+        E: Debug,
+    {
+        fn report(&self, test: &Test<'s, P, I, O, E>) {
+            let mut samples = test.samples.clone();
+            samples.sort();
+
+            let n = samples.len();
+            let min = samples[0];
+            let mean = samples.iter().sum::<Duration>() / n as u32;
+            let median = samples[n / 2];
+            let p95 = samples[((n * 95) / 100).min(n - 1)];
+
+            let len = test.span.input_len() as f64;
+            let throughput = if median.is_zero() {
+                f64::INFINITY
+            } else {
+                len / median.as_secs_f64() / 1_000_000.0
+            };
+
+            println!(
+                "when parsing {}{:?} {} samples =>",
+                source_name(test),
+                restrict(DebugWidth::Medium, test.span.clone()),
+                n
+            );
+            println!(
+                "min {:?} mean {:?} median {:?} p95 {:?} {:.2} MB/s",
+                min, mean, median, p95, throughput
+            );
+            match &test.result {
+                Ok(_) => {
+                    println!("OK");
+                }
+                Err(_) => {
+                    println!("ERROR");
+                }
+            }
+        }
+    }
+
+    /// Appends the outcome of the test as one line of JSON to the given file.
+    ///
+    /// The fields are name, input_hash, input_len, result, code, duration_ns,
+    /// failed and timestamp. Doesn't panic for a failed test, combine it with
+    /// one of the other reports for that.
+    ///
+    /// The input hash is FNV-1a 64bit, so it's stable between runs and platforms.
+    #[derive(Clone, Copy)]
+    pub struct JsonLines<'a>(pub &'a str);
+
+    impl<'a> ReportAnd for JsonLines<'a> {}
+
+    impl<'s, 'a, P, C, I, O> Report<Test<'s, P, I, O, ParserError<C, I>>> for JsonLines<'a>
+    where
+        I: AsBytes + InputLength,
+        C: Code,
+    {
+        fn report(&self, test: &Test<'s, P, I, O, ParserError<C, I>>) {
+            let code = match &test.result {
+                Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                    Some(format!("{:?}", e.code))
+                }
+                _ => None,
+            };
+            json_line(self.0, test, code);
+        }
+    }
+
+    impl<'s, 'a, P, C, I, O> Report<Test<'s, P, I, O, TokenizerError<C, I>>> for JsonLines<'a>
+    where
+        I: AsBytes + InputLength,
+        C: Code,
+    {
+        fn report(&self, test: &Test<'s, P, I, O, TokenizerError<C, I>>) {
+            let code = match &test.result {
+                Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                    Some(format!("{:?}", e.code))
+                }
+                _ => None,
+            };
+            json_line(self.0, test, code);
+        }
+    }
+
+    impl<'s, 'a, P, I, O> Report<Test<'s, P, I, O, nom::error::Error<I>>> for JsonLines<'a>
+    where
+        I: AsBytes + InputLength,
+    {
+        fn report(&self, test: &Test<'s, P, I, O, nom::error::Error<I>>) {
+            let code = match &test.result {
+                Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                    Some(format!("{:?}", e.code))
+                }
+                _ => None,
+            };
+            json_line(self.0, test, code);
+        }
+    }
+
+    fn json_line<P, I, O, E>(path: &str, test: &Test<'_, P, I, O, E>, code: Option<String>)
+    where
+        I: AsBytes + InputLength,
+    {
+        let result = match &test.result {
+            Ok(_) => "ok",
+            Err(nom::Err::Error(_)) => "error",
+            Err(nom::Err::Failure(_)) => "failure",
+            Err(nom::Err::Incomplete(_)) => "incomplete",
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.as_secs())
+            .unwrap_or(0);
+
+        let line = format!(
+            "{{\"name\":{},\"input_hash\":\"{:016x}\",\"input_len\":{},\"result\":\"{}\",\"code\":{},\"duration_ns\":{},\"failed\":{},\"timestamp\":{}}}\n",
+            json_str(test.name.borrow().as_deref()),
+            fnv1a(test.span.as_bytes()),
+            test.span.input_len(),
+            result,
+            json_str(code.as_deref()),
+            test.duration.as_nanos(),
+            test.failed.get(),
+            timestamp
+        );
+
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut f| f.write_all(line.as_bytes()));
+        if let Err(e) = written {
+            println!("Can't write test report {:?}: {}", path, e);
+        }
+    }
+
+    /// JSON string or null.
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        let mut hash = 0xcbf29ce484222325u64;
+        for b in bytes {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    /// Renders the error with the surrounding lines of the input if any test failed.
+    ///
+    /// Each expected and suggested code is shown at its own position.
+    #[derive(Clone, Copy)]
+    pub struct CheckDiagnostics;
+
+    impl ReportAnd for CheckDiagnostics {}
+
+    impl<'s, P, C, I, O> Report<Test<'s, P, I, O, ParserError<C, I>>> for CheckDiagnostics
+    where
+        C: Code,
+        I: AsBytes + Clone + Debug + PartialEq + SpanFragment<Result = &'s str>,
+        I: InputTake + InputIter + InputLength + Offset,
+        O: Debug,
+    {
+        #[track_caller]
+        fn report(&self, test: &Test<'s, P, I, O, ParserError<C, I>>) {
+            if test.failed.get() {
+                diagnostics(test, |e, txt| {
+                    for exp in e.iter_expected() {
+                        diagnostic(test, txt, "expected", exp.code, &exp.span, 0);
+                    }
+                    for sug in e.iter_suggested() {
+                        diagnostic(test, txt, "suggested", sug.code, &sug.span, 0);
+                    }
+                });
+                panic!("test failed")
+            }
+        }
+    }
+
+    impl<'s, P, C, I, O> Report<Test<'s, P, I, O, TokenizerError<C, I>>> for CheckDiagnostics
+    where
+        C: Code,
+        I: AsBytes + Clone + Debug + PartialEq + SpanFragment<Result = &'s str>,
+        I: InputTake + InputIter + InputLength + Offset,
+        O: Debug,
+    {
+        #[track_caller]
+        fn report(&self, test: &Test<'s, P, I, O, TokenizerError<C, I>>) {
+            if test.failed.get() {
+                diagnostics(test, |_, _| {});
+                panic!("test failed")
+            }
+        }
+    }
+
+    /// Renders the result. The hints of an error are rendered by the closure.
+    fn diagnostics<'s, P, C, I, O, E>(
+        test: &Test<'s, P, I, O, E>,
+        hints: impl Fn(&E, &SourceStr<'s>),
+    ) where
+        C: Code,
+        I: AsBytes + Clone + Debug + PartialEq + SpanFragment<Result = &'s str>,
+        I: InputTake + InputIter + InputLength + Offset,
+        O: Debug,
+        E: KParseError<C, I> + Debug,
+    {
+        let txt = Track::source_str(test.span.fragment());
+
+        println!();
+        println!(
+            "when parsing {}in {:?} =>",
+            source_name(test),
+            test.duration
+        );
+        match &test.result {
+            Ok((rest, _)) => {
+                println!("ok, rest at {}", test.location(rest));
+                print!("{}", txt.snippet(first_char(rest.fragment()), 3));
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                if let (Some(code), Some(span)) = (e.code(), e.span()) {
+                    diagnostic(test, &txt, "error", code, &span, 3);
+                }
+                hints(e, &txt);
+            }
+            Err(nom::Err::Incomplete(e)) => {
+                println!("incomplete {:?}", e);
+            }
+        }
+    }
+
+    /// One code with the lines around its span.
+    fn diagnostic<'s, P, C, I, O, E>(
+        test: &Test<'s, P, I, O, E>,
+        txt: &SourceStr<'s>,
+        kind: &str,
+        code: C,
+        span: &I,
+        n: usize,
+    ) where
+        C: Code,
+        I: AsBytes + Clone + Debug + PartialEq + SpanFragment<Result = &'s str>,
+        I: InputTake + InputIter + InputLength + Offset,
+        O: Debug,
+        E: Debug,
+    {
+        println!("{} {} at {}", kind, code, test.location(span));
+        print!("{}", txt.snippet(first_char(span.fragment()), n));
+    }
+
+    /// The caret points at the first character only, spans usually extend
+    /// to the end of the input.
+    fn first_char(span: &str) -> &str {
+        &span[..span.chars().next().map_or(0, char::len_utf8)]
+    }
+
     /// Dumps the Result data.
     #[derive(Clone, Copy)]
     pub struct Dump;
 
+    impl ReportAnd for Dump {}
+
     impl<'s, P, I, O, E> Report<Test<'s, P, I, O, E>> for Dump
     where
         I: AsBytes + Clone + Debug,
@@ -516,7 +2212,8 @@ mod report {
     {
         println!();
         println!(
-            "when parsing {:?} in {:?} =>",
+            "when parsing {}{:?} in {:?} =>",
+            source_name(test),
             restrict(DebugWidth::Medium, test.span.clone()),
             test.duration
         );
@@ -527,10 +2224,18 @@ mod report {
                 println!("rest");
                 println!("    {}:{:?}", test.span.offset(rest), rest);
             }
-            Err(e) => {
+            Err(nom::Err::Error(e)) => {
                 println!("error");
                 println!("    {:1?}", e);
             }
+            Err(nom::Err::Failure(e)) => {
+                println!("failure");
+                println!("    {:1?}", e);
+            }
+            Err(nom::Err::Incomplete(e)) => {
+                println!("incomplete");
+                println!("    {:1?}", e);
+            }
         }
     }
 
@@ -538,10 +2243,14 @@ mod report {
     #[derive(Clone, Copy)]
     pub struct CheckTrace;
 
+    impl ReportAnd for CheckTrace {}
+
     /// Dumps the full parser trace.
     #[derive(Clone, Copy)]
     pub struct Trace;
 
+    impl ReportAnd for Trace {}
+
     impl<'s, C, T, O, E> Report<Test<'s, StdTracker<C, T>, ParseSpan<'s, C, T>, O, E>> for CheckTrace
     where
         T: AsBytes + Clone + Debug,
@@ -599,7 +2308,8 @@ mod report {
     {
         println!();
         println!(
-            "when parsing {:?} in {:?} =>",
+            "when parsing {}{:?} in {:?} =>",
+            source_name(test),
             restrict_ref(DebugWidth::Medium, test.span.fragment()),
             test.duration
         );
@@ -670,7 +2380,8 @@ mod report {
     {
         println!();
         println!(
-            "when parsing {:?} in {:?} =>",
+            "when parsing {}{:?} in {:?} =>",
+            source_name(test),
             restrict_ref(DebugWidth::Medium, test.span.fragment()),
             test.duration
         );
@@ -735,7 +2446,8 @@ mod report {
     {
         println!();
         println!(
-            "when parsing {:?} in {:?} =>",
+            "when parsing {}{:?} in {:?} =>",
+            source_name(test),
             restrict_ref(DebugWidth::Medium, &test.span),
             test.duration
         );
@@ -796,7 +2508,8 @@ mod report {
     {
         println!();
         println!(
-            "when parsing {:?} in {:?} =>",
+            "when parsing {}{:?} in {:?} =>",
+            source_name(test),
             restrict_ref(DebugWidth::Medium, &test.span),
             test.duration
         );
@@ -809,7 +2522,7 @@ mod report {
                 println!("parsed");
                 println!("    {:0?}", token);
                 println!("rest");
-                println!("    {:?}", restrict_ref(DebugWidth::Medium, rest));
+                print!("{}", hexdump(rest));
             }
             Err(nom::Err::Error(e)) => {
                 println!("error");
@@ -826,3 +2539,82 @@ mod report {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test::{diff, from_hex, hexdump, line_diff};
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff("\"abc\"", "\"abd\"", false),
+            "expected \"ab[c\"]\nactual   \"ab[d\"]\nfirst difference at offset 3\n"
+        );
+        assert_eq!(
+            diff("\"\"", "\"b\"", false),
+            "expected \"[\"]\nactual   \"[b\"]\nfirst difference at offset 1\n"
+        );
+        assert_eq!(
+            diff("ab", "abc", true),
+            "expected ab\x1b[32m\x1b[0m\nactual   ab\x1b[31mc\x1b[0m\nfirst difference at offset 2\n"
+        );
+
+        let long = "x".repeat(100);
+        let long2 = format!("{}y{}", "x".repeat(50), "x".repeat(100));
+        let d = diff(&long, &long2, false);
+        assert!(d.starts_with(&format!(
+            "expected ...{}[{}]\n",
+            "x".repeat(20),
+            "x".repeat(50)
+        )));
+        assert!(d.contains(&format!(
+            "actual   ...{}[y{}]...\n",
+            "x".repeat(20),
+            "x".repeat(59)
+        )));
+
+        let d = diff("ääx", "äöx", false);
+        assert_eq!(
+            d,
+            "expected ä[äx]\nactual   ä[öx]\nfirst difference at offset 2\n"
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(from_hex("00 ff\n 1A"), Some(vec![0x00, 0xff, 0x1a]));
+        assert_eq!(from_hex(""), Some(vec![]));
+        assert_eq!(from_hex("0"), None);
+        assert_eq!(from_hex("xx"), None);
+
+        assert_eq!(
+            hexdump(b"ab\x00"),
+            "    0000  61 62 00                                         |ab.|\n"
+        );
+        assert_eq!(hexdump(b""), "    empty\n");
+        assert_eq!(hexdump(&[0u8; 17]).lines().count(), 2);
+        assert!(hexdump(&[0u8; 1000]).ends_with("    ... 1000 bytes\n"));
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let e = "A {\n    a: 1,\n    b: 2,\n}";
+        let a = "A {\n    a: 1,\n    b: 3,\n}";
+        assert_eq!(
+            line_diff(e, a, false),
+            "  A {\n      a: 1,\n-     b: 2,\n+     b: 3,\n  }\n"
+        );
+
+        let e = (0..20)
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let a = e.replace("10", "ten");
+        assert_eq!(
+            line_diff(&e, &a, false),
+            "  ...\n  7\n  8\n  9\n- 10\n+ ten\n  11\n  12\n  13\n  ...\n"
+        );
+
+        assert_eq!(line_diff("a", "a\nb", false), "  a\n+ b\n");
+    }
+}
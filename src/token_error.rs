@@ -3,16 +3,23 @@
 //!
 //! Can only hold one error code and a span.
 //!
+//! Creating a TokenizerError, with_code(), append_err() and the conversion
+//! from a nom error never allocate. For spans without tracking this makes
+//! the tokenizer stage allocation-free, see tests/test_token_alloc.rs and
+//! benches/tokenizer.rs.
+//!
 
 use crate::debug::{restrict, DebugWidth};
+use crate::examples::ExCode;
 use crate::parser_error::ParserError;
 use crate::spans::SpanFragment;
 use crate::{Code, ErrOrNomErr, KParseError};
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Debug, Display};
+use core::mem::size_of;
 use nom::error::ErrorKind;
 use nom::{InputIter, InputLength, InputTake};
-use std::error::Error;
-use std::fmt;
-use std::fmt::{Debug, Display};
 
 /// Shorter error type for the tokenizer stage.
 /// Nom parsers fail often, so it's good to keep this minimal.
@@ -23,6 +30,10 @@ pub struct TokenizerError<C, I> {
     pub span: I,
 }
 
+// Nothing but code and span, no room for anything on the heap.
+const _: () =
+    assert!(size_of::<TokenizerError<ExCode, &str>>() == size_of::<nom::error::Error<&str>>());
+
 impl<C, I> ErrOrNomErr for TokenizerError<C, I>
 where
     C: Code,
@@ -312,4 +323,15 @@ where
     pub fn failure(self) -> nom::Err<Self> {
         nom::Err::Failure(self)
     }
+
+    /// Converts the span of the error.
+    pub fn map_span<J, F>(self, f: F) -> TokenizerError<C, J>
+    where
+        F: FnOnce(I) -> J,
+    {
+        TokenizerError {
+            code: self.code,
+            span: f(self.span),
+        }
+    }
 }
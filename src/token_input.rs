@@ -0,0 +1,392 @@
+//!
+//! Input for a parser that runs on the tokens of a separate lexer.
+//!
+//! Each token knows its byte range in the source text. [TokenInput] works
+//! with the nom combinators, and keeps the source text, so the errors and
+//! the tracking can point to the original text.
+//!
+//! * The parser gets a TokenInput and uses [token] to match single tokens.
+//!   Any combinator of nom that works on InputTake and InputIter works too.
+//! * Convert the errors with `map_span(|v| v.source_span())` to get an error
+//!   for the source text. All of [report](crate::report) works with that.
+//! * TokenInput tracks with the [LocalTracker](crate::provider::LocalTracker),
+//!   the results are spans of the source text.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExNumber};
+//! use kparse::token_input::{token, TokenInput};
+//! use kparse::ParserError;
+//! use nom::multi::many1;
+//! use core::ops::Range;
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum Kind {
+//!     A,
+//!     Number,
+//! }
+//!
+//! type Tokens<'s> = TokenInput<'s, (Kind, Range<usize>)>;
+//! type Error<'s> = ParserError<ExCode, Tokens<'s>>;
+//!
+//! fn parse_numbers(i: Tokens<'_>) -> nom::IResult<Tokens<'_>, usize, Error<'_>> {
+//!     let number = token(ExNumber, |v: &(Kind, Range<usize>)| v.0 == Kind::Number);
+//!     let (rest, numbers) = many1(number)(i)?;
+//!     Ok((rest, numbers.len()))
+//! }
+//!
+//! let text = "1 2 a";
+//! let tokens = [(Kind::Number, 0..1), (Kind::Number, 2..3), (Kind::A, 4..5)];
+//!
+//! let (rest, n) = parse_numbers(TokenInput::new(text, &tokens)).expect("ok");
+//! assert_eq!(n, 2);
+//! assert_eq!(rest.source_span(), "a");
+//!
+//! let err = match parse_numbers(rest) {
+//!     Err(nom::Err::Error(e)) => e.map_span(|v| v.source_span()),
+//!     _ => unreachable!(),
+//! };
+//! assert_eq!(err.code, ExNumber);
+//! assert_eq!(err.span, "a");
+//! ```
+//!
+
+#[cfg(all(debug_assertions, feature = "std"))]
+use crate::provider::{location_span, track_local, TrackData};
+#[cfg(all(debug_assertions, feature = "std"))]
+use crate::report::Severity;
+use crate::spans::SpanFragment;
+use crate::{Code, KParseError, TrackedSpan};
+#[cfg(all(debug_assertions, feature = "std"))]
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Debug, Formatter};
+use core::iter::Enumerate;
+use core::ops::{Range, RangeFrom, RangeFull, RangeTo};
+use core::slice;
+use nom::{IResult, InputIter, InputLength, InputTake, Needed, Offset, Slice, UnspecializedInput};
+#[cfg(all(debug_assertions, feature = "std"))]
+use nom_locate::LocatedSpan;
+
+/// A token that knows where it comes from.
+pub trait SourceToken {
+    /// Byte range of the token in the source text.
+    fn source_range(&self) -> Range<usize>;
+}
+
+impl<K> SourceToken for (K, Range<usize>) {
+    fn source_range(&self) -> Range<usize> {
+        self.1.clone()
+    }
+}
+
+/// Input for a parser over the tokens of a lexer.
+///
+/// Holds the source text and all tokens, and the range of tokens that are
+/// still to parse.
+pub struct TokenInput<'s, T> {
+    text: &'s str,
+    all: &'s [T],
+    start: usize,
+    end: usize,
+}
+
+impl<'s, T> TokenInput<'s, T> {
+    /// New input for the tokens of the text.
+    pub fn new(text: &'s str, tokens: &'s [T]) -> Self {
+        Self {
+            text,
+            all: tokens,
+            start: 0,
+            end: tokens.len(),
+        }
+    }
+
+    /// The source text.
+    pub fn text(&self) -> &'s str {
+        self.text
+    }
+
+    /// The tokens of this input.
+    pub fn tokens(&self) -> &'s [T] {
+        &self.all[self.start..self.end]
+    }
+
+    /// Index of the first token within all tokens.
+    pub fn token_offset(&self) -> usize {
+        self.start
+    }
+}
+
+impl<'s, T> TokenInput<'s, T>
+where
+    T: SourceToken,
+{
+    /// Byte range in the source text from the first to the last token.
+    ///
+    /// Without tokens this is an empty range at the start of the next token,
+    /// or at the end of the last token if there is none.
+    pub fn source_range(&self) -> Range<usize> {
+        if self.start < self.end {
+            self.all[self.start].source_range().start..self.all[self.end - 1].source_range().end
+        } else {
+            let pos = match self.all.get(self.start) {
+                Some(v) => v.source_range().start,
+                None => self.all.last().map(|v| v.source_range().end).unwrap_or(0),
+            };
+            pos..pos
+        }
+    }
+
+    /// The source text from the first to the last token.
+    pub fn source_span(&self) -> &'s str {
+        &self.text[self.source_range()]
+    }
+
+    /// Offset, line and length in the source text for the tracker.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    fn local_span(&self) -> LocatedSpan<usize, ()> {
+        let range = self.source_range();
+        let lines = bytecount::count(&self.text.as_bytes()[..range.start], b'\n');
+        location_span(range.start, lines as u32 + 1, range.len())
+    }
+}
+
+impl<T> Clone for TokenInput<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TokenInput<'_, T> {}
+
+impl<T> PartialEq for TokenInput<'_, T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start && self.tokens() == other.tokens()
+    }
+}
+
+impl<T> Debug for TokenInput<'_, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{:?}", self.start, self.tokens())
+    }
+}
+
+impl<'s, T> SpanFragment for TokenInput<'s, T>
+where
+    T: Debug,
+{
+    type Result = [T];
+
+    fn fragment(&self) -> &Self::Result {
+        self.tokens()
+    }
+}
+
+impl<T> InputLength for TokenInput<'_, T> {
+    fn input_len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+impl<T> InputTake for TokenInput<'_, T> {
+    fn take(&self, count: usize) -> Self {
+        self.slice(..count)
+    }
+
+    fn take_split(&self, count: usize) -> (Self, Self) {
+        (self.slice(count..), self.slice(..count))
+    }
+}
+
+impl<'s, T> InputIter for TokenInput<'s, T> {
+    type Item = &'s T;
+    type Iter = Enumerate<slice::Iter<'s, T>>;
+    type IterElem = slice::Iter<'s, T>;
+
+    fn iter_indices(&self) -> Self::Iter {
+        self.tokens().iter().enumerate()
+    }
+
+    fn iter_elements(&self) -> Self::IterElem {
+        self.tokens().iter()
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.tokens().iter().position(predicate)
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        if self.input_len() >= count {
+            Ok(count)
+        } else {
+            Err(Needed::new(count - self.input_len()))
+        }
+    }
+}
+
+impl<T> UnspecializedInput for TokenInput<'_, T> {}
+
+impl<T> Offset for TokenInput<'_, T> {
+    fn offset(&self, second: &Self) -> usize {
+        second.start - self.start
+    }
+}
+
+impl<T> Slice<Range<usize>> for TokenInput<'_, T> {
+    fn slice(&self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end);
+        assert!(range.end <= self.input_len());
+        Self {
+            text: self.text,
+            all: self.all,
+            start: self.start + range.start,
+            end: self.start + range.end,
+        }
+    }
+}
+
+impl<T> Slice<RangeFrom<usize>> for TokenInput<'_, T> {
+    fn slice(&self, range: RangeFrom<usize>) -> Self {
+        self.slice(range.start..self.input_len())
+    }
+}
+
+impl<T> Slice<RangeTo<usize>> for TokenInput<'_, T> {
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        self.slice(0..range.end)
+    }
+}
+
+impl<T> Slice<RangeFull> for TokenInput<'_, T> {
+    fn slice(&self, _range: RangeFull) -> Self {
+        *self
+    }
+}
+
+/// Matches a single token.
+///
+/// Fails with the code if there is no token left or the predicate fails.
+pub fn token<'s, C, T, E>(
+    code: C,
+    predicate: impl Fn(&T) -> bool,
+) -> impl Fn(TokenInput<'s, T>) -> IResult<TokenInput<'s, T>, &'s T, E>
+where
+    C: Code,
+    T: 's,
+    E: KParseError<C, TokenInput<'s, T>>,
+{
+    move |i: TokenInput<'s, T>| match i.tokens().first() {
+        Some(v) if predicate(v) => Ok((i.slice(1..), v)),
+        _ => Err(nom::Err::Error(E::from(code, i))),
+    }
+}
+
+/// Tracks with the thread-local [LocalTracker](crate::provider::LocalTracker).
+/// The spans refer to the source text.
+#[cfg(all(debug_assertions, feature = "std"))]
+impl<C, T> TrackedSpan<C> for TokenInput<'_, T>
+where
+    T: SourceToken,
+    C: Code + 'static,
+{
+    #[inline(always)]
+    fn track_enter(&self, func: C) {
+        track_local::<C>(|_| TrackData::Enter(func, self.local_span()));
+    }
+
+    #[inline(always)]
+    fn track_debug(&self, debug: String) {
+        track_local::<C>(|_| TrackData::Debug(self.local_span(), debug));
+    }
+
+    #[inline(always)]
+    fn track_value<V: Debug>(&self, value: &V) {
+        track_local::<C>(|_| TrackData::Value(self.local_span(), format!("{:#?}", value)));
+    }
+
+    #[inline(always)]
+    fn track_info(&self, info: &'static str) {
+        track_local::<C>(|_| TrackData::Info(self.local_span(), info));
+    }
+
+    #[inline(always)]
+    fn track_warn(&self, warn: &'static str) {
+        track_local::<C>(|_| TrackData::Warn(self.local_span(), warn));
+    }
+
+    #[inline(always)]
+    fn track_ok(&self, parsed: Self) {
+        track_local::<C>(|_| TrackData::Ok(self.local_span(), parsed.local_span()));
+    }
+
+    #[inline(always)]
+    fn track_err<E: Debug>(&self, code: C, err: &E) {
+        self.track_err_expected(code, err, Vec::new);
+    }
+
+    #[inline(always)]
+    fn track_err_expected<E: Debug>(
+        &self,
+        code: C,
+        err: &E,
+        expected: impl FnOnce() -> Vec<(C, Self)>,
+    ) {
+        if code.severity() == Severity::Warning {
+            track_local::<C>(|_| TrackData::Warn(self.local_span(), "error with severity warning"));
+        }
+        track_local::<C>(|err_text| {
+            let expected = expected()
+                .iter()
+                .map(|(code, span)| (*code, span.local_span()))
+                .collect();
+            let text = if err_text {
+                format!("{:?}", err)
+            } else {
+                String::new()
+            };
+            TrackData::Err(self.local_span(), code, expected, text)
+        });
+    }
+
+    #[inline(always)]
+    fn track_exit(&self) {
+        track_local::<C>(|_| TrackData::Exit());
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "std")))]
+impl<C, T> TrackedSpan<C> for TokenInput<'_, T>
+where
+    T: SourceToken,
+    C: Code + 'static,
+{
+    #[inline(always)]
+    fn track_enter(&self, _func: C) {}
+
+    #[inline(always)]
+    fn track_debug(&self, _debug: String) {}
+
+    #[inline(always)]
+    fn track_info(&self, _info: &'static str) {}
+
+    #[inline(always)]
+    fn track_warn(&self, _warn: &'static str) {}
+
+    #[inline(always)]
+    fn track_ok(&self, _parsed: Self) {}
+
+    #[inline(always)]
+    fn track_err<E>(&self, _func: C, _err: &E) {}
+
+    #[inline(always)]
+    fn track_exit(&self) {}
+}
@@ -0,0 +1,361 @@
+//!
+//! Compatibility with winnow. Enabled with the feature `winnow`.
+//!
+//! For grammars that moved from nom to winnow. The error types,
+//! tracking and the test framework stay usable with winnow parsers.
+//!
+//! * ParserError and TokenizerError implement winnow's ParserError,
+//!   AddContext for a code and FromExternalError. The error keeps the
+//!   plain `&str` or `&[u8]` as span, even if the parser runs on a
+//!   LocatingSlice or Stateful input. See [WinnowInput].
+//! * [track] adds tracking to a winnow parser. It uses the
+//!   [LocalTracker](crate::provider::LocalTracker) and needs a
+//!   LocatingSlice to know the position.
+//! * [from_winnow] turns a winnow parser into a nom parser. The result can
+//!   use all of [KParser](crate::KParser).
+//! * [to_winnow] turns a nom parser into a winnow parser.
+//! * [winnow_parse](crate::test::winnow_parse) runs a winnow parser with
+//!   the test framework.
+//!
+//! ```rust
+//! use kparse::examples::{ExCode, ExTagA};
+//! use kparse::winnow::{from_winnow, to_winnow};
+//! use kparse::{KParser, ParserError};
+//! use winnow::combinator::repeat;
+//! use winnow::{ModalResult, Parser};
+//!
+//! type Error<'s> = ParserError<ExCode, &'s str>;
+//!
+//! fn nom_a(i: &str) -> nom::IResult<&str, &str, Error<'_>> {
+//!     nom::Parser::parse(&mut from_winnow("a").with_code(ExTagA), i)
+//! }
+//!
+//! fn winnow_many_a<'s>(i: &mut &'s str) -> ModalResult<Vec<&'s str>, Error<'s>> {
+//!     repeat(0.., to_winnow(nom_a)).parse_next(i)
+//! }
+//!
+//! let mut i = "aab";
+//! let many_a = winnow_many_a(&mut i).expect("ok");
+//! assert_eq!(i, "b");
+//! assert_eq!(many_a, vec!["a", "a"]);
+//! ```
+//!
+
+#[cfg(all(debug_assertions, feature = "std"))]
+use crate::provider::{location_span, track_local, TrackData};
+#[cfg(all(debug_assertions, feature = "std"))]
+use crate::report::Severity;
+use crate::{Code, ParserError, TokenizerError};
+#[cfg(all(debug_assertions, feature = "std"))]
+use alloc::format;
+#[cfg(all(debug_assertions, feature = "std"))]
+use alloc::string::String;
+use core::error::Error;
+use core::fmt::Debug;
+use nom::AsBytes;
+use winnow::error::{AddContext, ErrMode, FromExternalError};
+use winnow::stream::{Location, Offset, Stream};
+use winnow::{LocatingSlice, ModalResult, Stateful};
+
+/// Input types of winnow that kparse can use.
+///
+/// The span of the errors is the plain `&str` or `&[u8]` below any
+/// LocatingSlice and Stateful, so errors and results look the same as
+/// with nom.
+pub trait WinnowInput: Stream {
+    /// Span type for the errors.
+    type Span;
+
+    /// The current input as span.
+    fn span(&self) -> Self::Span;
+
+    /// Offset and line of the current input, if the input knows it.
+    fn location(&self) -> Option<(usize, u32)> {
+        None
+    }
+}
+
+impl<'s> WinnowInput for &'s str {
+    type Span = &'s str;
+
+    fn span(&self) -> Self::Span {
+        self
+    }
+}
+
+impl<'s> WinnowInput for &'s [u8] {
+    type Span = &'s [u8];
+
+    fn span(&self) -> Self::Span {
+        self
+    }
+}
+
+impl<I> WinnowInput for LocatingSlice<I>
+where
+    I: WinnowInput + Clone + Offset,
+    I::Span: AsBytes,
+{
+    type Span = I::Span;
+
+    fn span(&self) -> Self::Span {
+        self.as_ref().span()
+    }
+
+    fn location(&self) -> Option<(usize, u32)> {
+        let offset = self.current_token_start();
+        let mut start = self.clone();
+        start.reset_to_start();
+        let lines = bytecount::count(&start.span().as_bytes()[..offset], b'\n');
+        Some((offset, lines as u32 + 1))
+    }
+}
+
+impl<I, S> WinnowInput for Stateful<I, S>
+where
+    I: WinnowInput,
+    S: Debug,
+{
+    type Span = I::Span;
+
+    fn span(&self) -> Self::Span {
+        self.input.span()
+    }
+
+    fn location(&self) -> Option<(usize, u32)> {
+        self.input.location()
+    }
+}
+
+/// Maps Needed of winnow to nom.
+pub fn needed(needed: winnow::error::Needed) -> nom::Needed {
+    match needed {
+        winnow::error::Needed::Unknown => nom::Needed::Unknown,
+        winnow::error::Needed::Size(n) => nom::Needed::Size(n),
+    }
+}
+
+/// Maps Needed of nom to winnow.
+pub fn needed_winnow(needed: nom::Needed) -> winnow::error::Needed {
+    match needed {
+        nom::Needed::Unknown => winnow::error::Needed::Unknown,
+        nom::Needed::Size(n) => winnow::error::Needed::Size(n),
+    }
+}
+
+/// Maps ErrMode to nom::Err. Backtrack becomes Error and Cut becomes Failure.
+pub fn nom_err<E>(err: ErrMode<E>) -> nom::Err<E> {
+    match err {
+        ErrMode::Backtrack(e) => nom::Err::Error(e),
+        ErrMode::Cut(e) => nom::Err::Failure(e),
+        ErrMode::Incomplete(n) => nom::Err::Incomplete(needed(n)),
+    }
+}
+
+/// Maps nom::Err to ErrMode. Error becomes Backtrack and Failure becomes Cut.
+pub fn err_mode<E>(err: nom::Err<E>) -> ErrMode<E> {
+    match err {
+        nom::Err::Error(e) => ErrMode::Backtrack(e),
+        nom::Err::Failure(e) => ErrMode::Cut(e),
+        nom::Err::Incomplete(n) => ErrMode::Incomplete(needed_winnow(n)),
+    }
+}
+
+/// Runs a winnow parser as nom parser.
+#[inline]
+pub fn from_winnow<P, I, O, E>(mut parser: P) -> impl FnMut(I) -> nom::IResult<I, O, E>
+where
+    P: winnow::Parser<I, O, ErrMode<E>>,
+    I: Stream,
+{
+    move |mut i: I| match parser.parse_next(&mut i) {
+        Ok(o) => Ok((i, o)),
+        Err(e) => Err(nom_err(e)),
+    }
+}
+
+/// Runs a nom parser as winnow parser.
+///
+/// The nom parser gets the plain span of the input, so it works inside
+/// a LocatingSlice or Stateful too.
+#[inline]
+pub fn to_winnow<P, I, O, E>(mut parser: P) -> impl FnMut(&mut I) -> ModalResult<O, E>
+where
+    P: nom::Parser<I::Span, O, E>,
+    I: WinnowInput,
+    I::Span: Clone + nom::Offset,
+{
+    move |i: &mut I| {
+        let span = i.span();
+        match parser.parse(span.clone()) {
+            Ok((rest, o)) => {
+                i.next_slice(nom::Offset::offset(&span, &rest));
+                Ok(o)
+            }
+            Err(e) => Err(err_mode(e)),
+        }
+    }
+}
+
+/// Tracks the winnow parser with the given code.
+///
+/// The events go to the [LocalTracker](crate::provider::LocalTracker) of
+/// this thread. Nothing is tracked if there is none, or if the input
+/// doesn't know its location. Use a LocatingSlice for the latter.
+///
+/// This is compiled away in release builds.
+#[cfg(all(debug_assertions, feature = "std"))]
+#[inline]
+pub fn track<C, P, I, O, E>(func: C, mut parser: P) -> impl FnMut(&mut I) -> ModalResult<O, E>
+where
+    C: Code + 'static,
+    P: winnow::Parser<I, O, ErrMode<E>>,
+    I: WinnowInput,
+    E: Debug,
+{
+    move |i: &mut I| {
+        let Some((offset, line)) = i.location() else {
+            return parser.parse_next(i);
+        };
+        let start = i.checkpoint();
+        let span = location_span(offset, line, i.eof_offset());
+
+        track_local::<C>(|_| TrackData::Enter(func, span));
+        let result = parser.parse_next(i);
+        match &result {
+            Ok(_) => {
+                let parsed = location_span(offset, line, i.offset_from(&start));
+                let rest = match i.location() {
+                    Some((offset, line)) => location_span(offset, line, i.eof_offset()),
+                    None => span,
+                };
+                track_local::<C>(|_| TrackData::Ok(rest, parsed));
+            }
+            Err(err) => {
+                if func.severity() == Severity::Warning {
+                    track_local::<C>(|_| TrackData::Warn(span, "error with severity warning"));
+                }
+                track_local::<C>(|err_text| {
+                    let text = if err_text {
+                        format!("{:?}", err)
+                    } else {
+                        String::new()
+                    };
+                    TrackData::Err(span, func, Vec::new(), text)
+                });
+            }
+        }
+        track_local::<C>(|_| TrackData::Exit());
+        result
+    }
+}
+
+/// Tracks the winnow parser with the given code.
+///
+/// The events go to the [LocalTracker](crate::provider::LocalTracker) of
+/// this thread. Nothing is tracked if there is none, or if the input
+/// doesn't know its location. Use a LocatingSlice for the latter.
+///
+/// This is compiled away in release builds.
+#[cfg(not(all(debug_assertions, feature = "std")))]
+#[inline]
+pub fn track<C, P, I, O, E>(_func: C, mut parser: P) -> impl FnMut(&mut I) -> ModalResult<O, E>
+where
+    C: Code + 'static,
+    P: winnow::Parser<I, O, ErrMode<E>>,
+    I: WinnowInput,
+    E: Debug,
+{
+    move |i: &mut I| parser.parse_next(i)
+}
+
+impl<C, I> winnow::error::ParserError<I> for ParserError<C, I::Span>
+where
+    C: Code,
+    I: WinnowInput,
+    I::Span: Clone,
+{
+    type Inner = Self;
+
+    fn from_input(input: &I) -> Self {
+        ParserError::new(C::NOM_ERROR, input.span())
+    }
+
+    /// Combines two parser errors.
+    fn or(mut self, other: Self) -> Self {
+        self.append_err(other);
+        self
+    }
+
+    fn into_inner(self) -> Result<Self::Inner, Self> {
+        Ok(self)
+    }
+}
+
+impl<C, I> AddContext<I, C> for ParserError<C, I::Span>
+where
+    C: Code,
+    I: WinnowInput,
+    I::Span: Clone,
+{
+    fn add_context(self, _input: &I, _token_start: &I::Checkpoint, context: C) -> Self {
+        self.with_code(context)
+    }
+}
+
+impl<C, I, E> FromExternalError<I, E> for ParserError<C, I::Span>
+where
+    C: Code,
+    I: WinnowInput,
+    I::Span: Clone,
+    E: Error + 'static,
+{
+    fn from_external_error(input: &I, e: E) -> Self {
+        ParserError::new(C::NOM_ERROR, input.span()).with_cause(e)
+    }
+}
+
+impl<C, I> winnow::error::ParserError<I> for TokenizerError<C, I::Span>
+where
+    C: Code,
+    I: WinnowInput,
+    I::Span: Clone,
+{
+    type Inner = Self;
+
+    fn from_input(input: &I) -> Self {
+        TokenizerError::new(C::NOM_ERROR, input.span())
+    }
+
+    fn or(mut self, other: Self) -> Self {
+        self.append_err(other);
+        self
+    }
+
+    fn into_inner(self) -> Result<Self::Inner, Self> {
+        Ok(self)
+    }
+}
+
+impl<C, I> AddContext<I, C> for TokenizerError<C, I::Span>
+where
+    C: Code,
+    I: WinnowInput,
+    I::Span: Clone,
+{
+    fn add_context(self, _input: &I, _token_start: &I::Checkpoint, context: C) -> Self {
+        self.with_code(context)
+    }
+}
+
+impl<C, I, E> FromExternalError<I, E> for TokenizerError<C, I::Span>
+where
+    C: Code,
+    I: WinnowInput,
+    I::Span: Clone,
+{
+    fn from_external_error(input: &I, _e: E) -> Self {
+        // no place for the cause.
+        TokenizerError::new(C::NOM_ERROR, input.span())
+    }
+}
@@ -0,0 +1,83 @@
+use kparse::code_set::CodeSet;
+use kparse::combinators::fail_if_code_in;
+use kparse::{Code, KParser, ParserError};
+use nom::bytes::complete::tag;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SCode {
+    NomError,
+    Let,
+    If,
+    Ident,
+    Far,
+}
+
+impl Display for SCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Code for SCode {
+    const NOM_ERROR: Self = SCode::NomError;
+
+    fn id(&self) -> Option<u32> {
+        match self {
+            SCode::NomError => None,
+            SCode::Let => Some(1),
+            SCode::If => Some(2),
+            SCode::Ident => Some(3),
+            SCode::Far => Some(1000),
+        }
+    }
+}
+
+#[test]
+fn test_code_set() {
+    let starts = CodeSet::new().with(SCode::Let).with(SCode::If);
+    assert!(starts.contains(SCode::Let));
+    assert!(starts.contains(SCode::If));
+    assert!(!starts.contains(SCode::Ident));
+    assert!(!starts.contains(SCode::Far));
+    assert!(!starts.contains(SCode::NomError));
+
+    let other = [SCode::Far, SCode::NomError, SCode::Far]
+        .into_iter()
+        .collect::<CodeSet<_>>();
+    assert!(other.contains(SCode::Far));
+    assert!(other.contains(SCode::NomError));
+    assert!(!other.contains(SCode::Let));
+
+    let all = starts.union(&other);
+    assert!(all.contains(SCode::Let));
+    assert!(all.contains(SCode::Far));
+    assert!(!all.contains(SCode::Ident));
+
+    assert!(CodeSet::<SCode>::new().is_empty());
+    assert!(!all.is_empty());
+}
+
+#[test]
+fn test_fail_if_code_in() {
+    let starts = CodeSet::new().with(SCode::Let);
+
+    let mut parse_let = fail_if_code_in(tag("let").with_code(SCode::Let), &starts);
+    assert!(matches!(
+        parse_let("if"),
+        Err(nom::Err::Failure(ParserError {
+            code: SCode::Let,
+            ..
+        }))
+    ));
+    assert_eq!(parse_let("let x").expect("ok").0, " x");
+
+    let mut parse_if = fail_if_code_in(tag("if").with_code(SCode::If), &starts);
+    assert!(matches!(
+        parse_if("let"),
+        Err(nom::Err::Error(ParserError {
+            code: SCode::If,
+            ..
+        }))
+    ));
+}
@@ -0,0 +1,21 @@
+use kparse::{codes, Code};
+
+codes! {
+    /// Codes for the test.
+    #[derive(Hash)]
+    TCode {
+        /// A name.
+        TName => "Name",
+        TMenge => "Menge",
+        nom = TNomError,
+    }
+}
+
+#[test]
+fn test_codes() {
+    assert_eq!(TCode::NOM_ERROR, TCode::TNomError);
+    assert_eq!(TCode::TName.to_string(), "Name");
+    assert_eq!(TCode::TMenge.to_string(), "Menge");
+    assert_eq!(TCode::TNomError.to_string(), "TNomError");
+    assert_eq!(format!("{:?}", TCode::TMenge), "TMenge");
+}
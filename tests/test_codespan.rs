@@ -0,0 +1,28 @@
+#![cfg(feature = "codespan-reporting")]
+
+use codespan_reporting::diagnostic::LabelStyle;
+use kparse::examples::{ExAorB, ExTagA, ExTagB};
+use kparse::parser_error::to_codespan;
+use kparse::ParserError;
+use nom::Slice;
+use nom_locate::LocatedSpan;
+
+#[test]
+fn test_to_codespan() {
+    let span = LocatedSpan::new("a c");
+
+    let mut err = ParserError::new(ExAorB, span.slice(2..));
+    err.expect(ExTagA, span.slice(2..));
+    err.suggest(ExTagB, span.slice(1..));
+
+    let diag = to_codespan(&err, 7usize);
+    assert_eq!(diag.code.as_deref(), Some("A | B"));
+    assert_eq!(diag.labels.len(), 3);
+    assert_eq!(diag.labels[0].style, LabelStyle::Primary);
+    assert_eq!(diag.labels[0].file_id, 7);
+    assert_eq!(diag.labels[0].range, 2..2);
+    assert_eq!(diag.labels[1].style, LabelStyle::Secondary);
+    assert_eq!(diag.labels[1].message, "expected a");
+    assert_eq!(diag.labels[2].range, 1..1);
+    assert_eq!(diag.labels[2].message, "suggested b");
+}
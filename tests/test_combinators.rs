@@ -0,0 +1,649 @@
+use kparse::combinators::{
+    alt_with_codes, balanced, chainl1, chainr1, chars, cond, cond_opt, eof, float_token, limited,
+    none_of_code, one_of_code, recursion_guard, retry_with_sync, step_limit, take_till_newline,
+    take_while_ascii_ws, take_while_m_n, LeftRec, StepLimit,
+};
+use kparse::examples::{
+    ExAorB, ExAthenB, ExCode, ExNomError, ExNumber, ExParserResult, ExSpan, ExTagA, ExTagB,
+    ExTokenizerResult,
+};
+use kparse::prelude::*;
+use kparse::test::{str_parse, CheckTrace, NoReport};
+use kparse::Track;
+use nom::bytes::complete::tag;
+use nom::{InputLength, Offset, Parser};
+
+const R: CheckTrace = CheckTrace;
+
+fn nom_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+    tag("a").with_code(ExTagA).parse(i)
+}
+
+#[test]
+fn test_cond() {
+    str_parse(&mut None, "a", |i| cond(true, nom_a, ExTagB)(i))
+        .ok_any()
+        .q(R);
+    str_parse(&mut None, "b", |i| cond(true, nom_a, ExTagB)(i))
+        .err(ExTagA)
+        .q(R);
+    str_parse(&mut None, "a", |i| cond(false, nom_a, ExTagB)(i))
+        .err(ExTagB)
+        .q(R);
+}
+
+#[test]
+fn test_cond_opt() {
+    str_parse(&mut None, "a", |i| cond_opt(true, nom_a)(i))
+        .ok(|v, w: bool| v.is_some() == w, true)
+        .q(R);
+    str_parse(&mut None, "b", |i| cond_opt(true, nom_a)(i))
+        .err(ExTagA)
+        .q(R);
+    str_parse(&mut None, "a", |i| cond_opt(false, nom_a)(i))
+        .ok(|v, w: bool| v.is_some() == w, false)
+        .rest("a")
+        .q(R);
+}
+
+#[test]
+fn test_alt_with_codes() {
+    use nom::character::complete::digit1;
+    use nom::sequence::preceded;
+
+    fn a_or_b(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        alt_with_codes(
+            ExAorB,
+            ((ExTagA, tag("a")), (ExNumber, preceded(tag("b"), digit1))),
+        )(i)
+    }
+
+    str_parse(&mut None, "a", a_or_b).ok_any().q(R);
+    str_parse(&mut None, "b1", a_or_b).ok_any().q(R);
+    str_parse(&mut None, "c", a_or_b)
+        .err(ExAorB)
+        .expect(ExTagA)
+        .expect(ExNumber)
+        .q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, "bx");
+    let err = match a_or_b(span) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(Offset::offset(&span, &err.span), 1);
+}
+
+#[test]
+fn test_err_expected() {
+    use nom::character::complete::digit1;
+    use nom::sequence::preceded;
+
+    fn a_or_b(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        alt_with_codes(
+            ExAorB,
+            ((ExTagA, tag("a")), (ExNumber, preceded(tag("b"), digit1))),
+        )(i)
+    }
+
+    macro_rules! assert_fails {
+        ($txt:expr, $check:ident $args:tt) => {{
+            let mut buf = None;
+            let t = str_parse(&mut buf, $txt, a_or_b);
+            t.$check$args.q(NoReport);
+            assert!(t.failed.get());
+        }};
+    }
+
+    str_parse(&mut None, "bx", a_or_b)
+        .err(ExAorB)
+        .err_expected(ExTagA)
+        .err_expected(ExNumber)
+        .err_expected_at(ExTagA, 0)
+        .err_expected_at(ExNumber, 1)
+        .err_expected_all(&[ExNumber, ExTagA])
+        .q(R);
+
+    assert_fails!("bx", err_expected(ExAorB));
+    assert_fails!("bx", err_expected_at(ExNumber, 0));
+    assert_fails!("bx", err_expected_all(&[ExTagA]));
+    assert_fails!("bx", err_expected_all(&[ExTagA, ExNumber, ExTagB]));
+    assert_fails!("a", err_expected(ExTagA));
+}
+
+#[test]
+fn test_nom_err() {
+    use nom::character::complete::digit1;
+    use nom::error::ErrorKind;
+
+    fn number(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        digit1.with_code(ExNumber).parse(i)
+    }
+
+    fn raw_number(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        digit1(i)
+    }
+
+    str_parse(&mut None, "x", number)
+        .err(ExNumber)
+        .nom_err(ErrorKind::Digit)
+        .q(R);
+    str_parse(&mut None, "x", raw_number)
+        .nom_err(ErrorKind::Digit)
+        .q(R);
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "x", number);
+    t.nom_err(ErrorKind::Tag).q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_left_rec() {
+    use nom::branch::alt;
+    use nom::character::complete::digit1;
+    use nom::sequence::tuple;
+
+    type Memo<'s> = LeftRec<ExCode, ExSpan<'s>, (ExSpan<'s>, i64)>;
+
+    // expr := expr '-' number | number
+    fn expr<'s>(memo: &Memo<'s>, i: ExSpan<'s>) -> ExParserResult<'s, (ExSpan<'s>, i64)> {
+        memo.parse(i, |i| {
+            alt((
+                tuple((|i| expr(memo, i), tag("-"), number))
+                    .map(|((_, a), _, b)| a - b)
+                    .consumed(),
+                number.consumed(),
+            ))(i)
+        })
+    }
+
+    fn number(i: ExSpan<'_>) -> ExParserResult<'_, i64> {
+        digit1.with_code(ExNumber).parse_from_str(ExNumber).parse(i)
+    }
+
+    fn parse(i: ExSpan<'_>) -> ExParserResult<'_, (ExSpan<'_>, i64)> {
+        let memo = LeftRec::new(ExAorB);
+        expr(&memo, i)
+    }
+
+    str_parse(&mut None, "8", parse)
+        .ok(|v, w: i64| v.1 == w, 8)
+        .q(R);
+    str_parse(&mut None, "8-2-1", parse)
+        .ok(|v, w: i64| v.1 == w, 5)
+        .q(R);
+    str_parse(&mut None, "8-2-1x", parse)
+        .ok(|v, w: &str| *v.0.fragment() == w, "8-2-1")
+        .rest("x")
+        .q(R);
+    str_parse(&mut None, "8-", parse)
+        .ok(|v, w: i64| v.1 == w, 8)
+        .rest("-")
+        .q(R);
+    str_parse(&mut None, "x", parse)
+        .err(ExAorB)
+        .expect(ExNumber)
+        .q(R);
+}
+
+#[test]
+fn test_take_while_m_n() {
+    fn day(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        take_while_m_n(1, 2, |c| c.is_ascii_digit(), ExNumber)(i)
+    }
+
+    fn year(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        take_while_m_n(4, 4, |c| c.is_ascii_digit(), ExNumber)(i)
+    }
+
+    str_parse(&mut None, "1.", day).ok_any().rest(".").q(R);
+    str_parse(&mut None, "12.", day).ok_any().rest(".").q(R);
+    str_parse(&mut None, "123", day).ok_any().rest("3").q(R);
+    str_parse(&mut None, ".", day).err(ExNumber).q(R);
+    str_parse(&mut None, "2022-", year).ok_any().rest("-").q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, "22-");
+    let err = match year(span) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(err.code, ExNumber);
+    assert_eq!(err.span.input_len(), 2);
+}
+
+#[test]
+fn test_chars() {
+    fn two(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        chars(2, ExTagA)(i)
+    }
+
+    str_parse(&mut None, "äöü", two).ok_any().rest("ü").q(R);
+    str_parse(&mut None, "a", two).err(ExTagA).q(R);
+}
+
+#[test]
+fn test_float_token() {
+    fn float(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExSpan<'_>, f64)> {
+        float_token(ExNumber)(i)
+    }
+
+    fn eq(v: &(ExSpan<'_>, f64), w: f64) -> bool {
+        v.1 == w
+    }
+
+    str_parse(&mut None, "1", float).ok(eq, 1.0).q(R);
+    str_parse(&mut None, "-1.5x", float)
+        .ok(eq, -1.5)
+        .rest("x")
+        .q(R);
+    str_parse(&mut None, "+.5", float).ok(eq, 0.5).q(R);
+    str_parse(&mut None, "1.", float).ok(eq, 1.0).q(R);
+    str_parse(&mut None, "1.5e3", float).ok(eq, 1500.0).q(R);
+    str_parse(&mut None, "2E-1", float).ok(eq, 0.2).q(R);
+    str_parse(&mut None, ".", float).err(ExNumber).q(R);
+    str_parse(&mut None, "-x", float).err(ExNumber).q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, "1.5e+x");
+    let err = match float(span) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(Offset::offset(&span, &err.span), 3);
+    assert_eq!(err.span.input_len(), 2);
+}
+
+fn nom_number(i: ExSpan<'_>) -> ExTokenizerResult<'_, String> {
+    nom::character::complete::digit1
+        .map(|v: ExSpan<'_>| v.to_string())
+        .parse(i)
+}
+
+#[test]
+fn test_chainl1() {
+    fn sub(i: ExSpan<'_>) -> ExTokenizerResult<'_, String> {
+        chainl1(nom_number, tag("-"), |span: ExSpan<'_>, a, _, b| {
+            format!("({}={}-{})", span, a, b)
+        })(i)
+    }
+
+    str_parse(&mut None, "1", sub)
+        .ok(|v, w: &str| v == w, "1")
+        .q(R);
+    str_parse(&mut None, "1-2-3", sub)
+        .ok(|v, w: &str| v == w, "(1-2-3=(1-2=1-2)-3)")
+        .q(R);
+    str_parse(&mut None, "1-2-", sub).err(ExNomError).q(R);
+}
+
+#[test]
+fn test_chainr1() {
+    fn pow(i: ExSpan<'_>) -> ExTokenizerResult<'_, String> {
+        chainr1(nom_number, tag("^"), |span: ExSpan<'_>, a, _, b| {
+            format!("({}={}^{})", span, a, b)
+        })(i)
+    }
+
+    str_parse(&mut None, "1", pow)
+        .ok(|v, w: &str| v == w, "1")
+        .q(R);
+    str_parse(&mut None, "1^2^3x", pow)
+        .ok(|v, w: &str| v == w, "(1^2^3=1^(2^3=2^3))")
+        .rest("x")
+        .q(R);
+    str_parse(&mut None, "1^", pow).err(ExNomError).q(R);
+}
+
+#[test]
+fn test_balanced() {
+    use nom::branch::alt;
+    use nom::character::complete::digit1;
+
+    fn nested(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        alt((digit1, group))(i)
+    }
+
+    fn group(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        balanced(
+            tag("(").with_code(ExTagA),
+            tag(")").with_code(ExTagB),
+            nested,
+            ExAthenB,
+        )(i)
+    }
+
+    str_parse(&mut None, "(1)", group).ok_any().rest("").q(R);
+    str_parse(&mut None, "((1))x", group)
+        .ok_any()
+        .rest("x")
+        .q(R);
+    str_parse(&mut None, "1", group).err(ExTagA).q(R);
+    str_parse(&mut None, "((1)", group)
+        .err(ExAthenB)
+        .expect(ExTagB)
+        .q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, "((1)");
+    let err = match group(span) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(err.code, ExAthenB);
+    assert_eq!(Offset::offset(&span, &err.span), 4);
+    let open = err
+        .iter_expected()
+        .find(|v| v.code == ExAthenB)
+        .expect("open");
+    assert_eq!(Offset::offset(&span, &open.span), 0);
+}
+
+#[test]
+fn test_retry_with_sync() {
+    use kparse::examples::ExParserError;
+    use nom::character::complete::digit1;
+    use nom::multi::separated_list1;
+    use std::cell::RefCell;
+
+    fn list<'s>(
+        errors: &RefCell<Vec<ExParserError<'s>>>,
+        i: ExSpan<'s>,
+    ) -> ExParserResult<'s, Vec<ExSpan<'s>>> {
+        separated_list1(
+            tag(","),
+            retry_with_sync(digit1.with_code(ExNumber), tag(","), 2, errors),
+        )(i)
+    }
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+
+    let errors = RefCell::new(Vec::new());
+    let span = Track::new_span(&tracker, "1,x,3");
+    let (rest, v) = list(&errors, span).expect("list");
+    assert_eq!(v.len(), 2);
+    assert_eq!(rest.input_len(), 0);
+    assert_eq!(errors.borrow().len(), 1);
+    assert_eq!(errors.borrow()[0].code, ExNumber);
+
+    // limit
+    let errors = RefCell::new(Vec::new());
+    let span = Track::new_span(&tracker, "1,x,y,z,4");
+    let (rest, v) = list(&errors, span).expect("list");
+    assert_eq!(v.len(), 1);
+    assert_eq!(Offset::offset(&span, &rest), 1);
+    assert_eq!(errors.borrow().len(), 2);
+
+    // no sync
+    let errors = RefCell::new(Vec::new());
+    let span = Track::new_span(&tracker, "x");
+    assert!(list(&errors, span).is_err());
+    assert_eq!(errors.borrow().len(), 0);
+}
+
+#[test]
+fn test_errs() {
+    use kparse::examples::ExParserError;
+    use nom::character::complete::digit1;
+    use nom::multi::separated_list1;
+    use std::cell::RefCell;
+
+    fn list<'s>(
+        errors: &RefCell<Vec<ExParserError<'s>>>,
+        i: ExSpan<'s>,
+    ) -> ExParserResult<'s, Vec<ExSpan<'s>>> {
+        separated_list1(
+            tag(","),
+            retry_with_sync(digit1.with_code(ExNumber), tag(","), 5, errors),
+        )(i)
+    }
+
+    let errors = RefCell::new(Vec::new());
+    let mut buf = None;
+    let t = str_parse(&mut buf, "1,x,3,yy,5", |i| list(&errors, i));
+    t.ok_any()
+        .errs(&errors.borrow(), &[(ExNumber, 2), (ExNumber, 6)])
+        .q(R);
+
+    t.errs(&errors.borrow(), &[(ExNumber, 2)]).q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_one_of_code() {
+    fn sign(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExSpan<'_>, char)> {
+        one_of_code("+-", ExTagA)(i)
+    }
+
+    str_parse(&mut None, "+1", sign)
+        .ok(|v, w: char| v.1 == w, '+')
+        .rest("1")
+        .q(R);
+    str_parse(&mut None, "-", sign)
+        .ok(|v, w: char| v.1 == w, '-')
+        .q(R);
+    str_parse(&mut None, "1", sign).err(ExTagA).q(R);
+    str_parse(&mut None, "", sign).err(ExTagA).q(R);
+}
+
+#[test]
+fn test_none_of_code() {
+    fn not_sep(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExSpan<'_>, char)> {
+        none_of_code(",;", ExTagB)(i)
+    }
+
+    str_parse(&mut None, "ä,", not_sep)
+        .ok(|v, w: char| v.1 == w, 'ä')
+        .rest(",")
+        .q(R);
+    str_parse(&mut None, ",", not_sep).err(ExTagB).q(R);
+    str_parse(&mut None, "", not_sep).err(ExTagB).q(R);
+}
+
+#[test]
+fn test_char_class_bytes() {
+    use kparse::TokenizerError;
+
+    type BResult<'s> =
+        Result<(&'s [u8], (&'s [u8], char)), nom::Err<TokenizerError<ExCode, &'s [u8]>>>;
+
+    let r: BResult<'_> = none_of_code("a", ExTagA)(b"\xe9x".as_slice());
+    let (rest, (span, c)) = r.expect("ok");
+    assert_eq!(rest, b"x");
+    assert_eq!(span, b"\xe9");
+    assert_eq!(c, '\u{e9}');
+
+    let r: BResult<'_> = one_of_code("\u{e9}", ExTagA)(b"\xe9".as_slice());
+    let (rest, (span, _)) = r.expect("ok");
+    assert_eq!(rest, b"");
+    assert_eq!(span, b"\xe9");
+
+    let r: BResult<'_> = one_of_code("a", ExTagA)(b"\xe9".as_slice());
+    assert!(r.is_err());
+}
+
+#[test]
+fn test_eof() {
+    use nom::sequence::terminated;
+
+    fn a_eof(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        terminated(nom_a, eof(ExTagB))(i)
+    }
+
+    str_parse(&mut None, "", |i: ExSpan<'_>| -> ExTokenizerResult<'_, _> {
+        eof(ExTagB)(i)
+    })
+    .ok_any()
+    .q(R);
+    str_parse(&mut None, "a", a_eof).ok_any().q(R);
+    str_parse(&mut None, "ab", a_eof).err(ExTagB).q(R);
+}
+
+#[test]
+fn test_take_while_ascii_ws() {
+    fn ws(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        take_while_ascii_ws(i)
+    }
+
+    str_parse(&mut None, "", ws).ok_any().rest("").q(R);
+    str_parse(&mut None, "a", ws).ok_any().rest("a").q(R);
+    str_parse(&mut None, " \t\r\n a", ws)
+        .ok(|v, w: &str| *v.fragment() == w, " \t\r\n ")
+        .rest("a")
+        .q(R);
+    str_parse(&mut None, "  ", ws).ok_any().rest("").q(R);
+    str_parse(&mut None, " \u{a0}", ws)
+        .ok_any()
+        .rest("\u{a0}")
+        .q(R);
+
+    for n in 0..20 {
+        let text = format!("{}x\t", &" \t\r\n".repeat(8)[..n]);
+        let r: nom::IResult<&str, &str> = take_while_ascii_ws(text.as_str());
+        assert_eq!(r, Ok((&text[n..], &text[..n])));
+    }
+}
+
+#[test]
+fn test_take_till_newline() {
+    fn line(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        take_till_newline(i)
+    }
+
+    str_parse(&mut None, "", line).ok_any().rest("").q(R);
+    str_parse(&mut None, "abc", line)
+        .ok(|v, w: &str| *v.fragment() == w, "abc")
+        .rest("")
+        .q(R);
+    str_parse(&mut None, "abc\ndef", line)
+        .ok(|v, w: &str| *v.fragment() == w, "abc")
+        .rest("\ndef")
+        .q(R);
+    str_parse(&mut None, "abc\r\ndef", line)
+        .ok(|v, w: &str| *v.fragment() == w, "abc")
+        .rest("\r\ndef")
+        .q(R);
+    str_parse(&mut None, "\n", line)
+        .ok(|v, w: &str| *v.fragment() == w, "")
+        .rest("\n")
+        .q(R);
+}
+
+#[test]
+fn test_recursion_guard() {
+    use nom::branch::alt;
+    use nom::character::complete::digit1;
+    use nom::sequence::delimited;
+
+    fn nested(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        recursion_guard(
+            3,
+            ExAthenB,
+            alt((digit1, delimited(tag("("), nested, tag(")")))),
+        )(i)
+    }
+
+    str_parse(&mut None, "1", nested).ok_any().q(R);
+    str_parse(&mut None, "((1))", nested).ok_any().q(R);
+    str_parse(&mut None, "(((1)))", nested).err(ExAthenB).q(R);
+    // the depth is reset after the failure.
+    str_parse(&mut None, "((1))", nested).ok_any().q(R);
+}
+
+#[test]
+fn test_step_limit() {
+    use nom::multi::many0;
+    use std::time::Duration;
+
+    fn many_a<'s>(limit: &StepLimit, i: ExSpan<'s>) -> ExTokenizerResult<'s, Vec<ExSpan<'s>>> {
+        many0(step_limit(limit, ExTagB, nom_a))(i)
+    }
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+
+    let limit = StepLimit::new(5);
+    let r = many_a(&limit, Track::new_span(&tracker, "aaaa"));
+    assert!(r.is_ok());
+    assert_eq!(limit.steps(), 5);
+
+    let limit = StepLimit::new(5);
+    let r = many_a(&limit, Track::new_span(&tracker, "aaaaaa"));
+    assert!(matches!(r, Err(nom::Err::Failure(e)) if e.code == ExTagB));
+
+    let limit = StepLimit::new(100).with_timeout(Duration::ZERO);
+    let r = many_a(&limit, Track::new_span(&tracker, "a"));
+    assert!(matches!(r, Err(nom::Err::Failure(e)) if e.code == ExTagB));
+}
+
+#[test]
+fn test_limited() {
+    use nom::character::complete::alpha1;
+
+    fn two_tags(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExSpan<'_>, ExSpan<'_>)> {
+        limited(2, nom_a.and(tag("b").with_code(ExTagB)), ExNumber)(i)
+    }
+
+    fn word(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        limited(3, alpha1.with_code(ExTagA), ExNumber)(i)
+    }
+
+    str_parse(&mut None, "abc", two_tags)
+        .ok_any()
+        .rest("c")
+        .q(R);
+    str_parse(&mut None, "a", two_tags).err(ExNumber).q(R);
+    str_parse(&mut None, "aa", two_tags).err(ExTagB).q(R);
+    // reads past the boundary
+    str_parse(&mut None, "ab", |i| {
+        limited(1, nom_a.and(tag("b").with_code(ExTagB)), ExNumber)(i)
+    })
+    .err(ExNumber)
+    .q(R);
+    str_parse(&mut None, "abcde", word).ok_any().rest("de").q(R);
+
+    // length-prefixed binary data, the value is longer than the limit.
+    {
+        use kparse::TokenizerError;
+        use nom::number::complete::{be_u16, be_u32};
+
+        type BResult<'s, O> = Result<(&'s [u8], O), nom::Err<TokenizerError<ExCode, &'s [u8]>>>;
+
+        let input = b"\x00\x01\x02\x03\x04".as_slice();
+        let r: BResult<'_, u32> = limited(2, be_u32, ExNumber)(input);
+        match r {
+            Err(nom::Err::Error(e)) => assert_eq!(e.code, ExNumber),
+            _ => panic!("expected error"),
+        }
+        let r: BResult<'_, u16> = limited(2, be_u16, ExNumber)(input);
+        assert_eq!(r.expect("ok"), (&input[2..], 1));
+    }
+    str_parse(&mut None, "ab1", word).ok_any().rest("1").q(R);
+    str_parse(&mut None, "1ab", word).err(ExTagA).q(R);
+}
+
+#[test]
+fn test_not_followed_by() {
+    use nom::character::complete::{digit1, line_ending, space0};
+    use nom::combinator::opt;
+    use nom::sequence::tuple;
+
+    // optional item, but no number may follow on the next line.
+    fn item(i: ExSpan<'_>) -> ExTokenizerResult<'_, Option<ExSpan<'_>>> {
+        opt(nom_a)
+            .not_followed_by(tuple((line_ending, space0, digit1)), ExNumber)
+            .parse(i)
+    }
+
+    str_parse(&mut None, "a\nb", item)
+        .ok(|v, w: bool| v.is_some() == w, true)
+        .rest("\nb")
+        .q(R);
+    str_parse(&mut None, "x", item)
+        .ok(|v, w: bool| v.is_none() == w, true)
+        .rest("x")
+        .q(R);
+    str_parse(&mut None, "a\n  12", item).err(ExNumber).q(R);
+    str_parse(&mut None, "\n1", item).err(ExNumber).q(R);
+}
@@ -0,0 +1,24 @@
+use kparse::examples::{ExSpan, ExTagA, ExTagB, ExTokenizerResult};
+use kparse::prelude::*;
+use kparse::test::{code_coverage, str_parse, CheckTrace, CODE_COVERAGE};
+use nom::bytes::complete::tag;
+use nom::Parser;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn nom_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+    tag("a").with_code(ExTagA).parse(i)
+}
+
+// The only test in this file, it sets the environment variable.
+#[test]
+fn test_code_coverage() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("coverage.txt");
+    let _ = fs::remove_file(&path);
+    env::set_var(CODE_COVERAGE, &path);
+
+    str_parse(&mut None, "b", nom_a).err(ExTagA).q(CheckTrace);
+
+    assert_eq!(code_coverage(&[ExTagA, ExTagB]), vec![ExTagB]);
+}
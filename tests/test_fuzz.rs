@@ -0,0 +1,44 @@
+use kparse::examples::{ExSpan, ExTagA, ExTokenizerResult};
+use kparse::prelude::*;
+use kparse::test::fuzz::check_str;
+use nom::bytes::complete::tag;
+use nom::multi::many0;
+use nom::{Parser, Slice};
+
+fn nom_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+    tag("a").with_code(ExTagA).parse(i)
+}
+
+fn many_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, Vec<ExSpan<'_>>> {
+    many0(nom_a)(i)
+}
+
+#[test]
+fn test_check_str() {
+    for txt in ["", "a", "aab", "b", "äa", "a\u{0}a"] {
+        check_str(&mut None, txt, nom_a);
+        check_str(&mut None, txt, many_a);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_check_str_rest() {
+    // returns an empty span at the start as rest.
+    fn broken(i: ExSpan<'_>) -> ExTokenizerResult<'_, ()> {
+        let (_, _) = nom_a(i)?;
+        Ok((i.slice(..0), ()))
+    }
+
+    check_str(&mut None, "a", broken);
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn test_fuzz_str() {
+    use kparse::test::fuzz::fuzz_str;
+
+    for data in [&b""[..], b"aab", b"\xff\xfe", b"a\xc3\xa4"] {
+        fuzz_str(&mut None, data, many_a);
+    }
+}
@@ -0,0 +1,128 @@
+use kparse::examples::{ExAorB, ExCode, ExNumber, ExSpan, ExTagA, ExTagB, ExTokenizerResult};
+use kparse::keywords::KeywordSet;
+use kparse::test::{str_parse, CheckTrace};
+use nom::character::complete::space0;
+use nom::sequence::terminated;
+
+const R: CheckTrace = CheckTrace;
+
+thread_local! {
+    static KEYWORDS: KeywordSet<ExCode> = KeywordSet::new(ExAorB)
+        .keyword("a", ExTagA)
+        .keyword("ab", ExTagB)
+        .keywords([("abc1", ExNumber)])
+        .word_boundary(false);
+    static KEYWORDS_CI: KeywordSet<ExCode> = KeywordSet::new_ignore_case(ExAorB)
+        .keyword("Kunde", ExTagA)
+        .keyword("KDNR", ExTagB);
+}
+
+fn nom_keyword(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExCode, ExSpan<'_>)> {
+    KEYWORDS.with(|k| k.parse(i))
+}
+
+fn nom_keyword_ci(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExCode, ExSpan<'_>)> {
+    KEYWORDS_CI.with(|k| terminated(k, space0)(i))
+}
+
+fn code(v: &(ExCode, ExSpan<'_>), code: ExCode) -> bool {
+    v.0 == code
+}
+
+#[test]
+fn test_keywords() {
+    str_parse(&mut None, "a", nom_keyword).ok(code, ExTagA).q(R);
+    str_parse(&mut None, "ab", nom_keyword)
+        .ok(code, ExTagB)
+        .q(R);
+    str_parse(&mut None, "abc", nom_keyword)
+        .ok(code, ExTagB)
+        .rest("c")
+        .q(R);
+    str_parse(&mut None, "abc1", nom_keyword)
+        .ok(code, ExNumber)
+        .rest("")
+        .q(R);
+    str_parse(&mut None, "A", nom_keyword).err(ExAorB).q(R);
+    str_parse(&mut None, "", nom_keyword).err(ExAorB).q(R);
+}
+
+#[test]
+fn test_keywords_word_boundary() {
+    let keywords = KeywordSet::new(ExAorB)
+        .keyword("a", ExTagA)
+        .keyword("ab", ExTagB)
+        .keyword("<", ExNumber);
+
+    let r: Result<_, nom::Err<kparse::ParserError<ExCode, &str>>> = keywords.parse("ab c");
+    assert_eq!(r.expect("ok").1 .0, ExTagB);
+    let r: Result<_, nom::Err<kparse::ParserError<ExCode, &str>>> = keywords.parse("a_");
+    assert!(r.is_err());
+    let r: Result<_, nom::Err<kparse::ParserError<ExCode, &str>>> = keywords.parse("abc");
+    assert!(r.is_err());
+    let r: Result<_, nom::Err<kparse::ParserError<ExCode, &str>>> = keywords.parse("<a");
+    assert_eq!(r.expect("ok"), ("a", (ExNumber, "<")));
+}
+
+#[test]
+fn test_keywords_ignore_case() {
+    str_parse(&mut None, "kunde 1", nom_keyword_ci)
+        .ok(code, ExTagA)
+        .rest("1")
+        .q(R);
+    str_parse(&mut None, "KUNDE", nom_keyword_ci)
+        .ok(code, ExTagA)
+        .q(R);
+    str_parse(&mut None, "KdNr", nom_keyword_ci)
+        .ok(code, ExTagB)
+        .q(R);
+    str_parse(&mut None, "Kund", nom_keyword_ci)
+        .err(ExAorB)
+        .q(R);
+}
+
+#[test]
+fn test_keywords_suggest() {
+    let keywords = KeywordSet::new_ignore_case(ExAorB)
+        .keyword("Stichtag", ExTagA)
+        .keyword("Kunde", ExTagB)
+        .keyword("Kunden", ExNumber);
+
+    assert_eq!(keywords.closest("Stichtagg"), Some((ExTagA, "Stichtag")));
+    assert_eq!(keywords.closest("stchtag"), Some((ExTagA, "Stichtag")));
+    assert_eq!(keywords.closest("Kundx"), Some((ExTagB, "Kunde")));
+    assert_eq!(keywords.closest("Kundenn"), Some((ExNumber, "Kunden")));
+    assert_eq!(keywords.closest("Stich"), None);
+    assert_eq!(keywords.closest(""), None);
+    assert_eq!(keywords.max_distance(0).closest("Stichtagg"), None);
+
+    // ties go to the keyword added first.
+    let keywords = KeywordSet::new(ExAorB)
+        .keyword("zz", ExTagA)
+        .keyword("ab", ExTagB)
+        .keyword("zb", ExNumber);
+    assert_eq!(keywords.closest("xb"), Some((ExTagB, "ab")));
+
+    let keywords = KeywordSet::new(ExAorB).keyword("Stichtag", ExTagA);
+    let err = match keywords.parse_suggest("Stichtak: 1.1.") {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!(),
+    };
+    assert_eq!(err.code, ExAorB);
+    let rep = err.iter_replacements().next().expect("replacement");
+    assert_eq!(rep.code, ExTagA);
+    assert_eq!(rep.span, "Stichtak");
+    assert_eq!(rep.text, "Stichtag");
+
+    let err = match keywords.parse_suggest("Stichtagg 1") {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!(),
+    };
+    let rep = err.iter_replacements().next().expect("replacement");
+    assert_eq!(rep.span, "Stichtagg");
+    assert_eq!(rep.text, "Stichtag");
+
+    let (rest, (code, _)) = keywords.parse_suggest("Stichtag: 1.1.").expect("ok");
+    assert_eq!(code, ExTagA);
+    assert_eq!(rest, ": 1.1.");
+}
@@ -0,0 +1,657 @@
+use kparse::examples::{
+    ExCode, ExNumber, ExParserResult, ExSpan, ExTagA, ExTagB, ExTokenizerResult,
+};
+use kparse::prelude::*;
+use kparse::test::{
+    byte_parse, str_parse, str_timing, str_tracking_overhead, CheckDiagnostics, CheckTrace,
+    NoReport, Statistics,
+};
+use kparse::{kparse_cases, Track};
+use nom::bytes::complete::tag;
+use nom::{Offset, Parser, Slice};
+
+const R: CheckTrace = CheckTrace;
+
+fn nom_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+    tag("a").with_code(ExTagA).parse(i)
+}
+
+fn nom_b(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+    tag("b").with_code(ExTagB).parse(i)
+}
+
+kparse_cases! {
+    nom_a, R;
+    test_cases_a: "a" => ok_any();
+    test_cases_a_rest: "ab" => ok_any().rest("b");
+    test_cases_a_err: "b" => err(ExTagA);
+}
+
+#[test]
+fn test_rest_offset() {
+    use nom::character::complete::alpha1;
+
+    fn word(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        alpha1.with_code(ExTagA).parse(i)
+    }
+
+    str_parse(&mut None, "word rest", word)
+        .rest_offset(4)
+        .consumed(4)
+        .q(R);
+    str_parse(&mut None, "word", word)
+        .rest_offset(4)
+        .consumed(4)
+        .q(R);
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "word rest", word);
+    t.rest_offset(3).q(NoReport);
+    assert!(t.failed.get());
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "word rest", word);
+    t.consumed(5).q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_warns() {
+    fn lint_a(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        use nom::branch::alt;
+
+        Track.enter(ExTagA, i);
+        let r: ExParserResult<'_, _> = alt((tag("a"), tag("A"))).with_code(ExTagA).parse(i);
+        match r {
+            Ok((rest, tok)) => {
+                if *tok.fragment() == "A" {
+                    Track.warn::<ExCode, _>(tok, "uppercase a");
+                }
+                Track.ok(rest, i, tok)
+            }
+            Err(e) => Track.err(e),
+        }
+    }
+
+    str_parse(&mut None, "A", lint_a)
+        .ok_any()
+        .warns(ExTagA)
+        .q(R);
+    str_parse(&mut None, "a", lint_a)
+        .ok_any()
+        .no_warnings()
+        .q(R);
+
+    if cfg!(debug_assertions) {
+        let mut buf = None;
+        let t = str_parse(&mut buf, "a", lint_a);
+        t.warns(ExTagA).q(NoReport);
+        assert!(t.failed.get());
+
+        let mut buf = None;
+        let t = str_parse(&mut buf, "A", lint_a);
+        t.no_warnings().q(NoReport);
+        assert!(t.failed.get());
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_tracks() {
+    use kparse::provider::TrackData;
+
+    fn parse_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        Track.enter(ExTagA, i);
+        match nom_a(i) {
+            Ok((rest, tok)) => Track.ok(rest, i, tok),
+            Err(e) => Track.err(e),
+        }
+    }
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "ab", parse_a);
+    {
+        let tracks = t.tracks();
+        assert_eq!(tracks.len(), 3);
+        assert!(matches!(tracks[0].track, TrackData::Enter(ExTagA, _)));
+        assert!(matches!(tracks[1].track, TrackData::Ok(_, _)));
+        assert!(matches!(tracks[2].track, TrackData::Exit()));
+        assert_eq!(tracks[1].callstack, vec![ExTagA]);
+    }
+    t.ok_any().q(R);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_value() {
+    use kparse::provider::TrackData;
+    use nom::character::complete::digit1;
+
+    fn number(i: ExSpan<'_>) -> ExTokenizerResult<'_, u32> {
+        Track.enter(ExNumber, i);
+        let r: ExTokenizerResult<'_, u32> = digit1.parse_from_str(ExNumber).parse(i);
+        match r {
+            Ok((rest, v)) => {
+                Track.value(i, &v);
+                Track.ok(rest, i, v)
+            }
+            Err(e) => Track.err(e),
+        }
+    }
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "42", number);
+    {
+        let tracks = t.tracks();
+        assert_eq!(tracks.len(), 4);
+        assert!(matches!(tracks[1].track, TrackData::Value(_, ref v) if v == "42"));
+        assert_eq!(tracks[1].callstack, vec![ExNumber]);
+    }
+    t.ok_any().q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let _ = number(Track::new_span(&tracker, "42"));
+    let trace = format!("{:?}", tracker.results());
+    assert!(trace.contains("  number: value 42 for 0:\"42\""));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_value_pretty() {
+    use kparse::provider::TrackData;
+    use nom::character::complete::digit1;
+
+    fn pair(i: ExSpan<'_>) -> ExTokenizerResult<'_, [u32; 2]> {
+        Track.enter(ExNumber, i);
+        let r: ExTokenizerResult<'_, u32> = digit1.parse_from_str(ExNumber).parse(i);
+        match r {
+            Ok((rest, v)) => {
+                Track.value(i, &[v, v]);
+                Track.ok(rest, i, [v, v])
+            }
+            Err(e) => Track.err(e),
+        }
+    }
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "42", pair);
+    {
+        let tracks = t.tracks();
+        assert!(
+            matches!(tracks[1].track, TrackData::Value(_, ref v) if v == "[\n    42,\n    42,\n]")
+        );
+    }
+    t.ok_any().q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let _ = pair(Track::new_span(&tracker, "42"));
+    let trace = format!("{:?}", tracker.results());
+    assert!(trace.contains("  number: value [ 42, 42, ] for 0:\"42\""));
+}
+
+#[test]
+fn test_timing() {
+    let mut buf = None;
+    let t = str_timing(&mut buf, "ab", 2, 10, nom_a);
+    t.ok_any().rest("b").q(Statistics);
+    assert_eq!(t.samples.len(), 10);
+    assert!(!t.failed.get());
+
+    let mut buf = None;
+    let t = str_timing(&mut buf, "b", 0, 0, nom_a);
+    t.err(ExTagA).q(Statistics);
+    assert_eq!(t.samples.len(), 1);
+}
+
+#[test]
+fn test_max_duration() {
+    use std::time::Duration;
+
+    str_parse(&mut None, "ab", nom_a)
+        .ok_any()
+        .max_duration(Duration::from_secs(10))
+        .q(R);
+    str_timing(&mut None, "ab", 1, 5, nom_a)
+        .ok_any()
+        .max_duration(Duration::from_secs(10))
+        .q(R);
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "ab", nom_a);
+    t.max_duration(Duration::ZERO).q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_tracking_overhead() {
+    use kparse::spans::SpanFragment;
+    use kparse::{ParseSpan, ParserResult, TrackedSpan};
+    use nom::character::complete::alpha1;
+    use nom::{AsChar, InputIter, InputLength, InputTake, InputTakeAtPosition};
+    use std::fmt::Debug;
+
+    fn word<I>(i: I) -> ParserResult<ExCode, I, I>
+    where
+        I: Clone + Debug + SpanFragment + TrackedSpan<ExCode>,
+        I: InputTake + InputLength + InputIter + InputTakeAtPosition,
+        <I as InputTakeAtPosition>::Item: AsChar,
+    {
+        Track.enter(ExTagA, i.clone());
+        let r: ParserResult<ExCode, I, I> = alpha1.with_code(ExTagA).parse(i.clone());
+        match r {
+            Ok((rest, tok)) => Track.ok(rest, i, tok),
+            Err(e) => Track.err(e),
+        }
+    }
+
+    let mut buf = None;
+    let factor = str_tracking_overhead(
+        &mut buf,
+        "word",
+        10,
+        word::<ParseSpan<'_, ExCode, &str>>,
+        word::<&str>,
+    );
+    assert!(factor > 0.0);
+}
+
+#[test]
+fn test_roundtrip() {
+    use nom::character::complete::digit1;
+
+    fn number(i: ExSpan<'_>) -> ExTokenizerResult<'_, u32> {
+        digit1.parse_from_str(ExNumber).parse(i)
+    }
+
+    fn reparse(txt: &str) -> Option<u32> {
+        let tracker = Track::new_tracker::<ExCode, _>();
+        number(Track::new_span(&tracker, txt)).ok().map(|(_, v)| v)
+    }
+
+    str_parse(&mut None, "0042", number)
+        .roundtrip(|v| v.to_string(), reparse)
+        .q(R);
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "42", number);
+    t.roundtrip(|v| format!("{}0", v), reparse).q(NoReport);
+    assert!(t.failed.get());
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "42", number);
+    t.roundtrip(|v| format!("x{}", v), reparse).q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_named() {
+    use nom::character::complete::multispace0;
+
+    fn ws_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        multispace0.and(nom_a).recognize().parse(i)
+    }
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "\n \n  aäb", ws_a);
+    let t = t.named("data/file.txt");
+    let rest = t.result.as_ref().map(|(r, _)| *r).expect("ok");
+    assert_eq!(t.location(&rest), "data/file.txt:3:4");
+    t.rest("äb").q(R);
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "äb", |i| nom_a(i.slice(2..)));
+    let err = t.result.as_ref().map(|_| ()).expect_err("err");
+    let span = match err {
+        nom::Err::Error(e) => e.span,
+        _ => unreachable!(),
+    };
+    assert_eq!(t.location(&span), "1:2");
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "\r\n \r  aäb", ws_a);
+    let rest = t.result.as_ref().map(|(r, _)| *r).expect("ok");
+    assert_eq!(t.location(&rest), "3:4");
+}
+
+#[test]
+fn test_rest_hex() {
+    use kparse::{define_span, TokenizerResult};
+    use nom::number::complete::be_u16;
+
+    define_span!(BSpan = ExCode, [u8]);
+
+    fn word(i: BSpan<'_>) -> TokenizerResult<ExCode, BSpan<'_>, u16> {
+        be_u16.with_code(ExNumber).parse(i)
+    }
+
+    byte_parse(&mut None, b"\x01\x02\xff\x00", word)
+        .ok(|v: &u16, w: u16| *v == w, 0x0102)
+        .rest_hex("ff 00")
+        .consumed(2)
+        .q(R);
+    byte_parse(&mut None, b"\x01", word).err(ExNumber).q(R);
+
+    let mut buf = None;
+    let t = byte_parse(&mut buf, b"\x01\x02\xff\x00", word);
+    t.rest_hex("ff 01").q(NoReport);
+    assert!(t.failed.get());
+
+    let mut buf = None;
+    let t = byte_parse(&mut buf, b"\x01\x02\xff\x00", word);
+    t.rest_hex("ff 0").q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_define_span_cfg() {
+    use kparse::define_span;
+    use nom_locate::LocatedSpan;
+
+    define_span!(NoSpan = ExCode, str, cfg(any());
+        TokenizerResult = NoResult);
+    define_span!(TSpan = ExCode, str, cfg(all());
+        TokenizerError = TError, TokenizerResult = TResult);
+
+    fn no_a(i: NoSpan<'_>) -> NoResult<'_, NoSpan<'_>> {
+        tag("a").with_code(ExTagA).parse(i)
+    }
+    fn t_a(i: TSpan<'_>) -> TResult<'_, TSpan<'_>> {
+        tag("a").with_code(ExTagA).parse(i)
+    }
+
+    let plain: &str = "ab";
+    assert_eq!(no_a(plain).expect("ok"), ("b", "a"));
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span: LocatedSpan<_, _> = tracker.track_span("ab");
+    let (rest, _) = t_a(span).expect("ok");
+    let err: TError<'_> = match t_a(rest) {
+        Err(nom::Err::Error(e)) => e,
+        _ => unreachable!(),
+    };
+    assert_eq!(err.code, ExTagA);
+    assert_eq!(err.span.location_offset(), 1);
+}
+
+#[test]
+fn test_incomplete() {
+    fn stream_abc(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        nom::bytes::streaming::tag("abc")(i)
+    }
+
+    str_parse(&mut None, "ab", stream_abc)
+        .incomplete()
+        .needed(1)
+        .q(R);
+    str_parse(&mut None, "", stream_abc).needed(3).q(R);
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "abcd", stream_abc);
+    t.incomplete().q(NoReport);
+    assert!(t.failed.get());
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "ab", stream_abc);
+    t.needed(2).q(NoReport);
+    assert!(t.failed.get());
+
+    let mut buf = None;
+    let t = str_parse(&mut buf, "x", stream_abc);
+    t.incomplete().q(NoReport);
+    assert!(t.failed.get());
+}
+
+#[test]
+fn test_finish() {
+    use kparse::test::{Dump, TestFailure};
+
+    assert_eq!(
+        str_parse(&mut None, "a", nom_a).ok_any().finish(Dump),
+        Ok(())
+    );
+    assert_eq!(
+        str_parse(&mut None, "b", nom_a)
+            .named("b.txt")
+            .ok_any()
+            .finish(Dump),
+        Err(TestFailure {
+            name: Some("b.txt".into())
+        })
+    );
+}
+
+#[test]
+fn test_cut_on() {
+    let tracker = Track::new_tracker::<ExCode, _>();
+
+    let r = nom_a.cut_on(ExTagA).parse(Track::new_span(&tracker, "b"));
+    assert!(matches!(r, Err(nom::Err::Failure(_))));
+
+    let r = nom_a.cut_on(ExTagB).parse(Track::new_span(&tracker, "b"));
+    assert!(matches!(r, Err(nom::Err::Error(_))));
+
+    str_parse(&mut None, "a", |i| nom_a.cut_on(ExTagA).parse(i))
+        .ok_any()
+        .q(R);
+    str_parse(&mut None, "b", |i| nom_b.cut_on(ExTagA).parse(i))
+        .ok_any()
+        .q(R);
+}
+
+#[test]
+fn test_map_parser() {
+    use nom::character::complete::not_line_ending;
+
+    fn line_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        not_line_ending.map_parser(nom_a).parse(i)
+    }
+
+    str_parse(&mut None, "a\nb", line_a)
+        .ok_any()
+        .rest("\nb")
+        .q(R);
+    str_parse(&mut None, "b\na", line_a).err(ExTagA).q(R);
+}
+
+#[test]
+fn test_flat_map_code() {
+    use nom::branch::alt;
+
+    fn a_b_or_b_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        alt((nom_a, nom_b))
+            .flat_map_code(|t: ExSpan<'_>| {
+                let a = *t.fragment() == "a";
+                move |i| if a { nom_b(i) } else { nom_a(i) }
+            })
+            .parse(i)
+    }
+
+    str_parse(&mut None, "ab", a_b_or_b_a).ok_any().q(R);
+    str_parse(&mut None, "ba", a_b_or_b_a).ok_any().q(R);
+    str_parse(&mut None, "aa", a_b_or_b_a).err(ExTagB).q(R);
+}
+
+#[test]
+fn test_and_then_span() {
+    use nom::character::complete::alpha1;
+    use nom::sequence::tuple;
+
+    fn word_ab(i: ExSpan<'_>) -> ExTokenizerResult<'_, (ExSpan<'_>, ExSpan<'_>)> {
+        alpha1.and_then_span(tuple((nom_a, nom_b))).parse(i)
+    }
+
+    str_parse(&mut None, "ab c", word_ab)
+        .ok_any()
+        .rest(" c")
+        .q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, "  aa c");
+    let err = match word_ab(span.slice(2..)) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(err.code, ExTagB);
+    assert_eq!(Offset::offset(&span, &err.span), 3);
+}
+
+#[test]
+fn test_opt_or() {
+    use nom::character::complete::digit1;
+
+    fn count(i: ExSpan<'_>) -> ExTokenizerResult<'_, u32> {
+        digit1.parse_from_str::<_, u32>(ExNumber).opt_or(1).parse(i)
+    }
+
+    str_parse(&mut None, "12a", count)
+        .ok(|v: &u32, w: u32| *v == w, 12)
+        .rest("a")
+        .q(R);
+    str_parse(&mut None, "a", count)
+        .ok(|v: &u32, w: u32| *v == w, 1)
+        .rest("a")
+        .q(R);
+    str_parse(&mut None, "a", |i| nom_a.cut().opt_or(i).parse(i))
+        .ok_any()
+        .q(R);
+    str_parse(&mut None, "b", |i| nom_a.cut().opt_or(i).parse(i))
+        .err(ExTagA)
+        .q(R);
+}
+
+#[test]
+fn test_verify_map() {
+    use nom::character::complete::alpha1;
+
+    fn bool_flag(i: ExSpan<'_>) -> ExTokenizerResult<'_, bool> {
+        alpha1
+            .verify_map(
+                |v: ExSpan<'_>| match *v.fragment() {
+                    "yes" => Some(true),
+                    "no" => Some(false),
+                    _ => None,
+                },
+                ExTagA,
+            )
+            .parse(i)
+    }
+
+    str_parse(&mut None, "yes", bool_flag)
+        .ok(|v: &bool, w: bool| *v == w, true)
+        .q(R);
+    str_parse(&mut None, "no", bool_flag)
+        .ok(|v: &bool, w: bool| *v == w, false)
+        .q(R);
+    str_parse(&mut None, "maybe", bool_flag).err(ExTagA).q(R);
+}
+
+#[test]
+fn test_parse_from_str_radix() {
+    use nom::character::complete::{digit1, hex_digit1};
+
+    fn hex(i: ExSpan<'_>) -> ExTokenizerResult<'_, u16> {
+        hex_digit1.parse_from_str_radix(ExNumber, 16).parse(i)
+    }
+
+    fn decimal_comma(i: ExSpan<'_>) -> ExTokenizerResult<'_, f64> {
+        digit1
+            .terminated(nom::character::complete::char(','))
+            .and(digit1)
+            .recognize()
+            .parse_from_str_with(ExNumber, |s| s.replace(',', ".").parse::<f64>())
+            .parse(i)
+    }
+
+    str_parse(&mut None, "ff", hex)
+        .ok(|v: &u16, w: u16| *v == w, 255)
+        .q(R);
+    str_parse(&mut None, "fffff", hex).err(ExNumber).q(R);
+    str_parse(&mut None, "1,5", decimal_comma)
+        .ok(|v: &f64, w: f64| *v == w, 1.5)
+        .q(R);
+}
+
+#[test]
+fn test_value_with() {
+    #[derive(Debug, PartialEq)]
+    struct NoClone(u32);
+
+    fn no_clone(i: ExSpan<'_>) -> ExTokenizerResult<'_, NoClone> {
+        nom_a.value_with(|| NoClone(1)).parse(i)
+    }
+
+    str_parse(&mut None, "a", no_clone)
+        .ok(|v: &NoClone, w: u32| v.0 == w, 1)
+        .q(R);
+    str_parse(&mut None, "b", no_clone).err(ExTagA).q(R);
+}
+
+#[test]
+fn test_followed_by() {
+    use nom::character::complete::alpha1;
+
+    fn name(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        alpha1.followed_by(tag(":"), ExTagB).parse(i)
+    }
+
+    str_parse(&mut None, "name:", name).ok_any().rest(":").q(R);
+    str_parse(&mut None, "name ", name).err(ExTagB).q(R);
+    str_parse(&mut None, "name", name).err(ExTagB).q(R);
+}
+
+#[test]
+fn test_not_followed_by() {
+    use nom::character::complete::{alpha1, digit1};
+
+    fn name(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+        alpha1.not_followed_by(digit1, ExTagB).parse(i)
+    }
+
+    str_parse(&mut None, "name:", name).ok_any().rest(":").q(R);
+    str_parse(&mut None, "name", name).ok_any().rest("").q(R);
+    str_parse(&mut None, "name1", name).err(ExTagB).q(R);
+}
+
+#[test]
+fn test_padded() {
+    use nom::character::complete::multispace0;
+
+    str_parse(&mut None, " \ta  b", |i| nom_a.padded().parse(i))
+        .ok_any()
+        .rest("b")
+        .q(R);
+    str_parse(&mut None, "a", |i| nom_a.padded().parse(i))
+        .ok_any()
+        .rest("")
+        .q(R);
+    str_parse(&mut None, "\na\nb", |i| nom_a.padded().parse(i))
+        .err(ExTagA)
+        .q(R);
+    str_parse(&mut None, "\na\nb", |i| {
+        nom_a.padded_by(multispace0).parse(i)
+    })
+    .ok_any()
+    .rest("b")
+    .q(R);
+}
+
+#[test]
+fn test_diagnostics() {
+    use kparse::combinators::alt_with_codes;
+    use kparse::examples::ExAorB;
+
+    fn a_or_b(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        alt_with_codes(ExAorB, ((ExTagA, tag("a")), (ExTagB, tag("b"))))(i)
+    }
+
+    str_parse(&mut None, "a", a_or_b)
+        .ok_any()
+        .q(CheckDiagnostics);
+
+    let r = std::panic::catch_unwind(|| {
+        str_parse(&mut None, "first\nsecond x", a_or_b)
+            .ok_any()
+            .q(CheckDiagnostics);
+    });
+    assert!(r.is_err());
+}
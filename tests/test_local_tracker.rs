@@ -0,0 +1,100 @@
+use kparse::examples::{ExCode, ExTagA};
+use kparse::prelude::*;
+use kparse::ParserError;
+use nom::bytes::complete::tag;
+use nom::{IResult, Parser};
+use nom_locate::LocatedSpan;
+
+type LSpan<'s> = LocatedSpan<&'s str>;
+type LResult<'s, O> = IResult<LSpan<'s>, O, ParserError<ExCode, LSpan<'s>>>;
+
+fn nom_a(i: LSpan<'_>) -> LResult<'_, LSpan<'_>> {
+    Track.enter(ExTagA, i);
+    let r: LResult<'_, _> = tag("a").with_code(ExTagA).parse(i);
+    match r {
+        Ok((rest, tok)) => {
+            if *tok.fragment() == "a" {
+                Track.warn::<ExCode, _>(tok, "small a");
+            }
+            Track.ok(rest, i, tok)
+        }
+        Err(e) => Track.err(e),
+    }
+}
+
+#[test]
+fn test_local_tracker() {
+    let text = "ab";
+    let tracker = Track::new_local_tracker::<ExCode>().with_err_text(false);
+
+    let (rest, _) = nom_a(LocatedSpan::new(text)).expect("ok");
+    assert!(nom_a(rest).is_err());
+
+    if cfg!(debug_assertions) {
+        assert_eq!(tracker.warnings(), vec![(ExTagA, "small a")]);
+
+        let results = tracker.results(text);
+        let trace = format!("{:?}", results);
+        assert!(trace.contains("a: enter with 1:\"b\""));
+        assert!(trace.contains("err ExTagA for 1:\"b\""));
+
+        let tracker = Track::new_local_tracker::<ExCode>();
+        assert!(nom_a(rest).is_err());
+        let trace = format!("{:?}", tracker.results(text));
+        assert!(trace.contains("err parse error [ExTagA]"));
+    } else {
+        assert!(tracker.warnings().is_empty());
+    }
+}
+
+#[test]
+fn test_local_tracker_spans() {
+    let text = "xab";
+    let tracker = Track::new_local_tracker::<ExCode>();
+
+    let span = LocatedSpan::new(text);
+    let (rest, _) = tag::<_, _, ParserError<ExCode, _>>("x")(span).expect("ok");
+    let _ = nom_a(rest);
+
+    let results = format!("{:?}", tracker.results(text));
+    if cfg!(debug_assertions) {
+        assert!(results.contains("1:\"ab\""));
+    }
+    drop(tracker);
+
+    // no tracker installed.
+    let _ = nom_a(rest);
+
+    // other code type.
+    let tracker = Track::new_local_tracker::<ExCode>();
+    {
+        let _other = Track::new_local_tracker::<char_code::CCode>();
+        let _ = nom_a(rest);
+    }
+    let _ = nom_a(rest);
+    let results = tracker.results(text);
+    if cfg!(debug_assertions) {
+        let enter = format!("{:?}", results);
+        assert_eq!(enter.matches("a: enter").count(), 1);
+    }
+}
+
+mod char_code {
+    use kparse::Code;
+    use std::fmt::{Display, Formatter};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CCode {
+        NomError,
+    }
+
+    impl Display for CCode {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    impl Code for CCode {
+        const NOM_ERROR: Self = CCode::NomError;
+    }
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "logos")]
+
+use core::ops::Range;
+use kparse::examples::{ExAorB, ExCode, ExNomError, ExNumber, ExTagA, ExTagB};
+use kparse::logos::{kind, LogosTokens, TokenCode};
+use kparse::prelude::*;
+use kparse::token_input::TokenInput;
+use kparse::ParserError;
+use logos::Logos;
+use nom::branch::alt;
+use nom::multi::many0;
+use nom::IResult;
+
+#[derive(Logos, Debug, Clone, PartialEq)]
+#[logos(skip r"[ \t\n]+")]
+enum Token {
+    #[token("a")]
+    A,
+    #[token("b")]
+    B,
+    #[regex("[0-9]+", |lex| lex.slice().parse().ok())]
+    Number(u32),
+}
+
+impl TokenCode<ExCode> for Token {
+    fn code(&self) -> ExCode {
+        match self {
+            Token::A => ExTagA,
+            Token::B => ExTagB,
+            Token::Number(_) => ExNumber,
+        }
+    }
+}
+
+type Tokens<'s> = TokenInput<'s, (Token, Range<usize>)>;
+type TResult<'s, O> = IResult<Tokens<'s>, O, ParserError<ExCode, Tokens<'s>>>;
+
+fn parse_a_or_b(i: Tokens<'_>) -> TResult<'_, &(Token, Range<usize>)> {
+    Track.enter(ExAorB, i);
+    let r: TResult<'_, _> = alt((kind(Token::A), kind(Token::B)))(i);
+    match r {
+        Ok((rest, tok)) => Track.ok(rest, i, tok),
+        Err(e) => Track.err(e),
+    }
+}
+
+#[test]
+fn test_logos_tokens() {
+    let text = "a 12\n b";
+    let tokens = LogosTokens::new(Token::lexer(text), ExNomError).expect("lexed");
+    assert_eq!(tokens.text(), text);
+    assert_eq!(
+        tokens.tokens(),
+        &[
+            (Token::A, 0..1),
+            (Token::Number(12), 2..4),
+            (Token::B, 6..7)
+        ]
+    );
+
+    let (rest, a) = parse_a_or_b(tokens.input()).expect("ok");
+    assert_eq!(a.0, Token::A);
+
+    let (rest, n) = kind::<_, _, ParserError<ExCode, _>>(Token::Number(0))(rest).expect("ok");
+    assert_eq!(n.0, Token::Number(12));
+    assert_eq!(rest.source_span(), "b");
+
+    let (rest, all) = many0(parse_a_or_b)(rest).expect("ok");
+    assert_eq!(all.len(), 1);
+    assert_eq!(rest.tokens().len(), 0);
+}
+
+#[test]
+fn test_logos_errors() {
+    let err = LogosTokens::new(Token::lexer("a\n x"), ExNomError).expect_err("lex error");
+    assert_eq!(err.code, ExNomError);
+    assert_eq!(err.span, "x");
+
+    let text = "a\n 1";
+    let tokens = LogosTokens::new(Token::lexer(text), ExNomError).expect("lexed");
+    assert_eq!(tokens.tokens().len(), 2);
+
+    let err = match kind::<_, _, ParserError<ExCode, _>>(Token::Number(0))(tokens.input()) {
+        Err(nom::Err::Error(e)) => e.map_span(|v| v.source_span()),
+        _ => panic!("expected error"),
+    };
+    assert_eq!(err.code, ExNumber);
+    assert_eq!(err.span, "a\n 1");
+
+    let (rest, _) = parse_a_or_b(tokens.input()).expect("ok");
+    let err = match parse_a_or_b(rest) {
+        Err(nom::Err::Error(e)) => e.map_span(|v| v.source_span()),
+        _ => panic!("expected error"),
+    };
+    assert_eq!(err.code, ExTagA);
+    assert!(err.is_expected(ExTagB));
+    let source = Track::source_str(text);
+    assert_eq!(source.line(err.span), 2);
+}
+
+#[test]
+fn test_logos_track() {
+    let text = "a b";
+    let tokens = LogosTokens::new(Token::lexer(text), ExNomError).expect("lexed");
+    let tracker = Track::new_local_tracker::<ExCode>();
+
+    let (rest, _) = parse_a_or_b(tokens.input()).expect("ok");
+    assert!(parse_a_or_b(rest).is_ok());
+
+    let trace = format!("{:?}", tracker.results(text));
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("A | B: enter with 0:\"a b\""));
+        assert!(trace.contains("A | B: enter with 2:\"b\""));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}
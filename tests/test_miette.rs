@@ -0,0 +1,27 @@
+#![cfg(feature = "miette")]
+
+use kparse::examples::{ExAorB, ExTagA, ExTagB};
+use kparse::ParserError;
+use miette::Diagnostic;
+use nom::Slice;
+use nom_locate::LocatedSpan;
+
+#[test]
+fn test_diagnostic() {
+    let span = LocatedSpan::new("a c");
+
+    let mut err = ParserError::new(ExAorB, span.slice(2..));
+    err.expect(ExTagA, span.slice(2..));
+    err.suggest(ExTagB, span.slice(2..));
+
+    assert_eq!(err.code().expect("code").to_string(), "A | B");
+    assert_eq!(err.help().expect("help").to_string(), "suggested b");
+
+    let labels = err.labels().expect("labels").collect::<Vec<_>>();
+    assert_eq!(labels.len(), 2);
+    assert!(labels[0].primary());
+    assert_eq!(labels[0].offset(), 2);
+    assert_eq!(labels[0].label(), Some("A | B"));
+    assert_eq!(labels[1].offset(), 2);
+    assert_eq!(labels[1].label(), Some("expected a"));
+}
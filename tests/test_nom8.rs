@@ -0,0 +1,87 @@
+#![cfg(feature = "nom8")]
+
+use kparse::examples::{ExAorB, ExCode, ExNomError, ExTagA, ExTagB};
+use kparse::nom8::{error_kind, from_nom8, to_nom8};
+use kparse::{KParseError, KParser, ParserError, TokenizerError};
+
+type PError<'s> = ParserError<ExCode, &'s str>;
+type TError<'s> = TokenizerError<ExCode, &'s str>;
+
+fn nom_a(i: &str) -> nom::IResult<&str, &str, PError<'_>> {
+    use nom::Parser;
+    from_nom8(nom8::bytes::complete::tag("a"))
+        .with_code(ExTagA)
+        .parse(i)
+}
+
+fn nom_b(i: &str) -> nom::IResult<&str, &str, PError<'_>> {
+    use nom::Parser;
+    nom::bytes::complete::tag("b").with_code(ExTagB).parse(i)
+}
+
+#[test]
+fn test_from_nom8() {
+    assert_eq!(nom_a("ab").expect("ok"), ("b", "a"));
+
+    let err = nom_a("b").expect_err("err");
+    assert_eq!(err.code(), Some(ExTagA));
+    let nom = err.err().and_then(|e| e.nom());
+    assert_eq!(nom, Some(nom::error::ErrorKind::Tag));
+}
+
+#[test]
+fn test_to_nom8() {
+    use nom8::Parser;
+
+    let r = nom8::branch::alt((to_nom8(nom_a), to_nom8(nom_b))).parse("b");
+    assert_eq!(r.expect("ok"), ("", "b"));
+
+    let r = nom8::branch::alt((to_nom8(nom_a), to_nom8(nom_b))).parse("c");
+    match r {
+        Err(nom8::Err::Error(e)) => {
+            assert_eq!(e.code, ExTagA);
+            assert!(e.iter_expected().any(|v| v.code == ExTagB));
+        }
+        _ => panic!("expected error"),
+    }
+}
+
+#[test]
+fn test_nom8_tokenizer() {
+    use nom::Parser;
+
+    let mut a_or_b = from_nom8(nom8::branch::alt((
+        nom8::bytes::complete::tag::<_, _, TError<'_>>("a"),
+        nom8::bytes::complete::tag("b"),
+    )))
+    .with_code(ExAorB);
+
+    assert!(a_or_b.parse("a").is_ok());
+    let err = a_or_b.parse("c").expect_err("err");
+    assert_eq!(err.code(), Some(ExAorB));
+
+    let err = from_nom8(nom8::bytes::complete::tag::<_, _, TError<'_>>("a"))
+        .parse("c")
+        .expect_err("err");
+    assert_eq!(err.code(), Some(ExNomError));
+}
+
+#[test]
+fn test_nom8_incomplete() {
+    use nom::Parser;
+
+    let r = from_nom8(nom8::bytes::streaming::tag::<_, _, PError<'_>>("abc")).parse("ab");
+    assert!(matches!(r, Err(nom::Err::Incomplete(nom::Needed::Size(_)))));
+}
+
+#[test]
+fn test_error_kind() {
+    assert_eq!(
+        error_kind(nom8::error::ErrorKind::Digit),
+        nom::error::ErrorKind::Digit
+    );
+    assert_eq!(
+        error_kind(nom8::error::ErrorKind::Many),
+        nom::error::ErrorKind::Fail
+    );
+}
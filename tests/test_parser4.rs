@@ -301,8 +301,6 @@ pub mod parser4 {
         dump_diagnostics as dump_diagnostics_v4, dump_diagnostics_info as dump_diagnostics_info_v4,
         dump_trace as dump_trace_v4,
     };
-    #[cfg(debug_assertions)]
-    use kparse::prelude::*;
     use kparse::token_error::TokenizerError;
     use kparse::{define_span, Code, ParserError, ParserResult, TokenizerResult};
     use std::fmt::{Display, Formatter};
@@ -638,6 +636,7 @@ pub mod parser4 {
         use crate::parser4::APCode::*;
         use crate::parser4::APSpan;
         use chrono::NaiveDate;
+        #[cfg(not(debug_assertions))]
         use kparse::prelude::*;
         use std::fmt::{Debug, Formatter};
 
@@ -1281,7 +1280,7 @@ pub mod parser4 {
                     Err(e) => return Track.err(e),
                 }
 
-                if loop_rest.len() > 0 {
+                if !loop_rest.fragment().is_empty() {
                     match parse_kultur(loop_rest) {
                         Ok((rest, val)) => {
                             loop_rest = rest;
@@ -202,14 +202,12 @@ mod debug {
                 println!(" {:04}  {}", t_line, t);
             }
 
-            if expect.is_empty() {
-                if t_line == s_line {
-                    println!("      {}^", " ".repeat(s_column - 1));
-                    if !msg.is_empty() {
-                        println!("Erwarted war: {}", msg);
-                    } else {
-                        println!("Erwarted war: {}", err.code);
-                    }
+            if expect.is_empty() && t_line == s_line {
+                println!("      {}^", " ".repeat(s_column - 1));
+                if !msg.is_empty() {
+                    println!("Erwarted war: {}", msg);
+                } else {
+                    println!("Erwarted war: {}", err.code);
                 }
             }
 
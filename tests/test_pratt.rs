@@ -0,0 +1,135 @@
+use kparse::examples::{ExAthenB, ExCode, ExNumber, ExParserResult, ExSpan, ExTagA, ExTagB};
+use kparse::pratt::{pratt, Assoc};
+use kparse::prelude::*;
+use kparse::test::{str_parse, CheckTrace};
+use kparse::Track;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::{Offset, Parser};
+
+const R: CheckTrace = CheckTrace;
+
+fn expr(i: ExSpan<'_>) -> ExParserResult<'_, i64> {
+    pratt(
+        ExAthenB,
+        digit1.with_code(ExNumber).parse_from_str(ExNumber),
+    )
+    .infix(ExTagA, tag("+"), 1, Assoc::Left, |_, a, _, b| a + b)
+    .infix(ExTagA, tag("-"), 1, Assoc::Left, |_, a, _, b| a - b)
+    .infix(ExTagB, tag("*"), 2, Assoc::Left, |_, a, _, b| a * b)
+    .infix(ExTagB, tag("^"), 3, Assoc::Right, |_, a, _, b: i64| {
+        a.pow(b as u32)
+    })
+    .prefix(ExTagA, tag("-"), 4, |_, _, a: i64| -a)
+    .postfix(ExTagB, tag("!"), 5, |_, a, _| (1..=a).product())
+    .parse(i)
+}
+
+fn eq(v: &i64, w: i64) -> bool {
+    *v == w
+}
+
+#[test]
+fn test_pratt() {
+    str_parse(&mut None, "1", expr).ok(eq, 1).q(R);
+    str_parse(&mut None, "1+2*3", expr).ok(eq, 7).q(R);
+    str_parse(&mut None, "2*3+1", expr).ok(eq, 7).q(R);
+    str_parse(&mut None, "8-2-1", expr).ok(eq, 5).q(R);
+    str_parse(&mut None, "2^3^2", expr).ok(eq, 512).q(R);
+    str_parse(&mut None, "-2+3", expr).ok(eq, 1).q(R);
+    str_parse(&mut None, "-3!", expr).ok(eq, -6).q(R);
+    str_parse(&mut None, "3!*2", expr).ok(eq, 12).q(R);
+    str_parse(&mut None, "1+2)", expr).ok(eq, 3).rest(")").q(R);
+}
+
+#[test]
+fn test_pratt_err() {
+    str_parse(&mut None, "x", expr).err(ExNumber).q(R);
+    str_parse(&mut None, "1+", expr)
+        .err(ExAthenB)
+        .expect(ExTagA)
+        .q(R);
+    str_parse(&mut None, "1+-", expr)
+        .err(ExAthenB)
+        .expect(ExTagA)
+        .q(R);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, "1*2+");
+    let err = match expr(span) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(Offset::offset(&span, &err.span), 4);
+    let op = err
+        .iter_expected()
+        .find(|v| v.code == ExTagA)
+        .expect("expect");
+    assert_eq!(Offset::offset(&span, &op.span), 3);
+}
+
+#[test]
+fn test_pratt_incomplete() {
+    use kparse::provider::TrackData;
+    use nom::character::streaming;
+
+    fn sexpr(i: ExSpan<'_>) -> ExParserResult<'_, i64> {
+        pratt(
+            ExAthenB,
+            streaming::digit1
+                .with_code(ExNumber)
+                .parse_from_str(ExNumber),
+        )
+        .infix(ExTagA, tag("+"), 1, Assoc::Left, |_, a, _, b| a + b)
+        .parse(i)
+    }
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let r = sexpr(Track::new_span(&tracker, "1+2"));
+    assert!(matches!(r, Err(nom::Err::Incomplete(_))));
+
+    let tracks = tracker.tracks();
+    let enter = tracks
+        .iter()
+        .filter(|v| matches!(v.track, TrackData::Enter(_, _)))
+        .count();
+    let exit = tracks
+        .iter()
+        .filter(|v| matches!(v.track, TrackData::Exit()))
+        .count();
+    assert_eq!(enter, exit);
+    if cfg!(debug_assertions) {
+        assert!(tracks
+            .iter()
+            .any(|v| matches!(v.track, TrackData::Info(_, "incomplete"))));
+    }
+}
+
+#[test]
+fn test_pratt_span() {
+    fn sexpr(i: ExSpan<'_>) -> ExParserResult<'_, String> {
+        pratt(ExAthenB, digit1.map(|v: ExSpan<'_>| v.to_string()))
+            .infix(
+                ExTagA,
+                tag("+"),
+                1,
+                Assoc::Left,
+                |s: ExSpan<'_>, _, _, _| format!("({})", s),
+            )
+            .infix(
+                ExTagB,
+                tag("*"),
+                2,
+                Assoc::Left,
+                |s: ExSpan<'_>, _, _, _| format!("[{}]", s),
+            )
+            .parse(i)
+    }
+
+    str_parse(&mut None, "1+2*3", sexpr)
+        .ok(|v: &String, w: &str| v == w, "(1+2*3)")
+        .q(R);
+    str_parse(&mut None, "1*2+3", sexpr)
+        .ok(|v: &String, w: &str| v == w, "(1*2+3)")
+        .q(R);
+}
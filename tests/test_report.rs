@@ -0,0 +1,496 @@
+use kparse::examples::{ExAorB, ExCode, ExNumber, ExTagA, ExTagB};
+use kparse::provider::{TrackData, TrackedData};
+use kparse::report::{
+    explain, explain_name, to_json_bundle, to_json_diagnostics, to_json_diagnostics_with,
+    Diagnostics, RustcStyle, Severity,
+};
+use kparse::{Code, CodeKind, ParserError, Track};
+use nom::error::{ErrorKind, ParseError};
+use std::fmt::{Display, Formatter};
+
+#[test]
+fn test_diagnostics() {
+    let text = "first\nsecond x\nthird";
+    let txt = Track::source_str(text);
+
+    let mut err = ParserError::new(ExAorB, &text[13..]);
+    err.expect(ExTagA, &text[13..]);
+    err.expect(ExNumber, &text[6..]);
+    err.suggest(ExTagB, &text[13..]);
+
+    let msg = Diagnostics::new()
+        .context(1)
+        .prefix_error("FEHLER")
+        .format(&txt, "plan.txt", &err, "");
+    let expected = [
+        "FEHLER: plan.txt: A | B",
+        " 0001 first",
+        "*0002 second x",
+        "      ^",
+        "expected: number",
+        "             ^",
+        "expected: a",
+        " 0003 third",
+        "hint: b",
+        "",
+    ];
+    assert_eq!(msg, expected.join("\n"));
+}
+
+#[test]
+fn test_diagnostics_msg() {
+    let text = "first\nsecond x";
+    let txt = Track::source_str(text);
+
+    let err: ParserError<ExCode, _> = ParseError::from_error_kind(&text[13..], ErrorKind::Digit);
+    let err = err.with_code(ExAorB);
+
+    let msg = Diagnostics::new()
+        .prefix_expected("expected was")
+        .format(&txt, "plan.txt", &err, "a or b");
+    let expected = [
+        "error: plan.txt: a or b",
+        " 0001 first",
+        "*0002 second x",
+        "             ^",
+        "expected was: a or b",
+        "nom: Digit at 2:7",
+        "",
+    ];
+    assert_eq!(msg, expected.join("\n"));
+}
+
+#[test]
+fn test_json_diagnostics() {
+    let text = "first\nsecond x";
+    let txt = Track::source_str(text);
+
+    let mut err = ParserError::new(ExAorB, &text[13..]);
+    err.expect(ExTagA, &text[13..]);
+
+    assert_eq!(
+        to_json_diagnostics(&txt, &err, Severity::Warning),
+        concat!(
+            r#"{"version":1,"severity":"warning","code":"ExAorB","message":"A | B","#,
+            r#""span":{"offset":13,"line":2,"column":7},"#,
+            r#""expected":[{"code":"ExTagA","message":"a","span":{"offset":13,"line":2,"column":7}}],"#,
+            r#""suggested":[]}"#
+        )
+    );
+}
+
+#[test]
+fn test_rustc_style() {
+    let text = "first\nsecond x\nthird\nfourth";
+    let txt = Track::source_str(text);
+
+    let mut err = ParserError::new(ExAorB, &text[13..]);
+    err.expect(ExTagA, &text[13..]);
+    err.expect(ExNumber, &text[21..]);
+    err.suggest(ExTagB, &text[13..]);
+
+    let msg = RustcStyle::new()
+        .color(false)
+        .format(&txt, "plan.txt", &err);
+    let expected = [
+        "error[ExAorB]: A | B",
+        " --> plan.txt:2:8",
+        "  |",
+        "2 | second x",
+        "  |        ^ A | B",
+        "  |        - expected a",
+        "...",
+        "4 | fourth",
+        "  | - expected number",
+        "  |",
+        "  = help: suggested b",
+        "",
+    ];
+    assert_eq!(msg, expected.join("\n"));
+
+    let msg = RustcStyle::new()
+        .severity(Severity::Warning)
+        .color(true)
+        .format(&txt, "plan.txt", &err);
+    assert!(msg.starts_with("\x1b[1;33mwarning[ExAorB]\x1b[0m\x1b[1m: A | B\x1b[0m\n"));
+}
+
+#[test]
+fn test_explain() {
+    let text = "x";
+    let txt = Track::source_str(text);
+
+    let msg = RustcStyle::new()
+        .color(false)
+        .explain_cmd("plan")
+        .format_explained(&txt, "plan.txt", &ParserError::new(ExAorB, text));
+    assert!(msg.ends_with(
+        "^ A | B\n\nFor more information about this error, try `plan --explain ExAorB`.\n"
+    ));
+
+    let msg = RustcStyle::new().color(false).format_explained(
+        &txt,
+        "plan.txt",
+        &ParserError::new(ExTagA, text),
+    );
+    assert!(!msg.contains("--explain"));
+
+    assert_eq!(
+        explain(ExAorB).as_deref(),
+        Some("ExAorB: A | B\n\nEither an 'a' or a 'b' is required here.\n")
+    );
+    assert_eq!(explain(ExTagA), None);
+    assert_eq!(explain_name("ExAorB", &[ExTagA, ExAorB]), explain(ExAorB));
+    assert_eq!(explain_name("ExTagX", &[ExTagA, ExAorB]), None);
+}
+
+fn deutsch(code: ExCode) -> String {
+    match code {
+        ExAorB => "A oder B".to_string(),
+        ExTagA => "ein a".to_string(),
+        _ => code.to_string(),
+    }
+}
+
+#[test]
+fn test_code_display() {
+    let text = "first\nsecond x";
+    let txt = Track::source_str(text);
+
+    let mut err = ParserError::new(ExAorB, &text[13..]);
+    err.expect(ExTagA, &text[13..]);
+
+    let msg = Diagnostics::new()
+        .code_display(deutsch)
+        .format(&txt, "plan.txt", &err, "");
+    assert!(msg.starts_with("error: plan.txt: A oder B\n"));
+    assert!(msg.contains("expected: ein a\n"));
+
+    let msg = RustcStyle::new()
+        .color(false)
+        .code_display(deutsch)
+        .format(&txt, "plan.txt", &err);
+    assert!(msg.starts_with("error[ExAorB]: A oder B\n"));
+    assert!(msg.contains("^ A oder B\n"));
+    assert!(msg.contains("- expected ein a\n"));
+
+    let msg = to_json_diagnostics_with(&txt, &err, Severity::Error, &deutsch);
+    assert!(msg.contains(r#""code":"ExAorB","message":"A oder B""#));
+    assert!(msg.contains(r#""code":"ExTagA","message":"ein a""#));
+}
+
+#[test]
+fn test_diagnostics_all() {
+    let text = "first\nsecond x\nthird\nfourth\nfifth\nsixth y z";
+    let txt = Track::source_str(text);
+
+    let mut err1 = ParserError::new(ExAorB, &text[40..]);
+    err1.suggest(ExTagB, &text[40..]);
+    let err2 = ParserError::new(ExNumber, &text[13..]);
+    let err3 = ParserError::new(ExTagA, &text[42..]);
+    let warn = ParserError::new(ExTagB, &text[13..]);
+
+    let errors = [err1, err2, err3];
+    let warnings = [warn];
+
+    let msg = Diagnostics::new()
+        .context(1)
+        .format_all(&txt, "plan.txt", &errors, &warnings);
+    let expected = [
+        "plan.txt:",
+        " 0001 first",
+        "*0002 second x",
+        "             ^",
+        "error: number",
+        "             ^",
+        "warning: b",
+        " 0003 third",
+        "...",
+        " 0005 fifth",
+        "*0006 sixth y z",
+        "            ^",
+        "error: A | B",
+        "hint: b",
+        "              ^",
+        "error: a",
+        "",
+    ];
+    assert_eq!(msg, expected.join("\n"));
+
+    let msg = Diagnostics::new()
+        .context(0)
+        .max_errors(1)
+        .format_all(&txt, "plan.txt", &errors, &warnings);
+    let expected = [
+        "plan.txt:",
+        "*0002 second x",
+        "             ^",
+        "error: number",
+        "and 3 more",
+        "",
+    ];
+    assert_eq!(msg, expected.join("\n"));
+}
+
+#[test]
+fn test_json_bundle() {
+    let text = "first\nsecond x\nthird\nfourth";
+    let txt = Track::source_str(text);
+
+    let err = ParserError::new(ExAorB, &text[13..]);
+    let span = nom_locate::LocatedSpan::new(text);
+    let tracks = [
+        TrackedData {
+            func: ExAorB,
+            callstack: vec![ExAorB],
+            track: TrackData::Enter(ExAorB, span),
+        },
+        TrackedData {
+            func: ExAorB,
+            callstack: vec![ExAorB],
+            track: TrackData::Err(span, ExAorB, Vec::new(), "fail".to_string()),
+        },
+        TrackedData {
+            func: ExAorB,
+            callstack: vec![],
+            track: TrackData::Exit(),
+        },
+    ];
+
+    assert_eq!(
+        to_json_bundle(&txt, "plan.txt", &[err], &tracks, 1),
+        concat!(
+            r#"{"format":"kparse-bundle","version":1,"name":"plan.txt","errors":["#,
+            r#"{"version":1,"severity":"error","code":"ExAorB","message":"A | B","#,
+            r#""span":{"offset":13,"line":2,"column":7},"expected":[],"suggested":[]}],"#,
+            r#""tracks":[{"event":"enter","func":"ExAorB","depth":1,"offset":0,"line":1},"#,
+            r#"{"event":"err","func":"ExAorB","depth":1,"offset":0,"line":1,"code":"ExAorB","message":"fail"},"#,
+            r#"{"event":"exit","func":"ExAorB","depth":0}],"#,
+            r#""source":[{"line":1,"offset":0,"text":"first\u000a"},"#,
+            r#"{"line":2,"offset":6,"text":"second x\u000a"},"#,
+            r#"{"line":3,"offset":15,"text":"third\u000a"}]}"#
+        )
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KCode {
+    NomError,
+    Digit,
+    Value,
+    Deprecated,
+}
+
+impl Display for KCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Code for KCode {
+    const NOM_ERROR: Self = KCode::NomError;
+
+    fn kind(&self) -> CodeKind {
+        match self {
+            KCode::Digit => CodeKind::Token,
+            _ => CodeKind::Syntax,
+        }
+    }
+
+    fn id(&self) -> Option<u32> {
+        match self {
+            KCode::NomError => None,
+            KCode::Digit => Some(1),
+            KCode::Value => Some(2),
+            KCode::Deprecated => Some(3),
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            KCode::Deprecated => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[test]
+fn test_code_kind() {
+    let text = "value x";
+    let txt = Track::source_str(text);
+
+    let mut err = ParserError::new(KCode::Value, &text[6..]);
+    err.expect(KCode::Digit, &text[6..]);
+
+    let msg = Diagnostics::new().format(&txt, "plan.txt", &err, "");
+    assert!(!msg.contains("expected: Digit"));
+    let msg = Diagnostics::new()
+        .verbose(true)
+        .format(&txt, "plan.txt", &err, "");
+    assert!(msg.contains("expected: Digit"));
+
+    let msg = RustcStyle::new()
+        .color(false)
+        .format(&txt, "plan.txt", &err);
+    assert!(!msg.contains("expected Digit"));
+    let msg = RustcStyle::new()
+        .color(false)
+        .verbose(true)
+        .format(&txt, "plan.txt", &err);
+    assert!(msg.contains("expected Digit"));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_code_kind_trace() {
+    use kparse::provider::TrackProvider;
+    use kparse::TrackedSpan;
+
+    let tracker = Track::new_tracker::<KCode, &str>();
+    let span = Track::new_span(&tracker, "1");
+    Track.enter(KCode::Value, span);
+    Track.enter(KCode::Digit, span);
+    span.track_ok(span);
+    span.track_exit();
+    span.track_ok(span);
+    span.track_exit();
+
+    let trace = format!("{:?}", tracker.results());
+    assert!(trace.contains("Value"));
+    assert!(!trace.contains("Digit"));
+
+    Track.enter(KCode::Value, span);
+    Track.enter(KCode::Digit, span);
+    span.track_ok(span);
+    span.track_exit();
+    span.track_ok(span);
+    span.track_exit();
+
+    let trace = format!("{:1?}", tracker.results());
+    assert!(trace.contains("Digit"));
+}
+
+#[test]
+fn test_code_id() {
+    let text = "value x";
+    let txt = Track::source_str(text);
+
+    let mut err = ParserError::new(KCode::Value, &text[6..]);
+    err.expect(KCode::Digit, &text[6..]);
+    err.suggest(KCode::NomError, &text[6..]);
+
+    assert_eq!(
+        to_json_diagnostics(&txt, &err, Severity::Error),
+        concat!(
+            r#"{"version":1,"severity":"error","code":"Value","id":2,"message":"Value","#,
+            r#""span":{"offset":6,"line":1,"column":6},"#,
+            r#""expected":[{"code":"Digit","id":1,"message":"Digit","span":{"offset":6,"line":1,"column":6}}],"#,
+            r#""suggested":[{"code":"NomError","message":"NomError","span":{"offset":6,"line":1,"column":6}}]}"#
+        )
+    );
+}
+
+#[test]
+fn test_code_severity() {
+    let text = "old value";
+    let txt = Track::source_str(text);
+
+    let warn = ParserError::new(KCode::Deprecated, text);
+    let err = ParserError::new(KCode::Value, &text[4..]);
+
+    let msg = RustcStyle::new()
+        .color(false)
+        .format(&txt, "plan.txt", &warn);
+    assert!(msg.starts_with("warning[Deprecated]: Deprecated\n"));
+    let msg = RustcStyle::new()
+        .color(false)
+        .severity(Severity::Error)
+        .format(&txt, "plan.txt", &warn);
+    assert!(msg.starts_with("error[Deprecated]: Deprecated\n"));
+
+    let msg = Diagnostics::new().format_all(&txt, "plan.txt", &[warn, err], &[]);
+    assert!(msg.contains("warning: Deprecated\n"));
+    assert!(msg.contains("error: Value\n"));
+}
+
+#[test]
+fn test_recover_warning() {
+    use kparse::combinators::recover_warning;
+    use kparse::KParser;
+    use nom::bytes::complete::tag;
+    use std::cell::RefCell;
+
+    let warnings: RefCell<Vec<ParserError<KCode, &str>>> = RefCell::new(Vec::new());
+    let mut old = recover_warning(tag("old").with_code(KCode::Deprecated), &warnings);
+
+    let (rest, v) = old("new").expect("recovered");
+    assert_eq!(rest, "new");
+    assert!(v.is_none());
+    assert_eq!(warnings.borrow().len(), 1);
+    assert_eq!(warnings.borrow()[0].code, KCode::Deprecated);
+
+    let (rest, v) = old("old").expect("ok");
+    assert_eq!(rest, "");
+    assert_eq!(v, Some("old"));
+
+    let mut value = recover_warning(tag("value").with_code(KCode::Value), &warnings);
+    assert!(value("new").is_err());
+    assert_eq!(warnings.borrow().len(), 1);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_code_severity_trace() {
+    use kparse::TrackedSpan;
+
+    let tracker = Track::new_tracker::<KCode, &str>();
+    let span = Track::new_span(&tracker, "old");
+    Track.enter(KCode::Value, span);
+    span.track_err(KCode::Deprecated, &"deprecated");
+    span.track_exit();
+
+    assert_eq!(
+        tracker.warnings(),
+        vec![(KCode::Value, "error with severity warning")]
+    );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_err_text() {
+    use kparse::provider::TrackProvider;
+    use kparse::TrackedSpan;
+
+    let err_text = |tracker: &kparse::provider::StdTracker<KCode, &str>| {
+        tracker.tracks().iter().find_map(|v| match &v.track {
+            TrackData::Err(_, _, _, text) => Some(text.clone()),
+            _ => None,
+        })
+    };
+
+    let tracker = Track::new_tracker::<KCode, &str>();
+    let span = Track::new_span(&tracker, "x");
+    Track.enter(KCode::Value, span);
+    span.track_err(KCode::Digit, &"no digit");
+    span.track_exit();
+    assert_eq!(err_text(&tracker), Some("\"no digit\"".to_string()));
+
+    let tracker = Track::new_tracker::<KCode, &str>().with_err_text(false);
+    let span = Track::new_span(&tracker, "x");
+    Track.enter(KCode::Value, span);
+    span.track_err(KCode::Digit, &"no digit");
+    span.track_exit();
+    assert_eq!(err_text(&tracker), Some(String::new()));
+    assert!(format!("{:?}", tracker.results()).contains("err Digit for 0:\"x\""));
+
+    let tracker = Track::new_tracker::<KCode, &str>().with_err_text(false);
+    let span = Track::new_span(&tracker, "xy");
+    Track.enter(KCode::Value, span);
+    let mut err = ParserError::new(KCode::Value, span);
+    err.expect(KCode::Digit, nom::Slice::slice(&span, 1..));
+    let _ = Track.err::<_, _, (), _>(err);
+    assert_eq!(err_text(&tracker), Some(String::new()));
+    assert!(
+        format!("{:?}", tracker.results()).contains("err Value for 0:\"xy\" expect=Digit:1:\"y\",")
+    );
+}
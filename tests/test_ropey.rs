@@ -0,0 +1,216 @@
+#![cfg(feature = "ropey")]
+// a RopeSpan is a RopeSlice plus the offset, that's too big for clippy.
+#![allow(clippy::result_large_err)]
+
+use kparse::examples::{ExAthenB, ExCode, ExNumber, ExTagA, ExTagB};
+use kparse::prelude::*;
+use kparse::ropey::{RopeSpan, SourceRope};
+use kparse::source::SourceStr;
+use kparse::ParserError;
+use nom::bytes::complete::{tag, tag_no_case, take_until};
+use nom::character::complete::{char, digit1, multispace0};
+use nom::multi::many1;
+use nom::sequence::{preceded, tuple};
+use nom::{IResult, InputLength, Parser, Slice};
+use ropey::Rope;
+
+type RResult<'r, O> = IResult<RopeSpan<'r>, O, ParserError<ExCode, RopeSpan<'r>>>;
+
+fn parse_a_then_b(i: RopeSpan<'_>) -> RResult<'_, RopeSpan<'_>> {
+    Track.enter(ExAthenB, i);
+    let r: RResult<'_, _> = tuple((
+        tag("a").with_code(ExTagA),
+        preceded(multispace0, tag_no_case("B")).with_code(ExTagB),
+    ))
+    .parse(i);
+    match r {
+        Ok((rest, (a, _))) => Track.ok(rest, i, a),
+        Err(e) => Track.err(e.with_code(ExAthenB)),
+    }
+}
+
+fn parse_numbers(i: RopeSpan<'_>) -> RResult<'_, Vec<u32>> {
+    many1(preceded(
+        multispace0,
+        digit1.parse_from_str::<_, u32>(ExNumber),
+    ))(i)
+}
+
+#[test]
+fn test_rope_parse() {
+    let mut rope = Rope::from_str("a\nb 12 3");
+    // a rope with more than one chunk.
+    rope.insert(8, &" 4".repeat(1000));
+
+    let (rest, _) = parse_a_then_b(RopeSpan::from(&rope)).expect("ok");
+    assert_eq!(rest.location_offset(), 3);
+    let (rest, numbers) = parse_numbers(rest).expect("ok");
+    assert_eq!(numbers.len(), 1002);
+    assert_eq!(&numbers[..3], &[12, 3, 4]);
+    assert_eq!(rest.input_len(), 0);
+
+    let err = parse_a_then_b(RopeSpan::from(&rope).slice(2..)).expect_err("err");
+    assert_eq!(err.code(), Some(ExAthenB));
+
+    let (rest, skipped) =
+        take_until::<_, _, ParserError<ExCode, _>>("12")(RopeSpan::from(&rope)).expect("ok");
+    assert_eq!(skipped.to_string(), "a\nb ");
+    let (_, c) = char::<_, ParserError<ExCode, _>>('1')(rest).expect("ok");
+    assert_eq!(c, '1');
+}
+
+#[test]
+fn test_rope_track() {
+    let text = "a\nb\na c";
+    let rope = Rope::from_str(text);
+    let tracker = Track::new_local_tracker::<ExCode>().with_err_text(false);
+
+    let (rest, _) = parse_a_then_b(RopeSpan::from(&rope)).expect("ok");
+    let (rest, _) = multispace0::<_, ParserError<ExCode, _>>(rest).expect("ok");
+    assert!(parse_a_then_b(rest).is_err());
+
+    let trace = format!("{:?}", tracker.results(text));
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("A B: enter with 0:\"a\\nb\\na c\""));
+        assert!(trace.contains("A B: ok -> [ 0:\"a\\nb\", 3:\"\\na c\" ]"));
+        assert!(trace.contains("A B: err ExAthenB for 6:\"c\" expect=ExTagB:6:\"c\","));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}
+
+/// Runs the same queries on SourceStr and SourceRope.
+#[test]
+fn test_rope_source() {
+    fn str_spans<'s>(text: &str, v: impl IntoIterator<Item = &'s str>) -> Vec<(usize, String)> {
+        v.into_iter()
+            .map(|v| (v.as_ptr() as usize - text.as_ptr() as usize, v.to_string()))
+            .collect()
+    }
+    fn rope_spans<'r>(v: impl IntoIterator<Item = RopeSpan<'r>>) -> Vec<(usize, String)> {
+        v.into_iter()
+            .map(|v| (v.location_offset(), v.to_string()))
+            .collect()
+    }
+
+    let texts = [
+        "",
+        "a",
+        "a\n",
+        "ab\ncd\n\nef",
+        "x\r\ny\rz\n",
+        "\tä\tb\nc\t\td",
+    ];
+    for text in texts {
+        let rope = Rope::from_str(text);
+        let span = RopeSpan::from(&rope);
+        for (ascii, tab) in [(false, 1), (true, 1), (false, 4)] {
+            // ascii with non-ascii text gives offsets within a char.
+            if ascii && !text.is_ascii() {
+                continue;
+            }
+            let ss = Source::<&str>::with_tab_width(
+                Source::<&str>::with_ascii(SourceStr::new(text), ascii),
+                tab,
+            );
+            let rs: SourceRope<'_> = SourceRope::new(rope.slice(..))
+                .with_ascii(ascii)
+                .with_tab_width(tab);
+
+            assert_eq!(
+                str_spans(text, Source::<&str>::iter(&ss)),
+                rope_spans(rs.iter())
+            );
+            for line in 0..5 {
+                assert_eq!(
+                    str_spans(text, Source::<&str>::line_span(&ss, line)),
+                    rope_spans(rs.line_span(line)),
+                );
+                for column in 0..6 {
+                    assert_eq!(
+                        str_spans(text, Source::<&str>::span_at(&ss, line, column)),
+                        rope_spans(rs.span_at(line, column)),
+                        "{:?} {} {}",
+                        text,
+                        line,
+                        column
+                    );
+                }
+            }
+            for needle in ["\n", "a", "c", "ä"] {
+                assert_eq!(
+                    str_spans(text, Source::<&str>::find_all(&ss, needle.as_bytes())),
+                    rope_spans(rs.find_all(needle.as_bytes())),
+                );
+            }
+
+            let bounds: Vec<_> = text
+                .char_indices()
+                .map(|(v, _)| v)
+                .chain([text.len()])
+                .collect();
+            for start in bounds.iter().copied() {
+                for end in bounds.iter().copied().filter(|v| *v >= start) {
+                    let f = &text[start..end];
+                    let r = span.slice(start..end);
+                    let msg = format!("{:?} {}..{}", text, start, end);
+
+                    assert_eq!(ss.location(f), rs.location(r), "{}", msg);
+                    assert_eq!(
+                        str_spans(text, [ss.start(f)]),
+                        rope_spans([rs.start(r)]),
+                        "{}",
+                        msg
+                    );
+                    assert_eq!(
+                        str_spans(text, [ss.end(f)]),
+                        rope_spans([rs.end(r)]),
+                        "{}",
+                        msg
+                    );
+                    assert_eq!(
+                        str_spans(text, ss.current(f)),
+                        rope_spans(rs.current(r)),
+                        "{}",
+                        msg
+                    );
+                    assert_eq!(
+                        str_spans(text, ss.forward_from(f)),
+                        rope_spans(rs.forward_from(r)),
+                        "{}",
+                        msg
+                    );
+                    assert_eq!(
+                        str_spans(text, ss.backward_from(f)),
+                        rope_spans(rs.backward_from(r)),
+                        "{}",
+                        msg
+                    );
+                    assert_eq!(
+                        str_spans(text, ss.get_lines_around(f, 1)),
+                        rope_spans(rs.get_lines_around(r, 1)),
+                        "{}",
+                        msg
+                    );
+                    assert_eq!(
+                        str_spans(text, ss.split_lines(f)),
+                        rope_spans(rs.split_lines(r)),
+                        "{}",
+                        msg
+                    );
+                    let str_numbered: Vec<_> = ss
+                        .get_numbered_lines_around(f, 1)
+                        .into_iter()
+                        .map(|(n, _, r)| (n, r))
+                        .collect();
+                    let rope_numbered: Vec<_> = rs
+                        .get_numbered_lines_around(r, 1)
+                        .into_iter()
+                        .map(|(n, _, r)| (n, r))
+                        .collect();
+                    assert_eq!(str_numbered, rope_numbered, "{}", msg);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "serde")]
+
+use kparse::examples::{ExCode, ExTagA};
+use kparse::prelude::*;
+use kparse::ParserError;
+use nom::bytes::complete::tag;
+use nom::{IResult, Parser};
+use nom_locate::LocatedSpan;
+use serde_json::json;
+
+type LSpan<'s> = LocatedSpan<&'s str>;
+type LResult<'s, O> = IResult<LSpan<'s>, O, ParserError<ExCode, LSpan<'s>>>;
+
+fn nom_a(i: LSpan<'_>) -> LResult<'_, LSpan<'_>> {
+    Track.enter(ExTagA, i);
+    let r: LResult<'_, _> = tag("a").with_code(ExTagA).parse(i);
+    match r {
+        Ok((rest, tok)) => Track.ok(rest, i, tok),
+        Err(e) => Track.err(e),
+    }
+}
+
+#[test]
+fn test_serialize_trace() {
+    let text = "a\nb";
+    let tracker = Track::new_local_tracker::<ExCode>();
+
+    let (rest, _) = nom_a(LocatedSpan::new(text)).expect("ok");
+    let (rest, _) = tag::<_, _, ParserError<ExCode, _>>("\n")(rest).expect("ok");
+    assert!(nom_a(rest).is_err());
+
+    let trace = serde_json::to_value(tracker.results(text)).expect("json");
+    if cfg!(debug_assertions) {
+        let events = trace.as_array().expect("array");
+        assert_eq!(events.len(), 6);
+        assert_eq!(
+            events[0],
+            json!({
+                "func": "ExTagA",
+                "callstack": ["ExTagA"],
+                "track": {"Enter": {
+                    "func": "ExTagA",
+                    "span": {"offset": 0, "line": 1, "fragment": "a\nb"}
+                }}
+            })
+        );
+        assert_eq!(
+            events[1]["track"],
+            json!({"Ok": {
+                "rest": {"offset": 1, "line": 1, "fragment": "\nb"},
+                "input": {"offset": 0, "line": 1, "fragment": "a\nb"}
+            }})
+        );
+        assert_eq!(events[2]["track"], json!("Exit"));
+        assert_eq!(
+            events[4]["track"],
+            json!({"Err": {
+                "span": {"offset": 2, "line": 2, "fragment": "b"},
+                "code": "ExTagA",
+                "expected": [],
+                "err": "parse error [ExTagA] for \"b\" "
+            }})
+        );
+    } else {
+        assert_eq!(trace, json!([]));
+    }
+}
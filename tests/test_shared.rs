@@ -0,0 +1,121 @@
+use kparse::examples::{ExAthenB, ExCode, ExNumber, ExTagA, ExTagB};
+use kparse::prelude::*;
+use kparse::shared::SharedStr;
+use kparse::source::SourceStr;
+use kparse::{ParserError, Track};
+use nom::bytes::complete::{tag, tag_no_case, take_until};
+use nom::character::complete::{char, digit1, multispace0};
+use nom::multi::many1;
+use nom::sequence::{preceded, tuple};
+use nom::{IResult, InputLength, Parser};
+use nom_locate::LocatedSpan;
+
+type SSpan = LocatedSpan<SharedStr>;
+type SResult<O> = IResult<SSpan, O, ParserError<ExCode, SSpan>>;
+
+fn parse_a_then_b(i: SSpan) -> SResult<SSpan> {
+    Track.enter(ExAthenB, i.clone());
+    let r: SResult<_> = tuple((
+        tag("a").with_code(ExTagA),
+        preceded(multispace0, tag_no_case("B")).with_code(ExTagB),
+    ))
+    .parse(i.clone());
+    match r {
+        Ok((rest, (a, _))) => Track.ok(rest, i, a),
+        Err(e) => Track.err(e.with_code(ExAthenB)),
+    }
+}
+
+fn parse_numbers(i: SSpan) -> SResult<Vec<u32>> {
+    many1(preceded(
+        multispace0,
+        digit1
+            .with_code(ExNumber)
+            .parse_from_str::<_, u32>(ExNumber),
+    ))(i)
+}
+
+// the text only lives inside the function.
+fn parse_owned(text: String) -> Result<(SSpan, Vec<u32>), ParserError<ExCode, SSpan>> {
+    let input = SSpan::new(SharedStr::from(text));
+    let r = parse_a_then_b(input).and_then(|(rest, a)| {
+        let (rest, v) = parse_numbers(rest)?;
+        Ok((rest, (a, v)))
+    });
+    match r {
+        Ok((_, v)) => Ok(v),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(e),
+        Err(nom::Err::Incomplete(_)) => unreachable!(),
+    }
+}
+
+#[test]
+fn test_shared_parse() {
+    let (a, numbers) = parse_owned("a\nb 12 3".to_string()).expect("ok");
+    assert_eq!(a.fragment(), &"a");
+    assert_eq!(numbers, vec![12, 3]);
+
+    let err = parse_owned("a\nb\n  c".to_string()).expect_err("err");
+    assert_eq!(err.code, ExNumber);
+    assert_eq!(err.span.location_offset(), 6);
+    assert_eq!(err.span.location_line(), 3);
+
+    let text = err.span.fragment().text();
+    let source = SourceStr::new(&text);
+    assert_eq!(source.line(err.span.clone()), 3);
+    assert_eq!(source.column(err.span.clone()), 2);
+    assert_eq!(source.start(err.span.clone()).fragment(), &"  c");
+    assert_eq!(source.offset(err.span.fragment().clone()), 6);
+    assert_eq!(
+        source.get_lines_around(err.span.fragment().clone(), 1),
+        vec!["b\n", "  c"]
+    );
+
+    let err = parse_owned("b".to_string()).expect_err("err");
+    assert_eq!(err.code, ExAthenB);
+
+    let span = SharedStr::from("xä12yy");
+    let (rest, skipped) = take_until::<_, _, ParserError<ExCode, _>>("12")(span).expect("ok");
+    assert_eq!(skipped, "xä");
+    assert_eq!(rest.range(), 3..7);
+    let (rest, c) = char::<_, ParserError<ExCode, _>>('1')(rest).expect("ok");
+    assert_eq!(c, '1');
+    assert_eq!(rest.input_len(), 3);
+}
+
+#[test]
+fn test_shared_track() {
+    let text = "a\nc";
+    let tracker = Track::new_local_tracker::<ExCode>();
+    assert!(parse_a_then_b(LocatedSpan::new(SharedStr::from(text))).is_err());
+    let trace = format!("{:?}", tracker.results(text));
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("A B: enter with 0:\"a\\nc\""));
+        assert!(trace.contains("A B: err parse error [ExAthenB] for \"c\""));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}
+
+#[test]
+fn test_shared_new_span() {
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_span(&tracker, SharedStr::from("a b"));
+
+    let r = tuple((
+        tag("a"),
+        multispace0,
+        tag::<_, _, ParserError<ExCode, _>>("c"),
+    ))
+    .with_code(ExTagA)
+    .parse(span.clone());
+    let err = match r {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    // detach from the tracker.
+    #[cfg(debug_assertions)]
+    let err = err.map_span(|v| v.map_extra(|_| ()));
+    assert_eq!(err.code, ExTagA);
+    assert_eq!(err.span.fragment().as_str(), "b");
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-use kparse::parser_error::Hints;
+use kparse::examples::{ExCode, ExTagA, ExTagB};
+use kparse::parser_error::{HintVec, Hints};
 use kparse::provider::{StdTracker, TrackData, TrackedData};
 use kparse::{Code, ParseSpan, ParserError, ParserResult, Track};
 use nom_locate::LocatedSpan;
@@ -16,7 +17,7 @@ impl Copy for ZCode {}
 
 impl Clone for ZCode {
     fn clone(&self) -> Self {
-        unimplemented!()
+        *self
     }
 }
 
@@ -61,7 +62,8 @@ fn test_size2() {
 
     dbg!(size_of::<nom::error::Error<&str>>());
     dbg!(size_of::<ParserError<ZCode, &str>>());
-    dbg!(size_of::<Vec<Hints<ZCode, &str>>>());
+    dbg!(size_of::<Hints<ZCode, &str>>());
+    dbg!(size_of::<HintVec<ZCode, &str>>());
 
     dbg!(size_of::<Track>());
     dbg!(size_of::<ParserResult<ZCode, &str, &str>>());
@@ -73,3 +75,42 @@ fn test_size2() {
     dbg!(size_of::<StdTracker<ZCode, &str>>());
     dbg!(size_of::<TrackData<ZCode, &str>>());
 }
+
+// only code, span and the pointer to the rest are inline, in debug and
+// release. with a one word span that's at most 3 words.
+const _: () = assert!(size_of::<ParserError<ZCode, &u8>>() <= 3 * size_of::<usize>());
+// the &str span takes two words.
+const _: () = assert!(size_of::<ParserError<ZCode, &str>>() <= 4 * size_of::<usize>());
+
+#[test]
+fn test_parser_error_size() {
+    assert_eq!(
+        size_of::<ParserError<ZCode, &str>>(),
+        size_of::<ZCode>().max(size_of::<usize>()) + size_of::<&str>() + size_of::<usize>()
+    );
+}
+
+#[test]
+fn test_hint_vec() {
+    use nom::error::{ErrorKind, ParseError};
+
+    let err = ParserError::<ExCode, &str>::from_error_kind("x", ErrorKind::Tag);
+    assert_eq!(err.hints().count(), 0);
+    assert_eq!(err.nom(), Some(ErrorKind::Tag));
+
+    let mut err = ParserError::new(ExTagA, "x");
+    assert_eq!(err.hints().count(), 0);
+    assert_eq!(err.nom(), None);
+    err.expect(ExTagA, "x");
+    assert_eq!(err.hint_vec().map(HintVec::len), Some(1));
+    assert_eq!(err.hint_vec().map(HintVec::spilled), Some(false));
+    assert_eq!(err.hint_vec().map(HintVec::capacity), Some(1));
+    err.expect(ExTagB, "x");
+    assert_eq!(err.hint_vec().map(HintVec::len), Some(2));
+    assert_eq!(err.hint_vec().map(HintVec::spilled), Some(true));
+    assert!(err.hint_vec().map_or(0, HintVec::capacity) >= 2);
+    assert_eq!(
+        err.iter_expected().map(|v| v.code).collect::<Vec<_>>(),
+        vec![ExTagB, ExTagA]
+    );
+}
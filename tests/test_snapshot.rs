@@ -0,0 +1,153 @@
+use kparse::examples::{ExParserResult, ExSpan, ExTagA, ExTokenizerResult};
+use kparse::prelude::*;
+use kparse::test::{str_fixtures, str_parse, CheckDump, Fixtures, JsonLines, NoReport, ReportAnd};
+use kparse::Track;
+use nom::bytes::complete::tag;
+use nom::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn nom_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, ExSpan<'_>> {
+    tag("a").with_code(ExTagA).parse(i)
+}
+
+fn parse_a(input: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+    Track.enter(ExTagA, input);
+    let r: ExParserResult<'_, ExSpan<'_>> = tag("a").with_code(ExTagA).parse(input);
+    match r {
+        Ok((rest, tok)) => Track.ok(rest, input, tok),
+        Err(e) => Track.err(e),
+    }
+}
+
+fn snapshot_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("kparse-{}", std::process::id()))
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn ok_snapshot(path: &Path, text: &str) -> bool {
+    let mut buf = None;
+    let t = str_parse(&mut buf, text, nom_a);
+    t.ok_snapshot(path).q(NoReport);
+    t.failed.get()
+}
+
+fn trace_snapshot(path: &Path, text: &str) -> bool {
+    let mut buf = None;
+    let t = str_parse(&mut buf, text, parse_a);
+    t.trace_snapshot(path).q(NoReport);
+    t.failed.get()
+}
+
+#[test]
+fn test_ok_snapshot() {
+    let path = snapshot_dir("ok").join("a.snap");
+
+    // first run writes the golden file.
+    assert!(!ok_snapshot(&path, "ab"));
+    assert!(path.exists());
+
+    // second run compares.
+    assert!(!ok_snapshot(&path, "ab"));
+
+    fs::write(&path, "something else").expect("write");
+    assert!(ok_snapshot(&path, "ab"));
+
+    assert!(ok_snapshot(&path, "b"));
+}
+
+#[test]
+fn test_trace_snapshot() {
+    let path = snapshot_dir("trace").join("a.snap");
+
+    assert!(!trace_snapshot(&path, "ab"));
+
+    assert!(!trace_snapshot(&path, "ab"));
+
+    if cfg!(debug_assertions) {
+        assert!(trace_snapshot(&path, "b"));
+    }
+}
+
+fn list_a(i: ExSpan<'_>) -> ExTokenizerResult<'_, Vec<ExSpan<'_>>> {
+    use nom::multi::separated_list1;
+    separated_list1(tag(" "), nom_a)(i)
+}
+
+#[test]
+fn test_fixtures() {
+    let fixtures = Fixtures::read("tests/fixtures", "txt").expect("fixtures");
+    assert_eq!(fixtures.files.len(), 2);
+    assert!(fixtures.files[0].expected.is_none());
+    assert!(fixtures.files[1].expected.is_some());
+
+    let mut buf = None;
+    str_fixtures(&mut buf, &fixtures, |i| {
+        list_a(i).map(|(r, v)| (r, v.len()))
+    });
+}
+
+#[test]
+#[should_panic]
+fn test_fixtures_fail() {
+    let dir = snapshot_dir("fixtures");
+    fs::create_dir_all(&dir).expect("dir");
+    fs::write(dir.join("ok.txt"), "a a").expect("write");
+    fs::write(dir.join("rest.txt"), "a b").expect("write");
+
+    let fixtures = Fixtures::read(&dir, "txt").expect("fixtures");
+    let mut buf = None;
+    str_fixtures(&mut buf, &fixtures, list_a);
+}
+
+#[test]
+fn test_json_lines() {
+    let dir = snapshot_dir("json");
+    fs::create_dir_all(&dir).expect("dir");
+    let path = dir.join("report.jsonl");
+    let path = path.to_str().expect("path");
+
+    str_parse(&mut None, "ab", nom_a)
+        .named("a \"quoted\" name")
+        .ok_any()
+        .q(JsonLines(path));
+    str_parse(&mut None, "b", nom_a)
+        .err(ExTagA)
+        .q(JsonLines(path));
+
+    let report = fs::read_to_string(path).expect("report");
+    let lines = report.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(r#"{"name":"a \"quoted\" name","input_hash":""#));
+    assert!(lines[0].contains(r#""input_len":2,"result":"ok","code":null,"#));
+    assert!(lines[0].contains(r#""failed":false,"#));
+    assert!(lines[1].starts_with(r#"{"name":null,"#));
+    assert!(lines[1].contains(r#""result":"error","code":"ExTagA","#));
+}
+
+#[test]
+fn test_report_and() {
+    let dir = snapshot_dir("json");
+    fs::create_dir_all(&dir).expect("dir");
+    let path = dir.join("report_and.jsonl");
+    let _ = fs::remove_file(&path);
+    let path = path.to_str().expect("path");
+
+    str_parse(&mut None, "ab", nom_a)
+        .ok_any()
+        .q(JsonLines(path).and(NoReport).and(CheckDump));
+
+    let report = fs::read_to_string(path).expect("report");
+    assert_eq!(report.lines().count(), 1);
+}
+
+#[test]
+#[should_panic]
+fn test_report_and_fail() {
+    str_parse(&mut None, "b", nom_a)
+        .ok_any()
+        .q(NoReport.and(CheckDump));
+}
@@ -0,0 +1,200 @@
+use kparse::examples::ExCode;
+use kparse::prelude::*;
+use kparse::source::{SourceBytes, SourceStr};
+use kparse::Track;
+use nom::{InputLength, Offset, Slice};
+
+#[test]
+fn test_span_union_str() {
+    let txt = "first second third";
+    let first = &txt[0..5];
+    let second = &txt[6..12];
+    let third = &txt[13..];
+
+    assert_eq!(txt.span_union(&first, &second), "first second");
+    assert_eq!(txt.span_union(&second, &first), "first second");
+    assert_eq!(txt.span_union(&first, &third), txt);
+    assert_eq!(txt.span_union(&txt, &second), txt);
+    assert_eq!(txt.span_union(&second, &second), "second");
+
+    // only the part within the source is used.
+    let sub = &txt[6..];
+    assert_eq!(sub.span_union(&first, &second), "second");
+}
+
+#[test]
+fn test_span_union_bytes() {
+    let txt = b"first second third".as_slice();
+    let first = &txt[0..5];
+    let third = &txt[13..];
+
+    assert_eq!(txt.span_union(&third, &first), txt);
+    assert_eq!((&txt[6..]).span_union(&first, &third), b"second third");
+}
+
+#[test]
+fn test_span_union_ex_span() {
+    // ExSpan is a LocatedSpan in debug builds and a &str in release builds.
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let input = Track::new_span(&tracker, "first second third");
+    let first = input.slice(0..5);
+    let second = input.slice(6..12);
+
+    let span = input.span_union(&second, &first);
+    assert_eq!(Offset::offset(&input, &span), 0);
+    assert_eq!(span.input_len(), 12);
+}
+
+#[test]
+fn test_to_range() {
+    let txt = "first\nsecond\nthird";
+    let source = SourceStr::new(txt);
+
+    let second = &txt[6..12];
+    let range = second.to_range(&txt);
+    assert_eq!(range, 6..12);
+    assert_eq!(Source::<&str>::span_for(&source, range.clone()), "second");
+
+    let span = Source::<nom_locate::LocatedSpan<&str>>::span_for(&source, range);
+    assert_eq!(*span.fragment(), "second");
+    assert_eq!(span.location_offset(), 6);
+    assert_eq!(span.location_line(), 2);
+
+    let located = nom_locate::LocatedSpan::new(txt);
+    assert_eq!(located.slice(6..12).to_range(&located), 6..12);
+
+    let bytes = txt.as_bytes();
+    let source = SourceBytes::new(bytes);
+    let range = (&bytes[13..]).to_range(&bytes);
+    assert_eq!(Source::<&[u8]>::span_for(&source, range), b"third");
+}
+
+#[test]
+#[should_panic]
+fn test_span_for_char_boundary() {
+    let txt = "äöü";
+    let source = SourceStr::new(txt);
+    Source::<&str>::span_for(&source, 1..2);
+}
+
+#[test]
+fn test_span_geometry() {
+    use std::cmp::Ordering;
+
+    let txt = "first second third";
+    let first = &txt[0..5];
+    let second = &txt[6..12];
+    let around = &txt[3..8];
+
+    assert!(txt.contains_span(&second));
+    assert!(!second.contains_span(&txt));
+    assert!(!first.intersects(&second));
+    assert!(!first.intersects(&&txt[5..6]));
+    assert!(first.intersects(&&txt[2..2]));
+    assert!(around.intersects(&second));
+    assert_eq!(around.intersection(&second), Some("se"));
+    assert_eq!(around.intersection(&first), Some("st"));
+    assert_eq!(first.intersection(&second), None);
+    assert_eq!(first.ordering_by_offset(&second), Ordering::Less);
+    assert_eq!(first.ordering_by_offset(&&txt[0..3]), Ordering::Greater);
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let input = Track::new_span(&tracker, txt);
+    let around = input.slice(3..8);
+    let second = input.slice(6..12);
+    let common = around.intersection(&second).expect("intersection");
+    assert_eq!(Offset::offset(&input, &common), 6);
+    assert_eq!(common.input_len(), 2);
+    assert!(input.contains_span(&common));
+    assert_eq!(second.ordering_by_offset(&around), Ordering::Greater);
+}
+
+#[test]
+fn test_owned_error() {
+    use kparse::examples::{ExParserResult, ExSpan, ExTagA};
+    use kparse::ParserError;
+    use nom::bytes::complete::tag;
+    use nom::Parser;
+    use std::ops::Range;
+
+    fn nom_a(i: ExSpan<'_>) -> ExParserResult<'_, ExSpan<'_>> {
+        tag("a").with_code(ExTagA).parse(i)
+    }
+
+    // the text only lives inside the function.
+    fn parse_owned(txt: String) -> Result<usize, ParserError<ExCode, Range<usize>>> {
+        let tracker = Track::new_tracker::<ExCode, _>();
+        let input = Track::new_span(&tracker, txt.as_str());
+        match nom_a.parse(input.slice(2..)) {
+            Ok((rest, _)) => Ok(Offset::offset(&input, &rest)),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                Err(e.map_span(|v| v.to_range(&input)))
+            }
+            Err(nom::Err::Incomplete(_)) => unreachable!(),
+        }
+    }
+
+    assert!(matches!(parse_owned("  a".to_string()), Ok(3)));
+    match parse_owned("  b".to_string()) {
+        Err(e) => {
+            assert_eq!(e.code, ExTagA);
+            assert_eq!(e.span, 2..3);
+        }
+        Ok(_) => panic!("expected error"),
+    }
+}
+
+#[test]
+fn test_span_resize() {
+    let txt = "  name  \nnext";
+    let name = &txt[0..8];
+    assert_eq!(name.trim_end_ws(), "  name");
+    assert_eq!(name.trim_start_ws(), "name  ");
+    assert_eq!("äöü".slice_chars(1..3), "öü");
+    assert_eq!("äöü".slice_chars(3..3), "");
+    assert_eq!((&txt[2..4]).extend_to(&txt, &&txt[9..]), "name  \nnext");
+
+    let bytes = b"  name  ".as_slice();
+    assert_eq!(bytes.trim_end_ws(), b"  name");
+    assert_eq!(bytes.trim_start_ws(), b"name  ");
+
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let input = Track::new_span(&tracker, txt);
+    let name = input.slice(0..8).trim_start_ws().trim_end_ws();
+    assert_eq!(Offset::offset(&input, &name), 2);
+    assert_eq!(name.input_len(), 4);
+    let na = name.slice_chars(0..2);
+    assert_eq!(Offset::offset(&input, &na), 2);
+    assert_eq!(na.input_len(), 2);
+    let all = na.extend_to(&input, &input.slice(9..));
+    assert_eq!(Offset::offset(&input, &all), 2);
+    assert_eq!(all.input_len(), txt.len() - 2);
+}
+
+#[test]
+fn test_trim_end_ws() {
+    let txt = "first\nname \t rest";
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let input = Track::new_span(&tracker, txt);
+
+    // a token with trailing whitespace on the second line.
+    let tok = input.slice(6..13);
+    let trim = tok.trim_end_ws();
+    assert_eq!(trim.fragment(), &"name");
+    assert_eq!(Offset::offset(&input, &trim), 6);
+    assert_eq!(Offset::offset(&input, &tok.slice(7..)), 13);
+    #[cfg(debug_assertions)]
+    {
+        assert_eq!(trim.location_line(), 2);
+        assert_eq!(trim.location_offset(), tok.location_offset());
+    }
+
+    assert_eq!(input.slice(6..10).trim_end_ws().input_len(), 4);
+    assert_eq!(input.slice(10..13).trim_end_ws().input_len(), 0);
+}
+
+#[test]
+#[should_panic]
+fn test_slice_chars_bounds() {
+    "abc".slice_chars(1..4);
+}
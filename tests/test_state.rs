@@ -0,0 +1,81 @@
+use kparse::examples::{ExCode, ExNumber};
+use kparse::prelude::*;
+use kparse::ParserError;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::{IResult, Parser};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Symbols {
+    count: Cell<u32>,
+    names: RefCell<HashMap<String, u32>>,
+}
+
+impl Symbols {
+    fn intern(&self, name: &str) -> u32 {
+        let mut names = self.names.borrow_mut();
+        if let Some(id) = names.get(name) {
+            *id
+        } else {
+            let id = self.count.get();
+            self.count.set(id + 1);
+            names.insert(name.to_string(), id);
+            id
+        }
+    }
+}
+
+type SSpan<'s> = StateSpan<'s, ExCode, &'s str, Symbols>;
+type SResult<'s, O> = IResult<SSpan<'s>, O, ParserError<ExCode, SSpan<'s>>>;
+
+fn symbol(i: SSpan<'_>) -> SResult<'_, u32> {
+    Track.enter(ExNumber, i);
+    let r: SResult<'_, _> = digit1.with_code(ExNumber).parse(i);
+    match r {
+        Ok((rest, tok)) => {
+            let id = rest.state().intern(tok.fragment());
+            Track.ok(rest, i, id)
+        }
+        Err(e) => Track.err(e),
+    }
+}
+
+#[test]
+fn test_state() {
+    let symbols = Symbols::default();
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_state_span(&tracker, &symbols, "12a12a7");
+
+    let (rest, v0) = symbol(span).expect("ok");
+    let (rest, _) = tag::<_, _, ParserError<ExCode, _>>("a")(rest).expect("ok");
+    let (rest, v1) = symbol(rest).expect("ok");
+    let (rest, _) = tag::<_, _, ParserError<ExCode, _>>("a")(rest).expect("ok");
+    let (rest, v2) = symbol(rest).expect("ok");
+
+    assert_eq!((v0, v1, v2), (0, 0, 1));
+    assert_eq!(symbols.count.get(), 2);
+    assert!(std::ptr::eq(rest.state(), &symbols));
+    assert_eq!(rest.location_offset(), 7);
+}
+
+#[test]
+fn test_state_track() {
+    let text = "1a";
+    let symbols = Symbols::default();
+    let tracker = Track::new_tracker::<ExCode, _>();
+    let span = Track::new_state_span(&tracker, &symbols, text);
+
+    let (rest, _) = symbol(span).expect("ok");
+    let err = symbol(rest).expect_err("err");
+    assert_eq!(err.code(), Some(ExNumber));
+
+    let trace = format!("{:?}", tracker.results());
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("number: enter with 0:\"1a\""));
+        assert!(trace.contains("number: err parse error [ExNumber] for \"a\""));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}
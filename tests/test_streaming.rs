@@ -0,0 +1,135 @@
+use kparse::combinators::track;
+use kparse::examples::{ExCode, ExNumber, ExTagA};
+use kparse::prelude::*;
+use kparse::streaming::{
+    chars, take_till_newline, take_while_ascii_ws, take_while_m_n, StreamBuffer,
+};
+use kparse::{ParserError, TokenizerError};
+use nom::bytes::streaming::tag;
+use nom::sequence::{preceded, terminated};
+use nom::{IResult, Needed};
+use nom_locate::LocatedSpan;
+
+type TError<'s> = TokenizerError<ExCode, &'s str>;
+type TResult<'s, O> = IResult<&'s str, O, TError<'s>>;
+
+fn record(i: &str) -> TResult<'_, &str> {
+    preceded(
+        take_while_ascii_ws,
+        terminated(take_till_newline, tag("\n")),
+    )(i)
+}
+
+fn number(i: &str) -> TResult<'_, &str> {
+    take_while_m_n(1, 3, |c| c.is_ascii_digit(), ExNumber)(i)
+}
+
+#[test]
+fn test_streaming_combinators() {
+    assert!(matches!(record("  abc"), Err(nom::Err::Incomplete(_))));
+    assert!(matches!(record("  "), Err(nom::Err::Incomplete(_))));
+    assert_eq!(record("  abc\nx").expect("ok"), ("x", "abc"));
+    assert_eq!(
+        take_till_newline::<_, TError<'_>>("abc\r\nx").expect("ok"),
+        ("\r\nx", "abc")
+    );
+
+    assert_eq!(number("12x").expect("ok"), ("x", "12"));
+    assert_eq!(number("1234").expect("ok"), ("4", "123"));
+    assert!(matches!(
+        number(""),
+        Err(nom::Err::Incomplete(Needed::Size(_)))
+    ));
+    assert!(matches!(number("12"), Err(nom::Err::Incomplete(_))));
+    assert!(matches!(number("x"), Err(nom::Err::Error(_))));
+
+    assert_eq!(
+        chars::<_, _, TError<'_>>(2, ExTagA)("abc").expect("ok"),
+        ("c", "ab")
+    );
+    assert!(matches!(
+        chars::<_, _, TError<'_>>(2, ExTagA)("a"),
+        Err(nom::Err::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_stream_buffer() {
+    let mut buf = StreamBuffer::new();
+    let mut records = Vec::new();
+
+    for chunk in ["one\n  tw", "o\n", "\nthr", "ee"] {
+        buf.push(chunk.as_bytes());
+        while let Some(r) = buf.parse_str(record).expect("ok") {
+            records.push(r.to_string());
+        }
+    }
+    assert_eq!(records, vec!["one", "two"]);
+    assert_eq!(buf.offset(), 10);
+    assert_eq!(buf.line(), 3);
+    assert_eq!(buf.unparsed(), b"\nthree");
+
+    buf.close();
+    assert!(matches!(
+        buf.parse_str(record),
+        Err(nom::Err::Incomplete(_))
+    ));
+    let rest = buf
+        .parse_str(kparse::combinators::take_till_newline::<_, TError<'_>>)
+        .expect("ok");
+    assert_eq!(rest, Some(""));
+}
+
+#[test]
+fn test_stream_buffer_bytes() {
+    let mut buf = StreamBuffer::new();
+    buf.push(b"ab");
+    let r = buf.parse(tag::<_, _, nom::error::Error<_>>(&b"abc"[..]));
+    assert_eq!(r.expect("ok"), None);
+    buf.push(b"cd");
+    let r = buf.parse(tag::<_, _, nom::error::Error<_>>(&b"abc"[..]));
+    assert_eq!(r.expect("ok"), Some(&b"abc"[..]));
+    assert_eq!(buf.unparsed(), b"d");
+    assert_eq!(buf.len(), 1);
+}
+
+#[test]
+fn test_stream_buffer_utf8() {
+    let text = "ä\n";
+    let mut buf = StreamBuffer::new();
+    buf.push(&text.as_bytes()[..1]);
+    assert_eq!(buf.parse_str(record).expect("ok"), None);
+    buf.push(&text.as_bytes()[1..]);
+    assert_eq!(buf.parse_str(record).expect("ok"), Some("ä"));
+
+    buf.push(b"x\xff\n");
+    match buf.parse_str(record) {
+        Err(nom::Err::Failure(e)) => assert_eq!(e.code, ExCode::ExNomError),
+        _ => panic!("expected failure"),
+    }
+}
+
+type LSpan<'s> = LocatedSpan<&'s str>;
+
+fn tracked_a(i: LSpan<'_>) -> IResult<LSpan<'_>, LSpan<'_>, ParserError<ExCode, LSpan<'_>>> {
+    track(ExTagA, tag("abc"))(i)
+}
+
+#[test]
+fn test_track_incomplete() {
+    let tracker = Track::new_local_tracker::<ExCode>();
+
+    let r = tracked_a(LocatedSpan::new("ab"));
+    assert!(matches!(r, Err(nom::Err::Incomplete(_))));
+    assert!(tracked_a(LocatedSpan::new("abc")).is_ok());
+
+    let trace = format!("{:?}", tracker.results("abc"));
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("a: enter with 0:\"ab\""));
+        assert!(trace.contains("a: info incomplete"));
+        // the incomplete call was closed, the next one is on the same level.
+        assert!(trace.contains("\n  a: ok"));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}
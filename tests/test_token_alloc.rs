@@ -0,0 +1,113 @@
+//!
+//! The tokenizer stage with TokenizerError doesn't allocate.
+//!
+//! Counts the allocations with a global allocator, so there is only
+//! this one test in this file.
+//!
+
+use kparse::examples::{ExAorB, ExCode, ExNumber, ExTagA, ExTagB};
+use kparse::keywords::KeywordSet;
+use kparse::{KParseError, KParser, ParserError, TokenizerError};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::error::{ErrorKind, ParseError};
+use nom::{IResult, Parser};
+use nom_locate::LocatedSpan;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountAlloc = CountAlloc;
+
+fn allocs<R>(f: impl FnOnce() -> R) -> (usize, R) {
+    let before = ALLOCS.load(Ordering::Relaxed);
+    let r = f();
+    (ALLOCS.load(Ordering::Relaxed) - before, r)
+}
+
+fn nom_a_or_b<I>(i: I) -> IResult<I, I, TokenizerError<ExCode, I>>
+where
+    I: Clone
+        + std::fmt::Debug
+        + kparse::spans::SpanFragment
+        + nom::InputTake
+        + nom::InputLength
+        + nom::InputIter
+        + nom::Compare<&'static str>
+        + nom::InputTakeAtPosition,
+    <I as nom::InputTakeAtPosition>::Item: nom::AsChar,
+{
+    alt((
+        tag("a").with_code(ExTagA),
+        tag("b").with_code(ExTagB),
+        digit1.with_code(ExNumber),
+    ))
+    .with_code(ExAorB)
+    .parse(i)
+}
+
+#[test]
+fn test_tokenizer_no_alloc() {
+    let keywords = KeywordSet::new(ExAorB)
+        .keyword("a", ExTagA)
+        .keyword("b", ExTagB);
+
+    let (n, r) = allocs(|| nom_a_or_b("x"));
+    assert_eq!(r.code(), Some(ExAorB));
+    assert_eq!(n, 0);
+
+    let (n, r) = allocs(|| nom_a_or_b(LocatedSpan::new("x")));
+    assert_eq!(r.code(), Some(ExAorB));
+    assert_eq!(n, 0);
+
+    let (n, r) = allocs(|| TokenizerError::new(ExTagA, "x").with_code(ExTagB));
+    assert_eq!(r.code, ExTagB);
+    assert_eq!(n, 0);
+
+    let (n, r) = allocs(|| {
+        keywords
+            .parse::<_, TokenizerError<ExCode, _>>("x")
+            .map(|(_, (code, _))| code)
+    });
+    assert!(r.is_err());
+    assert_eq!(n, 0);
+    // code and span are inline. debug builds may capture a backtrace.
+    let (n, r) = allocs(|| ParserError::new(ExAorB, "x"));
+    assert_eq!(r.code, ExAorB);
+    if !cfg!(debug_assertions) {
+        assert_eq!(n, 0);
+    }
+
+    // the nom error kind is boxed with the hints, that's one allocation.
+    let (n, r) = allocs(|| {
+        <ParserError<ExCode, &str> as ParseError<&str>>::from_error_kind("x", ErrorKind::Tag)
+    });
+    assert_eq!(r.nom(), Some(ErrorKind::Tag));
+    if !cfg!(debug_assertions) {
+        assert_eq!(n, 1);
+    }
+
+    // the counter works, ParserError boxes the hints.
+    let (n, _) = allocs(|| {
+        let mut err = ParserError::new(ExAorB, "x");
+        err.expect(ExTagA, "x");
+        err
+    });
+    assert!(n > 0);
+}
@@ -0,0 +1,118 @@
+use core::ops::Range;
+use kparse::examples::{ExAthenB, ExCode, ExNumber, ExTagA, ExTagB};
+use kparse::prelude::*;
+use kparse::token_input::{token, TokenInput};
+use kparse::{KParseError, ParserError};
+use nom::bytes::complete::take;
+use nom::multi::many0;
+use nom::sequence::tuple;
+use nom::{IResult, InputLength, InputTake};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    A,
+    B,
+    Number,
+}
+
+type Token = (Kind, Range<usize>);
+type Tokens<'s> = TokenInput<'s, Token>;
+type TResult<'s, O> = IResult<Tokens<'s>, O, ParserError<ExCode, Tokens<'s>>>;
+
+fn lex(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (pos, c) in text.char_indices() {
+        match c {
+            'a' => tokens.push((Kind::A, pos..pos + 1)),
+            'b' => tokens.push((Kind::B, pos..pos + 1)),
+            '0'..='9' => tokens.push((Kind::Number, pos..pos + 1)),
+            _ => {}
+        }
+    }
+    tokens
+}
+
+fn tok_a(i: Tokens<'_>) -> TResult<'_, &Token> {
+    token(ExTagA, |v: &Token| v.0 == Kind::A)(i)
+}
+
+fn tok_b(i: Tokens<'_>) -> TResult<'_, &Token> {
+    token(ExTagB, |v: &Token| v.0 == Kind::B)(i)
+}
+
+fn parse_a_then_b(i: Tokens<'_>) -> TResult<'_, Tokens<'_>> {
+    Track.enter(ExAthenB, i);
+    match tuple((tok_a, tok_b))(i) {
+        Ok((rest, _)) => Track.ok(rest, i, i.take(2)),
+        Err(e) => Track.err(e.with_code(ExAthenB)),
+    }
+}
+
+#[test]
+fn test_token_input() {
+    let text = "a b\n1 2";
+    let tokens = lex(text);
+    let i = TokenInput::new(text, &tokens);
+    assert_eq!(i.input_len(), 4);
+    assert_eq!(i.source_span(), "a b\n1 2");
+
+    let (rest, ab) = parse_a_then_b(i).expect("ok");
+    assert_eq!(ab.source_span(), "a b");
+    assert_eq!(rest.token_offset(), 2);
+
+    let (rest, numbers) = many0(token::<_, _, ParserError<ExCode, _>>(
+        ExNumber,
+        |v: &Token| v.0 == Kind::Number,
+    ))(rest)
+    .expect("ok");
+    assert_eq!(numbers.len(), 2);
+    assert_eq!(rest.input_len(), 0);
+    assert_eq!(rest.source_span(), "");
+    assert_eq!(rest.source_range(), 7..7);
+
+    let (rest, two) = take::<_, _, ParserError<ExCode, _>>(2usize)(i).expect("ok");
+    assert_eq!(two.tokens(), &tokens[..2]);
+    assert_eq!(rest.source_span(), "1 2");
+}
+
+#[test]
+fn test_token_input_err() {
+    let text = "a\n a 1";
+    let tokens = lex(text);
+
+    let err = match parse_a_then_b(TokenInput::new(text, &tokens)) {
+        Err(nom::Err::Error(e)) => e,
+        _ => panic!("expected error"),
+    };
+    assert_eq!(err.code, ExAthenB);
+    assert!(err.is_expected(ExTagB));
+
+    let err = err.map_span(|v| v.source_span());
+    assert_eq!(err.span, "a 1");
+    let exp = err.iter_expected().next().expect("expected");
+    assert_eq!(exp.code, ExTagB);
+    assert_eq!(exp.span, "a 1");
+
+    let source = Track::source_str(text);
+    assert_eq!(source.line(err.span), 2);
+    assert_eq!(source.column(err.span), 1);
+}
+
+#[test]
+fn test_token_input_track() {
+    let text = "a b\na 1";
+    let tokens = lex(text);
+    let tracker = Track::new_local_tracker::<ExCode>().with_err_text(false);
+
+    let (rest, _) = parse_a_then_b(TokenInput::new(text, &tokens)).expect("ok");
+    assert!(parse_a_then_b(rest).is_err());
+
+    let trace = format!("{:?}", tracker.results(text));
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("A B: enter with 0:\"a b\\na 1\""));
+        assert!(trace.contains("A B: ok -> [ 0:\"a b\\n\", 4:\"a 1\" ]"));
+        assert!(trace.contains("A B: err ExAthenB for 6:\"1\""));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "winnow")]
+
+use kparse::examples::{ExAorB, ExCode, ExNomError, ExNumber, ExTagA, ExTagB};
+use kparse::prelude::*;
+use kparse::test::{winnow_parse, CheckDump};
+use kparse::winnow::{from_winnow, to_winnow, track};
+use kparse::{KParseError, ParserError, TokenizerError};
+use winnow::combinator::{alt, cut_err};
+use winnow::error::ErrMode;
+use winnow::token::take_while;
+use winnow::{LocatingSlice, ModalResult, Parser, Stateful};
+
+type PError<'s> = ParserError<ExCode, &'s str>;
+type TError<'s> = TokenizerError<ExCode, &'s str>;
+type LInput<'s> = LocatingSlice<&'s str>;
+
+fn nom_a(i: &str) -> nom::IResult<&str, &str, PError<'_>> {
+    nom::Parser::parse(&mut from_winnow("a").with_code(ExTagA), i)
+}
+
+fn winnow_b<'s>(i: &mut LInput<'s>) -> ModalResult<&'s str, PError<'s>> {
+    "b".context(ExTagB).parse_next(i)
+}
+
+fn winnow_a_or_b<'s>(i: &mut LInput<'s>) -> ModalResult<&'s str, PError<'s>> {
+    track(ExAorB, alt((to_winnow(nom_a), winnow_b))).parse_next(i)
+}
+
+#[test]
+fn test_from_winnow() {
+    assert_eq!(nom_a("ab").expect("ok"), ("b", "a"));
+
+    let err = nom_a("b").expect_err("err");
+    assert_eq!(err.code(), Some(ExTagA));
+
+    let mut cut = from_winnow(cut_err("a".context(ExTagA)));
+    let r: nom::IResult<&str, &str, PError<'_>> = cut("b");
+    assert!(matches!(r, Err(nom::Err::Failure(_))));
+}
+
+#[test]
+fn test_to_winnow() {
+    let mut i = LocatingSlice::new("ba");
+    assert_eq!(winnow_a_or_b(&mut i).expect("ok"), "b");
+    assert_eq!(winnow_a_or_b(&mut i).expect("ok"), "a");
+
+    let mut i = LocatingSlice::new("c");
+    match winnow_a_or_b(&mut i) {
+        Err(ErrMode::Backtrack(e)) => {
+            assert_eq!(e.code, ExTagA);
+            assert_eq!(e.span, "c");
+            assert!(e.iter_expected().any(|v| v.code == ExTagB));
+        }
+        _ => panic!("expected error"),
+    }
+}
+
+#[test]
+fn test_external_error() {
+    let mut i = "x1";
+    let r: ModalResult<u32, PError<'_>> =
+        "x1".try_map(|v: &str| v.parse::<u32>()).parse_next(&mut i);
+    match r {
+        Err(ErrMode::Backtrack(e)) => {
+            assert_eq!(e.code, ExNomError);
+            assert!(e.cause().is_some());
+        }
+        _ => panic!("expected error"),
+    }
+}
+
+#[test]
+fn test_stateful() {
+    fn count_a<'s>(i: &mut Stateful<&'s str, u32>) -> ModalResult<&'s str, PError<'s>> {
+        let v = "a".context(ExTagA).parse_next(i)?;
+        i.state += 1;
+        Ok(v)
+    }
+
+    let mut i = Stateful {
+        input: "aab",
+        state: 0,
+    };
+    assert!(count_a(&mut i).is_ok());
+    assert!(count_a(&mut i).is_ok());
+    match count_a(&mut i) {
+        Err(ErrMode::Backtrack(e)) => {
+            assert_eq!(e.code, ExTagA);
+            assert_eq!(e.span, "b");
+        }
+        _ => panic!("expected error"),
+    }
+    assert_eq!(i.state, 2);
+}
+
+#[test]
+fn test_winnow_tokenizer() {
+    let mut i = "c";
+    let r: ModalResult<&str, TError<'_>> = alt(("a", "b")).context(ExAorB).parse_next(&mut i);
+    match r {
+        Err(ErrMode::Backtrack(e)) => assert_eq!(e.code, ExAorB),
+        _ => panic!("expected error"),
+    }
+
+    let mut i = "c";
+    let r: ModalResult<&str, TError<'_>> = "a".parse_next(&mut i);
+    match r {
+        Err(ErrMode::Backtrack(e)) => assert_eq!(e.code, ExNomError),
+        _ => panic!("expected error"),
+    }
+}
+
+#[test]
+fn test_winnow_parse() {
+    winnow_parse("ab", winnow_a_or_b)
+        .ok(|v, w| *v == w, "a")
+        .rest("b")
+        .q(CheckDump);
+    winnow_parse("c", winnow_a_or_b)
+        .err(ExTagA)
+        .expect(ExTagB)
+        .q(CheckDump);
+    winnow_parse("12", |i| -> ModalResult<u32, PError<'_>> {
+        take_while(1.., |c: char| c.is_ascii_digit())
+            .try_map(str::parse::<u32>)
+            .context(ExNumber)
+            .parse_next(i)
+    })
+    .ok(|v, w| *v == w, 12)
+    .q(CheckDump);
+}
+
+#[test]
+fn test_winnow_track() {
+    let text = "a\nb";
+    let tracker = Track::new_local_tracker::<ExCode>().with_err_text(false);
+
+    let mut i = LocatingSlice::new(text);
+    assert!(winnow_a_or_b(&mut i).is_ok());
+    assert!(Parser::<_, _, PError<'_>>::parse_next(&mut "\n", &mut i).is_ok());
+    assert!(winnow_a_or_b(&mut i).is_ok());
+    assert!(winnow_a_or_b(&mut i).is_err());
+
+    let trace = format!("{:?}", tracker.results(text));
+    if cfg!(debug_assertions) {
+        assert!(trace.contains("A | B: enter with 0:\"a\\nb\""));
+        assert!(trace.contains("A | B: ok -> [ 2:\"b\", 3:\"\" ]"));
+        assert!(trace.contains("A | B: err ExAorB for 3:\"\""));
+    } else {
+        assert!(!trace.contains("enter"));
+    }
+}